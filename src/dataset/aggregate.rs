@@ -0,0 +1,394 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use super::key::key_of;
+use super::value_order::collated;
+use super::{
+    DataCell, DataColumn, DataError, DataRow, DataTable, DataValue, ExpressionError, RowState,
+};
+
+/// Aggregate functions, with SQL Server's NULL handling: NULL inputs are ignored, and
+/// every function but `Count` returns NULL when no value remains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregateFunction {
+    /// Number of rows, or of non-NULL values when applied to a column.
+    Count,
+    /// Integer sums widen to `bigint`; decimal and float sums keep their type.
+    Sum,
+    /// Averages of integers and decimals are `decimal` (integer averages are not
+    /// truncated as in SQL Server); float averages are `float`.
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "COUNT" => Self::Count,
+            "SUM" => Self::Sum,
+            "AVG" => Self::Avg,
+            "MIN" => Self::Min,
+            "MAX" => Self::Max,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+}
+
+/// One aggregate column of a [`GroupBy`], built with [`count`], [`count_of`], [`sum`],
+/// [`avg`], [`min`] or [`max`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    /// Column aggregated, or `None` for `count()` over rows.
+    pub column: Option<String>,
+    /// Name of the result column, such as `sum_amount`.
+    pub alias: String,
+}
+
+impl Aggregate {
+    fn new(function: AggregateFunction, column: Option<&str>) -> Self {
+        let alias = match column {
+            Some(column) => format!("{}_{}", function.name(), column),
+            None => function.name().to_string(),
+        };
+        Self {
+            function,
+            column: column.map(str::to_string),
+            alias,
+        }
+    }
+
+    /// Name the result column.
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = alias.into();
+        self
+    }
+}
+
+/// Number of rows in each group.
+pub fn count() -> Aggregate {
+    Aggregate::new(AggregateFunction::Count, None)
+}
+
+/// Number of non-NULL values of `column`.
+pub fn count_of(column: &str) -> Aggregate {
+    Aggregate::new(AggregateFunction::Count, Some(column))
+}
+
+pub fn sum(column: &str) -> Aggregate {
+    Aggregate::new(AggregateFunction::Sum, Some(column))
+}
+
+pub fn avg(column: &str) -> Aggregate {
+    Aggregate::new(AggregateFunction::Avg, Some(column))
+}
+
+pub fn min(column: &str) -> Aggregate {
+    Aggregate::new(AggregateFunction::Min, Some(column))
+}
+
+pub fn max(column: &str) -> Aggregate {
+    Aggregate::new(AggregateFunction::Max, Some(column))
+}
+
+/// Rows of a table grouped by key columns, from [`DataTable::group_by`].
+///
+/// Groups appear in the order their first row does. NULL keys form one group, and
+/// text keys compare exactly (case-sensitive). Deleted rows are ignored.
+#[derive(Debug, Clone)]
+pub struct GroupBy<'a> {
+    table: &'a DataTable,
+    columns: Vec<String>,
+}
+
+impl<'a> GroupBy<'a> {
+    pub(crate) fn new(table: &'a DataTable, columns: &[&str]) -> Self {
+        Self {
+            table,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Build a table with the key columns followed by one column per aggregate, and
+    /// one row per group. Without key columns the whole table is one group, so an
+    /// empty table still yields a row, as in SQL.
+    pub fn agg<I>(self, aggregates: I) -> Result<DataTable, DataError>
+    where
+        I: IntoIterator<Item = Aggregate>,
+    {
+        let aggregates: Vec<Aggregate> = aggregates.into_iter().collect();
+        let names: Vec<&str> = self
+            .columns
+            .iter()
+            .map(String::as_str)
+            .chain(aggregates.iter().filter_map(|a| a.column.as_deref()))
+            .collect();
+        self.table.check_columns(&names)?;
+
+        let mut groups: Vec<(&DataRow, Vec<Accumulator>)> = Vec::new();
+        let mut positions = HashMap::new();
+        let new_group = |row| {
+            (
+                row,
+                aggregates
+                    .iter()
+                    .map(|a| Accumulator::new(a.function))
+                    .collect(),
+            )
+        };
+        for row in self
+            .table
+            .rows
+            .iter()
+            .filter(|r| r.state() != RowState::Deleted)
+        {
+            let group = *positions
+                .entry(key_of(row, &self.columns))
+                .or_insert_with(|| {
+                    groups.push(new_group(row));
+                    groups.len() - 1
+                });
+            for (aggregate, accumulator) in aggregates.iter().zip(&mut groups[group].1) {
+                match &aggregate.column {
+                    Some(column) => accumulator.add(row.get(column).unwrap_or(&DataValue::Null))?,
+                    None => accumulator.add(&DataValue::Bool(true))?,
+                }
+            }
+        }
+        let empty = DataRow::default();
+        if groups.is_empty() && self.columns.is_empty() {
+            groups.push(new_group(&empty));
+        }
+
+        let mut result = DataTable::new(&self.table.name);
+        for name in &self.columns {
+            result.columns.push(self.source_column(name));
+        }
+        let mut rows = Vec::with_capacity(groups.len());
+        for (first, accumulators) in groups {
+            let mut row = DataRow::default();
+            for name in &self.columns {
+                let value = first.get(name).cloned().unwrap_or_default();
+                row.cells.insert(name.clone(), DataCell::new(value));
+            }
+            for (aggregate, accumulator) in aggregates.iter().zip(accumulators) {
+                row.cells.insert(
+                    aggregate.alias.clone(),
+                    DataCell::new(accumulator.finish()?),
+                );
+            }
+            rows.push(row);
+        }
+        for aggregate in &aggregates {
+            let sql_type = match rows
+                .iter()
+                .filter_map(|r| r.get(&aggregate.alias))
+                .find(|v| !v.is_null())
+            {
+                Some(value) => column_type(value),
+                None => match (&aggregate.function, &aggregate.column) {
+                    (AggregateFunction::Min | AggregateFunction::Max, Some(column)) => {
+                        self.source_column(column).sql_type
+                    }
+                    _ => String::new(),
+                },
+            };
+            result.columns.push(DataColumn {
+                name: aggregate.alias.clone(),
+                sql_type,
+                nullable: aggregate.function != AggregateFunction::Count,
+                ..Default::default()
+            });
+        }
+        result.rows = rows;
+        Ok(result)
+    }
+
+    fn source_column(&self, name: &str) -> DataColumn {
+        self.table
+            .columns
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+            .unwrap_or_else(|| DataColumn::new(name, ""))
+    }
+}
+
+// Column type name of an aggregate result, in the form loaded columns use.
+fn column_type(value: &DataValue) -> String {
+    match value {
+        DataValue::TinyInt(_) => "Int1",
+        DataValue::SmallInt(_) => "Int2",
+        DataValue::Int(_) => "Int4",
+        DataValue::BigInt(_) => "Int8",
+        DataValue::Decimal(_) | DataValue::Numeric(_) => "Decimaln",
+        DataValue::Real(_) => "Float4",
+        DataValue::Float(_) => "Float8",
+        DataValue::Money(_) => "Money",
+        DataValue::Xml(_) => "Xml",
+        DataValue::Variant { .. } => "SSVariant",
+        DataValue::Udt { .. } | DataValue::Spatial(_) | DataValue::HierarchyId(_) => "Udt",
+        DataValue::Bool(_) => "Bit",
+        DataValue::Text(_) => "NVarchar",
+        DataValue::Binary(_) => "BigVarBin",
+        DataValue::Guid(_) => "Guid",
+        DataValue::Date(_) => "Daten",
+        DataValue::Time(_) => "Timen",
+        DataValue::DateTime(_) => "Datetime2",
+        DataValue::DateTimeOffset(_) => "DatetimeOffsetn",
+        DataValue::Null => "",
+    }
+    .to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Total {
+    Integer(i128),
+    Decimal(Decimal),
+    Float(f64),
+}
+
+/// Running state of one aggregate over a group of values.
+#[derive(Debug, Clone)]
+pub(crate) struct Accumulator {
+    function: AggregateFunction,
+    count: i64,
+    total: Option<Total>,
+    best: Option<DataValue>,
+}
+
+impl Accumulator {
+    pub fn new(function: AggregateFunction) -> Self {
+        Self {
+            function,
+            count: 0,
+            total: None,
+            best: None,
+        }
+    }
+
+    /// Add a value; NULLs are ignored.
+    pub fn add(&mut self, value: &DataValue) -> Result<(), ExpressionError> {
+        if value.is_null() {
+            return Ok(());
+        }
+        self.count += 1;
+        match self.function {
+            AggregateFunction::Count => {}
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                self.total = Some(add(self.total, value, self.function)?);
+            }
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let wanted = if self.function == AggregateFunction::Min {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                if self
+                    .best
+                    .as_ref()
+                    .is_none_or(|best| collated(value, best) == wanted)
+                {
+                    self.best = Some(value.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<DataValue, ExpressionError> {
+        Ok(match (self.function, self.total) {
+            (AggregateFunction::Count, _) => match i32::try_from(self.count) {
+                Ok(count) => DataValue::Int(count),
+                Err(_) => DataValue::BigInt(self.count),
+            },
+            (AggregateFunction::Min | AggregateFunction::Max, _) => {
+                self.best.unwrap_or(DataValue::Null)
+            }
+            (_, None) => DataValue::Null,
+            (AggregateFunction::Sum, Some(Total::Integer(sum))) => {
+                DataValue::BigInt(i64::try_from(sum).map_err(|_| ExpressionError::Overflow)?)
+            }
+            (AggregateFunction::Sum, Some(Total::Decimal(sum))) => DataValue::Decimal(sum),
+            (AggregateFunction::Sum, Some(Total::Float(sum))) => DataValue::Float(sum),
+            (_, Some(Total::Float(sum))) => DataValue::Float(sum / self.count as f64),
+            (_, Some(total)) => {
+                let sum = match total {
+                    Total::Integer(sum) => Decimal::try_from_i128_with_scale(sum, 0)
+                        .map_err(|_| ExpressionError::Overflow)?,
+                    Total::Decimal(sum) => sum,
+                    Total::Float(_) => unreachable!("float averages are handled above"),
+                };
+                DataValue::Decimal(
+                    sum.checked_div(Decimal::from(self.count))
+                        .ok_or(ExpressionError::Overflow)?,
+                )
+            }
+        })
+    }
+}
+
+fn add(
+    total: Option<Total>,
+    value: &DataValue,
+    function: AggregateFunction,
+) -> Result<Total, ExpressionError> {
+    let value = match value {
+        DataValue::TinyInt(v) => Total::Integer(*v as i128),
+        DataValue::SmallInt(v) => Total::Integer(*v as i128),
+        DataValue::Int(v) => Total::Integer(*v as i128),
+        DataValue::BigInt(v) => Total::Integer(*v as i128),
+        DataValue::Decimal(d) | DataValue::Money(d) => Total::Decimal(*d),
+        DataValue::Numeric(n) => match n.to_decimal() {
+            Some(d) => Total::Decimal(d),
+            None => Total::Float(n.to_f64()),
+        },
+        DataValue::Real(f) => Total::Float(*f as f64),
+        DataValue::Float(f) => Total::Float(*f),
+        DataValue::Variant { value, .. } => return add(total, value, function),
+        other => {
+            return Err(ExpressionError::TypeMismatch(format!(
+                "cannot {} values of type {}",
+                function.name(),
+                super::expression::type_name(other)
+            )))
+        }
+    };
+    let overflow = ExpressionError::Overflow;
+    Ok(match (total.unwrap_or(Total::Integer(0)), value) {
+        (Total::Integer(a), Total::Integer(b)) => Total::Integer(a.checked_add(b).ok_or(overflow)?),
+        (Total::Float(a), b) | (b, Total::Float(a)) => Total::Float(a + as_f64(b)),
+        (a, b) => Total::Decimal(as_decimal(a)?.checked_add(as_decimal(b)?).ok_or(overflow)?),
+    })
+}
+
+fn as_f64(total: Total) -> f64 {
+    match total {
+        Total::Integer(v) => v as f64,
+        Total::Decimal(d) => rust_decimal::prelude::ToPrimitive::to_f64(&d).unwrap_or_default(),
+        Total::Float(f) => f,
+    }
+}
+
+fn as_decimal(total: Total) -> Result<Decimal, ExpressionError> {
+    match total {
+        Total::Integer(v) => {
+            Decimal::try_from_i128_with_scale(v, 0).map_err(|_| ExpressionError::Overflow)
+        }
+        Total::Decimal(d) => Ok(d),
+        Total::Float(_) => unreachable!("float totals are handled before"),
+    }
+}
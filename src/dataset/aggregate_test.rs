@@ -0,0 +1,132 @@
+use rust_decimal::Decimal;
+
+use super::aggregate::{avg, count, count_of, sum, Accumulator};
+use super::*;
+
+fn aggregate(
+    function: AggregateFunction,
+    values: &[DataValue],
+) -> Result<DataValue, ExpressionError> {
+    let mut accumulator = Accumulator::new(function);
+    for value in values {
+        accumulator.add(value)?;
+    }
+    accumulator.finish()
+}
+
+#[test]
+fn ignores_nulls() {
+    let values = [DataValue::Int(1), DataValue::Null, DataValue::Int(4)];
+    assert_eq!(
+        aggregate(AggregateFunction::Sum, &values),
+        Ok(DataValue::BigInt(5))
+    );
+    assert_eq!(
+        aggregate(AggregateFunction::Avg, &values),
+        Ok(DataValue::Decimal(Decimal::new(25, 1)))
+    );
+    assert_eq!(
+        aggregate(AggregateFunction::Count, &values),
+        Ok(DataValue::Int(2))
+    );
+    assert_eq!(
+        aggregate(AggregateFunction::Min, &values),
+        Ok(DataValue::Int(1))
+    );
+    assert_eq!(
+        aggregate(AggregateFunction::Max, &values),
+        Ok(DataValue::Int(4))
+    );
+}
+
+#[test]
+fn returns_null_when_every_value_is_null() {
+    for values in [vec![], vec![DataValue::Null, DataValue::Null]] {
+        for function in [
+            AggregateFunction::Sum,
+            AggregateFunction::Avg,
+            AggregateFunction::Min,
+            AggregateFunction::Max,
+        ] {
+            assert_eq!(
+                aggregate(function, &values),
+                Ok(DataValue::Null),
+                "{function:?}"
+            );
+        }
+        assert_eq!(
+            aggregate(AggregateFunction::Count, &values),
+            Ok(DataValue::Int(0))
+        );
+    }
+}
+
+#[test]
+fn keeps_sum_and_average_types() {
+    let decimals = [
+        Decimal::new(150, 2).into(),
+        DataValue::Null,
+        Decimal::new(25, 1).into(),
+    ];
+    assert_eq!(
+        aggregate(AggregateFunction::Sum, &decimals),
+        Ok(DataValue::Decimal(Decimal::new(400, 2)))
+    );
+    let floats = [DataValue::Float(1.0), DataValue::Real(2.0), DataValue::Null];
+    assert_eq!(
+        aggregate(AggregateFunction::Avg, &floats),
+        Ok(DataValue::Float(1.5))
+    );
+    // Mixing integers and decimals sums as decimal.
+    let mixed = [DataValue::Int(1), Decimal::new(5, 1).into()];
+    assert_eq!(
+        aggregate(AggregateFunction::Sum, &mixed),
+        Ok(DataValue::Decimal(Decimal::new(15, 1)))
+    );
+    assert_eq!(
+        aggregate(
+            AggregateFunction::Sum,
+            &[DataValue::BigInt(i64::MAX), DataValue::Int(1)]
+        ),
+        Err(ExpressionError::Overflow)
+    );
+    assert_eq!(
+        aggregate(AggregateFunction::Sum, &["a".into()]),
+        Err(ExpressionError::TypeMismatch(
+            "cannot sum values of type nvarchar".into()
+        ))
+    );
+    // Text is counted but not summed.
+    assert_eq!(
+        aggregate(AggregateFunction::Count, &["a".into()]),
+        Ok(DataValue::Int(1))
+    );
+}
+
+#[test]
+fn counts_rows_and_values_in_groups() {
+    let mut table = DataTable::new("scores");
+    for (team, score) in [
+        ("a", DataValue::Null),
+        ("a", DataValue::Null),
+        ("b", 3.into()),
+        ("b", DataValue::Null),
+    ] {
+        let mut row = DataRow::default();
+        row.cells.insert("team".into(), DataCell::new(team));
+        row.cells.insert("score".into(), DataCell::new(score));
+        table.rows.push(row);
+    }
+    let totals = table
+        .group_by(&["team"])
+        .agg([count(), count_of("score"), sum("score"), avg("score")])
+        .unwrap();
+    assert_eq!(totals[0]["count"], 2);
+    assert_eq!(totals[0]["count_score"], 0);
+    assert!(totals[0]["sum_score"].is_null());
+    assert!(totals[0]["avg_score"].is_null());
+    assert_eq!(totals[1]["count"], 2);
+    assert_eq!(totals[1]["count_score"], 1);
+    assert_eq!(totals[1]["sum_score"], DataValue::BigInt(3));
+    assert_eq!(totals[1]["avg_score"], Decimal::new(3, 0));
+}
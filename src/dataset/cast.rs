@@ -0,0 +1,543 @@
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike,
+};
+use rust_decimal::{Decimal, RoundingStrategy};
+use uuid::Uuid;
+
+use super::expression::{parse_datetime, text_of, type_name};
+use super::{CastError, DataValue, Numeric, SqlType};
+
+impl DataValue {
+    /// Convert the value to `target` following SQL Server's `CAST` rules:
+    ///
+    /// - numbers convert between numeric types, truncating toward zero for integer
+    ///   targets (rounding from `money`) and rounding for `decimal` and `money`;
+    /// - text is parsed as the target type;
+    /// - values become text as SQL Server formats them by default, except floats,
+    ///   which use the shortest text that reads back exactly. Text and binary values
+    ///   are truncated to the target length, other values must fit it;
+    /// - integers become big-endian binary and text its UTF-16 bytes;
+    /// - `datetime` rounds to 1/300 of a second and `smalldatetime` to the minute.
+    ///
+    /// NULL stays NULL and a `sql_variant` converts its base value. Values outside the
+    /// target's range fail with [`CastError::Overflow`], text that does not parse with
+    /// [`CastError::Conversion`], and conversions SQL Server does not allow, such as
+    /// `date` to `int`, with [`CastError::NotAllowed`].
+    pub fn cast(&self, target: SqlType) -> Result<DataValue, CastError> {
+        let value = match self {
+            DataValue::Null => return Ok(DataValue::Null),
+            DataValue::Variant { value, .. } => return value.cast(target),
+            value => value,
+        };
+        if !is_valid(target) {
+            return Err(CastError::InvalidType(target));
+        }
+        let cast = Cast { value, target };
+        match target {
+            SqlType::Bit => cast.to_bit(),
+            SqlType::TinyInt | SqlType::SmallInt | SqlType::Int | SqlType::BigInt => {
+                cast.to_integer()
+            }
+            SqlType::Real | SqlType::Float => cast.to_float(),
+            SqlType::Decimal { precision, scale } => cast.to_decimal(precision, scale),
+            SqlType::Money | SqlType::SmallMoney => cast.to_money(),
+            SqlType::Char(_) | SqlType::VarChar(_) | SqlType::NChar(_) | SqlType::NVarChar(_) => {
+                cast.to_text()
+            }
+            SqlType::Binary(_) | SqlType::VarBinary(_) => cast.to_binary(),
+            SqlType::UniqueIdentifier => cast.to_guid(),
+            SqlType::Xml => cast.to_xml(),
+            SqlType::Date
+            | SqlType::Time
+            | SqlType::SmallDateTime
+            | SqlType::DateTime
+            | SqlType::DateTime2
+            | SqlType::DateTimeOffset => cast.to_temporal(),
+        }
+    }
+}
+
+fn is_valid(target: SqlType) -> bool {
+    match target {
+        SqlType::Decimal { precision, scale } => {
+            (1..=38).contains(&precision) && scale <= precision
+        }
+        SqlType::Char(n)
+        | SqlType::Binary(n)
+        | SqlType::VarChar(Some(n))
+        | SqlType::VarBinary(Some(n)) => (1..=8000).contains(&n),
+        SqlType::NChar(n) | SqlType::NVarChar(Some(n)) => (1..=4000).contains(&n),
+        _ => true,
+    }
+}
+
+// A numeric value, exact or floating point.
+enum Number {
+    Exact(Numeric),
+    Approximate(f64),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            // The exact text parses to the nearest double.
+            Number::Exact(n) => n.to_string().parse().unwrap_or_default(),
+            Number::Approximate(f) => *f,
+        }
+    }
+}
+
+struct Cast<'a> {
+    value: &'a DataValue,
+    target: SqlType,
+}
+
+impl Cast<'_> {
+    fn conversion(&self) -> CastError {
+        CastError::Conversion {
+            value: text_of(self.value).unwrap_or_default(),
+            target: self.target,
+        }
+    }
+
+    fn overflow(&self) -> CastError {
+        CastError::Overflow {
+            value: text_of(self.value).unwrap_or_default(),
+            target: self.target,
+        }
+    }
+
+    fn not_allowed(&self) -> CastError {
+        CastError::NotAllowed {
+            from: type_name(self.value),
+            target: self.target,
+        }
+    }
+
+    fn number(&self) -> Result<Number, CastError> {
+        let integer = |v: i128| Number::Exact(Numeric::new(v, 0).unwrap());
+        Ok(match self.value {
+            DataValue::Bool(v) => integer(*v as i128),
+            DataValue::TinyInt(v) => integer(*v as i128),
+            DataValue::SmallInt(v) => integer(*v as i128),
+            DataValue::Int(v) => integer(*v as i128),
+            DataValue::BigInt(v) => integer(*v as i128),
+            DataValue::Decimal(d) | DataValue::Money(d) => Number::Exact(Numeric::from(*d)),
+            DataValue::Numeric(n) => Number::Exact(*n),
+            DataValue::Real(v) => Number::Approximate(*v as f64),
+            DataValue::Float(v) => Number::Approximate(*v),
+            _ => return Err(self.not_allowed()),
+        })
+    }
+
+    // Integer text such as ` -12 `; empty text is zero.
+    fn parse_integer(&self, text: &str) -> Result<i128, CastError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(0);
+        }
+        let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.conversion());
+        }
+        text.parse().map_err(|_| self.overflow())
+    }
+
+    fn to_bit(&self) -> Result<DataValue, CastError> {
+        let bit = match self.value {
+            DataValue::Text(s) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                other => self.parse_integer(other)? != 0,
+            },
+            DataValue::Binary(b) => b.iter().any(|&b| b != 0),
+            _ => match self.number()? {
+                Number::Exact(n) => n.mantissa() != 0,
+                Number::Approximate(f) => f != 0.0,
+            },
+        };
+        Ok(DataValue::Bool(bit))
+    }
+
+    fn to_integer(&self) -> Result<DataValue, CastError> {
+        let (min, max) = match self.target {
+            SqlType::TinyInt => (0, u8::MAX as i128),
+            SqlType::SmallInt => (i16::MIN as i128, i16::MAX as i128),
+            SqlType::Int => (i32::MIN as i128, i32::MAX as i128),
+            _ => (i64::MIN as i128, i64::MAX as i128),
+        };
+        let value = match self.value {
+            DataValue::Text(s) => self.parse_integer(s)?,
+            // The rightmost bytes, most significant first.
+            DataValue::Binary(b) => {
+                let size = match self.target {
+                    SqlType::TinyInt => 1,
+                    SqlType::SmallInt => 2,
+                    SqlType::Int => 4,
+                    _ => 8,
+                };
+                let start = b.len().saturating_sub(size);
+                let raw = b[start..].iter().fold(0u64, |n, &b| n << 8 | b as u64);
+                match self.target {
+                    SqlType::TinyInt => raw as u8 as i128,
+                    SqlType::SmallInt => raw as u16 as i16 as i128,
+                    SqlType::Int => raw as u32 as i32 as i128,
+                    _ => raw as i64 as i128,
+                }
+            }
+            DataValue::Money(d) => d
+                .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                .mantissa(),
+            _ => match self.number()? {
+                Number::Exact(n) => n.mantissa() / 10i128.pow(n.scale() as u32),
+                Number::Approximate(f) if f.is_finite() && f.abs() < 1e38 => f.trunc() as i128,
+                Number::Approximate(_) => return Err(self.overflow()),
+            },
+        };
+        if value < min || value > max {
+            return Err(self.overflow());
+        }
+        Ok(match self.target {
+            SqlType::TinyInt => DataValue::TinyInt(value as u8),
+            SqlType::SmallInt => DataValue::SmallInt(value as i16),
+            SqlType::Int => DataValue::Int(value as i32),
+            _ => DataValue::BigInt(value as i64),
+        })
+    }
+
+    fn to_float(&self) -> Result<DataValue, CastError> {
+        let value = match self.value {
+            DataValue::Text(s) if s.trim().is_empty() => 0.0,
+            DataValue::Text(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| self.conversion())?,
+            _ => self.number()?.to_f64(),
+        };
+        match self.target {
+            SqlType::Real => {
+                let real = value as f32;
+                if real.is_infinite() && value.is_finite() {
+                    return Err(self.overflow());
+                }
+                Ok(DataValue::Real(real))
+            }
+            _ => Ok(DataValue::Float(value)),
+        }
+    }
+
+    // The value rounded half away from zero to `scale` places, failing when it needs
+    // more than `precision` digits.
+    fn exact(&self, scale: u8, precision: u8) -> Result<Numeric, CastError> {
+        let value = match self.value {
+            DataValue::Text(s) => s.trim().parse().map_err(|_| self.conversion())?,
+            _ => match self.number()? {
+                Number::Exact(n) => n,
+                Number::Approximate(f) if f.is_finite() && f.abs() < 1e38 => {
+                    float_digits(f, scale as usize + 1)
+                        .parse()
+                        .map_err(|_| self.overflow())?
+                }
+                Number::Approximate(_) => return Err(self.overflow()),
+            },
+        };
+        rescale(value, scale)
+            .filter(|n| n.mantissa().unsigned_abs() < 10u128.pow(precision as u32))
+            .ok_or_else(|| self.overflow())
+    }
+
+    fn to_decimal(&self, precision: u8, scale: u8) -> Result<DataValue, CastError> {
+        let n = self.exact(scale, precision)?;
+        Ok(
+            Decimal::try_from_i128_with_scale(n.mantissa(), n.scale() as u32)
+                .map_or(DataValue::Numeric(n), DataValue::Decimal),
+        )
+    }
+
+    fn to_money(&self) -> Result<DataValue, CastError> {
+        let (min, max) = match self.target {
+            SqlType::SmallMoney => (i32::MIN as i128, i32::MAX as i128),
+            _ => (i64::MIN as i128, i64::MAX as i128),
+        };
+        let n = match self.value {
+            // Money text may carry a currency sign and group separators.
+            DataValue::Text(s) => {
+                let plain: String = s.trim().replacen('$', "", 1).replace(',', "");
+                Cast {
+                    value: &DataValue::Text(plain),
+                    target: self.target,
+                }
+                .exact(4, 38)
+                .map_err(|_| self.conversion())?
+            }
+            _ => self.exact(4, 38)?,
+        };
+        if n.mantissa() < min || n.mantissa() > max {
+            return Err(self.overflow());
+        }
+        Ok(DataValue::Money(Decimal::from_i128_with_scale(
+            n.mantissa(),
+            4,
+        )))
+    }
+
+    fn to_text(&self) -> Result<DataValue, CastError> {
+        let (length, fixed, unicode) = match self.target {
+            SqlType::Char(n) => (Some(n), true, false),
+            SqlType::VarChar(n) => (n, false, false),
+            SqlType::NChar(n) => (Some(n), true, true),
+            SqlType::NVarChar(n) => (n, false, true),
+            _ => unreachable!(),
+        };
+        let (mut text, truncate) = match self.value {
+            DataValue::Text(s) | DataValue::Xml(s) => (s.clone(), true),
+            // Binary data is read as characters: UTF-16 for Unicode text, one byte
+            // per character otherwise.
+            DataValue::Binary(b) if unicode => {
+                let units: Vec<u16> = b
+                    .chunks(2)
+                    .map(|c| u16::from_le_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                    .collect();
+                (String::from_utf16_lossy(&units), true)
+            }
+            DataValue::Binary(b) => (b.iter().map(|&b| b as char).collect(), true),
+            DataValue::Bool(b) => (if *b { "1" } else { "0" }.to_string(), false),
+            DataValue::Money(d) => (
+                format!(
+                    "{:.2}",
+                    d.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+                ),
+                false,
+            ),
+            DataValue::Time(t) => (time_text(t), false),
+            DataValue::DateTime(dt) => (
+                format!("{} {}", dt.date().format("%Y-%m-%d"), time_text(&dt.time())),
+                false,
+            ),
+            DataValue::DateTimeOffset(dt) => (
+                format!(
+                    "{} {} {}",
+                    dt.date_naive().format("%Y-%m-%d"),
+                    time_text(&dt.time()),
+                    dt.offset()
+                ),
+                false,
+            ),
+            DataValue::Udt { .. } => return Err(self.not_allowed()),
+            other => (text_of(other).unwrap_or_default(), false),
+        };
+        if let Some(n) = length.map(|n| n as usize) {
+            let count = text.chars().count();
+            if count > n {
+                if !truncate {
+                    return Err(self.overflow());
+                }
+                text = text.chars().take(n).collect();
+            } else if fixed {
+                text.extend(std::iter::repeat_n(' ', n - count));
+            }
+        }
+        Ok(DataValue::Text(text))
+    }
+
+    fn to_binary(&self) -> Result<DataValue, CastError> {
+        let (mut bytes, integer) = match self.value {
+            DataValue::Binary(b) | DataValue::Udt { bytes: b, .. } => (b.clone(), false),
+            DataValue::Text(s) => (s.encode_utf16().flat_map(u16::to_le_bytes).collect(), false),
+            DataValue::Bool(v) => (vec![*v as u8], true),
+            DataValue::TinyInt(v) => (vec![*v], true),
+            DataValue::SmallInt(v) => (v.to_be_bytes().to_vec(), true),
+            DataValue::Int(v) => (v.to_be_bytes().to_vec(), true),
+            DataValue::BigInt(v) => (v.to_be_bytes().to_vec(), true),
+            // SQL Server stores the first three groups little-endian.
+            DataValue::Guid(g) => (g.to_bytes_le().to_vec(), false),
+            DataValue::Spatial(s) => (s.to_bytes(), false),
+            DataValue::HierarchyId(h) => (h.to_bytes(), false),
+            _ => return Err(self.not_allowed()),
+        };
+        let (length, fixed) = match self.target {
+            SqlType::Binary(n) => (Some(n as usize), true),
+            SqlType::VarBinary(n) => (n.map(|n| n as usize), false),
+            _ => unreachable!(),
+        };
+        // Integers keep their least significant bytes and pad on the left; other
+        // values are cut and padded on the right.
+        if let Some(n) = length {
+            if bytes.len() > n {
+                if integer {
+                    bytes.drain(..bytes.len() - n);
+                } else {
+                    bytes.truncate(n);
+                }
+            } else if fixed && bytes.len() < n {
+                if integer {
+                    bytes.splice(0..0, std::iter::repeat_n(0, n - bytes.len()));
+                } else {
+                    bytes.resize(n, 0);
+                }
+            }
+        }
+        Ok(DataValue::Binary(bytes))
+    }
+
+    fn to_guid(&self) -> Result<DataValue, CastError> {
+        match self.value {
+            DataValue::Guid(g) => Ok(DataValue::Guid(*g)),
+            DataValue::Text(s) => Uuid::parse_str(s.trim())
+                .map(DataValue::Guid)
+                .map_err(|_| self.conversion()),
+            DataValue::Binary(b) if b.len() >= 16 => {
+                let bytes: [u8; 16] = b[..16].try_into().unwrap();
+                Ok(DataValue::Guid(Uuid::from_bytes_le(bytes)))
+            }
+            DataValue::Binary(_) => Err(self.conversion()),
+            _ => Err(self.not_allowed()),
+        }
+    }
+
+    fn to_xml(&self) -> Result<DataValue, CastError> {
+        match self.value {
+            DataValue::Text(s) | DataValue::Xml(s) => Ok(DataValue::Xml(s.clone())),
+            _ => Err(self.not_allowed()),
+        }
+    }
+
+    fn to_temporal(&self) -> Result<DataValue, CastError> {
+        let source = match self.value {
+            DataValue::Text(s) => parse_temporal(s.trim()).ok_or_else(|| self.conversion())?,
+            DataValue::Date(_)
+            | DataValue::Time(_)
+            | DataValue::DateTime(_)
+            | DataValue::DateTimeOffset(_) => self.value.clone(),
+            // Only `datetime` and `smalldatetime` accept numbers, as days since
+            // 1900-01-01.
+            _ if matches!(self.target, SqlType::DateTime | SqlType::SmallDateTime) => {
+                let days = self.number()?.to_f64();
+                if !(-53_690.0..2_958_464.0).contains(&days) {
+                    return Err(self.overflow());
+                }
+                let micros = (days * 86_400_000_000.0).round() as i64;
+                DataValue::DateTime(epoch() + Duration::microseconds(micros))
+            }
+            _ => return Err(self.not_allowed()),
+        };
+        let local = match &source {
+            DataValue::Date(d) => d.and_time(NaiveTime::MIN),
+            DataValue::Time(t) => epoch().date().and_time(*t),
+            DataValue::DateTime(dt) => *dt,
+            DataValue::DateTimeOffset(dt) => dt.naive_local(),
+            _ => unreachable!(),
+        };
+        Ok(match (self.target, &source) {
+            (SqlType::Date, DataValue::Time(_)) | (SqlType::Time, DataValue::Date(_)) => {
+                return Err(self.not_allowed())
+            }
+            (SqlType::Date, _) => DataValue::Date(local.date()),
+            (SqlType::Time, _) => DataValue::Time(local.time()),
+            (SqlType::DateTime2, _) => DataValue::DateTime(local),
+            (SqlType::DateTimeOffset, DataValue::DateTimeOffset(dt)) => {
+                DataValue::DateTimeOffset(*dt)
+            }
+            (SqlType::DateTimeOffset, _) => {
+                DataValue::DateTimeOffset(local.and_utc().fixed_offset())
+            }
+            (SqlType::DateTime, _) => {
+                let rounded = round_to_ticks(local);
+                let min = NaiveDate::from_ymd_opt(1753, 1, 1)
+                    .unwrap()
+                    .and_time(NaiveTime::MIN);
+                if rounded < min || rounded.year() > 9999 {
+                    return Err(self.overflow());
+                }
+                DataValue::DateTime(rounded)
+            }
+            (_, _) => {
+                let rounded = round_to_minute(local);
+                let max = NaiveDate::from_ymd_opt(2079, 6, 6)
+                    .unwrap()
+                    .and_hms_opt(23, 59, 0)
+                    .unwrap();
+                if rounded < epoch() || rounded > max {
+                    return Err(self.overflow());
+                }
+                DataValue::DateTime(rounded)
+            }
+        })
+    }
+}
+
+// The shortest decimal form of `f`, cut to `places` fractional digits. Formatting
+// with a precision would round half to even first; one digit past the target scale
+// is enough for `rescale` to round half away from zero.
+fn float_digits(f: f64, places: usize) -> String {
+    let mut text = f.to_string();
+    if let Some(point) = text.find('.') {
+        text.truncate((point + 1 + places).min(text.len()));
+    }
+    text
+}
+
+// Rescale to `scale` places, rounding half away from zero.
+fn rescale(n: Numeric, scale: u8) -> Option<Numeric> {
+    let (value, current) = (n.mantissa(), n.scale());
+    if current <= scale {
+        let value = value.checked_mul(10i128.pow((scale - current) as u32))?;
+        return Numeric::new(value, scale);
+    }
+    let unit = 10i128.pow((current - scale) as u32);
+    let (quotient, remainder) = (value / unit, value % unit);
+    let rounded = if remainder.abs() * 2 >= unit {
+        quotient + value.signum()
+    } else {
+        quotient
+    };
+    Numeric::new(rounded, scale)
+}
+
+// SQL Server's zero date for `datetime` and for times without a date.
+fn epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1900, 1, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN)
+}
+
+// `datetime` keeps time in 1/300 second ticks.
+fn round_to_ticks(dt: NaiveDateTime) -> NaiveDateTime {
+    let nanos = (dt.nanosecond() % 1_000_000_000) as i64;
+    let ticks = (nanos * 300 + 500_000_000) / 1_000_000_000;
+    dt.with_nanosecond(0).unwrap() + Duration::nanoseconds(ticks * 10_000_000 / 3)
+}
+
+// `smalldatetime` rounds up from 29.999 seconds.
+fn round_to_minute(dt: NaiveDateTime) -> NaiveDateTime {
+    let millis = dt.second() * 1000 + dt.nanosecond() % 1_000_000_000 / 1_000_000;
+    let minute = dt.with_second(0).unwrap().with_nanosecond(0).unwrap();
+    if millis >= 29_999 {
+        minute + Duration::minutes(1)
+    } else {
+        minute
+    }
+}
+
+// `hh:mm:ss.fffffff`, the default text of `time` and `datetime2`.
+fn time_text(t: &NaiveTime) -> String {
+    let fraction = t.nanosecond() % 1_000_000_000 / 100;
+    format!("{}.{fraction:07}", t.format("%H:%M:%S"))
+}
+
+fn parse_temporal(s: &str) -> Option<DataValue> {
+    if let Some(dt) = DateTime::<FixedOffset>::parse_from_rfc3339(s)
+        .ok()
+        .or_else(|| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f %:z").ok())
+    {
+        return Some(DataValue::DateTimeOffset(dt));
+    }
+    parse_datetime(s).or_else(|| {
+        ["%H:%M:%S%.f", "%H:%M"]
+            .iter()
+            .find_map(|f| NaiveTime::parse_from_str(s, f).ok())
+            .map(DataValue::Time)
+    })
+}
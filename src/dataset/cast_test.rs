@@ -0,0 +1,369 @@
+use chrono::{NaiveDate, NaiveTime};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::*;
+
+fn text(s: &str) -> DataValue {
+    DataValue::Text(s.into())
+}
+
+#[test]
+fn casts_between_numbers() {
+    let decimal = |v: i64, scale: u32| DataValue::Decimal(Decimal::new(v, scale));
+    assert_eq!(
+        decimal(27, 1).cast(SqlType::Int).unwrap(),
+        DataValue::Int(2)
+    );
+    assert_eq!(
+        DataValue::Float(-2.7).cast(SqlType::Int).unwrap(),
+        DataValue::Int(-2)
+    );
+    assert_eq!(
+        DataValue::Money(Decimal::new(25000, 4))
+            .cast(SqlType::Int)
+            .unwrap(),
+        DataValue::Int(3)
+    );
+    assert_eq!(
+        decimal(12345, 3)
+            .cast(SqlType::Decimal {
+                precision: 5,
+                scale: 2
+            })
+            .unwrap(),
+        decimal(1235, 2)
+    );
+    assert_eq!(
+        DataValue::Int(5).cast(SqlType::Bit).unwrap(),
+        DataValue::Bool(true)
+    );
+    assert_eq!(
+        DataValue::Int(300).cast(SqlType::Float).unwrap(),
+        DataValue::Float(300.0)
+    );
+    assert!(matches!(
+        DataValue::Int(256).cast(SqlType::TinyInt),
+        Err(CastError::Overflow { .. })
+    ));
+    assert!(matches!(
+        decimal(1000, 0).cast(SqlType::Decimal {
+            precision: 3,
+            scale: 0
+        }),
+        Err(CastError::Overflow { .. })
+    ));
+    assert!(matches!(
+        DataValue::Float(1e40).cast(SqlType::Real),
+        Err(CastError::Overflow { .. })
+    ));
+    assert!(matches!(
+        DataValue::BigInt(i64::MAX).cast(SqlType::SmallMoney),
+        Err(CastError::Overflow { .. })
+    ));
+    assert_eq!(
+        DataValue::Int(1).cast(SqlType::Decimal {
+            precision: 40,
+            scale: 0
+        }),
+        Err(CastError::InvalidType(SqlType::Decimal {
+            precision: 40,
+            scale: 0
+        }))
+    );
+}
+
+#[test]
+fn casts_text() {
+    assert_eq!(
+        text(" 42 ").cast(SqlType::SmallInt).unwrap(),
+        DataValue::SmallInt(42)
+    );
+    assert_eq!(text("").cast(SqlType::Int).unwrap(), DataValue::Int(0));
+    assert_eq!(
+        text("TRUE").cast(SqlType::Bit).unwrap(),
+        DataValue::Bool(true)
+    );
+    assert_eq!(
+        text("$1,234.5").cast(SqlType::Money).unwrap(),
+        DataValue::Money(Decimal::new(12345000, 4))
+    );
+    assert_eq!(
+        text("2024-06-01 08:30").cast(SqlType::Date).unwrap(),
+        DataValue::Date(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap())
+    );
+    assert_eq!(
+        text("12:30").cast(SqlType::DateTime).unwrap(),
+        DataValue::DateTime(
+            NaiveDate::from_ymd_opt(1900, 1, 1)
+                .unwrap()
+                .and_hms_opt(12, 30, 0)
+                .unwrap()
+        )
+    );
+    let error = text("1.5").cast(SqlType::Int).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "conversion failed when converting \"1.5\" to int"
+    );
+    assert!(matches!(
+        text("99999999999").cast(SqlType::Int),
+        Err(CastError::Overflow { .. })
+    ));
+
+    assert_eq!(
+        text("abcdef").cast(SqlType::VarChar(Some(3))).unwrap(),
+        "abc"
+    );
+    assert_eq!(text("ab").cast(SqlType::NChar(4)).unwrap(), "ab  ");
+    assert_eq!(
+        DataValue::Int(-12).cast(SqlType::NVarChar(None)).unwrap(),
+        "-12"
+    );
+    assert!(matches!(
+        DataValue::Int(123).cast(SqlType::VarChar(Some(2))),
+        Err(CastError::Overflow { .. })
+    ));
+    assert_eq!(
+        DataValue::Money(Decimal::new(123456, 4))
+            .cast(SqlType::VarChar(None))
+            .unwrap(),
+        "12.35"
+    );
+    let at = NaiveDate::from_ymd_opt(2024, 6, 1)
+        .unwrap()
+        .and_hms_milli_opt(8, 30, 0, 5)
+        .unwrap();
+    assert_eq!(
+        DataValue::DateTime(at)
+            .cast(SqlType::VarChar(None))
+            .unwrap(),
+        "2024-06-01 08:30:00.0050000"
+    );
+}
+
+#[test]
+fn casts_dates_and_times() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let at = |h, m, s, ms| DataValue::DateTime(date.and_hms_milli_opt(h, m, s, ms).unwrap());
+    assert_eq!(
+        at(8, 30, 0, 0).cast(SqlType::Date).unwrap(),
+        DataValue::Date(date)
+    );
+    assert_eq!(
+        at(8, 30, 0, 0).cast(SqlType::Time).unwrap(),
+        DataValue::Time(NaiveTime::from_hms_opt(8, 30, 0).unwrap())
+    );
+    assert_eq!(
+        at(8, 30, 29, 998).cast(SqlType::SmallDateTime).unwrap(),
+        at(8, 30, 0, 0)
+    );
+    assert_eq!(
+        at(8, 30, 29, 999).cast(SqlType::SmallDateTime).unwrap(),
+        at(8, 31, 0, 0)
+    );
+    let DataValue::DateTime(rounded) = at(8, 30, 0, 2).cast(SqlType::DateTime).unwrap() else {
+        panic!("expected a datetime");
+    };
+    assert_eq!(rounded.and_utc().timestamp_subsec_nanos(), 3_333_333);
+    assert_eq!(
+        DataValue::Int(1).cast(SqlType::DateTime).unwrap(),
+        DataValue::DateTime(
+            NaiveDate::from_ymd_opt(1900, 1, 2)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        )
+    );
+    assert!(matches!(
+        DataValue::Date(NaiveDate::from_ymd_opt(1700, 1, 1).unwrap()).cast(SqlType::DateTime),
+        Err(CastError::Overflow { .. })
+    ));
+    assert_eq!(
+        DataValue::Date(date).cast(SqlType::Int),
+        Err(CastError::NotAllowed {
+            from: "date",
+            target: SqlType::Int
+        })
+    );
+    assert!(DataValue::Date(date).cast(SqlType::Time).is_err());
+}
+
+#[test]
+fn casts_binary_and_guids() {
+    assert_eq!(
+        DataValue::Int(1).cast(SqlType::Binary(6)).unwrap(),
+        DataValue::Binary(vec![0, 0, 0, 0, 0, 1])
+    );
+    assert_eq!(
+        DataValue::Int(0x0102_0304)
+            .cast(SqlType::VarBinary(Some(2)))
+            .unwrap(),
+        DataValue::Binary(vec![3, 4])
+    );
+    assert_eq!(
+        DataValue::Binary(vec![0, 0, 1, 0])
+            .cast(SqlType::Int)
+            .unwrap(),
+        DataValue::Int(256)
+    );
+    assert_eq!(
+        text("AB").cast(SqlType::Binary(6)).unwrap(),
+        DataValue::Binary(vec![0x41, 0, 0x42, 0, 0, 0])
+    );
+    assert_eq!(
+        DataValue::Binary(vec![0x41, 0, 0x42, 0])
+            .cast(SqlType::NVarChar(None))
+            .unwrap(),
+        "AB"
+    );
+
+    let guid = Uuid::parse_str("6F9619FF-8B86-D011-B42D-00C04FC964FF").unwrap();
+    let bytes = DataValue::Guid(guid)
+        .cast(SqlType::VarBinary(None))
+        .unwrap();
+    assert_eq!(
+        bytes,
+        DataValue::Binary(vec![
+            0xFF, 0x19, 0x96, 0x6F, 0x86, 0x8B, 0x11, 0xD0, 0xB4, 0x2D, 0x00, 0xC0, 0x4F, 0xC9,
+            0x64, 0xFF
+        ])
+    );
+    assert_eq!(
+        bytes.cast(SqlType::UniqueIdentifier).unwrap(),
+        DataValue::Guid(guid)
+    );
+    assert_eq!(
+        text("{6f9619ff-8b86-d011-b42d-00c04fc964ff}")
+            .cast(SqlType::UniqueIdentifier)
+            .unwrap(),
+        DataValue::Guid(guid)
+    );
+    assert!(DataValue::Null
+        .cast(SqlType::UniqueIdentifier)
+        .unwrap()
+        .is_null());
+}
+
+#[test]
+fn reports_overflow_when_narrowing() {
+    let decimal = |v: i64, scale: u32| DataValue::Decimal(Decimal::new(v, scale));
+    assert_eq!(
+        DataValue::BigInt(255).cast(SqlType::TinyInt).unwrap(),
+        DataValue::TinyInt(255)
+    );
+    for value in [256, -1] {
+        assert!(matches!(
+            DataValue::BigInt(value).cast(SqlType::TinyInt),
+            Err(CastError::Overflow { .. })
+        ));
+    }
+    assert!(matches!(
+        DataValue::BigInt(i64::MAX).cast(SqlType::Int),
+        Err(CastError::Overflow { .. })
+    ));
+    assert!(matches!(
+        DataValue::Int(40_000).cast(SqlType::SmallInt),
+        Err(CastError::Overflow { .. })
+    ));
+    // The fraction is dropped before the range is checked.
+    assert!(matches!(
+        decimal(21474836485, 1).cast(SqlType::Int),
+        Err(CastError::Overflow { .. })
+    ));
+    assert_eq!(
+        decimal(-21474836489, 1).cast(SqlType::Int).unwrap(),
+        DataValue::Int(i32::MIN)
+    );
+    assert!(matches!(
+        DataValue::Float(1e20).cast(SqlType::BigInt),
+        Err(CastError::Overflow { .. })
+    ));
+    assert!(matches!(
+        DataValue::Money(Decimal::new(214749, 0)).cast(SqlType::SmallMoney),
+        Err(CastError::Overflow { .. })
+    ));
+    let error = DataValue::BigInt(300).cast(SqlType::TinyInt).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "arithmetic overflow converting \"300\" to tinyint"
+    );
+}
+
+#[test]
+fn truncates_to_integers_and_rounds_to_decimals() {
+    let decimal = |v: i64, scale: u32| DataValue::Decimal(Decimal::new(v, scale));
+    let decimal_type = |precision, scale| SqlType::Decimal { precision, scale };
+    // Exact and approximate numbers lose their fraction converting to an integer...
+    assert_eq!(
+        decimal(29, 1).cast(SqlType::Int).unwrap(),
+        DataValue::Int(2)
+    );
+    assert_eq!(
+        decimal(-29, 1).cast(SqlType::BigInt).unwrap(),
+        DataValue::BigInt(-2)
+    );
+    assert_eq!(
+        DataValue::Float(9.99).cast(SqlType::TinyInt).unwrap(),
+        DataValue::TinyInt(9)
+    );
+    // ...while money rounds, as do conversions to a smaller scale.
+    assert_eq!(
+        DataValue::Money(Decimal::new(-25000, 4))
+            .cast(SqlType::Int)
+            .unwrap(),
+        DataValue::Int(-3)
+    );
+    assert_eq!(
+        decimal(1245, 3).cast(decimal_type(5, 2)).unwrap(),
+        decimal(125, 2)
+    );
+    assert_eq!(
+        decimal(-1245, 3).cast(decimal_type(5, 2)).unwrap(),
+        decimal(-125, 2)
+    );
+    assert_eq!(
+        DataValue::Float(2.5).cast(decimal_type(2, 0)).unwrap(),
+        decimal(3, 0)
+    );
+    assert_eq!(
+        DataValue::Float(2.675).cast(decimal_type(3, 2)).unwrap(),
+        decimal(268, 2)
+    );
+    assert_eq!(
+        DataValue::Float(1e-300).cast(decimal_type(10, 2)).unwrap(),
+        decimal(0, 2)
+    );
+    assert_eq!(text("2.5").cast(decimal_type(2, 0)).unwrap(), decimal(3, 0));
+    assert_eq!(
+        decimal(123456, 5).cast(SqlType::Money).unwrap(),
+        DataValue::Money(Decimal::new(12346, 4))
+    );
+}
+
+#[test]
+fn rejects_invalid_text_for_dates_and_guids() {
+    for (value, target) in [
+        ("2024-02-30", SqlType::Date),
+        ("yesterday", SqlType::DateTime),
+        ("25:00", SqlType::Time),
+        ("2024-06-01 08:30 +25:00", SqlType::DateTimeOffset),
+        ("6F9619FF-8B86-D011-B42D", SqlType::UniqueIdentifier),
+        (
+            "6F9619FF-8B86-D011-B42D-00C04FC964FG",
+            SqlType::UniqueIdentifier,
+        ),
+    ] {
+        assert!(
+            matches!(text(value).cast(target), Err(CastError::Conversion { .. })),
+            "{value} as {target:?}"
+        );
+    }
+    assert_eq!(
+        text("2024-02-30")
+            .cast(SqlType::Date)
+            .unwrap_err()
+            .to_string(),
+        "conversion failed when converting \"2024-02-30\" to date"
+    );
+}
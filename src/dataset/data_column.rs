@@ -5,3 +5,25 @@ pub struct DataColumn {
     pub size: Option<u32>,
     pub nullable: bool,
 }
+
+impl DataColumn {
+    /// A nullable column without a size.
+    pub fn new(name: &str, sql_type: &str) -> Self {
+        Self {
+            name: name.into(),
+            sql_type: sql_type.into(),
+            size: None,
+            nullable: true,
+        }
+    }
+
+    pub fn with_size(mut self, size: u32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+}
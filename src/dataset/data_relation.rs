@@ -0,0 +1,120 @@
+use super::key::{key_of, KeyPart};
+use super::{DataError, DataRow, DataSet};
+
+/// What happens to child rows when the parent key they reference is deleted or
+/// changed, like SQL Server's `ON DELETE` and `ON UPDATE` actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rule {
+    /// Refuse the change while child rows reference the key.
+    #[default]
+    NoAction,
+    /// Delete the child rows, or change their foreign key to the new value.
+    Cascade,
+    /// Set the foreign key columns of the child rows to NULL.
+    SetNull,
+}
+
+/// A parent/child link between two tables of a [`DataSet`], like ADO.NET's
+/// `DataRelation`. Created with [`DataSet::add_relation`].
+///
+/// A relation is only used for navigation until it is enforced. Enforced relations
+/// act as a foreign key for [`DataSet::add_row`], [`DataSet::set_value`] and
+/// [`DataSet::delete_row`]: child rows must reference an existing parent, and the
+/// delete and update rules decide what happens to children of a changed parent.
+/// Rows already in the tables are not re-checked, and changes made directly on a
+/// table bypass the relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRelation {
+    pub name: String,
+    pub parent_table: String,
+    pub parent_columns: Vec<String>,
+    pub child_table: String,
+    pub child_columns: Vec<String>,
+    pub enforced: bool,
+    pub delete_rule: Rule,
+    pub update_rule: Rule,
+}
+
+impl DataRelation {
+    /// Check foreign keys with [`Rule::NoAction`] unless other rules are set.
+    pub fn enforce(&mut self) -> &mut Self {
+        self.enforced = true;
+        self
+    }
+
+    /// Enforce the relation and apply `rule` when a parent row is deleted.
+    pub fn on_delete(&mut self, rule: Rule) -> &mut Self {
+        self.delete_rule = rule;
+        self.enforce()
+    }
+
+    /// Enforce the relation and apply `rule` when a parent key changes.
+    pub fn on_update(&mut self, rule: Rule) -> &mut Self {
+        self.update_rule = rule;
+        self.enforce()
+    }
+
+    /// Live rows of the child table that reference `parent`.
+    pub fn child_rows<'a>(
+        &self,
+        ds: &'a DataSet,
+        parent: &DataRow,
+    ) -> Result<Vec<&'a DataRow>, DataError> {
+        let table = ds.table(&self.child_table)?;
+        Ok(self
+            .child_indices(ds, parent)?
+            .into_iter()
+            .map(|i| &table.rows[i])
+            .collect())
+    }
+
+    /// The parent row `child` references, or `None` when its foreign key is NULL or
+    /// has no match.
+    pub fn parent_row<'a>(
+        &self,
+        ds: &'a DataSet,
+        child: &DataRow,
+    ) -> Result<Option<&'a DataRow>, DataError> {
+        let table = ds.table(&self.parent_table)?;
+        let key = key_of(child, &self.child_columns);
+        if key.contains(&KeyPart::Null) {
+            return Ok(None);
+        }
+        Ok(table
+            .find_by(&self.parent_columns, &key)
+            .map(|i| &table.rows[i]))
+    }
+
+    pub(crate) fn child_indices(
+        &self,
+        ds: &DataSet,
+        parent: &DataRow,
+    ) -> Result<Vec<usize>, DataError> {
+        let table = ds.table(&self.child_table)?;
+        let key = key_of(parent, &self.parent_columns);
+        if key.contains(&KeyPart::Null) {
+            return Ok(Vec::new());
+        }
+        Ok(table.matching(&self.child_columns, &key))
+    }
+
+    // Fails when `child` holds a non-NULL foreign key without a parent row.
+    pub(crate) fn check_parent(&self, ds: &DataSet, child: &DataRow) -> Result<(), DataError> {
+        match self.parent_row(ds, child)? {
+            None if !key_of(child, &self.child_columns).contains(&KeyPart::Null) => {
+                Err(self.violation(child, &self.child_columns))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn violation(&self, row: &DataRow, columns: &[String]) -> DataError {
+        DataError::ForeignKeyViolation {
+            relation: self.name.clone(),
+            key: columns
+                .iter()
+                .map(|c| row.get(c).cloned().unwrap_or_default())
+                .collect(),
+        }
+    }
+}
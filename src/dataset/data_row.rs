@@ -1,17 +1,220 @@
 use std::collections::HashMap;
 use std::ops::Index;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-use super::{DataCell, DataValue};
+use super::{DataCell, DataError, DataSet, DataValue};
 
-#[derive(Debug, Clone, PartialEq, Default)]
+/// Change-tracking state of a [`DataRow`], mirroring ADO.NET's `DataRowState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RowState {
+    /// Loaded from the database or accepted; no pending changes.
+    #[default]
+    Unchanged,
+    /// Added to the table since the last `accept_changes`.
+    Added,
+    /// At least one value changed since the last `accept_changes`.
+    Modified,
+    /// Deleted since the last `accept_changes`; still kept to generate a DELETE.
+    Deleted,
+}
+
+// Shared by a table and its rows: the columns of the table's key indexes and a count
+// of changes to them made through `DataRow` methods, so the table notices a key
+// changed behind its back and re-indexes before trusting its indexes again.
+#[derive(Debug, Default)]
+pub(crate) struct KeyWatch {
+    columns: Vec<String>,
+    changes: AtomicU64,
+}
+
+impl KeyWatch {
+    pub(crate) fn new(columns: Vec<String>) -> Self {
+        Self {
+            columns,
+            changes: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub(crate) fn changes(&self) -> u64 {
+        self.changes.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct DataRow {
     pub cells: HashMap<String, DataCell>,
+    state: RowState,
+    original: Option<HashMap<String, DataCell>>,
+    watch: Option<Arc<KeyWatch>>,
+}
+
+impl DataRow {
+    pub fn state(&self) -> RowState {
+        self.state
+    }
+
+    /// Current value of a column, if present.
+    pub fn get(&self, column: &str) -> Option<&DataValue> {
+        self.cells.get(column).map(|c| &c.value)
+    }
+
+    /// Value of a column as of the last `accept_changes`. For unchanged and added
+    /// rows this is the current value.
+    pub fn original(&self, column: &str) -> Option<&DataValue> {
+        match &self.original {
+            Some(original) => original.get(column).map(|c| &c.value),
+            None => self.get(column),
+        }
+    }
+
+    /// Set a column value, recording the original values the first time an
+    /// unchanged row is modified.
+    pub fn set<T: Into<DataValue>>(&mut self, column: &str, value: T) -> Result<(), DataError> {
+        match self.state {
+            RowState::Deleted => return Err(DataError::DeletedRow),
+            RowState::Unchanged => {
+                self.original = Some(self.cells.clone());
+                self.state = RowState::Modified;
+            }
+            RowState::Added | RowState::Modified => {}
+        }
+        self.cells
+            .insert(column.to_string(), DataCell::new(value.into()));
+        self.touch(|c| c == column);
+        Ok(())
+    }
+
+    /// Mark an unchanged row as added, so it is inserted on the next update.
+    pub fn set_added(&mut self) {
+        if self.state == RowState::Unchanged {
+            self.state = RowState::Added;
+        }
+    }
+
+    /// Mark an unchanged row as modified without changing any value.
+    pub fn set_modified(&mut self) {
+        if self.state == RowState::Unchanged {
+            self.original = Some(self.cells.clone());
+            self.state = RowState::Modified;
+        }
+    }
+
+    /// Rows of the child table that reference this row through `relation`.
+    pub fn children<'a>(
+        &self,
+        ds: &'a DataSet,
+        relation: &str,
+    ) -> Result<Vec<&'a DataRow>, DataError> {
+        ds.relation(relation)
+            .ok_or_else(|| DataError::RelationNotFound(relation.into()))?
+            .child_rows(ds, self)
+    }
+
+    /// The row of the parent table this row references through `relation`.
+    pub fn parent<'a>(
+        &self,
+        ds: &'a DataSet,
+        relation: &str,
+    ) -> Result<Option<&'a DataRow>, DataError> {
+        ds.relation(relation)
+            .ok_or_else(|| DataError::RelationNotFound(relation.into()))?
+            .parent_row(ds, self)
+    }
+
+    // Drop a column from the current and original values.
+    pub(crate) fn remove_column(&mut self, column: &str) {
+        self.touch(|c| c == column);
+        self.cells.remove(column);
+        if let Some(original) = &mut self.original {
+            original.remove(column);
+        }
+    }
+
+    // Copy the columns of `other` this row lacks, with their original values.
+    pub(crate) fn fill_missing(&mut self, other: &DataRow) {
+        self.touch(|c| !self.cells.contains_key(c) && other.cells.contains_key(c));
+        for (name, cell) in &other.cells {
+            if self.cells.contains_key(name) {
+                continue;
+            }
+            self.cells.insert(name.clone(), cell.clone());
+            if let Some(original) = &mut self.original {
+                let old = other.original.as_ref().and_then(|o| o.get(name));
+                original.insert(name.clone(), old.unwrap_or(cell).clone());
+            }
+        }
+    }
+
+    pub(crate) fn watch(&mut self, watch: &Arc<KeyWatch>) {
+        self.watch = Some(Arc::clone(watch));
+    }
+
+    // Record a change to the watched key columns `changed` selects.
+    fn touch(&self, changed: impl Fn(&str) -> bool) {
+        if let Some(watch) = &self.watch {
+            if watch.columns.iter().any(|c| changed(c)) {
+                watch.changes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub(crate) fn mark_deleted(&mut self) {
+        if self.state == RowState::Unchanged {
+            self.original = Some(self.cells.clone());
+        }
+        self.state = RowState::Deleted;
+    }
+
+    /// Commit pending changes: the current values become the original values.
+    pub fn accept_changes(&mut self) {
+        self.original = None;
+        self.state = RowState::Unchanged;
+    }
+
+    /// Discard pending changes and restore the original values.
+    pub fn reject_changes(&mut self) {
+        if let Some(original) = self.original.take() {
+            self.cells = original;
+            self.touch(|_| true);
+        }
+        self.state = RowState::Unchanged;
+    }
+}
+
+// A copy is detached from the table, so changing it does not affect the table's
+// indexes until it is stored back.
+impl Clone for DataRow {
+    fn clone(&self) -> Self {
+        Self {
+            cells: self.cells.clone(),
+            state: self.state,
+            original: self.original.clone(),
+            watch: None,
+        }
+    }
+}
+
+// Rows are equal when their values and change state match; the table they belong to
+// is ignored.
+impl PartialEq for DataRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells && self.state == other.state && self.original == other.original
+    }
 }
 
 impl Index<&str> for DataRow {
     type Output = DataValue;
 
     fn index(&self, column: &str) -> &Self::Output {
-        &self.cells.get(column).expect(&format!("Column '{}' not found", column)).value
+        &self
+            .cells
+            .get(column)
+            .unwrap_or_else(|| panic!("Column '{}' not found", column))
+            .value
     }
 }
@@ -1,16 +1,409 @@
 use std::collections::HashMap;
 
-use super::DataTable;
+use super::data_relation::{DataRelation, Rule};
+use super::key::key_of;
+use super::render::render_set;
+use super::{
+    DataError, DataRow, DataTable, DataValue, ExpressionError, MissingSchemaAction, RowState,
+    TableFormat,
+};
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct DataSet {
     pub tables: HashMap<String, DataTable>,
+    relations: Vec<DataRelation>,
 }
 
+// A change to one row: the table, row index and the new values of some columns.
+type RowChange = (String, usize, Vec<String>, Vec<DataValue>);
+
 impl DataSet {
     pub fn new() -> Self {
-        Self {
-            tables: HashMap::new(),
+        Self::default()
+    }
+
+    /// Relations in the order they were added.
+    pub fn relations(&self) -> &[DataRelation] {
+        &self.relations
+    }
+
+    pub fn relation(&self, name: &str) -> Option<&DataRelation> {
+        self.relations.iter().find(|r| r.name == name)
+    }
+
+    /// The relation called `name`, to change its rules.
+    pub fn relation_mut(&mut self, name: &str) -> Option<&mut DataRelation> {
+        self.relations.iter_mut().find(|r| r.name == name)
+    }
+
+    /// Relate `parent` (table, column) to `child` (table, column). The parent column
+    /// gets a unique constraint unless it already is a key. The returned relation can
+    /// be enforced with [`DataRelation::enforce`], [`DataRelation::on_delete`] or
+    /// [`DataRelation::on_update`].
+    pub fn add_relation(
+        &mut self,
+        name: &str,
+        parent: (&str, &str),
+        child: (&str, &str),
+    ) -> Result<&mut DataRelation, DataError> {
+        if self.relation(name).is_some() {
+            return Err(DataError::DuplicateRelation(name.into()));
+        }
+        self.table(child.0)?.check_columns(&[child.1])?;
+        let parent_table = self.table_mut(parent.0)?;
+        parent_table.check_columns(&[parent.1])?;
+        let parent_columns = vec![parent.1.to_string()];
+        if !parent_table.has_unique(&parent_columns) {
+            parent_table.add_unique_constraint(&[parent.1])?;
+        }
+        self.relations.push(DataRelation {
+            name: name.into(),
+            parent_table: parent.0.into(),
+            parent_columns,
+            child_table: child.0.into(),
+            child_columns: vec![child.1.into()],
+            enforced: false,
+            delete_rule: Rule::NoAction,
+            update_rule: Rule::NoAction,
+        });
+        Ok(self.relations.last_mut().expect("relation was just added"))
+    }
+
+    /// Add a row to `table` like [`DataTable::add_row`],
+    /// checking the foreign keys of enforced relations.
+    pub fn add_row(&mut self, table: &str, row: DataRow) -> Result<(), DataError> {
+        for relation in self.enforced().filter(|r| r.child_table == table) {
+            relation.check_parent(self, &row)?;
+        }
+        let refresh = self.related_computed(&[table]);
+        if refresh.is_empty() {
+            return self.table_mut(table)?.add_row(row);
+        }
+        let mut tables = vec![table];
+        tables.extend(refresh.iter().map(String::as_str));
+        self.apply(&tables, |ds| {
+            ds.table_mut(table)?.add_row(row)?;
+            Ok(ds.refresh_tables(&refresh)?)
+        })
+    }
+
+    /// Set a value like [`DataTable::set_value`], checking
+    /// foreign keys and applying the update rule of enforced relations when a parent
+    /// key changes. Nothing is changed when a relation forbids the change or one of
+    /// the cascaded changes breaks a constraint.
+    pub fn set_value<T: Into<DataValue>>(
+        &mut self,
+        table: &str,
+        index: usize,
+        column: &str,
+        value: T,
+    ) -> Result<(), DataError> {
+        let value = value.into();
+        let current = self
+            .table(table)?
+            .rows
+            .get(index)
+            .ok_or(DataError::RowOutOfRange(index))?;
+        let mut updated = current.clone();
+        updated.set(column, value.clone())?;
+        for relation in self
+            .enforced()
+            .filter(|r| r.child_table == table && r.child_columns.iter().any(|c| c == column))
+        {
+            relation.check_parent(self, &updated)?;
+        }
+        let mut changes = vec![(
+            table.to_string(),
+            index,
+            vec![column.to_string()],
+            vec![value],
+        )];
+        self.plan_update(table, current, &updated, &mut changes)?;
+        let mut tables: Vec<&str> = changes.iter().map(|(table, ..)| table.as_str()).collect();
+        let refresh = self.related_computed(&tables);
+        tables.extend(refresh.iter().map(String::as_str));
+        self.apply(&tables, |ds| {
+            ds.set_values(&changes)?;
+            Ok(ds.refresh_tables(&refresh)?)
+        })
+    }
+
+    /// Delete a row like [`DataTable::delete_row`],
+    /// applying the delete rule of enforced relations to its children. Nothing is
+    /// changed when a [`Rule::NoAction`] relation still has children or setting a
+    /// child's foreign key to NULL breaks a constraint.
+    pub fn delete_row(&mut self, table: &str, index: usize) -> Result<(), DataError> {
+        let mut deletes = Vec::new();
+        let mut nulls = Vec::new();
+        self.plan_delete(table, index, &mut deletes, &mut nulls)?;
+        nulls.retain(|(table, index, _, _)| !deletes.contains(&(table.clone(), *index)));
+        // Highest index first, since removing added rows shifts later ones.
+        deletes.sort();
+        deletes.dedup();
+        let mut tables: Vec<&str> = deletes
+            .iter()
+            .map(|(table, _)| table.as_str())
+            .chain(nulls.iter().map(|(table, ..)| table.as_str()))
+            .collect();
+        let refresh = self.related_computed(&tables);
+        tables.extend(refresh.iter().map(String::as_str));
+        self.apply(&tables, |ds| {
+            ds.set_values(&nulls)?;
+            for (table, index) in deletes.iter().rev() {
+                ds.table_mut(table)?.delete_row(*index)?;
+            }
+            Ok(ds.refresh_tables(&refresh)?)
+        })
+    }
+
+    /// Render every table in name order; see [`TableFormat`](super::TableFormat).
+    pub fn render(&self, format: &TableFormat) -> String {
+        render_set(self, format)
+    }
+
+    /// Merge `other` into this data set, like ADO.NET's `DataSet.Merge`. Tables are
+    /// matched by name and their rows merged with [`DataTable::merge`]; `action`
+    /// decides what happens to tables, columns and relations this data set lacks.
+    pub fn merge(&mut self, other: &DataSet, action: MissingSchemaAction) -> Result<(), DataError> {
+        if action == MissingSchemaAction::Error {
+            for (name, table) in &other.tables {
+                let own = self
+                    .tables
+                    .get(name)
+                    .ok_or_else(|| DataError::MissingSchema(name.clone()))?;
+                if let Some(column) = own.missing_columns(table).first() {
+                    return Err(DataError::MissingSchema(column.name.clone()));
+                }
+            }
+            if let Some(relation) = other
+                .relations
+                .iter()
+                .find(|r| self.relation(&r.name).is_none())
+            {
+                return Err(DataError::MissingSchema(relation.name.clone()));
+            }
+        }
+        let mut names: Vec<&String> = other.tables.keys().collect();
+        names.sort();
+        for name in names {
+            let table = &other.tables[name];
+            match self.tables.get_mut(name) {
+                Some(own) => own.merge(table, action)?,
+                None if action == MissingSchemaAction::Add => {
+                    self.tables.insert(name.clone(), table.clone());
+                }
+                None => {}
+            }
+        }
+        if action == MissingSchemaAction::Add {
+            for relation in &other.relations {
+                if self.relation(&relation.name).is_none() {
+                    self.relations.push(relation.clone());
+                }
+            }
+        }
+        Ok(self.refresh_computed()?)
+    }
+
+    /// Recompute the computed columns of every table, resolving aggregates over child
+    /// rows and `Parent` references. `add_row`, `set_value` and `delete_row` do this
+    /// for the tables they change and the tables related to them.
+    pub fn refresh_computed(&mut self) -> Result<(), ExpressionError> {
+        let names: Vec<String> = self
+            .tables
+            .iter()
+            .filter(|(_, t)| has_computed(t))
+            .map(|(name, _)| name.clone())
+            .collect();
+        self.refresh_tables(&names)
+    }
+
+    // Tables with computed columns that `tables` are related to, directly or through
+    // other relations, including `tables` themselves.
+    fn related_computed(&self, tables: &[&str]) -> Vec<String> {
+        let mut related: Vec<&str> = tables.to_vec();
+        let mut i = 0;
+        while i < related.len() {
+            for relation in &self.relations {
+                for (from, to) in [
+                    (&relation.parent_table, &relation.child_table),
+                    (&relation.child_table, &relation.parent_table),
+                ] {
+                    if from == related[i] && !related.contains(&to.as_str()) {
+                        related.push(to);
+                    }
+                }
+            }
+            i += 1;
+        }
+        let mut names: Vec<String> = related
+            .into_iter()
+            .filter(|name| self.tables.get(*name).is_some_and(has_computed))
+            .map(String::from)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn refresh_tables(&mut self, names: &[String]) -> Result<(), ExpressionError> {
+        // Columns may read computed columns of related tables, so repeat until values
+        // settle, once per table at most.
+        for _ in 0..names.len() {
+            let mut changed = false;
+            for name in names {
+                let values = self.tables[name].computed_values(Some(self))?;
+                if let Some(table) = self.tables.get_mut(name) {
+                    changed |= table.apply_computed(values);
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn table(&self, name: &str) -> Result<&DataTable, DataError> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| DataError::TableNotFound(name.into()))
+    }
+
+    pub(crate) fn table_mut(&mut self, name: &str) -> Result<&mut DataTable, DataError> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| DataError::TableNotFound(name.into()))
+    }
+
+    fn enforced(&self) -> impl Iterator<Item = &DataRelation> {
+        self.relations.iter().filter(|r| r.enforced)
+    }
+
+    // Collect the child rows a change of `current` to `updated` cascades to, failing
+    // before anything changes when a relation forbids it.
+    fn plan_update(
+        &self,
+        table: &str,
+        current: &DataRow,
+        updated: &DataRow,
+        changes: &mut Vec<RowChange>,
+    ) -> Result<(), DataError> {
+        for relation in self.enforced().filter(|r| r.parent_table == table) {
+            let columns = &relation.parent_columns;
+            if key_of(current, columns) == key_of(updated, columns) {
+                continue;
+            }
+            let children = relation.child_indices(self, current)?;
+            if children.is_empty() {
+                continue;
+            }
+            let values: Vec<DataValue> = match relation.update_rule {
+                Rule::NoAction => return Err(relation.violation(current, columns)),
+                Rule::Cascade => columns
+                    .iter()
+                    .map(|c| updated.get(c).cloned().unwrap_or_default())
+                    .collect(),
+                Rule::SetNull => vec![DataValue::Null; relation.child_columns.len()],
+            };
+            let child_table = self.table(&relation.child_table)?;
+            for child in children {
+                let row = &child_table.rows[child];
+                let mut changed = row.clone();
+                for (column, value) in relation.child_columns.iter().zip(&values) {
+                    changed.set(column, value.clone())?;
+                }
+                changes.push((
+                    relation.child_table.clone(),
+                    child,
+                    relation.child_columns.clone(),
+                    values.clone(),
+                ));
+                self.plan_update(&relation.child_table, row, &changed, changes)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Collect the rows a delete affects, failing before anything changes when a
+    // relation forbids it.
+    fn plan_delete(
+        &self,
+        table: &str,
+        index: usize,
+        deletes: &mut Vec<(String, usize)>,
+        nulls: &mut Vec<RowChange>,
+    ) -> Result<(), DataError> {
+        if deletes.iter().any(|(t, i)| t == table && *i == index) {
+            return Ok(());
+        }
+        let row = self
+            .table(table)?
+            .rows
+            .get(index)
+            .ok_or(DataError::RowOutOfRange(index))?;
+        if row.state() == RowState::Deleted {
+            return Err(DataError::DeletedRow);
+        }
+        deletes.push((table.to_string(), index));
+        for relation in self.enforced().filter(|r| r.parent_table == table) {
+            let children = relation.child_indices(self, row)?;
+            if children.is_empty() {
+                continue;
+            }
+            match relation.delete_rule {
+                Rule::NoAction => return Err(relation.violation(row, &relation.parent_columns)),
+                Rule::Cascade => {
+                    for child in children {
+                        self.plan_delete(&relation.child_table, child, deletes, nulls)?;
+                    }
+                }
+                Rule::SetNull => {
+                    for child in children {
+                        nulls.push((
+                            relation.child_table.clone(),
+                            child,
+                            relation.child_columns.clone(),
+                            vec![DataValue::Null; relation.child_columns.len()],
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_values(&mut self, changes: &[RowChange]) -> Result<(), DataError> {
+        for (table, index, columns, values) in changes {
+            let table = self.table_mut(table)?;
+            for (column, value) in columns.iter().zip(values) {
+                table.set_value(*index, column, value.clone())?;
+            }
         }
+        Ok(())
     }
+
+    // Run `apply` and put back `tables` when it fails, so a constraint violation part
+    // way through leaves the data set unchanged.
+    fn apply<F>(&mut self, tables: &[&str], apply: F) -> Result<(), DataError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), DataError>,
+    {
+        let mut names = tables.to_vec();
+        names.sort();
+        names.dedup();
+        let saved: Vec<(String, DataTable)> = names
+            .into_iter()
+            .filter_map(|name| Some((name.to_string(), self.tables.get(name)?.clone())))
+            .collect();
+        let result = apply(self);
+        if result.is_err() {
+            self.tables.extend(saved);
+        }
+        result
+    }
+}
+
+fn has_computed(table: &DataTable) -> bool {
+    table.columns.iter().any(|c| table.is_computed(&c.name))
 }
@@ -1,5 +1,8 @@
 use super::*;
-use crate::dataset::{DataCell, DataColumn, DataRow, DataValue};
+use crate::dataset::{
+    DataCell, DataColumn, DataError, DataRow, DataValue, Expression, ExpressionError,
+    MissingSchemaAction, RowState, Rule,
+};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 use chrono::{DateTime, NaiveDate, NaiveTime};
@@ -278,4 +281,258 @@ fn null_comparison() {
     let mut ds = DataSet::new();
     ds.tables.insert(table.name.clone(), table);
     assert!(ds.tables["table1"][0]["null_col"].is_null());
-}
\ No newline at end of file
+}
+
+fn row(values: &[(&str, DataValue)]) -> DataRow {
+    let mut row = DataRow::default();
+    for (name, value) in values {
+        row.cells
+            .insert(name.to_string(), DataCell::new(value.clone()));
+    }
+    row
+}
+
+fn orders_with_lines() -> DataSet {
+    let mut orders = DataTable::new("orders");
+    for id in [1, 2] {
+        orders.rows.push(row(&[("id", id.into())]));
+    }
+    let mut lines = DataTable::new("lines");
+    for (id, order_id) in [(10, 1), (11, 1), (12, 2)] {
+        lines
+            .rows
+            .push(row(&[("id", id.into()), ("order_id", order_id.into())]));
+    }
+    let mut ds = DataSet::new();
+    ds.tables.insert(orders.name.clone(), orders);
+    ds.tables.insert(lines.name.clone(), lines);
+    ds
+}
+
+#[test]
+fn navigates_relations() {
+    let mut ds = orders_with_lines();
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap();
+    assert!(matches!(
+        ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id")),
+        Err(DataError::DuplicateRelation(_))
+    ));
+
+    let order = &ds.tables["orders"][0];
+    let children = order.children(&ds, "order_lines").unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[1]["id"], 11);
+
+    let line = &ds.tables["lines"][2];
+    assert_eq!(line.parent(&ds, "order_lines").unwrap().unwrap()["id"], 2);
+    assert_eq!(
+        line.parent(&ds, "missing"),
+        Err(DataError::RelationNotFound("missing".into()))
+    );
+}
+
+#[test]
+fn enforces_foreign_keys() {
+    let mut ds = orders_with_lines();
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap()
+        .enforce();
+
+    let orphan = row(&[("id", 13.into()), ("order_id", 9.into())]);
+    assert!(matches!(
+        ds.add_row("lines", orphan),
+        Err(DataError::ForeignKeyViolation { .. })
+    ));
+    ds.add_row(
+        "lines",
+        row(&[("id", 14.into()), ("order_id", DataValue::Null)]),
+    )
+    .unwrap();
+    assert!(ds.set_value("lines", 0, "order_id", 9).is_err());
+    assert!(ds.delete_row("orders", 0).is_err());
+    assert!(ds.set_value("orders", 0, "id", 5).is_err());
+    assert!(!ds.tables["orders"].has_changes());
+}
+
+#[test]
+fn applies_cascade_rules() {
+    let mut ds = orders_with_lines();
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap()
+        .on_delete(Rule::Cascade)
+        .on_update(Rule::Cascade);
+
+    ds.set_value("orders", 0, "id", 5).unwrap();
+    assert_eq!(ds.tables["lines"][0]["order_id"], 5);
+    assert_eq!(ds.tables["lines"][1]["order_id"], 5);
+
+    ds.delete_row("orders", 0).unwrap();
+    let lines = &ds.tables["lines"];
+    assert_eq!(lines[0].state(), RowState::Deleted);
+    assert_eq!(lines[1].state(), RowState::Deleted);
+    assert_eq!(lines[2].state(), RowState::Unchanged);
+
+    ds.relation_mut("order_lines")
+        .unwrap()
+        .on_delete(Rule::SetNull);
+    ds.delete_row("orders", 1).unwrap();
+    assert_eq!(ds.tables["lines"][2]["order_id"], DataValue::Null);
+    assert_eq!(ds.tables["lines"][2].state(), RowState::Modified);
+}
+
+#[test]
+fn cascades_nothing_when_a_cascaded_change_fails() {
+    let mut ds = orders_with_lines();
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap()
+        .on_update(Rule::Cascade);
+    let lines = ds.tables.get_mut("lines").unwrap();
+    lines
+        .rows
+        .push(row(&[("id", 11.into()), ("order_id", 3.into())]));
+    lines.add_unique_constraint(&["id", "order_id"]).unwrap();
+    let before = ds.clone();
+
+    assert!(matches!(
+        ds.set_value("orders", 0, "id", 3),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+    assert_eq!(ds, before);
+    assert_eq!(ds.relations().len(), 1);
+}
+
+#[test]
+fn computes_columns_over_relations() {
+    let mut ds = orders_with_lines();
+    for (row, qty) in ds
+        .tables
+        .get_mut("lines")
+        .unwrap()
+        .rows
+        .iter_mut()
+        .zip([2, 3, 5])
+    {
+        row.cells.insert("qty".into(), DataCell::new(qty));
+    }
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap();
+    let orders = ds.tables.get_mut("orders").unwrap();
+    orders.add_computed_column("qty", "SUM(Child.qty)").unwrap();
+    orders
+        .add_computed_column("lines", "COUNT(Child(order_lines).id)")
+        .unwrap();
+    ds.tables
+        .get_mut("lines")
+        .unwrap()
+        .add_computed_column("share", "qty * 1.0 / Parent.qty")
+        .unwrap();
+    // Relations are only resolved through the data set.
+    assert!(ds.tables["orders"][0]["qty"].is_null());
+
+    ds.refresh_computed().unwrap();
+    assert_eq!(ds.tables["orders"][0]["qty"], DataValue::BigInt(5));
+    assert_eq!(ds.tables["orders"][1]["lines"], 1);
+    assert_eq!(ds.tables["lines"][1]["share"], Decimal::new(6, 1));
+
+    ds.add_row(
+        "lines",
+        row(&[("id", 13.into()), ("order_id", 2.into()), ("qty", 5.into())]),
+    )
+    .unwrap();
+    assert_eq!(ds.tables["orders"][1]["qty"], DataValue::BigInt(10));
+    assert_eq!(ds.tables["lines"][3]["share"], Decimal::new(5, 1));
+
+    let expression = Expression::parse("SUM(Child.qty)").unwrap();
+    let order = ds.tables["orders"][0].clone();
+    assert_eq!(
+        expression.evaluate_in(&ds, "invoices", &order),
+        Err(ExpressionError::UnknownTable("invoices".into()))
+    );
+    ds.tables.remove("lines");
+    assert_eq!(
+        expression.evaluate_in(&ds, "orders", &order),
+        Err(ExpressionError::UnknownTable("lines".into()))
+    );
+}
+
+#[test]
+fn refreshes_only_related_tables_and_rolls_back_failures() {
+    let mut ds = orders_with_lines();
+    ds.add_relation("order_lines", ("orders", "id"), ("lines", "order_id"))
+        .unwrap();
+    ds.tables
+        .get_mut("orders")
+        .unwrap()
+        .add_computed_column("per_line", "10 / COUNT(Child.id)")
+        .unwrap();
+    ds.refresh_computed().unwrap();
+    // A table no change touches, holding a row that cannot be computed.
+    let mut audit = DataTable::new("audit");
+    audit.add_computed_column("ratio", "1 / n").unwrap();
+    audit.rows.push(row(&[("n", 0.into())]));
+    ds.tables.insert(audit.name.clone(), audit);
+    assert_eq!(ds.refresh_computed(), Err(ExpressionError::DivideByZero));
+
+    ds.add_row("lines", row(&[("id", 13.into()), ("order_id", 2.into())]))
+        .unwrap();
+    assert_eq!(ds.tables["orders"][1]["per_line"], 5);
+    ds.set_value("lines", 3, "order_id", 1).unwrap();
+    assert_eq!(ds.tables["orders"][0]["per_line"], 3);
+    assert_eq!(ds.tables["audit"][0].get("ratio"), None);
+
+    // An order without lines divides by zero, so it is not added.
+    let before = ds.clone();
+    assert_eq!(
+        ds.add_row("orders", row(&[("id", 3.into())])),
+        Err(DataError::Expression(ExpressionError::DivideByZero))
+    );
+    assert_eq!(ds, before);
+}
+
+#[test]
+fn merges_data_sets() {
+    let mut ds = orders_with_lines();
+    ds.tables
+        .get_mut("orders")
+        .unwrap()
+        .set_primary_key(&["id"])
+        .unwrap();
+
+    let mut other = DataSet::new();
+    let mut orders = DataTable::new("orders");
+    orders.columns.push(DataColumn {
+        name: "status".into(),
+        ..Default::default()
+    });
+    orders
+        .rows
+        .push(row(&[("id", 2.into()), ("status", "PAID".into())]));
+    orders
+        .rows
+        .push(row(&[("id", 3.into()), ("status", "OPEN".into())]));
+    other.tables.insert("orders".into(), orders);
+    other
+        .tables
+        .insert("customers".into(), DataTable::new("customers"));
+
+    let mut strict = ds.clone();
+    assert_eq!(
+        strict.merge(&other, MissingSchemaAction::Error),
+        Err(DataError::MissingSchema("customers".into()))
+    );
+    assert_eq!(strict.tables["orders"].rows.len(), 2);
+
+    let mut ignored = ds.clone();
+    ignored.merge(&other, MissingSchemaAction::Ignore).unwrap();
+    assert!(!ignored.tables.contains_key("customers"));
+    assert_eq!(ignored.tables["orders"].rows.len(), 3);
+
+    ds.merge(&other, MissingSchemaAction::Add).unwrap();
+    assert!(ds.tables.contains_key("customers"));
+    let orders = &ds.tables["orders"];
+    assert_eq!(orders.rows.len(), 3);
+    assert!(orders[0].get("status").is_none());
+    assert_eq!(orders[1]["status"], "PAID");
+    assert_eq!(orders.find(&[3.into()]).unwrap()["status"], "OPEN");
+}
@@ -1,21 +1,635 @@
+use std::collections::HashMap;
 use std::ops::Index;
+use std::sync::Arc;
 
-use super::{DataColumn, DataRow};
+use super::data_row::KeyWatch;
+use super::expression::Scope;
+use super::key::{key_of, row_key, KeyPart};
+use super::render::render_table;
+use super::{
+    DataCell, DataColumn, DataError, DataRow, DataSet, DataValue, DataView, Expression,
+    ExpressionError, GroupBy, MissingSchemaAction, RowState, TableDiff, TableFormat,
+};
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Default)]
 pub struct DataTable {
     pub name: String,
     pub columns: Vec<DataColumn>,
     pub rows: Vec<DataRow>,
+    /// Values the configured conversion policy replaced while loading the table.
+    pub warnings: Vec<ConversionWarning>,
+    // Expressions of the computed columns, by column name.
+    expressions: HashMap<String, String>,
+    constraints: Vec<UniqueIndex>,
+    // Row count and key change count when the indexes were last in sync with `rows`.
+    // Lookups fall back to a scan whenever rows were pushed or removed, or a key was
+    // changed through `DataRow` methods, behind the table's back.
+    indexed_rows: usize,
+    indexed_at: u64,
+    watch: Arc<KeyWatch>,
+}
+
+/// A column value that could not be converted when a query result was read and was
+/// replaced according to the
+/// [`ConversionPolicy`](crate::infrastructure::mssql::ConversionPolicy).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionWarning {
+    pub row: usize,
+    pub column: String,
+    pub message: String,
+}
+
+// A unique constraint together with the hash index that enforces it.
+#[derive(Debug, Clone, Default)]
+struct UniqueIndex {
+    columns: Vec<String>,
+    primary: bool,
+    rows: HashMap<Vec<KeyPart>, usize>,
 }
 
 impl DataTable {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.into(),
-            columns: Vec::new(),
-            rows: Vec::new(),
+            ..Default::default()
+        }
+    }
+
+    /// Columns of the primary key, or an empty slice when the table has none.
+    pub fn primary_key(&self) -> &[String] {
+        self.constraints
+            .iter()
+            .find(|c| c.primary)
+            .map(|c| c.columns.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Make `columns` the primary key, replacing any previous one. Key columns may not
+    /// be NULL and existing rows must be unique. An empty slice removes the key.
+    pub fn set_primary_key(&mut self, columns: &[&str]) -> Result<(), DataError> {
+        if columns.is_empty() {
+            self.constraints.retain(|c| !c.primary);
+            return Ok(());
+        }
+        let index = self.new_index(columns, true)?;
+        self.constraints.retain(|c| !c.primary);
+        self.constraints.insert(0, index);
+        self.rebuild_indexes()
+    }
+
+    /// Require the combination of `columns` to be unique. As in SQL Server, NULL counts
+    /// as a value, so only one row may hold a NULL key.
+    pub fn add_unique_constraint(&mut self, columns: &[&str]) -> Result<(), DataError> {
+        if columns.is_empty() {
+            return Ok(());
+        }
+        let index = self.new_index(columns, false)?;
+        self.constraints.push(index);
+        self.rebuild_indexes()
+    }
+
+    /// Row whose primary key equals `key`, given in key column order. Deleted rows are
+    /// never returned.
+    pub fn find(&self, key: &[DataValue]) -> Option<&DataRow> {
+        self.find_index(key).map(|i| &self.rows[i])
+    }
+
+    /// Position of the row whose primary key equals `key`.
+    pub fn find_index(&self, key: &[DataValue]) -> Option<usize> {
+        let primary = self.constraints.iter().find(|c| c.primary)?;
+        if key.len() != primary.columns.len() {
+            return None;
+        }
+        self.lookup(primary, &row_key(key))
+    }
+
+    /// A view over the rows that are not deleted, to filter and sort without copying.
+    pub fn view(&self) -> DataView<'_> {
+        DataView::new(self)
+    }
+
+    /// Rows matching `filter`, ordered by `sort`, like ADO.NET's `DataTable.Select`.
+    /// Either string may be empty. See [`Expression`](super::Expression) and
+    /// [`SortOrder`](super::SortOrder) for the syntax.
+    pub fn select(&self, filter: &str, sort: &str) -> Result<Vec<&DataRow>, ExpressionError> {
+        Ok(self.view().filter(filter)?.sort(sort)?.iter().collect())
+    }
+
+    /// Group the rows that are not deleted by `columns`, to aggregate them with
+    /// [`GroupBy::agg`](super::GroupBy::agg).
+    pub fn group_by(&self, columns: &[&str]) -> GroupBy<'_> {
+        GroupBy::new(self, columns)
+    }
+
+    /// Add a read-only column computed from each row by `expression`, such as
+    /// `"Quantity * UnitPrice"` or `"SUM(Child.Amount)"`, and compute it for every row.
+    /// See [`Expression`] for the syntax.
+    pub fn add_computed_column(
+        &mut self,
+        name: &str,
+        expression: &str,
+    ) -> Result<(), ExpressionError> {
+        Expression::parse(expression)?;
+        self.columns.push(DataColumn::new(name, ""));
+        self.expressions.insert(name.into(), expression.into());
+        let result = self.refresh_computed();
+        if result.is_err() {
+            self.columns.pop();
+            self.expressions.remove(name);
+        }
+        result
+    }
+
+    /// Expression of a computed column, or `None` for stored columns.
+    pub fn expression(&self, column: &str) -> Option<&str> {
+        self.expressions.get(column).map(String::as_str)
+    }
+
+    /// Whether `column` is computed. Computed columns are read-only and never written
+    /// back to the database.
+    pub fn is_computed(&self, column: &str) -> bool {
+        self.expressions.contains_key(column)
+    }
+
+    /// Recompute the computed columns of every row. `add_row` and `set_value` keep the
+    /// row they change current; call this after changing `rows` directly or when a
+    /// column aggregates other rows. Columns referencing relations stay NULL here; use
+    /// [`DataSet::refresh_computed`] for those.
+    pub fn refresh_computed(&mut self) -> Result<(), ExpressionError> {
+        let values = self.computed_values(None)?;
+        self.apply_computed(values);
+        Ok(())
+    }
+
+    /// Render the rows that are not deleted as text; see [`TableFormat`](super::TableFormat).
+    /// `Display` renders with the default format.
+    pub fn render(&self, format: &TableFormat) -> String {
+        render_table(self, format)
+    }
+
+    /// Compare with `other`, matching rows by the `key` columns. An empty key compares
+    /// rows by position.
+    pub fn diff(&self, other: &DataTable, key: &[&str]) -> Result<TableDiff, DataError> {
+        let key: Vec<String> = key.iter().map(|c| c.to_string()).collect();
+        TableDiff::compute(self, other, &key)
+    }
+
+    /// Merge the rows of `other` into this table, like ADO.NET's `DataTable.Merge`.
+    /// A row whose primary key matches an existing row replaces its values and state;
+    /// other rows are appended. Without a primary key every row is appended.
+    pub fn merge(
+        &mut self,
+        other: &DataTable,
+        action: MissingSchemaAction,
+    ) -> Result<(), DataError> {
+        let missing: Vec<DataColumn> = self.missing_columns(other).into_iter().cloned().collect();
+        match action {
+            MissingSchemaAction::Error if !missing.is_empty() => {
+                return Err(DataError::MissingSchema(missing[0].name.clone()))
+            }
+            MissingSchemaAction::Add => {
+                for column in &missing {
+                    if let Some(expression) = other.expression(&column.name) {
+                        self.expressions
+                            .insert(column.name.clone(), expression.into());
+                    }
+                }
+                self.columns.extend(missing.iter().cloned());
+            }
+            _ => {}
+        }
+        let key = self.primary_key().to_vec();
+        for row in &other.rows {
+            let mut incoming = row.clone();
+            if action == MissingSchemaAction::Ignore {
+                for column in &missing {
+                    incoming.remove_column(&column.name);
+                }
+            }
+            let existing = match key.is_empty() {
+                true => None,
+                false => self.find_by(&key, &key_of(&incoming, &key)),
+            };
+            match existing {
+                Some(i) => {
+                    incoming.fill_missing(&self.rows[i]);
+                    self.rows[i] = incoming;
+                }
+                None => self.rows.push(incoming),
+            }
+        }
+        self.rebuild_indexes()?;
+        Ok(self.refresh_computed()?)
+    }
+
+    /// Rebuild the key indexes and re-check every constraint. `add_row`, `set_value`
+    /// and `delete_row` keep the indexes current, and the next of these rebuilds them
+    /// after rows are pushed or removed or a key is changed with [`DataRow::set`]
+    /// through `rows`; lookups scan until then. Writing to `cells` directly or
+    /// replacing a row in place is not noticed and always needs an explicit rebuild.
+    pub fn rebuild_indexes(&mut self) -> Result<(), DataError> {
+        self.indexed_rows = usize::MAX;
+        let mut columns: Vec<String> = self
+            .constraints
+            .iter()
+            .flat_map(|c| c.columns.iter().cloned())
+            .collect();
+        columns.sort();
+        columns.dedup();
+        if columns != self.watch.columns() {
+            self.watch = Arc::new(KeyWatch::new(columns));
+        }
+        for row in &mut self.rows {
+            row.watch(&self.watch);
+        }
+        for constraint in &mut self.constraints {
+            constraint.build(&self.rows)?;
+        }
+        self.mark_indexed();
+        Ok(())
+    }
+
+    /// Append a row and mark it as added. Use `rows.push` to load existing data.
+    /// Fails without adding the row when it violates a key or unique constraint.
+    pub fn add_row(&mut self, mut row: DataRow) -> Result<(), DataError> {
+        self.sync_indexes()?;
+        self.compute_row(&mut row)?;
+        for constraint in &self.constraints {
+            constraint.check_nulls(&row)?;
+            if self.lookup(constraint, &constraint.key(&row)).is_some() {
+                return Err(constraint.violation(&row));
+            }
+        }
+        row.accept_changes();
+        row.set_added();
+        row.watch(&self.watch);
+        let index = self.rows.len();
+        for constraint in &mut self.constraints {
+            constraint.rows.insert(constraint.key(&row), index);
+        }
+        self.rows.push(row);
+        self.mark_indexed();
+        Ok(())
+    }
+
+    /// Set a column value like [`DataRow::set`], enforcing key and unique constraints.
+    pub fn set_value<T: Into<DataValue>>(
+        &mut self,
+        index: usize,
+        column: &str,
+        value: T,
+    ) -> Result<(), DataError> {
+        if index >= self.rows.len() {
+            return Err(DataError::RowOutOfRange(index));
+        }
+        if self.is_computed(column) {
+            return Err(DataError::ReadOnlyColumn(column.into()));
+        }
+        self.sync_indexes()?;
+        let row = &self.rows[index];
+        let mut updated = row.clone();
+        updated.set(column, value)?;
+        self.compute_row(&mut updated)?;
+        for constraint in &self.constraints {
+            constraint.check_nulls(&updated)?;
+            match self.lookup(constraint, &constraint.key(&updated)) {
+                Some(other) if other != index => return Err(constraint.violation(&updated)),
+                _ => {}
+            }
+        }
+        for constraint in &mut self.constraints {
+            let old = constraint.key(row);
+            if constraint.rows.get(&old) == Some(&index) {
+                constraint.rows.remove(&old);
+            }
+            constraint.rows.insert(constraint.key(&updated), index);
+        }
+        updated.watch(&self.watch);
+        self.rows[index] = updated;
+        self.mark_indexed();
+        Ok(())
+    }
+
+    /// Delete a row. Rows added since the last `accept_changes` are removed right
+    /// away; other rows are marked deleted until changes are accepted. The row is
+    /// deleted even when re-indexing the remaining rows reports a constraint
+    /// violation left by direct changes to `rows`.
+    pub fn delete_row(&mut self, index: usize) -> Result<(), DataError> {
+        let row = self
+            .rows
+            .get_mut(index)
+            .ok_or(DataError::RowOutOfRange(index))?;
+        match row.state() {
+            RowState::Added => {
+                self.rows.remove(index);
+                // Later rows moved up.
+                self.rebuild_indexes()?;
+            }
+            RowState::Deleted => return Err(DataError::DeletedRow),
+            // Lookups skip deleted rows, so their index entries can stay.
+            RowState::Unchanged | RowState::Modified => row.mark_deleted(),
+        }
+        Ok(())
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.rows.iter().any(|r| r.state() != RowState::Unchanged)
+    }
+
+    /// A copy of the table holding only added, modified and deleted rows, or `None`
+    /// when there are no pending changes.
+    pub fn get_changes(&self) -> Option<DataTable> {
+        if !self.has_changes() {
+            return None;
+        }
+        Some(DataTable {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            expressions: self.expressions.clone(),
+            rows: self
+                .rows
+                .iter()
+                .filter(|r| r.state() != RowState::Unchanged)
+                .cloned()
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Commit all pending changes: deleted rows are dropped and every other row
+    /// becomes unchanged. The changes are committed even when the rows then violate
+    /// a constraint, which is reported after the fact; lookups scan until the
+    /// violation is fixed and the indexes are rebuilt.
+    pub fn accept_changes(&mut self) -> Result<(), DataError> {
+        self.rows.retain(|r| r.state() != RowState::Deleted);
+        for row in &mut self.rows {
+            row.accept_changes();
         }
+        self.rebuild_indexes()
+    }
+
+    /// Roll back all pending changes: added rows are dropped and modified or deleted
+    /// rows get their original values back. Restored rows may clash with values set
+    /// directly through `rows`; such a violation is reported like in
+    /// [`accept_changes`](Self::accept_changes).
+    pub fn reject_changes(&mut self) -> Result<(), DataError> {
+        self.rows.retain(|r| r.state() != RowState::Added);
+        for row in &mut self.rows {
+            row.reject_changes();
+        }
+        self.rebuild_indexes()
+    }
+
+    // Fails when the table has column metadata and one of `columns` is not in it.
+    pub(crate) fn check_columns(&self, columns: &[&str]) -> Result<(), DataError> {
+        if self.columns.is_empty() {
+            return Ok(());
+        }
+        match columns
+            .iter()
+            .find(|name| !self.columns.iter().any(|c| c.name == **name))
+        {
+            Some(missing) => Err(DataError::ColumnNotFound(missing.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    // Computed column values of the rows that are not deleted, in column order so
+    // later columns see earlier ones.
+    pub(crate) fn computed_values(
+        &self,
+        ds: Option<&DataSet>,
+    ) -> Result<Vec<(usize, DataRow)>, ExpressionError> {
+        let computed = self.computed_columns()?;
+        if computed.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut values = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            if row.state() == RowState::Deleted {
+                continue;
+            }
+            let mut row = row.clone();
+            for (name, expression) in &computed {
+                let scope = Scope {
+                    row: &row,
+                    table: Some(self),
+                    dataset: ds,
+                };
+                let value = local_value(expression.evaluate_scoped(&scope))?;
+                row.cells.insert(name.clone(), DataCell::new(value));
+            }
+            values.push((i, row));
+        }
+        Ok(values)
+    }
+
+    // Copy computed cells into the rows without touching their change state. Returns
+    // whether any value changed.
+    pub(crate) fn apply_computed(&mut self, values: Vec<(usize, DataRow)>) -> bool {
+        let names: Vec<String> = self.expressions.keys().cloned().collect();
+        let mut changed = false;
+        for (i, mut computed) in values {
+            for name in &names {
+                if let Some(cell) = computed.cells.remove(name) {
+                    changed |= self.rows[i].get(name) != Some(&cell.value);
+                    self.rows[i].cells.insert(name.clone(), cell);
+                }
+            }
+        }
+        changed
+    }
+
+    fn computed_columns(&self) -> Result<Vec<(String, Expression)>, ExpressionError> {
+        self.columns
+            .iter()
+            .filter_map(|c| Some((c.name.clone(), self.expression(&c.name)?)))
+            .map(|(name, text)| Ok((name, Expression::parse(text)?)))
+            .collect()
+    }
+
+    fn compute_row(&self, row: &mut DataRow) -> Result<(), ExpressionError> {
+        for (name, expression) in self.computed_columns()? {
+            let value = local_value(expression.evaluate_scoped(&Scope::in_table(row, self)))?;
+            row.cells.insert(name, DataCell::new(value));
+        }
+        Ok(())
+    }
+
+    // Columns of `other` this table does not define. A table without column
+    // metadata accepts any column.
+    pub(crate) fn missing_columns<'a>(&self, other: &'a DataTable) -> Vec<&'a DataColumn> {
+        if self.columns.is_empty() {
+            return Vec::new();
+        }
+        other
+            .columns
+            .iter()
+            .filter(|c| !self.columns.iter().any(|own| own.name == c.name))
+            .collect()
+    }
+
+    pub(crate) fn has_unique(&self, columns: &[String]) -> bool {
+        self.constraints.iter().any(|c| c.columns == columns)
+    }
+
+    fn new_index(&self, columns: &[&str], primary: bool) -> Result<UniqueIndex, DataError> {
+        self.check_columns(columns)?;
+        let mut index = UniqueIndex {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            primary,
+            rows: HashMap::new(),
+        };
+        index.build(&self.rows)?;
+        Ok(index)
+    }
+
+    // Live row whose `columns` hold `key`, through a unique index on exactly those
+    // columns when there is one.
+    pub(crate) fn find_by(&self, columns: &[String], key: &[KeyPart]) -> Option<usize> {
+        match self.constraints.iter().find(|c| c.columns == columns) {
+            Some(constraint) => self.lookup(constraint, key),
+            None => self.matching(columns, key).into_iter().next(),
+        }
+    }
+
+    // Every live row whose `columns` hold `key`.
+    pub(crate) fn matching(&self, columns: &[String], key: &[KeyPart]) -> Vec<usize> {
+        (0..self.rows.len())
+            .filter(|&i| {
+                let row = &self.rows[i];
+                row.state() != RowState::Deleted && key_of(row, columns) == key
+            })
+            .collect()
+    }
+
+    // Whether lookups can use the indexes rather than scanning.
+    pub(crate) fn indexes_current(&self) -> bool {
+        self.indexed_rows == self.rows.len() && self.indexed_at == self.watch.changes()
+    }
+
+    fn mark_indexed(&mut self) {
+        self.indexed_rows = self.rows.len();
+        self.indexed_at = self.watch.changes();
+    }
+
+    // Rebuild the indexes when rows changed behind the table's back.
+    fn sync_indexes(&mut self) -> Result<(), DataError> {
+        match self.indexes_current() {
+            true => Ok(()),
+            false => self.rebuild_indexes(),
+        }
+    }
+
+    // Live row holding `key` in `constraint`, using the index while it is current.
+    fn lookup(&self, constraint: &UniqueIndex, key: &[KeyPart]) -> Option<usize> {
+        let live = |i: &usize| {
+            self.rows
+                .get(*i)
+                .is_some_and(|r| r.state() != RowState::Deleted && constraint.key(r) == key)
+        };
+        if self.indexes_current() {
+            constraint.rows.get(key).copied().filter(live)
+        } else {
+            (0..self.rows.len()).find(live)
+        }
+    }
+}
+
+// Relation references cannot be resolved without the data set; they read as NULL
+// until the data set refreshes them.
+fn local_value(value: Result<DataValue, ExpressionError>) -> Result<DataValue, ExpressionError> {
+    match value {
+        Err(ExpressionError::RequiresTable(_)) => Ok(DataValue::Null),
+        other => other,
+    }
+}
+
+impl UniqueIndex {
+    fn key(&self, row: &DataRow) -> Vec<KeyPart> {
+        key_of(row, &self.columns)
+    }
+
+    fn check_nulls(&self, row: &DataRow) -> Result<(), DataError> {
+        if !self.primary {
+            return Ok(());
+        }
+        match self
+            .columns
+            .iter()
+            .find(|c| row.get(c).is_none_or(DataValue::is_null))
+        {
+            Some(column) => Err(DataError::NullKey(column.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn violation(&self, row: &DataRow) -> DataError {
+        DataError::ConstraintViolation {
+            columns: self.columns.clone(),
+            key: self
+                .columns
+                .iter()
+                .map(|c| row.get(c).cloned().unwrap_or_default())
+                .collect(),
+        }
+    }
+
+    fn build(&mut self, rows: &[DataRow]) -> Result<(), DataError> {
+        self.rows.clear();
+        for (i, row) in rows.iter().enumerate() {
+            if row.state() == RowState::Deleted {
+                continue;
+            }
+            self.check_nulls(row)?;
+            if self.rows.insert(self.key(row), i).is_some() {
+                return Err(self.violation(row));
+            }
+        }
+        Ok(())
+    }
+}
+
+// A copy gets its own key watch, so changes to its rows leave the original's indexes
+// alone.
+impl Clone for DataTable {
+    fn clone(&self) -> Self {
+        let watch = Arc::new(KeyWatch::new(self.watch.columns().to_vec()));
+        let mut rows = self.rows.clone();
+        for row in &mut rows {
+            row.watch(&watch);
+        }
+        Self {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            rows,
+            warnings: self.warnings.clone(),
+            expressions: self.expressions.clone(),
+            constraints: self.constraints.clone(),
+            indexed_rows: match self.indexes_current() {
+                true => self.indexed_rows,
+                false => usize::MAX,
+            },
+            indexed_at: 0,
+            watch,
+        }
+    }
+}
+
+// Tables are equal when their data and constraints match; index state is ignored.
+impl PartialEq for DataTable {
+    fn eq(&self, other: &Self) -> bool {
+        let constraints = |t: &DataTable| -> Vec<(Vec<String>, bool)> {
+            t.constraints
+                .iter()
+                .map(|c| (c.columns.clone(), c.primary))
+                .collect()
+        };
+        self.name == other.name
+            && self.columns == other.columns
+            && self.expressions == other.expressions
+            && self.rows == other.rows
+            && constraints(self) == constraints(other)
     }
 }
 
@@ -0,0 +1,349 @@
+use super::*;
+
+fn people() -> DataTable {
+    let mut table = DataTable::new("people");
+    for (id, name) in [(1, "Ann"), (2, "Bob"), (3, "Cid")] {
+        let mut row = DataRow::default();
+        row.cells.insert("id".into(), DataCell::new(id));
+        row.cells.insert("name".into(), DataCell::new(name));
+        table.rows.push(row);
+    }
+    table
+}
+
+#[test]
+fn tracks_row_states_and_original_values() {
+    let mut table = people();
+    assert!(!table.has_changes());
+
+    table.rows[0].set("name", "Anne").unwrap();
+    assert_eq!(table[0].state(), RowState::Modified);
+    assert_eq!(table[0]["name"], "Anne");
+    assert_eq!(table[0].original("name").unwrap(), &DataValue::from("Ann"));
+
+    let mut row = DataRow::default();
+    row.cells.insert("id".into(), DataCell::new(4));
+    table.add_row(row).unwrap();
+    assert_eq!(table[3].state(), RowState::Added);
+
+    table.delete_row(1).unwrap();
+    assert_eq!(table[1].state(), RowState::Deleted);
+    assert_eq!(table.rows[1].set("name", "x"), Err(DataError::DeletedRow));
+
+    let changes = table.get_changes().unwrap();
+    assert_eq!(changes.rows.len(), 3);
+}
+
+#[test]
+fn deleting_added_row_removes_it() {
+    let mut table = people();
+    table.add_row(DataRow::default()).unwrap();
+    table.delete_row(3).unwrap();
+    assert_eq!(table.rows.len(), 3);
+    assert_eq!(table.delete_row(9), Err(DataError::RowOutOfRange(9)));
+}
+
+#[test]
+fn accept_and_reject_changes() {
+    let mut table = people();
+    table.rows[0].set("name", "Anne").unwrap();
+    table.delete_row(1).unwrap();
+    table.add_row(DataRow::default()).unwrap();
+
+    let mut rejected = table.clone();
+    rejected.reject_changes().unwrap();
+    assert_eq!(rejected, people());
+
+    table.accept_changes().unwrap();
+    assert_eq!(table.rows.len(), 3);
+    assert!(table.rows.iter().all(|r| r.state() == RowState::Unchanged));
+    assert_eq!(table[0]["name"], "Anne");
+    assert_eq!(table[0].original("name").unwrap(), &DataValue::from("Anne"));
+    assert!(table.get_changes().is_none());
+}
+
+#[test]
+fn finds_rows_by_primary_key() {
+    let mut table = people();
+    table.set_primary_key(&["id"]).unwrap();
+    assert_eq!(table.primary_key(), ["id".to_string()]);
+    assert_eq!(table.find(&[2.into()]).unwrap()["name"], "Bob");
+    // Whole numbers match regardless of their integer type.
+    assert_eq!(table.find_index(&[DataValue::BigInt(3)]), Some(2));
+    assert!(table.find(&[9.into()]).is_none());
+
+    table.delete_row(1).unwrap();
+    assert!(table.find(&[2.into()]).is_none());
+
+    let mut row = DataRow::default();
+    row.cells.insert("id".into(), DataCell::new(2));
+    table.add_row(row).unwrap();
+    assert_eq!(table.find_index(&[2.into()]), Some(3));
+
+    // Rows pushed directly are still found by scanning.
+    let mut row = DataRow::default();
+    row.cells.insert("id".into(), DataCell::new(7));
+    table.rows.push(row);
+    assert_eq!(table.find_index(&[7.into()]), Some(4));
+}
+
+#[test]
+fn enforces_primary_key_and_unique_constraints() {
+    let mut table = people();
+    table.set_primary_key(&["id"]).unwrap();
+    table.add_unique_constraint(&["name"]).unwrap();
+
+    let mut row = DataRow::default();
+    row.cells.insert("id".into(), DataCell::new(1));
+    row.cells.insert("name".into(), DataCell::new("Dee"));
+    assert_eq!(
+        table.add_row(row),
+        Err(DataError::ConstraintViolation {
+            columns: vec!["id".into()],
+            key: vec![1.into()],
+        })
+    );
+    assert_eq!(
+        table.add_row(DataRow::default()),
+        Err(DataError::NullKey("id".into()))
+    );
+    assert!(matches!(
+        table.set_value(2, "name", "Ann"),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+    assert_eq!(table.rows.len(), 3);
+
+    table.set_value(0, "id", 10).unwrap();
+    assert_eq!(table.find(&[10.into()]).unwrap()["name"], "Ann");
+    assert!(table.find(&[1.into()]).is_none());
+
+    table.rows[1].set("id", 10).unwrap();
+    assert!(matches!(
+        table.rebuild_indexes(),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+    assert!(matches!(
+        table.accept_changes(),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+    assert!(table.rows.iter().all(|r| r.state() == RowState::Unchanged));
+}
+
+#[test]
+fn lookups_see_keys_changed_through_rows() {
+    let mut table = people();
+    table.set_primary_key(&["id"]).unwrap();
+    table.rows[0].set("id", 5).unwrap();
+    assert_eq!(table.find_index(&[5.into()]), Some(0));
+    assert!(table.find(&[1.into()]).is_none());
+
+    // The next change through the table re-indexes, catching clashes made directly.
+    table.rows[1].set("id", 5).unwrap();
+    assert!(matches!(
+        table.set_value(2, "name", "Cy"),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+    table.rows[1].set("id", 2).unwrap();
+    table.set_value(2, "name", "Cy").unwrap();
+    assert_eq!(table.find(&[3.into()]).unwrap()["name"], "Cy");
+}
+
+#[test]
+fn keeps_indexes_when_other_values_change() {
+    let mut table = people();
+    table.set_primary_key(&["id"]).unwrap();
+    let mut other = table.clone();
+    table.rows[0].set("name", "Anne").unwrap();
+    other.rows[0].set("id", 7).unwrap();
+    let mut copy = table.rows[1].clone();
+    copy.set("id", 8).unwrap();
+    assert!(table.indexes_current());
+    assert_eq!(table.find(&[1.into()]).unwrap()["name"], "Anne");
+
+    assert!(!other.indexes_current());
+    assert_eq!(other.find_index(&[7.into()]), Some(0));
+    other.set_value(1, "name", "Bo").unwrap();
+    assert!(other.indexes_current());
+}
+
+#[test]
+fn rejects_invalid_keys() {
+    let mut table = people();
+    table.columns.push(DataColumn {
+        name: "id".into(),
+        ..Default::default()
+    });
+    assert_eq!(
+        table.set_primary_key(&["missing"]),
+        Err(DataError::ColumnNotFound("missing".into()))
+    );
+    table.rows[1].cells.insert("id".into(), DataCell::new(1));
+    assert!(table.set_primary_key(&["id"]).is_err());
+    assert!(table.primary_key().is_empty());
+}
+
+#[test]
+fn groups_and_aggregates() {
+    use super::aggregate::{avg, count, count_of, max, min, sum};
+    use rust_decimal::Decimal;
+
+    let mut table = DataTable::new("sales");
+    let rows: [(DataValue, i32, DataValue, DataValue); 5] = [
+        ("north".into(), 10, Decimal::new(150, 2).into(), 2.5.into()),
+        (
+            "south".into(),
+            20,
+            Decimal::new(200, 2).into(),
+            DataValue::Null,
+        ),
+        ("north".into(), 30, DataValue::Null, 3.5.into()),
+        (DataValue::Null, 40, Decimal::new(100, 2).into(), 1.0.into()),
+        ("north".into(), 50, Decimal::new(50, 2).into(), 4.0.into()),
+    ];
+    for (region, qty, amount, price) in rows {
+        let mut row = DataRow::default();
+        row.cells.insert("region".into(), DataCell::new(region));
+        row.cells.insert("qty".into(), DataCell::new(qty));
+        row.cells.insert("amount".into(), DataCell::new(amount));
+        row.cells.insert("price".into(), DataCell::new(price));
+        table.rows.push(row);
+    }
+    table.delete_row(4).unwrap();
+
+    let totals = table
+        .group_by(&["region"])
+        .agg([
+            sum("qty"),
+            sum("amount").alias("total"),
+            count(),
+            count_of("amount"),
+            avg("price"),
+            min("amount"),
+            max("qty"),
+        ])
+        .unwrap();
+    assert_eq!(totals.rows.len(), 3);
+    let north = &totals[0];
+    assert_eq!(north["region"], "north");
+    assert_eq!(north["sum_qty"], DataValue::BigInt(40));
+    assert_eq!(north["total"], Decimal::new(150, 2));
+    assert_eq!(north["count"], 2);
+    assert_eq!(north["count_amount"], 1);
+    assert_eq!(north["avg_price"], 3.0);
+    assert_eq!(north["max_qty"], 30);
+    assert!(totals[1]["avg_price"].is_null());
+    assert!(totals[2]["region"].is_null());
+    let types: Vec<&str> = totals.columns.iter().map(|c| c.sql_type.as_str()).collect();
+    assert_eq!(types[1..5], ["Int8", "Decimaln", "Int4", "Int4"]);
+
+    let empty = DataTable::new("empty")
+        .group_by(&[])
+        .agg([count(), sum("x")])
+        .unwrap();
+    assert_eq!(empty[0]["count"], 0);
+    assert!(empty[0]["sum_x"].is_null());
+
+    table.columns.push(DataColumn {
+        name: "region".into(),
+        ..Default::default()
+    });
+    assert_eq!(
+        table
+            .group_by(&["region"])
+            .agg([sum("missing")])
+            .unwrap_err(),
+        DataError::ColumnNotFound("missing".into())
+    );
+}
+
+#[test]
+fn diffs_tables() {
+    let expected = people();
+    let mut actual = people();
+    actual.rows[1].set("name", "Bobby").unwrap();
+    actual.delete_row(2).unwrap();
+    let mut row = DataRow::default();
+    row.cells.insert("id".into(), DataCell::new(4));
+    row.cells.insert("name".into(), DataCell::new("Dan"));
+    actual.add_row(row).unwrap();
+
+    let diff = expected.diff(&actual, &["id"]).unwrap();
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0]["name"], "Cid");
+    assert_eq!(diff.added[0]["name"], "Dan");
+    assert_eq!(
+        diff.changed,
+        vec![RowDiff {
+            key: vec![2.into()],
+            columns: vec![ColumnDiff {
+                column: "name".into(),
+                before: "Bob".into(),
+                after: "Bobby".into(),
+            }],
+        }]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "- id=Int(3) name=Text(\"Cid\")\n+ id=Int(4) name=Text(\"Dan\")\n~ [Int(2)]\n    name: Text(\"Bob\") -> Text(\"Bobby\")\n"
+    );
+    assert!(expected.diff(&people(), &[]).unwrap().is_empty());
+    // By position, the added row lines up with the deleted one.
+    assert_eq!(expected.diff(&actual, &[]).unwrap().changed.len(), 2);
+
+    let mut duplicated = people();
+    duplicated.rows.push(people().rows.remove(0));
+    assert!(matches!(
+        expected.diff(&duplicated, &["id"]),
+        Err(DataError::ConstraintViolation { .. })
+    ));
+}
+
+#[test]
+fn renders_tables() {
+    let mut table = people();
+    table.columns = ["id", "name"]
+        .map(|name| DataColumn {
+            name: name.into(),
+            ..Default::default()
+        })
+        .to_vec();
+    table.rows[1].set("name", DataValue::Null).unwrap();
+    table.rows[2].set("id", 100).unwrap();
+    table.rows[2].set("name", "Cid\nthe | long name").unwrap();
+
+    assert_eq!(
+        table.to_string(),
+        "+-----+---------------------+\n\
+         | id  | name                |\n\
+         +-----+---------------------+\n\
+         |   1 | Ann                 |\n\
+         |   2 | NULL                |\n\
+         | 100 | Cid the | long name |\n\
+         +-----+---------------------+\n"
+    );
+
+    let format = TableFormat::new(TableStyle::Unicode)
+        .with_max_rows(Some(2))
+        .with_max_width(5)
+        .with_null("-");
+    assert_eq!(
+        table.render(&format),
+        "┌────┬──────┐\n\
+         │ id │ name │\n\
+         ├────┼──────┤\n\
+         │  1 │ Ann  │\n\
+         │  2 │ -    │\n\
+         └────┴──────┘\n\
+         (1 more row)\n"
+    );
+
+    let markdown = table.render(&TableFormat::new(TableStyle::Markdown));
+    assert_eq!(
+        markdown,
+        "| id | name |\n| ---: | --- |\n| 1 | Ann |\n| 2 | NULL |\n| 100 | Cid the \\| long name |\n"
+    );
+    let html = table.render(&TableFormat::new(TableStyle::Html).with_max_rows(Some(1)));
+    assert!(html.contains("<tr><th>id</th><th>name</th></tr>"));
+    assert!(html.contains("<tr><td class=\"number\">1</td><td>Ann</td></tr>"));
+    assert!(html.contains("<td colspan=\"2\">(2 more rows)</td>"));
+}
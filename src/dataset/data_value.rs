@@ -1,43 +1,96 @@
-use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, PartialEq)]
+use super::{HierarchyId, Numeric, ServerTimeZone, Spatial};
+
+/// A single SQL Server value.
+///
+/// Equality is structural: `Int(1)` and `BigInt(1)` differ, while NaN floats equal
+/// themselves so values can be `HashMap` keys. Ordering follows SQL Server's comparison
+/// of `sql_variant` values: NULL first, then uniqueidentifier, binary, text, numeric and
+/// date and time values. Numbers compare by value across types and text ignores case
+/// and trailing spaces; values SQL Server considers equal are then ordered by type.
+#[derive(Debug, Clone, Default)]
 pub enum DataValue {
     TinyInt(u8),
     SmallInt(i16),
     Int(i32),
     BigInt(i64),
+    /// `real`, a 32-bit float.
+    Real(f32),
     Float(f64),
     Decimal(Decimal),
+    /// `decimal`/`numeric` beyond the 28 digits of `Decimal`; see
+    /// [`NumericMapping`](crate::infrastructure::mssql::NumericMapping).
+    Numeric(Numeric),
+    /// `money` or `smallmoney`, with four decimal places.
+    Money(Decimal),
     Bool(bool),
     Text(String),
+    Xml(String),
     Binary(Vec<u8>),
     Guid(Uuid),
     Date(NaiveDate),
     Time(NaiveTime),
     DateTime(NaiveDateTime),
     DateTimeOffset(DateTime<FixedOffset>),
+    /// `sql_variant` value together with the name of its base type, such as `int` or
+    /// `nvarchar(20)`. Tiberius cannot read `sql_variant` columns, so values of this
+    /// variant are only built by hand, to pass as parameters.
+    Variant {
+        base_type: String,
+        value: Box<DataValue>,
+    },
+    /// CLR user-defined type (`hierarchyid`, `geography`, `geometry` or a custom type)
+    /// in its serialized form. Like `Variant`, only built by hand, since tiberius cannot
+    /// read UDT columns.
+    Udt {
+        type_name: String,
+        bytes: Vec<u8>,
+    },
+    /// `geography` or `geometry` value.
+    Spatial(Spatial),
+    HierarchyId(HierarchyId),
+    #[default]
     Null,
 }
 
 impl DataValue {
+    /// Parameter value for tiberius. Tiberius has no wire type for `money`,
+    /// `sql_variant` or CLR types, so `money` is sent as a decimal with four places,
+    /// `sql_variant` as its base value, and UDTs, spatial values and `hierarchyid` as
+    /// `varbinary`. [`Command`](crate::Command) converts these back to their own type on
+    /// the server.
     pub fn to_tiberius(&self) -> Box<dyn tiberius::ToSql + Send + Sync> {
         match self {
             DataValue::TinyInt(v) => Box::new(*v),
             DataValue::SmallInt(v) => Box::new(*v),
             DataValue::Int(v) => Box::new(*v),
             DataValue::BigInt(v) => Box::new(*v),
+            DataValue::Real(v) => Box::new(*v),
             DataValue::Float(v) => Box::new(*v),
             DataValue::Decimal(v) => Box::new(*v),
+            DataValue::Numeric(v) => Box::new(tiberius::numeric::Numeric::new_with_scale(
+                v.mantissa(),
+                v.scale(),
+            )),
+            DataValue::Money(v) => Box::new(v.round_dp(4)),
             DataValue::Bool(v) => Box::new(*v),
             DataValue::Text(v) => Box::new(v.clone()),
+            DataValue::Xml(v) => Box::new(tiberius::xml::XmlData::new(v)),
             DataValue::Binary(v) => Box::new(v.clone()),
             DataValue::Guid(v) => Box::new(*v),
             DataValue::Date(v) => Box::new(*v),
             DataValue::Time(v) => Box::new(*v),
             DataValue::DateTime(v) => Box::new(*v),
             DataValue::DateTimeOffset(v) => Box::new(*v),
+            DataValue::Variant { value, .. } => value.to_tiberius(),
+            DataValue::Udt { bytes, .. } => Box::new(bytes.clone()),
+            DataValue::Spatial(v) => Box::new(v.to_bytes()),
+            DataValue::HierarchyId(v) => Box::new(v.to_bytes()),
             DataValue::Null => Box::new(Option::<i32>::None),
         }
     }
@@ -68,6 +121,12 @@ impl From<i64> for DataValue {
     }
 }
 
+impl From<f32> for DataValue {
+    fn from(v: f32) -> Self {
+        DataValue::Real(v)
+    }
+}
+
 impl From<f64> for DataValue {
     fn from(v: f64) -> Self {
         DataValue::Float(v)
@@ -80,6 +139,12 @@ impl From<Decimal> for DataValue {
     }
 }
 
+impl From<Numeric> for DataValue {
+    fn from(v: Numeric) -> Self {
+        DataValue::Numeric(v)
+    }
+}
+
 impl From<bool> for DataValue {
     fn from(v: bool) -> Self {
         DataValue::Bool(v)
@@ -98,6 +163,18 @@ impl From<&str> for DataValue {
     }
 }
 
+impl From<Spatial> for DataValue {
+    fn from(v: Spatial) -> Self {
+        DataValue::Spatial(v)
+    }
+}
+
+impl From<HierarchyId> for DataValue {
+    fn from(v: HierarchyId) -> Self {
+        DataValue::HierarchyId(v)
+    }
+}
+
 impl From<Vec<u8>> for DataValue {
     fn from(v: Vec<u8>) -> Self {
         DataValue::Binary(v)
@@ -140,13 +217,16 @@ impl From<DateTime<FixedOffset>> for DataValue {
     }
 }
 
+// Comparisons with Rust values accept every variant holding that kind of value, so
+// `real`, `money` and `xml` values compare with `f64`, `Decimal` and strings like
+// `float`, `decimal` and text values do.
 impl PartialEq<i32> for DataValue {
     fn eq(&self, other: &i32) -> bool {
         match self {
             DataValue::Int(v) => v == other,
             DataValue::TinyInt(v) => (*v as i32) == *other,
             DataValue::SmallInt(v) => (*v as i32) == *other,
-            DataValue::BigInt(v) => (*v as i64) == (*other as i64),
+            DataValue::BigInt(v) => *v == (*other as i64),
             _ => false,
         }
     }
@@ -165,6 +245,16 @@ impl PartialEq<f64> for DataValue {
     fn eq(&self, other: &f64) -> bool {
         match self {
             DataValue::Float(v) => v == other,
+            DataValue::Real(v) => f64::from(*v) == *other,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<f32> for DataValue {
+    fn eq(&self, other: &f32) -> bool {
+        match self {
+            DataValue::Real(v) => v == other,
             _ => false,
         }
     }
@@ -173,7 +263,7 @@ impl PartialEq<f64> for DataValue {
 impl PartialEq<Decimal> for DataValue {
     fn eq(&self, other: &Decimal) -> bool {
         match self {
-            DataValue::Decimal(v) => v == other,
+            DataValue::Decimal(v) | DataValue::Money(v) => v == other,
             _ => false,
         }
     }
@@ -182,7 +272,7 @@ impl PartialEq<Decimal> for DataValue {
 impl PartialEq<&str> for DataValue {
     fn eq(&self, other: &&str) -> bool {
         match self {
-            DataValue::Text(v) => v == other,
+            DataValue::Text(v) | DataValue::Xml(v) => v == other,
             _ => false,
         }
     }
@@ -191,7 +281,7 @@ impl PartialEq<&str> for DataValue {
 impl PartialEq<String> for DataValue {
     fn eq(&self, other: &String) -> bool {
         match self {
-            DataValue::Text(v) => v == other,
+            DataValue::Text(v) | DataValue::Xml(v) => v == other,
             _ => false,
         }
     }
@@ -262,9 +352,36 @@ impl PartialEq<Vec<u8>> for DataValue {
     }
 }
 
-impl Default for DataValue {
-    fn default() -> Self {
-        DataValue::Null
+/// Text of the value with ISO 8601 dates and times (`2024-06-01T08:30:00-05:00`),
+/// uppercase hexadecimal binary (`0x1F00`), uppercase GUIDs, WKT for spatial values
+/// and `NULL` for NULL.
+impl fmt::Display for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataValue::Null => f.write_str("NULL"),
+            DataValue::TinyInt(v) => write!(f, "{v}"),
+            DataValue::SmallInt(v) => write!(f, "{v}"),
+            DataValue::Int(v) => write!(f, "{v}"),
+            DataValue::BigInt(v) => write!(f, "{v}"),
+            DataValue::Real(v) => write!(f, "{v}"),
+            DataValue::Float(v) => write!(f, "{v}"),
+            DataValue::Decimal(v) | DataValue::Money(v) => write!(f, "{v}"),
+            DataValue::Numeric(v) => write!(f, "{v}"),
+            DataValue::Bool(v) => write!(f, "{v}"),
+            DataValue::Text(s) | DataValue::Xml(s) => f.write_str(s),
+            DataValue::Binary(bytes) | DataValue::Udt { bytes, .. } => {
+                f.write_str("0x")?;
+                bytes.iter().try_for_each(|b| write!(f, "{b:02X}"))
+            }
+            DataValue::Guid(v) => write!(f, "{}", v.to_string().to_uppercase()),
+            DataValue::Date(v) => write!(f, "{}", v.format("%Y-%m-%d")),
+            DataValue::Time(v) => write!(f, "{}", v.format("%H:%M:%S%.f")),
+            DataValue::DateTime(v) => write!(f, "{}", v.format("%Y-%m-%dT%H:%M:%S%.f")),
+            DataValue::DateTimeOffset(v) => write!(f, "{}", v.format("%Y-%m-%dT%H:%M:%S%.f%:z")),
+            DataValue::Variant { value, .. } => write!(f, "{value}"),
+            DataValue::Spatial(v) => write!(f, "{v}"),
+            DataValue::HierarchyId(v) => write!(f, "{v}"),
+        }
     }
 }
 
@@ -273,3 +390,32 @@ impl DataValue {
         matches!(self, DataValue::Null)
     }
 }
+
+impl DataValue {
+    /// The instant of a date and time value: `DateTime` is read as wall-clock time in
+    /// `zone`, `DateTimeOffset` already names one. `None` for other values and for
+    /// times that do not exist in `zone`.
+    pub fn to_zoned(&self, zone: &ServerTimeZone) -> Option<DateTime<FixedOffset>> {
+        match self {
+            DataValue::DateTime(v) => zone.from_local(v),
+            DataValue::DateTimeOffset(v) => Some(*v),
+            DataValue::Variant { value, .. } => value.to_zoned(zone),
+            _ => None,
+        }
+    }
+
+    /// [`to_zoned`](Self::to_zoned) in UTC.
+    pub fn to_utc(&self, zone: &ServerTimeZone) -> Option<DateTime<Utc>> {
+        self.to_zoned(zone).map(|v| v.with_timezone(&Utc))
+    }
+
+    /// The value to bind for a `datetime` or `datetime2` column of a server in `zone`:
+    /// `DateTimeOffset` becomes that instant's wall-clock time there. Other values are
+    /// returned unchanged.
+    pub fn to_server_local(&self, zone: &ServerTimeZone) -> DataValue {
+        match self {
+            DataValue::DateTimeOffset(v) => DataValue::DateTime(zone.to_local(v)),
+            other => other.clone(),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::*;
+
+#[test]
+fn hashes_and_compares_values_structurally() {
+    let mut counts: HashMap<DataValue, usize> = HashMap::new();
+    for value in [
+        DataValue::Int(1),
+        DataValue::BigInt(1),
+        DataValue::Int(1),
+        DataValue::Float(f64::NAN),
+        DataValue::Float(f64::NAN),
+        DataValue::Float(-0.0),
+        DataValue::Float(0.0),
+        DataValue::Decimal(Decimal::new(150, 2)),
+        DataValue::Decimal(Decimal::new(15, 1)),
+    ] {
+        *counts.entry(value).or_default() += 1;
+    }
+    assert_eq!(counts[&DataValue::Int(1)], 2);
+    assert_eq!(counts[&DataValue::BigInt(1)], 1);
+    assert_eq!(counts[&DataValue::Float(f64::NAN)], 2);
+    assert_eq!(counts[&DataValue::Float(0.0)], 2);
+    assert_eq!(counts[&DataValue::Decimal(Decimal::new(15, 1))], 2);
+    assert_ne!(
+        DataValue::Numeric(Numeric::new(150, 2).unwrap()),
+        DataValue::Numeric(Numeric::new(15, 1).unwrap())
+    );
+}
+
+#[test]
+fn orders_values_like_sql_server() {
+    let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let mut values = vec![
+        DataValue::DateTime(date.and_hms_opt(12, 0, 0).unwrap()),
+        DataValue::Date(date),
+        DataValue::Text("b".into()),
+        DataValue::Text("A".into()),
+        DataValue::Text("a".into()),
+        DataValue::Float(2.5),
+        DataValue::BigInt(2),
+        DataValue::Int(2),
+        DataValue::Decimal(Decimal::new(-15, 1)),
+        DataValue::Bool(true),
+        DataValue::Binary(vec![1]),
+        DataValue::Guid(Uuid::nil()),
+        DataValue::Null,
+    ];
+    values.sort();
+    let expected = vec![
+        DataValue::Null,
+        DataValue::Guid(Uuid::nil()),
+        DataValue::Binary(vec![1]),
+        DataValue::Text("A".into()),
+        DataValue::Text("a".into()),
+        DataValue::Text("b".into()),
+        DataValue::Decimal(Decimal::new(-15, 1)),
+        DataValue::Bool(true),
+        DataValue::Int(2),
+        DataValue::BigInt(2),
+        DataValue::Float(2.5),
+        DataValue::Date(date),
+        DataValue::DateTime(date.and_hms_opt(12, 0, 0).unwrap()),
+    ];
+    assert_eq!(values, expected);
+
+    // datetimeoffset values compare by their UTC time.
+    let offset = FixedOffset::east_opt(3600).unwrap();
+    let noon = offset.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    assert!(
+        DataValue::DateTimeOffset(noon) < DataValue::DateTime(date.and_hms_opt(11, 30, 0).unwrap())
+    );
+    // Numbers beyond an f64's precision still order exactly.
+    let big = |v: i128| DataValue::Numeric(Numeric::new(v, 0).unwrap());
+    let set: BTreeSet<DataValue> = [big(10i128.pow(37) + 1), big(10i128.pow(37))].into();
+    assert_eq!(set.first(), Some(&big(10i128.pow(37))));
+    // uniqueidentifier sorts by its last group first.
+    let a = Uuid::parse_str("ffffffff-0000-0000-0000-000000000001").unwrap();
+    let b = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+    assert!(DataValue::Guid(a) < DataValue::Guid(b));
+}
+
+#[test]
+fn displays_values_in_iso_formats() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let offset = FixedOffset::west_opt(5 * 3600).unwrap();
+    let cases = [
+        (DataValue::Null, "NULL"),
+        (DataValue::Int(-7), "-7"),
+        (DataValue::Decimal(Decimal::new(1250, 2)), "12.50"),
+        (DataValue::Bool(true), "true"),
+        (DataValue::Binary(vec![0x1F, 0]), "0x1F00"),
+        (DataValue::Date(date), "2024-06-01"),
+        (
+            DataValue::DateTime(date.and_hms_milli_opt(8, 30, 0, 250).unwrap()),
+            "2024-06-01T08:30:00.250",
+        ),
+        (
+            DataValue::DateTimeOffset(offset.with_ymd_and_hms(2024, 6, 1, 8, 30, 0).unwrap()),
+            "2024-06-01T08:30:00-05:00",
+        ),
+        (
+            DataValue::Guid(Uuid::parse_str("6f9619ff-8b86-d011-b42d-00c04fc964ff").unwrap()),
+            "6F9619FF-8B86-D011-B42D-00C04FC964FF",
+        ),
+    ];
+    for (value, text) in cases {
+        assert_eq!(value.to_string(), text);
+    }
+}
+
+#[test]
+fn compares_sql_specific_variants_with_rust_values() {
+    let xml = DataValue::Xml("<a/>".into());
+    assert_eq!(xml, "<a/>");
+    assert_eq!(xml, String::from("<a/>"));
+    assert_ne!(xml, "<b/>");
+    assert_eq!(DataValue::Money(Decimal::new(125, 2)), Decimal::new(125, 2));
+    assert_eq!(DataValue::Real(1.5), 1.5f64);
+    assert_eq!(DataValue::Real(1.5), 1.5f32);
+    assert_ne!(DataValue::Real(0.1), 0.1f64);
+}
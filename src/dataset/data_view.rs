@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::ops::Index;
+
+use super::expression::Scope;
+use super::{DataRow, DataTable, Expression, ExpressionError, RowState, SortOrder};
+
+/// A filtered and sorted projection of a [`DataTable`], like ADO.NET's `DataView`.
+///
+/// The view only holds row positions, so rows are never copied. Deleted rows are not
+/// part of a view. Filters and sorts accept either closures or expression strings
+/// (see [`Expression`] and [`SortOrder`]).
+#[derive(Debug, Clone)]
+pub struct DataView<'a> {
+    table: &'a DataTable,
+    indices: Vec<usize>,
+}
+
+impl<'a> DataView<'a> {
+    /// A view over every row of `table` that is not deleted, in table order.
+    pub fn new(table: &'a DataTable) -> Self {
+        let indices = (0..table.rows.len())
+            .filter(|&i| table.rows[i].state() != RowState::Deleted)
+            .collect();
+        Self { table, indices }
+    }
+
+    /// Keep the rows for which `expression` is TRUE. An empty string keeps every row.
+    pub fn filter(mut self, expression: &str) -> Result<Self, ExpressionError> {
+        if expression.trim().is_empty() {
+            return Ok(self);
+        }
+        let expression = Expression::parse(expression)?;
+        self.check_columns(&expression.columns())?;
+        let mut kept = Vec::with_capacity(self.indices.len());
+        for i in self.indices {
+            if expression.matches_scoped(&Scope::in_table(&self.table.rows[i], self.table))? {
+                kept.push(i);
+            }
+        }
+        self.indices = kept;
+        Ok(self)
+    }
+
+    /// Keep the rows for which `predicate` returns true.
+    pub fn filter_by<F>(mut self, mut predicate: F) -> Self
+    where
+        F: FnMut(&DataRow) -> bool,
+    {
+        let table = self.table;
+        self.indices.retain(|&i| predicate(&table.rows[i]));
+        self
+    }
+
+    /// Sort by a specification such as `"CreatedAt DESC, Name"`. The sort is stable,
+    /// and an empty string leaves the order unchanged.
+    pub fn sort(mut self, order: &str) -> Result<Self, ExpressionError> {
+        let order = SortOrder::parse(order)?;
+        if order.is_empty() {
+            return Ok(self);
+        }
+        self.check_columns(&order.columns())?;
+        let mut keyed = self
+            .indices
+            .iter()
+            .map(|&i| {
+                Ok((
+                    order.key(&Scope::in_table(&self.table.rows[i], self.table))?,
+                    i,
+                ))
+            })
+            .collect::<Result<Vec<_>, ExpressionError>>()?;
+        keyed.sort_by(|(a, _), (b, _)| order.compare(a, b));
+        self.indices = keyed.into_iter().map(|(_, i)| i).collect();
+        Ok(self)
+    }
+
+    /// Sort with a comparison function. The sort is stable.
+    pub fn sort_by<F>(mut self, mut compare: F) -> Self
+    where
+        F: FnMut(&DataRow, &DataRow) -> Ordering,
+    {
+        let table = self.table;
+        self.indices
+            .sort_by(|&a, &b| compare(&table.rows[a], &table.rows[b]));
+        self
+    }
+
+    pub fn table(&self) -> &'a DataTable {
+        self.table
+    }
+
+    /// Positions in the underlying table of the rows in the view, in view order.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&'a DataRow> {
+        let table = self.table;
+        self.indices.get(index).map(|&i| &table.rows[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a DataRow> + '_ {
+        let table = self.table;
+        self.indices.iter().map(move |&i| &table.rows[i])
+    }
+
+    /// Copy the rows of the view, in view order, into a new table with the same
+    /// columns.
+    pub fn to_table(&self) -> DataTable {
+        let mut table = DataTable::new(&self.table.name);
+        table.columns = self.table.columns.clone();
+        table.rows = self.iter().cloned().collect();
+        table
+    }
+
+    // With column metadata, reject unknown names up front instead of failing on the
+    // first row (or not at all for an empty table).
+    fn check_columns(&self, names: &[&str]) -> Result<(), ExpressionError> {
+        let columns = &self.table.columns;
+        if columns.is_empty() {
+            return Ok(());
+        }
+        match names
+            .iter()
+            .find(|n| !columns.iter().any(|c| c.name.eq_ignore_ascii_case(n)))
+        {
+            Some(name) => Err(ExpressionError::UnknownColumn(name.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Index<usize> for DataView<'_> {
+    type Output = DataRow;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.table.rows[self.indices[index]]
+    }
+}
+
+impl<'a, 'v> IntoIterator for &'v DataView<'a> {
+    type Item = &'a DataRow;
+    type IntoIter = Box<dyn Iterator<Item = &'a DataRow> + 'v>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
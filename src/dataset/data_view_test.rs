@@ -0,0 +1,246 @@
+use super::*;
+use rust_decimal::Decimal;
+
+fn orders() -> DataTable {
+    let mut table = DataTable::new("orders");
+    for name in ["id", "status", "amount", "customer"] {
+        table.columns.push(DataColumn {
+            name: name.into(),
+            ..Default::default()
+        });
+    }
+    let rows: [(i32, &str, DataValue, DataValue); 5] = [
+        (1, "PAID", Decimal::new(15000, 2).into(), "ann".into()),
+        (2, "paid  ", Decimal::new(5000, 2).into(), DataValue::Null),
+        (3, "OPEN", 250.into(), "Bob".into()),
+        (4, "PAID", DataValue::Null, "cid".into()),
+        (5, "VOID", 120.5.into(), "Ann".into()),
+    ];
+    for (id, status, amount, customer) in rows {
+        let mut row = DataRow::default();
+        row.cells.insert("id".into(), DataCell::new(id));
+        row.cells.insert("status".into(), DataCell::new(status));
+        row.cells.insert("amount".into(), DataCell::new(amount));
+        row.cells.insert("customer".into(), DataCell::new(customer));
+        table.rows.push(row);
+    }
+    table
+}
+
+fn ids(rows: &[&DataRow]) -> Vec<i32> {
+    rows.iter()
+        .map(|r| match r["id"] {
+            DataValue::Int(id) => id,
+            _ => panic!("unexpected id"),
+        })
+        .collect()
+}
+
+#[test]
+fn filters_with_sql_semantics() {
+    let table = orders();
+    let select = |filter: &str| ids(&table.select(filter, "").unwrap());
+
+    // Case-insensitive, trailing spaces ignored; NULL amounts are unknown, not false.
+    assert_eq!(select("Status = 'paid' AND Amount > 100"), vec![1]);
+    assert_eq!(select("status = 'PAID'"), vec![1, 2, 4]);
+    assert_eq!(select("NOT (amount > 100)"), vec![2]);
+    assert_eq!(select("amount IS NULL OR customer IS NULL"), vec![2, 4]);
+    assert_eq!(select("customer <> 'ann'"), vec![3, 4]);
+    assert_eq!(select("customer LIKE 'a%'"), vec![1, 5]);
+    assert_eq!(select("customer LIKE '[b-c]_[^x]'"), vec![3, 4]);
+    assert_eq!(select("id IN (1, 3, NULL)"), vec![1, 3]);
+    assert_eq!(select("id NOT IN (1, 3, NULL)"), Vec::<i32>::new());
+    assert_eq!(select("amount BETWEEN 100 AND 200"), vec![1, 5]);
+    assert_eq!(select("id * 2 + 1 = 7 OR id % 4 = 0"), vec![3, 4]);
+    assert_eq!(select("id = '2'"), vec![2]);
+}
+
+#[test]
+fn sorts_with_nulls_first() {
+    let table = orders();
+    let sort = |order: &str| ids(&table.select("", order).unwrap());
+    assert_eq!(sort("amount"), vec![4, 2, 5, 1, 3]);
+    assert_eq!(sort("amount DESC"), vec![3, 1, 5, 2, 4]);
+    assert_eq!(sort("[status] ASC, id DESC"), vec![3, 4, 2, 1, 5]);
+    assert_eq!(
+        ids(&table.select("status = 'paid'", "customer DESC").unwrap()),
+        vec![4, 1, 2]
+    );
+}
+
+#[test]
+fn views_filter_and_sort_with_closures() {
+    let mut table = orders();
+    table.delete_row(0).unwrap();
+    let view = table
+        .view()
+        .filter_by(|r| r["id"] != 3)
+        .sort_by(|a, b| ids(&[b]).cmp(&ids(&[a])));
+    assert_eq!(view.indices(), &[4, 3, 1]);
+    assert_eq!(view.len(), 3);
+    assert_eq!(view[0]["status"], "VOID");
+
+    let copy = view.filter("amount IS NOT NULL").unwrap().to_table();
+    assert_eq!(copy.rows.len(), 2);
+    assert_eq!(copy.columns.len(), 4);
+}
+
+#[test]
+fn reports_expression_errors() {
+    let table = orders();
+    assert!(matches!(
+        table.select("status = ", ""),
+        Err(ExpressionError::Syntax { position: 9, .. })
+    ));
+    assert_eq!(
+        table.select("missing = 1", ""),
+        Err(ExpressionError::UnknownColumn("missing".into()))
+    );
+    assert_eq!(
+        table.select("id / 0 = 1", ""),
+        Err(ExpressionError::DivideByZero)
+    );
+    assert!(matches!(
+        table.select("status > 1", ""),
+        Err(ExpressionError::Conversion { .. })
+    ));
+    assert!(matches!(
+        table.select("id + 1", ""),
+        Err(ExpressionError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn evaluates_expressions() {
+    let table = orders();
+    let eval = |text: &str| {
+        Expression::parse(text)
+            .unwrap()
+            .evaluate(&table[0])
+            .unwrap()
+    };
+    assert_eq!(eval("amount * 2"), Decimal::new(30000, 2));
+    assert_eq!(eval("id / 2"), 0);
+    assert_eq!(eval("7 / 2.0"), Decimal::new(35, 1));
+    assert_eq!(eval("status + '-' + customer"), "PAID-ann");
+    assert_eq!(eval("#2024-01-31# = '2024-01-31'"), true);
+    assert!(eval("NULL = NULL").is_null());
+    assert_eq!(
+        Expression::parse("[amount] > id AND x = 1")
+            .unwrap()
+            .columns(),
+        vec!["amount", "id", "x"]
+    );
+}
+
+#[test]
+fn evaluates_functions_and_promotes_types() {
+    let table = orders();
+    let eval = |text: &str| {
+        Expression::parse(text)
+            .unwrap()
+            .evaluate(&table[1])
+            .unwrap_or_else(|e| panic!("{text}: {e}"))
+    };
+    assert_eq!(eval("IIF(ISNULL(customer, 'x') = 'x', 'Y', 'N')"), "Y");
+    assert_eq!(eval("IIF(NULL > 1, 1, 2)"), 2);
+    assert_eq!(eval("COALESCE(customer, status)"), "paid  ");
+    assert!(eval("NULLIF(id, 2)").is_null());
+    assert!(eval("UPPER(customer)").is_null());
+    assert_eq!(eval("CONCAT(customer, '#', id)"), "#2");
+    assert_eq!(eval("LEN(status)"), 4);
+    assert_eq!(
+        eval("SUBSTRING('abcdef', 0, 3) + LEFT('abc', 1) + RIGHT('abc', 5)"),
+        "abaabc"
+    );
+    assert_eq!(
+        eval("REPLACE('Paid paid', 'PAID', 'x') + LTRIM('  y')"),
+        "x xy"
+    );
+    assert_eq!(eval("CHARINDEX('D', status)"), 4);
+    assert_eq!(eval("ROUND(2.345, 2) + ABS(-1)"), Decimal::new(335, 2));
+    assert_eq!(eval("YEAR('2024-02-29') + MONTH(#2024-02-29#)"), 2026);
+    assert_eq!(
+        eval("DATEADD(month, 1, #2024-01-31#)"),
+        DataValue::Date(chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())
+    );
+    assert_eq!(eval("DATEDIFF(year, '2023-12-31', '2024-01-01')"), 1);
+    assert_eq!(eval("DATEDIFF(week, '2024-01-06', '2024-01-07')"), 1);
+    assert_eq!(eval("DATEPART(weekday, '2024-01-07')"), 1);
+
+    // int -> bigint -> decimal -> float
+    assert_eq!(eval("id + 2147483648"), DataValue::BigInt(2_147_483_650));
+    assert_eq!(eval("id * 1.5"), Decimal::new(30, 1));
+    assert_eq!(eval("id * 1e0"), 2.0);
+    assert!(matches!(
+        Expression::parse("id / 0").unwrap().evaluate(&table[1]),
+        Err(ExpressionError::DivideByZero)
+    ));
+    assert!(matches!(
+        Expression::parse("id + 2147483647")
+            .unwrap()
+            .evaluate(&table[1]),
+        Err(ExpressionError::Overflow)
+    ));
+    assert!(matches!(
+        Expression::parse("nope(1)"),
+        Err(ExpressionError::UnknownFunction(_))
+    ));
+    assert!(matches!(
+        Expression::parse("LEN(1, 2)"),
+        Err(ExpressionError::ArgumentCount { .. })
+    ));
+}
+
+#[test]
+fn computes_columns() {
+    let mut table = orders();
+    table.add_computed_column("double", "amount * 2").unwrap();
+    table
+        .add_computed_column("big", "IIF(ISNULL(double, 0) > 250, 'Y', 'N')")
+        .unwrap();
+    table.add_computed_column("total", "SUM(amount)").unwrap();
+    assert_eq!(table[0]["double"], Decimal::new(30000, 2));
+    assert_eq!(table[0]["big"], "Y");
+    assert_eq!(table[3]["big"], "N");
+    assert_eq!(table[0]["total"], 570.5);
+    assert_eq!(table[0].state(), RowState::Unchanged);
+
+    table.set_value(1, "amount", 200).unwrap();
+    assert_eq!(table[1]["double"], 400);
+    assert_eq!(
+        table.set_value(1, "double", 1),
+        Err(DataError::ReadOnlyColumn("double".into()))
+    );
+    assert!(table.add_computed_column("bad", "missing(1)").is_err());
+    assert_eq!(table.columns.len(), 7);
+    assert_eq!(table.expression("double"), Some("amount * 2"));
+    assert!(!table.is_computed("amount") && !table.is_computed("bad"));
+}
+
+#[test]
+fn evaluates_sql_specific_types() {
+    let mut row = DataRow::default();
+    let values = [
+        ("real", DataValue::Real(1.5)),
+        ("price", DataValue::Money(Decimal::new(19990, 4))),
+        ("doc", DataValue::Xml("<a>1</a>".into())),
+        (
+            "variant",
+            DataValue::Variant {
+                base_type: "int".into(),
+                value: Box::new(DataValue::Int(7)),
+            },
+        ),
+    ];
+    for (name, value) in values {
+        row.cells.insert(name.into(), DataCell::new(value));
+    }
+    let eval = |text: &str| Expression::parse(text).unwrap().evaluate(&row).unwrap();
+    assert_eq!(eval("real * 2"), 3.0);
+    assert_eq!(eval("price + 1"), Decimal::new(29990, 4));
+    assert_eq!(eval("variant + 1"), 8);
+    assert_eq!(eval("doc LIKE '<a>%'"), true);
+    assert_eq!(eval("price > real"), true);
+}
@@ -0,0 +1,122 @@
+use thiserror::Error;
+
+use super::{DataValue, SqlType};
+
+/// Errors raised when manipulating in-memory [`DataTable`](super::DataTable)s and rows.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DataError {
+    #[error("row {0} is out of range")]
+    RowOutOfRange(usize),
+    #[error("row has been deleted")]
+    DeletedRow,
+    #[error("column {0:?} does not exist")]
+    ColumnNotFound(String),
+    #[error("primary key column {0:?} cannot be NULL")]
+    NullKey(String),
+    #[error("table {0:?} does not exist")]
+    TableNotFound(String),
+    #[error("relation {0:?} does not exist")]
+    RelationNotFound(String),
+    #[error("relation {0:?} already exists")]
+    DuplicateRelation(String),
+    #[error("foreign key {key:?} violates relation {relation:?}")]
+    ForeignKeyViolation {
+        relation: String,
+        key: Vec<DataValue>,
+    },
+    #[error("key {key:?} already exists for unique constraint on {columns:?}")]
+    ConstraintViolation {
+        columns: Vec<String>,
+        key: Vec<DataValue>,
+    },
+    #[error("{0:?} is not part of the target schema")]
+    MissingSchema(String),
+    #[error("column {0:?} is computed and cannot be set")]
+    ReadOnlyColumn(String),
+    #[error("column {column:?} does not hold valid JSON: {message}")]
+    InvalidJson { column: String, message: String },
+    #[error(transparent)]
+    Expression(#[from] ExpressionError),
+}
+
+/// Errors raised when parsing or evaluating an [`Expression`](super::Expression).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ExpressionError {
+    #[error("syntax error at position {position}: {message}")]
+    Syntax {
+        position: usize,
+        message: &'static str,
+    },
+    #[error("column {0:?} does not exist")]
+    UnknownColumn(String),
+    #[error("type mismatch: {0}")]
+    TypeMismatch(String),
+    #[error("conversion failed when converting {value:?} to {target}")]
+    Conversion { value: String, target: &'static str },
+    #[error("unknown function {0:?}")]
+    UnknownFunction(String),
+    #[error("{function} does not take {count} arguments")]
+    ArgumentCount { function: String, count: usize },
+    #[error("invalid argument to {function}: {message}")]
+    InvalidArgument {
+        function: String,
+        message: &'static str,
+    },
+    #[error("{0}")]
+    UnknownRelation(String),
+    #[error("table {0:?} does not exist")]
+    UnknownTable(String),
+    #[error("{0} need a table to be evaluated against")]
+    RequiresTable(&'static str),
+    #[error("divide by zero")]
+    DivideByZero,
+    #[error("arithmetic overflow")]
+    Overflow,
+}
+
+/// Errors raised by [`DataValue::cast`](super::DataValue::cast).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CastError {
+    #[error("conversion failed when converting {value:?} to {target}")]
+    Conversion { value: String, target: SqlType },
+    #[error("arithmetic overflow converting {value:?} to {target}")]
+    Overflow { value: String, target: SqlType },
+    #[error("explicit conversion from {from} to {target} is not allowed")]
+    NotAllowed { from: &'static str, target: SqlType },
+    #[error("{0} is not a valid type")]
+    InvalidType(SqlType),
+}
+
+/// Error parsing a [`Numeric`](super::Numeric) from text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid numeric value")]
+pub struct ParseNumericError;
+
+/// Errors raised when decoding or parsing a [`HierarchyId`](super::HierarchyId).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum HierarchyIdError {
+    #[error("invalid hierarchyid path {0:?}")]
+    InvalidPath(String),
+    #[error("label {0} is outside the supported range -4168 to 5199")]
+    LabelOutOfRange(i64),
+    #[error("invalid hierarchyid data: {0}")]
+    InvalidBinary(&'static str),
+}
+
+/// Errors raised when decoding or parsing a [`Spatial`](super::Spatial) value.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SpatialError {
+    #[error("spatial data ends unexpectedly")]
+    Truncated,
+    #[error("unsupported spatial serialization version {0}")]
+    UnsupportedVersion(u8),
+    #[error("shape type {0} is not supported")]
+    UnsupportedShape(u8),
+    #[error("invalid spatial data: {0}")]
+    Invalid(&'static str),
+    #[error("WKT syntax error at position {position}: {message}")]
+    Wkt {
+        position: usize,
+        message: &'static str,
+    },
+}
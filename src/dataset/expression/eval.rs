@@ -0,0 +1,703 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::super::aggregate::Accumulator;
+use super::super::{
+    DataError, DataRelation, DataRow, DataSet, DataValue, ExpressionError, RowState,
+};
+use super::{functions, BinaryOp, Expr, RowSource, Scope};
+
+pub(crate) fn evaluate(expr: &Expr, scope: &Scope) -> Result<DataValue, ExpressionError> {
+    Ok(match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Column(name) => operand(column(scope.row, name)?),
+        Expr::Call { function, args } => functions::call(function, args, scope)?,
+        Expr::Aggregate {
+            function,
+            source,
+            column: name,
+        } => {
+            let rows: Vec<&DataRow> = match source {
+                RowSource::Table => scope
+                    .table
+                    .ok_or(ExpressionError::RequiresTable("aggregates"))?
+                    .rows
+                    .iter()
+                    .filter(|r| r.state() != RowState::Deleted)
+                    .collect(),
+                RowSource::Child(relation) => {
+                    let (ds, relation) = related(scope, relation.as_deref(), true)?;
+                    relation.child_rows(ds, scope.row).map_err(relation_error)?
+                }
+            };
+            let mut accumulator = Accumulator::new(*function);
+            for row in rows {
+                match name {
+                    Some(name) => accumulator.add(column(row, name)?)?,
+                    // COUNT(*) counts rows, so feed it a non-NULL value per row.
+                    None => accumulator.add(&DataValue::Bool(true))?,
+                }
+            }
+            accumulator.finish()?
+        }
+        Expr::Parent {
+            relation,
+            column: name,
+        } => {
+            let (ds, relation) = related(scope, relation.as_deref(), false)?;
+            match relation.parent_row(ds, scope.row).map_err(relation_error)? {
+                Some(parent) => operand(column(parent, name)?),
+                None => DataValue::Null,
+            }
+        }
+        Expr::Negate(inner) => negate(evaluate(inner, scope)?)?,
+        Expr::Not(inner) => logical(truth(&evaluate(inner, scope)?)?.map(|b| !b)),
+        Expr::Binary(BinaryOp::And, left, right) => {
+            let left = truth(&evaluate(left, scope)?)?;
+            if left == Some(false) {
+                return Ok(DataValue::Bool(false));
+            }
+            match (left, truth(&evaluate(right, scope)?)?) {
+                (_, Some(false)) => DataValue::Bool(false),
+                (Some(true), Some(true)) => DataValue::Bool(true),
+                _ => DataValue::Null,
+            }
+        }
+        Expr::Binary(BinaryOp::Or, left, right) => {
+            let left = truth(&evaluate(left, scope)?)?;
+            if left == Some(true) {
+                return Ok(DataValue::Bool(true));
+            }
+            match (left, truth(&evaluate(right, scope)?)?) {
+                (_, Some(true)) => DataValue::Bool(true),
+                (Some(false), Some(false)) => DataValue::Bool(false),
+                _ => DataValue::Null,
+            }
+        }
+        Expr::Binary(op, left, right) => {
+            let (left, right) = (evaluate(left, scope)?, evaluate(right, scope)?);
+            match op {
+                BinaryOp::Equal => comparison(&left, &right, Ordering::is_eq)?,
+                BinaryOp::NotEqual => comparison(&left, &right, Ordering::is_ne)?,
+                BinaryOp::Less => comparison(&left, &right, Ordering::is_lt)?,
+                BinaryOp::LessOrEqual => comparison(&left, &right, Ordering::is_le)?,
+                BinaryOp::Greater => comparison(&left, &right, Ordering::is_gt)?,
+                BinaryOp::GreaterOrEqual => comparison(&left, &right, Ordering::is_ge)?,
+                _ => arithmetic(*op, left, right)?,
+            }
+        }
+        Expr::IsNull { expr, negated } => {
+            DataValue::Bool(evaluate(expr, scope)?.is_null() != *negated)
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            negated,
+        } => {
+            let (value, pattern) = (evaluate(expr, scope)?, evaluate(pattern, scope)?);
+            match (text_of(&value), text_of(&pattern)) {
+                (Some(value), Some(pattern)) => DataValue::Bool(like(&value, &pattern) != *negated),
+                _ => DataValue::Null,
+            }
+        }
+        Expr::In {
+            expr,
+            list,
+            negated,
+        } => {
+            let value = evaluate(expr, scope)?;
+            let mut result = Some(false);
+            if value.is_null() {
+                result = None;
+            }
+            for item in list {
+                if result == Some(true) || value.is_null() {
+                    break;
+                }
+                match compare(&value, &evaluate(item, scope)?)? {
+                    Some(Ordering::Equal) => result = Some(true),
+                    None => result = None,
+                    Some(_) => {}
+                }
+            }
+            logical(result.map(|b| b != *negated))
+        }
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let value = evaluate(expr, scope)?;
+            let above = compare(&value, &evaluate(low, scope)?)?.map(Ordering::is_ge);
+            let below = compare(&value, &evaluate(high, scope)?)?.map(Ordering::is_le);
+            let within = match (above, below) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            };
+            logical(within.map(|b| b != *negated))
+        }
+    })
+}
+
+// The relation a `Child` (`as_parent`) or `Parent` reference goes through: the named
+// one, or the only relation linking the current table in that direction.
+fn related<'a>(
+    scope: &Scope<'a>,
+    name: Option<&str>,
+    as_parent: bool,
+) -> Result<(&'a DataSet, &'a DataRelation), ExpressionError> {
+    let (ds, table) = match (scope.dataset, scope.table) {
+        (Some(ds), Some(table)) => (ds, table),
+        _ => {
+            return Err(ExpressionError::RequiresTable(
+                "Parent and Child references",
+            ))
+        }
+    };
+    let links = |r: &&DataRelation| {
+        if as_parent {
+            r.parent_table == table.name
+        } else {
+            r.child_table == table.name
+        }
+    };
+    let mut candidates = ds
+        .relations()
+        .iter()
+        .filter(|r| name.is_none_or(|n| r.name == n))
+        .filter(links);
+    match (candidates.next(), candidates.next()) {
+        (Some(relation), None) => Ok((ds, relation)),
+        (None, _) => Err(ExpressionError::UnknownRelation(match name {
+            Some(name) => format!("relation {name:?} does not link table {:?}", table.name),
+            None => format!("table {:?} has no such relation", table.name),
+        })),
+        (Some(_), Some(_)) => Err(ExpressionError::UnknownRelation(format!(
+            "table {:?} has several relations; name the one to use",
+            table.name
+        ))),
+    }
+}
+
+// A relation whose tables are missing from the data set.
+fn relation_error(error: DataError) -> ExpressionError {
+    match error {
+        DataError::TableNotFound(table) => ExpressionError::UnknownTable(table),
+        other => ExpressionError::UnknownRelation(other.to_string()),
+    }
+}
+
+/// Compare two values like SQL Server with a case-insensitive collation: NULL
+/// compares as unknown (`None`), text ignores case and trailing spaces, numbers
+/// compare across types, and text is converted to the type of the other operand.
+pub(crate) fn compare(a: &DataValue, b: &DataValue) -> Result<Option<Ordering>, ExpressionError> {
+    use DataValue::*;
+    if a.is_null() || b.is_null() {
+        return Ok(None);
+    }
+    if let Some(a) = plain(a) {
+        return compare(&a, b);
+    }
+    if let Some(b) = plain(b) {
+        return compare(a, &b);
+    }
+    Ok(Some(match (a, b) {
+        (Text(x), Text(y)) => compare_text(x, y),
+        (Text(x), other) => return compare(&convert_text(x, other)?, other),
+        (other, Text(y)) => return compare(other, &convert_text(y, other)?),
+        (Bool(x), Bool(y)) => x.cmp(y),
+        _ if is_numeric(a) && is_numeric(b) => compare_numbers(a, b),
+        (Guid(x), Guid(y)) => x.cmp(y),
+        (Binary(x), Binary(y)) => x.cmp(y),
+        (
+            Udt {
+                type_name: t,
+                bytes: x,
+            },
+            Udt {
+                type_name: u,
+                bytes: y,
+            },
+        ) if t.eq_ignore_ascii_case(u) => x.cmp(y),
+        (Spatial(x), Spatial(y)) if x.kind == y.kind => x.to_bytes().cmp(&y.to_bytes()),
+        (HierarchyId(x), HierarchyId(y)) => x.cmp(y),
+        (Time(x), Time(y)) => x.cmp(y),
+        (DateTimeOffset(x), DateTimeOffset(y)) => x.cmp(y),
+        (DateTimeOffset(x), other) => match naive(other) {
+            Some(y) => x.naive_utc().cmp(&y),
+            None => return Err(mismatch("compare", a, b)),
+        },
+        (other, DateTimeOffset(y)) => match naive(other) {
+            Some(x) => x.cmp(&y.naive_utc()),
+            None => return Err(mismatch("compare", a, b)),
+        },
+        _ => match (naive(a), naive(b)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => return Err(mismatch("compare", a, b)),
+        },
+    }))
+}
+
+// The value expressions work with: `real` widens to `float`, `money` becomes
+// `decimal`, as does a `Numeric` that fits (others become `float`), `xml` becomes text
+// and `sql_variant` yields its base value.
+fn plain(value: &DataValue) -> Option<DataValue> {
+    Some(match value {
+        DataValue::Real(v) => DataValue::Float(*v as f64),
+        DataValue::Money(v) => DataValue::Decimal(*v),
+        DataValue::Numeric(n) => n
+            .to_decimal()
+            .map_or(DataValue::Float(n.to_f64()), DataValue::Decimal),
+        DataValue::Xml(s) => DataValue::Text(s.clone()),
+        DataValue::Variant { value, .. } => return Some(operand(value)),
+        _ => return None,
+    })
+}
+
+pub(crate) fn operand(value: &DataValue) -> DataValue {
+    plain(value).unwrap_or_else(|| value.clone())
+}
+
+/// Text of a value as used by `LIKE` and string functions.
+pub(crate) fn text_of(value: &DataValue) -> Option<String> {
+    Some(match value {
+        DataValue::Null => return None,
+        DataValue::Text(s) => s.clone(),
+        DataValue::TinyInt(v) => v.to_string(),
+        DataValue::SmallInt(v) => v.to_string(),
+        DataValue::Int(v) => v.to_string(),
+        DataValue::BigInt(v) => v.to_string(),
+        DataValue::Real(v) => v.to_string(),
+        DataValue::Float(v) => v.to_string(),
+        DataValue::Decimal(v) | DataValue::Money(v) => v.to_string(),
+        DataValue::Numeric(v) => v.to_string(),
+        DataValue::Bool(v) => if *v { "1" } else { "0" }.to_string(),
+        DataValue::Xml(s) => s.clone(),
+        DataValue::Binary(v) | DataValue::Udt { bytes: v, .. } => format!(
+            "0x{}",
+            v.iter().map(|b| format!("{:02X}", b)).collect::<String>()
+        ),
+        DataValue::Variant { value, .. } => return text_of(value),
+        DataValue::Spatial(s) => s.to_wkt(),
+        DataValue::HierarchyId(h) => h.to_string(),
+        DataValue::Guid(v) => v.to_string().to_uppercase(),
+        DataValue::Date(v) => v.format("%Y-%m-%d").to_string(),
+        DataValue::Time(v) => v.format("%H:%M:%S%.f").to_string(),
+        DataValue::DateTime(v) => v.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+        DataValue::DateTimeOffset(v) => v.format("%Y-%m-%d %H:%M:%S%.f %:z").to_string(),
+    })
+}
+
+pub(crate) fn type_name(value: &DataValue) -> &'static str {
+    match value {
+        DataValue::TinyInt(_) => "tinyint",
+        DataValue::SmallInt(_) => "smallint",
+        DataValue::Int(_) => "int",
+        DataValue::BigInt(_) => "bigint",
+        DataValue::Real(_) => "real",
+        DataValue::Float(_) => "float",
+        DataValue::Decimal(_) | DataValue::Numeric(_) => "decimal",
+        DataValue::Money(_) => "money",
+        DataValue::Bool(_) => "bit",
+        DataValue::Text(_) => "nvarchar",
+        DataValue::Xml(_) => "xml",
+        DataValue::Variant { .. } => "sql_variant",
+        DataValue::Udt { .. } => "user-defined type",
+        DataValue::Spatial(s) => s.kind.type_name(),
+        DataValue::HierarchyId(_) => "hierarchyid",
+        DataValue::Binary(_) => "varbinary",
+        DataValue::Guid(_) => "uniqueidentifier",
+        DataValue::Date(_) => "date",
+        DataValue::Time(_) => "time",
+        DataValue::DateTime(_) => "datetime2",
+        DataValue::DateTimeOffset(_) => "datetimeoffset",
+        DataValue::Null => "null",
+    }
+}
+
+pub(crate) fn mismatch(action: &str, a: &DataValue, b: &DataValue) -> ExpressionError {
+    ExpressionError::TypeMismatch(format!(
+        "cannot {} {} and {}",
+        action,
+        type_name(a),
+        type_name(b)
+    ))
+}
+
+// Cell value for a column, matching the name case-insensitively when there is no
+// exact match.
+pub(crate) fn column<'a>(row: &'a DataRow, name: &str) -> Result<&'a DataValue, ExpressionError> {
+    if let Some(value) = row.get(name) {
+        return Ok(value);
+    }
+    row.cells
+        .iter()
+        .find(|(column, _)| column.eq_ignore_ascii_case(name))
+        .map(|(_, cell)| &cell.value)
+        .ok_or_else(|| ExpressionError::UnknownColumn(name.to_string()))
+}
+
+pub(crate) fn truth(value: &DataValue) -> Result<Option<bool>, ExpressionError> {
+    match value {
+        DataValue::Bool(b) => Ok(Some(*b)),
+        DataValue::Null => Ok(None),
+        other => Err(ExpressionError::TypeMismatch(format!(
+            "expected a boolean, found {}",
+            type_name(other)
+        ))),
+    }
+}
+
+pub(crate) fn logical(value: Option<bool>) -> DataValue {
+    value.map(DataValue::Bool).unwrap_or(DataValue::Null)
+}
+
+fn comparison(
+    left: &DataValue,
+    right: &DataValue,
+    test: fn(Ordering) -> bool,
+) -> Result<DataValue, ExpressionError> {
+    Ok(logical(compare(left, right)?.map(test)))
+}
+
+pub(crate) fn compare_text(x: &str, y: &str) -> Ordering {
+    let fold = |s: &str| {
+        s.trim_end_matches(' ')
+            .chars()
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>()
+    };
+    fold(x).cmp(&fold(y))
+}
+
+fn is_numeric(value: &DataValue) -> bool {
+    numeric_rank(value).is_some()
+}
+
+// Precedence of numeric types, from tinyint up to float. `bit` converts to a number
+// when combined with one.
+fn numeric_rank(value: &DataValue) -> Option<u8> {
+    Some(match value {
+        DataValue::Bool(_) => 0,
+        DataValue::TinyInt(_) => 1,
+        DataValue::SmallInt(_) => 2,
+        DataValue::Int(_) => 3,
+        DataValue::BigInt(_) => 4,
+        DataValue::Decimal(_) => 5,
+        DataValue::Float(_) => 6,
+        _ => return None,
+    })
+}
+
+pub(crate) fn to_decimal(value: &DataValue) -> Option<Decimal> {
+    match value {
+        DataValue::Bool(b) => Some(Decimal::from(*b as u8)),
+        DataValue::TinyInt(v) => Some(Decimal::from(*v)),
+        DataValue::SmallInt(v) => Some(Decimal::from(*v)),
+        DataValue::Int(v) => Some(Decimal::from(*v)),
+        DataValue::BigInt(v) => Some(Decimal::from(*v)),
+        DataValue::Decimal(d) => Some(*d),
+        DataValue::Float(f) => Decimal::from_f64(*f),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_f64(value: &DataValue) -> Option<f64> {
+    match value {
+        DataValue::Float(f) => Some(*f),
+        other => to_decimal(other)?.to_f64(),
+    }
+}
+
+pub(crate) fn to_i64(value: &DataValue) -> Option<i64> {
+    match value {
+        DataValue::Bool(b) => Some(*b as i64),
+        DataValue::TinyInt(v) => Some(*v as i64),
+        DataValue::SmallInt(v) => Some(*v as i64),
+        DataValue::Int(v) => Some(*v as i64),
+        DataValue::BigInt(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn compare_numbers(a: &DataValue, b: &DataValue) -> Ordering {
+    if matches!(a, DataValue::Float(_)) || matches!(b, DataValue::Float(_)) {
+        let (x, y) = (to_f64(a).unwrap_or_default(), to_f64(b).unwrap_or_default());
+        x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+    } else {
+        to_decimal(a).cmp(&to_decimal(b))
+    }
+}
+
+fn naive(value: &DataValue) -> Option<NaiveDateTime> {
+    match value {
+        DataValue::Date(d) => d.and_hms_opt(0, 0, 0),
+        DataValue::DateTime(dt) => Some(*dt),
+        _ => None,
+    }
+}
+
+// Convert text to the type of `target`, failing like SQL Server's implicit conversion.
+pub(crate) fn convert_text(text: &str, target: &DataValue) -> Result<DataValue, ExpressionError> {
+    let s = text.trim();
+    let converted = match target {
+        DataValue::Bool(_) => match s.to_ascii_lowercase().as_str() {
+            "1" | "true" => Some(DataValue::Bool(true)),
+            "0" | "false" => Some(DataValue::Bool(false)),
+            _ => None,
+        },
+        DataValue::Float(_) => s.parse().ok().map(DataValue::Float),
+        v if is_numeric(v) => parse_number(s),
+        DataValue::Guid(_) => Uuid::parse_str(s).ok().map(DataValue::Guid),
+        DataValue::Time(_) => ["%H:%M:%S%.f", "%H:%M"]
+            .iter()
+            .find_map(|f| NaiveTime::parse_from_str(s, f).ok())
+            .map(DataValue::Time),
+        DataValue::Date(_) | DataValue::DateTime(_) => parse_datetime(s),
+        DataValue::DateTimeOffset(_) => DateTime::parse_from_rfc3339(s)
+            .or_else(|_| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f %:z"))
+            .ok()
+            .map(DataValue::DateTimeOffset)
+            .or_else(|| parse_datetime(s)),
+        _ => None,
+    };
+    converted.ok_or_else(|| ExpressionError::Conversion {
+        value: text.to_string(),
+        target: type_name(target),
+    })
+}
+
+fn parse_number(s: &str) -> Option<DataValue> {
+    if let Ok(v) = s.parse::<i32>() {
+        return Some(DataValue::Int(v));
+    }
+    if let Ok(v) = s.parse::<i64>() {
+        return Some(DataValue::BigInt(v));
+    }
+    if let Ok(d) = s.parse::<Decimal>() {
+        return Some(DataValue::Decimal(d));
+    }
+    s.parse().ok().map(DataValue::Float)
+}
+
+pub(crate) fn parse_datetime(s: &str) -> Option<DataValue> {
+    if let Some(d) = ["%Y-%m-%d", "%Y%m%d", "%m/%d/%Y"]
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+    {
+        return Some(DataValue::Date(d));
+    }
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%m/%d/%Y %H:%M:%S%.f",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+    .map(DataValue::DateTime)
+}
+
+pub(crate) fn negate(value: DataValue) -> Result<DataValue, ExpressionError> {
+    Ok(match value {
+        DataValue::Null => DataValue::Null,
+        DataValue::Float(f) => DataValue::Float(-f),
+        DataValue::Decimal(d) => DataValue::Decimal(-d),
+        DataValue::Text(s) => return negate(convert_text(&s, &DataValue::Decimal(Decimal::ZERO))?),
+        v => match to_i64(&v) {
+            Some(i) => {
+                let rank = numeric_rank(&v).unwrap_or(3).max(2);
+                narrow(i.checked_neg().ok_or(ExpressionError::Overflow)?, rank)?
+            }
+            None => {
+                return Err(ExpressionError::TypeMismatch(format!(
+                    "cannot negate {}",
+                    type_name(&v)
+                )))
+            }
+        },
+    })
+}
+
+// Arithmetic on the wider of the two operand types, with SQL Server's integer
+// division and errors for overflow and division by zero.
+fn arithmetic(
+    op: BinaryOp,
+    left: DataValue,
+    right: DataValue,
+) -> Result<DataValue, ExpressionError> {
+    if left.is_null() || right.is_null() {
+        return Ok(DataValue::Null);
+    }
+    let (left, right) = match (left, right) {
+        (DataValue::Text(x), DataValue::Text(y)) if op == BinaryOp::Add => {
+            return Ok(DataValue::Text(x + &y));
+        }
+        (DataValue::Text(x), right) if is_numeric(&right) => (convert_text(&x, &right)?, right),
+        (left, DataValue::Text(y)) if is_numeric(&left) => {
+            let right = convert_text(&y, &left)?;
+            (left, right)
+        }
+        (left, right) => (left, right),
+    };
+    let name = match op {
+        BinaryOp::Add => "add",
+        BinaryOp::Subtract => "subtract",
+        BinaryOp::Multiply => "multiply",
+        BinaryOp::Divide => "divide",
+        _ => "take the modulo of",
+    };
+    let rank = match (numeric_rank(&left), numeric_rank(&right)) {
+        (Some(a), Some(b)) => a.max(b).max(1),
+        _ => return Err(mismatch(name, &left, &right)),
+    };
+    match rank {
+        6 => {
+            let (x, y) = (
+                to_f64(&left).unwrap_or_default(),
+                to_f64(&right).unwrap_or_default(),
+            );
+            if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && y == 0.0 {
+                return Err(ExpressionError::DivideByZero);
+            }
+            Ok(DataValue::Float(match op {
+                BinaryOp::Add => x + y,
+                BinaryOp::Subtract => x - y,
+                BinaryOp::Multiply => x * y,
+                BinaryOp::Divide => x / y,
+                _ => x % y,
+            }))
+        }
+        5 => {
+            let (x, y) = (
+                to_decimal(&left).unwrap_or_default(),
+                to_decimal(&right).unwrap_or_default(),
+            );
+            if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && y.is_zero() {
+                return Err(ExpressionError::DivideByZero);
+            }
+            match op {
+                BinaryOp::Add => x.checked_add(y),
+                BinaryOp::Subtract => x.checked_sub(y),
+                BinaryOp::Multiply => x.checked_mul(y),
+                BinaryOp::Divide => x.checked_div(y),
+                _ => x.checked_rem(y),
+            }
+            .map(DataValue::Decimal)
+            .ok_or(ExpressionError::Overflow)
+        }
+        _ => {
+            let (x, y) = (
+                to_i64(&left).unwrap_or_default(),
+                to_i64(&right).unwrap_or_default(),
+            );
+            if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && y == 0 {
+                return Err(ExpressionError::DivideByZero);
+            }
+            let value = match op {
+                BinaryOp::Add => x.checked_add(y),
+                BinaryOp::Subtract => x.checked_sub(y),
+                BinaryOp::Multiply => x.checked_mul(y),
+                BinaryOp::Divide => x.checked_div(y),
+                _ => x.checked_rem(y),
+            }
+            .ok_or(ExpressionError::Overflow)?;
+            narrow(value, rank)
+        }
+    }
+}
+
+// Store an integer result in the integer type of `rank`, failing when it does not fit.
+fn narrow(value: i64, rank: u8) -> Result<DataValue, ExpressionError> {
+    let overflow = |_| ExpressionError::Overflow;
+    Ok(match rank {
+        0 | 1 => DataValue::TinyInt(u8::try_from(value).map_err(overflow)?),
+        2 => DataValue::SmallInt(i16::try_from(value).map_err(overflow)?),
+        3 => DataValue::Int(i32::try_from(value).map_err(overflow)?),
+        _ => DataValue::BigInt(value),
+    })
+}
+
+#[derive(Debug)]
+enum PatternPart {
+    AnyRun,
+    AnyChar,
+    Set {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Char(char),
+}
+
+// SQL Server `LIKE` with `%`, `_`, `[abc]`, `[a-z]` and `[^abc]`, ignoring case.
+pub(crate) fn like(value: &str, pattern: &str) -> bool {
+    let value: Vec<char> = value.chars().flat_map(char::to_lowercase).collect();
+    let parts = parse_pattern(&pattern.to_lowercase());
+    // matched[i]: the parts so far can match the first i characters.
+    let mut matched = vec![false; value.len() + 1];
+    matched[0] = true;
+    for part in &parts {
+        let mut next = vec![false; value.len() + 1];
+        for i in 0..=value.len() {
+            match part {
+                PatternPart::AnyRun => next[i] = matched[i] || (i > 0 && next[i - 1]),
+                _ if i == 0 => {}
+                PatternPart::AnyChar => next[i] = matched[i - 1],
+                PatternPart::Char(c) => next[i] = matched[i - 1] && value[i - 1] == *c,
+                PatternPart::Set { negated, ranges } => {
+                    let c = value[i - 1];
+                    let inside = ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+                    next[i] = matched[i - 1] && inside != *negated;
+                }
+            }
+        }
+        matched = next;
+    }
+    matched[value.len()]
+}
+
+fn parse_pattern(pattern: &str) -> Vec<PatternPart> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '%' => parts.push(PatternPart::AnyRun),
+            '_' => parts.push(PatternPart::AnyChar),
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                // `[]` and an unclosed `[` are literal brackets.
+                Some(len) if len > 0 => {
+                    let mut set = &chars[i + 1..i + 1 + len];
+                    let negated = set.len() > 1 && set[0] == '^';
+                    if negated {
+                        set = &set[1..];
+                    }
+                    let mut ranges = Vec::new();
+                    let mut j = 0;
+                    while j < set.len() {
+                        if j + 2 < set.len() && set[j + 1] == '-' {
+                            ranges.push((set[j], set[j + 2]));
+                            j += 3;
+                        } else {
+                            ranges.push((set[j], set[j]));
+                            j += 1;
+                        }
+                    }
+                    parts.push(PatternPart::Set { negated, ranges });
+                    i += len + 1;
+                }
+                _ => parts.push(PatternPart::Char('[')),
+            },
+            c => parts.push(PatternPart::Char(c)),
+        }
+        i += 1;
+    }
+    parts
+}
@@ -0,0 +1,125 @@
+use rust_decimal::Decimal;
+
+use super::*;
+use crate::dataset::DataCell;
+
+fn row(cells: &[(&str, DataValue)]) -> DataRow {
+    let mut row = DataRow::default();
+    for (name, value) in cells {
+        row.cells
+            .insert(name.to_string(), DataCell::new(value.clone()));
+    }
+    row
+}
+
+fn eval(text: &str) -> Result<DataValue, ExpressionError> {
+    Expression::parse(text)?.evaluate(&DataRow::default())
+}
+
+#[test]
+fn promotes_arithmetic_like_sql_server() {
+    assert_eq!(eval("7 / 2"), Ok(DataValue::Int(3)));
+    assert_eq!(eval("-7 / 2"), Ok(DataValue::Int(-3)));
+    assert_eq!(eval("-7 % 3"), Ok(DataValue::Int(-1)));
+    assert_eq!(eval("7 / 2.0"), Ok(DataValue::Decimal(Decimal::new(35, 1))));
+    assert_eq!(eval("1 + 2147483648"), Ok(DataValue::BigInt(2147483649)));
+    assert_eq!(eval("'1' + 2"), Ok(DataValue::Int(3)));
+    assert_eq!(eval("'a' + 'b'"), Ok(DataValue::Text("ab".into())));
+    assert_eq!(eval("1 + NULL"), Ok(DataValue::Null));
+    assert!(matches!(
+        eval("'x' + 1"),
+        Err(ExpressionError::Conversion { .. })
+    ));
+    // `bit` is numeric, dates are not.
+    assert_eq!(eval("TRUE + 1"), Ok(DataValue::Int(2)));
+    assert_eq!(
+        eval("#2024-01-01# * 2"),
+        Err(ExpressionError::TypeMismatch(
+            "cannot multiply date and int".into()
+        ))
+    );
+}
+
+#[test]
+fn fails_on_integer_overflow() {
+    assert_eq!(eval("2147483647 + 1"), Err(ExpressionError::Overflow));
+    assert_eq!(eval("-2147483647 - 2"), Err(ExpressionError::Overflow));
+    assert_eq!(eval("65536 * 65536"), Err(ExpressionError::Overflow));
+    assert_eq!(
+        eval("9223372036854775807 + 1"),
+        Err(ExpressionError::Overflow)
+    );
+    // The result keeps the wider operand type, as in SQL Server.
+    let small = row(&[("n", DataValue::SmallInt(i16::MAX))]);
+    let evaluate = |text: &str| Expression::parse(text).unwrap().evaluate(&small);
+    assert_eq!(evaluate("n + n"), Err(ExpressionError::Overflow));
+    assert_eq!(evaluate("n + 1"), Ok(DataValue::Int(32768)));
+    let tiny = row(&[("n", DataValue::TinyInt(200))]);
+    assert_eq!(
+        Expression::parse("-n").unwrap().evaluate(&tiny),
+        Ok(DataValue::SmallInt(-200))
+    );
+}
+
+#[test]
+fn fails_on_division_by_zero() {
+    assert_eq!(eval("1 / 0"), Err(ExpressionError::DivideByZero));
+    assert_eq!(eval("1 % 0"), Err(ExpressionError::DivideByZero));
+    assert_eq!(eval("1.5 / 0"), Err(ExpressionError::DivideByZero));
+    let zero = row(&[("x", DataValue::Float(0.0))]);
+    assert_eq!(
+        Expression::parse("1 / x").unwrap().evaluate(&zero),
+        Err(ExpressionError::DivideByZero)
+    );
+    // NULL wins before the division is attempted.
+    assert_eq!(eval("NULL / 0"), Ok(DataValue::Null));
+}
+
+#[test]
+fn compares_with_three_valued_logic() {
+    let row = row(&[("a", DataValue::Null), ("b", DataValue::Int(1))]);
+    let matches = |text: &str| Expression::parse(text).unwrap().matches(&row).unwrap();
+    assert!(!matches("a = 1"));
+    assert!(!matches("NOT a = 1"));
+    assert!(matches("a IS NULL"));
+    assert!(matches("a = 1 OR b = 1"));
+    assert!(!matches("a = 1 AND b = 1"));
+    assert!(!matches("b NOT IN (2, NULL)"));
+    assert!(matches("b IN (NULL, 1)"));
+    assert!(matches("'abc ' = 'ABC'"));
+    assert!(matches("b BETWEEN 0 AND 1"));
+}
+
+#[test]
+fn matches_like_patterns_and_escapes() {
+    let like = |value: &str, pattern: &str| {
+        let row = row(&[("v", value.into()), ("p", pattern.into())]);
+        Expression::parse("v LIKE p")
+            .unwrap()
+            .matches(&row)
+            .unwrap()
+    };
+    assert!(like("Abc", "a%"));
+    assert!(like("abc", "_b_"));
+    assert!(!like("abcd", "_b_"));
+    assert!(like("b", "[a-c]"));
+    assert!(!like("b", "[^a-c]"));
+    // Brackets escape the wildcards and themselves.
+    assert!(like("50%", "50[%]"));
+    assert!(!like("500", "50[%]"));
+    assert!(like("a_b", "a[_]b"));
+    assert!(!like("axb", "a[_]b"));
+    assert!(like("[x]", "[[]x]"));
+    // An empty or unclosed bracket is literal.
+    assert!(like("a[]", "a[]"));
+    assert!(like("a[b", "a[b"));
+    assert_eq!(eval("NULL LIKE 'a'"), Ok(DataValue::Null));
+}
+
+#[test]
+fn reports_unknown_columns() {
+    assert_eq!(
+        eval("missing + 1"),
+        Err(ExpressionError::UnknownColumn("missing".into()))
+    );
+}
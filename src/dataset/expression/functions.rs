@@ -0,0 +1,424 @@
+use std::cmp::Ordering;
+
+use chrono::{Datelike, Duration, Local, Months, NaiveDateTime, TimeZone, Timelike};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use super::super::{DataValue, ExpressionError};
+use super::eval::{compare, evaluate, negate, parse_datetime, text_of, to_i64, truth, type_name};
+use super::{Expr, Scope};
+
+/// Least and most arguments a scalar function accepts, or `None` if it is unknown.
+pub(crate) fn arity(function: &str) -> Option<(usize, usize)> {
+    Some(match function {
+        "GETDATE" => (0, 0),
+        "LEN" | "UPPER" | "LOWER" | "TRIM" | "LTRIM" | "RTRIM" | "YEAR" | "MONTH" | "DAY"
+        | "ABS" => (1, 1),
+        "ROUND" => (1, 2),
+        "ISNULL" | "NULLIF" | "LEFT" | "RIGHT" | "CHARINDEX" | "DATEPART" => (2, 2),
+        "IIF" | "SUBSTRING" | "REPLACE" | "DATEADD" | "DATEDIFF" => (3, 3),
+        "COALESCE" | "CONCAT" => (1, usize::MAX),
+        _ => return None,
+    })
+}
+
+/// Functions whose first argument is a date part such as `day` or `month`.
+pub(crate) fn takes_date_part(function: &str) -> bool {
+    matches!(function, "DATEPART" | "DATEADD" | "DATEDIFF")
+}
+
+pub(crate) fn call(
+    function: &str,
+    args: &[Expr],
+    scope: &Scope,
+) -> Result<DataValue, ExpressionError> {
+    let arg = |i: usize| evaluate(&args[i], scope);
+    // Functions that handle NULL themselves or evaluate arguments lazily.
+    match function {
+        "IIF" => {
+            return if truth(&arg(0)?)? == Some(true) {
+                arg(1)
+            } else {
+                arg(2)
+            }
+        }
+        "ISNULL" => {
+            let value = arg(0)?;
+            return if value.is_null() { arg(1) } else { Ok(value) };
+        }
+        "COALESCE" => {
+            for expr in args {
+                let value = evaluate(expr, scope)?;
+                if !value.is_null() {
+                    return Ok(value);
+                }
+            }
+            return Ok(DataValue::Null);
+        }
+        "CONCAT" => {
+            let mut text = String::new();
+            for expr in args {
+                if let Some(part) = text_of(&evaluate(expr, scope)?) {
+                    text.push_str(&part);
+                }
+            }
+            return Ok(DataValue::Text(text));
+        }
+        "NULLIF" => {
+            let (value, other) = (arg(0)?, arg(1)?);
+            return Ok(match compare(&value, &other)? {
+                Some(Ordering::Equal) => DataValue::Null,
+                _ => value,
+            });
+        }
+        "GETDATE" => return Ok(DataValue::DateTime(Local::now().naive_local())),
+        _ => {}
+    }
+    let values = args
+        .iter()
+        .map(|expr| evaluate(expr, scope))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Every other function returns NULL when an argument is NULL.
+    if values.iter().any(DataValue::is_null) {
+        return Ok(DataValue::Null);
+    }
+    let text = |i: usize| text_of(&values[i]).unwrap_or_default();
+    let invalid = |message: &'static str| ExpressionError::InvalidArgument {
+        function: function.to_string(),
+        message,
+    };
+    Ok(match function {
+        "LEN" => count(text(0).trim_end_matches(' ').chars().count())?,
+        "UPPER" => DataValue::Text(text(0).to_uppercase()),
+        "LOWER" => DataValue::Text(text(0).to_lowercase()),
+        "TRIM" => DataValue::Text(text(0).trim_matches(' ').to_string()),
+        "LTRIM" => DataValue::Text(text(0).trim_start_matches(' ').to_string()),
+        "RTRIM" => DataValue::Text(text(0).trim_end_matches(' ').to_string()),
+        "SUBSTRING" => {
+            let chars: Vec<char> = text(0).chars().collect();
+            let (start, length) = (integer(&values[1])?, integer(&values[2])?);
+            if length < 0 {
+                return Err(invalid("length cannot be negative"));
+            }
+            // Positions are 1-based; the part before the first character is dropped.
+            let first = start.max(1);
+            let end = start.saturating_add(length).min(chars.len() as i64 + 1);
+            let taken: String = if end > first {
+                chars[(first - 1) as usize..(end - 1) as usize]
+                    .iter()
+                    .collect()
+            } else {
+                String::new()
+            };
+            DataValue::Text(taken)
+        }
+        "LEFT" | "RIGHT" => {
+            let chars: Vec<char> = text(0).chars().collect();
+            let n = usize::try_from(integer(&values[1])?)
+                .map_err(|_| invalid("length cannot be negative"))?
+                .min(chars.len());
+            let taken = if function == "LEFT" {
+                &chars[..n]
+            } else {
+                &chars[chars.len() - n..]
+            };
+            DataValue::Text(taken.iter().collect())
+        }
+        "REPLACE" => DataValue::Text(replace(&text(0), &text(1), &text(2))),
+        "CHARINDEX" => count(find(&text(1), &text(0)).map_or(0, |i| i + 1))?,
+        "YEAR" => DataValue::Int(moment(&values[0], false)?.year()),
+        "MONTH" => DataValue::Int(moment(&values[0], false)?.month() as i32),
+        "DAY" => DataValue::Int(moment(&values[0], false)?.day() as i32),
+        "DATEPART" => {
+            let value = date_part(&DatePart::parse(&values[0])?, moment(&values[1], false)?);
+            DataValue::Int(value as i32)
+        }
+        "DATEADD" => date_add(
+            DatePart::parse(&values[0])?,
+            integer(&values[1])?,
+            &values[2],
+        )?,
+        "DATEDIFF" => {
+            let part = DatePart::parse(&values[0])?;
+            let start = moment(&values[1], true)?;
+            let end = moment(&values[2], true)?;
+            let diff = date_diff(&part, start, end);
+            DataValue::Int(i32::try_from(diff).map_err(|_| ExpressionError::Overflow)?)
+        }
+        "ABS" => {
+            let value = values[0].clone();
+            match compare(&value, &DataValue::Int(0))? {
+                Some(Ordering::Less) => negate(value)?,
+                _ => value,
+            }
+        }
+        "ROUND" => {
+            let places = match values.get(1) {
+                Some(v) => integer(v)?,
+                None => 0,
+            };
+            round(values[0].clone(), places)?
+        }
+        _ => return Err(ExpressionError::UnknownFunction(function.to_string())),
+    })
+}
+
+fn count(n: usize) -> Result<DataValue, ExpressionError> {
+    i32::try_from(n)
+        .map(DataValue::Int)
+        .map_err(|_| ExpressionError::Overflow)
+}
+
+fn integer(value: &DataValue) -> Result<i64, ExpressionError> {
+    if let Some(i) = to_i64(value) {
+        return Ok(i);
+    }
+    match value {
+        DataValue::Decimal(d) => {
+            rust_decimal::prelude::ToPrimitive::to_i64(&d.trunc()).ok_or(ExpressionError::Overflow)
+        }
+        DataValue::Float(f) if f.is_finite() => Ok(f.trunc() as i64),
+        DataValue::Text(s) => s.trim().parse().map_err(|_| ExpressionError::Conversion {
+            value: s.clone(),
+            target: "int",
+        }),
+        other => Err(ExpressionError::TypeMismatch(format!(
+            "expected a number, found {}",
+            type_name(other)
+        ))),
+    }
+}
+
+fn chars_equal(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+// Character index of the first case-insensitive occurrence of `needle`.
+fn find(haystack: &str, needle: &str) -> Option<usize> {
+    let haystack: Vec<char> = haystack.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() {
+        return None;
+    }
+    haystack
+        .windows(needle.len())
+        .position(|w| w.iter().zip(&needle).all(|(&a, &b)| chars_equal(a, b)))
+}
+
+// Replace every case-insensitive occurrence, like REPLACE under a CI collation.
+fn replace(text: &str, from: &str, to: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let from: Vec<char> = from.chars().collect();
+    if from.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let matches = chars.len() - i >= from.len()
+            && chars[i..i + from.len()]
+                .iter()
+                .zip(&from)
+                .all(|(&a, &b)| chars_equal(a, b));
+        if matches {
+            out.push_str(to);
+            i += from.len();
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn round(value: DataValue, places: i64) -> Result<DataValue, ExpressionError> {
+    let places32 = i32::try_from(places).map_err(|_| ExpressionError::Overflow)?;
+    Ok(match value {
+        DataValue::Float(f) => {
+            let factor = 10f64.powi(places32);
+            DataValue::Float((f * factor).round() / factor)
+        }
+        DataValue::Decimal(d) if places >= 0 => DataValue::Decimal(
+            d.round_dp_with_strategy(places as u32, RoundingStrategy::MidpointAwayFromZero),
+        ),
+        DataValue::Decimal(d) => {
+            let factor = Decimal::from(
+                10i64
+                    .checked_pow((-places) as u32)
+                    .ok_or(ExpressionError::Overflow)?,
+            );
+            DataValue::Decimal(
+                (d / factor).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                    * factor,
+            )
+        }
+        v if places >= 0 => v,
+        v => {
+            let i = to_i64(&v).ok_or_else(|| {
+                ExpressionError::TypeMismatch(format!("cannot round {}", type_name(&v)))
+            })?;
+            let factor = 10i64
+                .checked_pow((-places) as u32)
+                .ok_or(ExpressionError::Overflow)?;
+            let rounded = (i + i.signum() * factor / 2) / factor * factor;
+            match v {
+                DataValue::BigInt(_) => DataValue::BigInt(rounded),
+                _ => DataValue::Int(i32::try_from(rounded).map_err(|_| ExpressionError::Overflow)?),
+            }
+        }
+    })
+}
+
+enum DatePart {
+    Year,
+    Quarter,
+    Month,
+    DayOfYear,
+    Day,
+    Week,
+    Weekday,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+}
+
+impl DatePart {
+    fn parse(value: &DataValue) -> Result<Self, ExpressionError> {
+        let name = text_of(value).unwrap_or_default().to_ascii_lowercase();
+        Ok(match name.as_str() {
+            "year" | "yy" | "yyyy" => Self::Year,
+            "quarter" | "qq" | "q" => Self::Quarter,
+            "month" | "mm" | "m" => Self::Month,
+            "dayofyear" | "dy" | "y" => Self::DayOfYear,
+            "day" | "dd" | "d" => Self::Day,
+            "week" | "wk" | "ww" => Self::Week,
+            "weekday" | "dw" | "w" => Self::Weekday,
+            "hour" | "hh" => Self::Hour,
+            "minute" | "mi" | "n" => Self::Minute,
+            "second" | "ss" | "s" => Self::Second,
+            "millisecond" | "ms" => Self::Millisecond,
+            _ => {
+                return Err(ExpressionError::InvalidArgument {
+                    function: "date function".into(),
+                    message: "unknown date part",
+                })
+            }
+        })
+    }
+
+    fn is_time(&self) -> bool {
+        matches!(
+            self,
+            Self::Hour | Self::Minute | Self::Second | Self::Millisecond
+        )
+    }
+}
+
+// Date and time of a value. `utc` converts datetimeoffset values to UTC, as DATEDIFF
+// does; otherwise their local time is used.
+fn moment(value: &DataValue, utc: bool) -> Result<NaiveDateTime, ExpressionError> {
+    let value = match value {
+        DataValue::Text(s) => {
+            parse_datetime(s.trim()).ok_or_else(|| ExpressionError::Conversion {
+                value: s.clone(),
+                target: "datetime2",
+            })?
+        }
+        other => other.clone(),
+    };
+    match value {
+        DataValue::Date(d) => Ok(d.and_time(chrono::NaiveTime::MIN)),
+        DataValue::DateTime(dt) => Ok(dt),
+        DataValue::DateTimeOffset(dt) if utc => Ok(dt.naive_utc()),
+        DataValue::DateTimeOffset(dt) => Ok(dt.naive_local()),
+        other => Err(ExpressionError::TypeMismatch(format!(
+            "expected a date, found {}",
+            type_name(&other)
+        ))),
+    }
+}
+
+fn date_part(part: &DatePart, dt: NaiveDateTime) -> i64 {
+    match part {
+        DatePart::Year => dt.year() as i64,
+        DatePart::Quarter => (dt.month0() / 3 + 1) as i64,
+        DatePart::Month => dt.month() as i64,
+        DatePart::DayOfYear => dt.ordinal() as i64,
+        DatePart::Day => dt.day() as i64,
+        // Weeks start on Sunday and January 1st is in week 1 (SET DATEFIRST 7).
+        DatePart::Week => {
+            let jan1 = dt.with_ordinal(1).unwrap_or(dt);
+            ((dt.ordinal0() + jan1.weekday().num_days_from_sunday()) / 7 + 1) as i64
+        }
+        DatePart::Weekday => (dt.weekday().num_days_from_sunday() + 1) as i64,
+        DatePart::Hour => dt.hour() as i64,
+        DatePart::Minute => dt.minute() as i64,
+        DatePart::Second => dt.second() as i64,
+        DatePart::Millisecond => (dt.nanosecond() % 1_000_000_000 / 1_000_000) as i64,
+    }
+}
+
+fn date_add(part: DatePart, n: i64, value: &DataValue) -> Result<DataValue, ExpressionError> {
+    if part.is_time() && matches!(value, DataValue::Date(_)) {
+        return Err(ExpressionError::TypeMismatch(
+            "cannot add a time part to a date".into(),
+        ));
+    }
+    let dt = moment(value, false)?;
+    let overflow = ExpressionError::Overflow;
+    let months = |m: i64| -> Option<NaiveDateTime> {
+        let abs = Months::new(u32::try_from(m.unsigned_abs()).ok()?);
+        if m >= 0 {
+            dt.checked_add_months(abs)
+        } else {
+            dt.checked_sub_months(abs)
+        }
+    };
+    let added = match part {
+        DatePart::Year => months(n.checked_mul(12).ok_or(overflow.clone())?),
+        DatePart::Quarter => months(n.checked_mul(3).ok_or(overflow.clone())?),
+        DatePart::Month => months(n),
+        DatePart::DayOfYear | DatePart::Day | DatePart::Weekday => {
+            Duration::try_days(n).and_then(|d| dt.checked_add_signed(d))
+        }
+        DatePart::Week => Duration::try_weeks(n).and_then(|d| dt.checked_add_signed(d)),
+        DatePart::Hour => Duration::try_hours(n).and_then(|d| dt.checked_add_signed(d)),
+        DatePart::Minute => Duration::try_minutes(n).and_then(|d| dt.checked_add_signed(d)),
+        DatePart::Second => Duration::try_seconds(n).and_then(|d| dt.checked_add_signed(d)),
+        DatePart::Millisecond => {
+            Duration::try_milliseconds(n).and_then(|d| dt.checked_add_signed(d))
+        }
+    }
+    .ok_or(overflow)?;
+    Ok(match value {
+        DataValue::Date(_) => DataValue::Date(added.date()),
+        DataValue::DateTimeOffset(original) => {
+            match original.offset().from_local_datetime(&added).single() {
+                Some(dt) => DataValue::DateTimeOffset(dt),
+                None => return Err(ExpressionError::Overflow),
+            }
+        }
+        _ => DataValue::DateTime(added),
+    })
+}
+
+// Number of `part` boundaries crossed between `start` and `end`, like DATEDIFF.
+fn date_diff(part: &DatePart, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+    let months = |dt: NaiveDateTime| dt.year() as i64 * 12 + dt.month0() as i64;
+    let seconds = |dt: NaiveDateTime| dt.and_utc().timestamp();
+    let days = |dt: NaiveDateTime| dt.date().num_days_from_ce() as i64;
+    match part {
+        DatePart::Year => (end.year() - start.year()) as i64,
+        DatePart::Quarter => months(end).div_euclid(3) - months(start).div_euclid(3),
+        DatePart::Month => months(end) - months(start),
+        DatePart::DayOfYear | DatePart::Day | DatePart::Weekday => days(end) - days(start),
+        // Day 0 of the common era is a Sunday, so this counts Sundays crossed.
+        DatePart::Week => days(end).div_euclid(7) - days(start).div_euclid(7),
+        DatePart::Hour => seconds(end).div_euclid(3600) - seconds(start).div_euclid(3600),
+        DatePart::Minute => seconds(end).div_euclid(60) - seconds(start).div_euclid(60),
+        DatePart::Second => seconds(end) - seconds(start),
+        DatePart::Millisecond => {
+            end.and_utc().timestamp_millis() - start.and_utc().timestamp_millis()
+        }
+    }
+}
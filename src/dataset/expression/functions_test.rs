@@ -0,0 +1,100 @@
+use rust_decimal::Decimal;
+
+use super::*;
+use crate::dataset::DataCell;
+
+fn eval_with(text: &str, cells: &[(&str, DataValue)]) -> Result<DataValue, ExpressionError> {
+    let mut row = DataRow::default();
+    for (name, value) in cells {
+        row.cells
+            .insert(name.to_string(), DataCell::new(value.clone()));
+    }
+    Expression::parse(text)?.evaluate(&row)
+}
+
+fn eval(text: &str) -> Result<DataValue, ExpressionError> {
+    eval_with(text, &[])
+}
+
+#[test]
+fn handles_nulls_in_iif_and_isnull() {
+    // An unknown condition takes the false branch.
+    assert_eq!(eval("IIF(NULL = 1, 'yes', 'no')"), Ok("no".into()));
+    assert_eq!(eval("IIF(1 = 1, 'yes', 'no')"), Ok("yes".into()));
+    assert_eq!(eval("IIF(1 = 1, NULL, 'no')"), Ok(DataValue::Null));
+    // Only the branch taken is evaluated.
+    assert_eq!(eval("IIF(1 = 1, 1, 1 / 0)"), Ok(DataValue::Int(1)));
+    assert_eq!(eval("IIF(1 = 0, 1 / 0, 2)"), Ok(DataValue::Int(2)));
+
+    let null = [("x", DataValue::Null)];
+    assert_eq!(eval_with("ISNULL(x, 5)", &null), Ok(DataValue::Int(5)));
+    assert_eq!(eval_with("ISNULL(x, NULL)", &null), Ok(DataValue::Null));
+    assert_eq!(
+        eval_with("ISNULL(x, 0)", &[("x", DataValue::Int(3))]),
+        Ok(DataValue::Int(3))
+    );
+    // The replacement is only evaluated when it is needed.
+    assert_eq!(eval("ISNULL(2, 1 / 0)"), Ok(DataValue::Int(2)));
+    assert_eq!(eval_with("COALESCE(x, NULL, 'c')", &null), Ok("c".into()));
+    assert_eq!(eval_with("COALESCE(x)", &null), Ok(DataValue::Null));
+    assert_eq!(eval("NULLIF(1, 1)"), Ok(DataValue::Null));
+    assert_eq!(eval("NULLIF(1, 2)"), Ok(DataValue::Int(1)));
+}
+
+#[test]
+fn returns_null_for_null_arguments() {
+    for text in [
+        "LEN(NULL)",
+        "UPPER(NULL)",
+        "SUBSTRING('abc', NULL, 1)",
+        "ABS(NULL)",
+        "ROUND(NULL, 1)",
+        "DATEADD(day, 1, NULL)",
+    ] {
+        assert_eq!(eval(text), Ok(DataValue::Null), "{text}");
+    }
+    assert_eq!(eval("CONCAT('a', NULL, 'b')"), Ok("ab".into()));
+}
+
+#[test]
+fn works_on_text() {
+    assert_eq!(eval("LEN('abc  ')"), Ok(DataValue::Int(3)));
+    assert_eq!(eval("SUBSTRING('abcdef', 2, 3)"), Ok("bcd".into()));
+    assert_eq!(eval("LEFT('abc', 2) + RIGHT('abc', 1)"), Ok("abc".into()));
+    assert_eq!(eval("CHARINDEX('C', 'abc')"), Ok(DataValue::Int(3)));
+    assert_eq!(eval("REPLACE('a-b-c', '-', '')"), Ok("abc".into()));
+    assert_eq!(eval("TRIM('  a  ')"), Ok("a".into()));
+}
+
+#[test]
+fn fails_on_overflow_in_functions() {
+    assert_eq!(
+        eval_with("ABS(x)", &[("x", DataValue::Int(i32::MIN))]),
+        Err(ExpressionError::Overflow)
+    );
+    assert_eq!(
+        eval_with("ABS(x)", &[("x", DataValue::BigInt(i64::MIN))]),
+        Err(ExpressionError::Overflow)
+    );
+    assert_eq!(
+        eval("ROUND(2147483647, -1)"),
+        Err(ExpressionError::Overflow)
+    );
+    assert_eq!(
+        eval("DATEDIFF(second, #1900-01-01#, #2024-01-01#)"),
+        Err(ExpressionError::Overflow)
+    );
+}
+
+#[test]
+fn rounds_half_away_from_zero() {
+    assert_eq!(
+        eval("ROUND(2.5, 0)"),
+        Ok(DataValue::Decimal(Decimal::new(30, 1)))
+    );
+    assert_eq!(
+        eval("ROUND(-2.5, 0)"),
+        Ok(DataValue::Decimal(Decimal::new(-30, 1)))
+    );
+    assert_eq!(eval("ROUND(1234, -2)"), Ok(DataValue::Int(1200)));
+}
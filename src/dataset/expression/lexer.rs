@@ -0,0 +1,135 @@
+use super::super::ExpressionError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    Number(String),
+    Str(String),
+    /// `#2024-01-31#` date literal.
+    Date(String),
+    /// A name; `quoted` when written as `[name]` or `` `name` ``, which is never a keyword.
+    Name {
+        name: String,
+        quoted: bool,
+    },
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub position: usize,
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, TokenKind::Name { name, quoted: false } if name.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.kind, TokenKind::Symbol(s) if s == symbol)
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    "<>", "!=", "<=", ">=", "=", "<", ">", "+", "-", "*", "/", "%", "(", ")", ",", ".",
+];
+
+pub(crate) fn tokenize(text: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (position, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let kind = match c {
+            '\'' => {
+                let (value, next) = delimited(&chars, i, '\'')
+                    .ok_or_else(|| syntax(position, "unterminated string literal"))?;
+                i = next;
+                TokenKind::Str(value)
+            }
+            '[' | '`' => {
+                let close = if c == '[' { ']' } else { '`' };
+                let (name, next) = delimited(&chars, i, close)
+                    .ok_or_else(|| syntax(position, "unterminated column name"))?;
+                i = next;
+                TokenKind::Name { name, quoted: true }
+            }
+            '#' => {
+                let (value, next) = delimited(&chars, i, '#')
+                    .ok_or_else(|| syntax(position, "unterminated date literal"))?;
+                i = next;
+                TokenKind::Date(value)
+            }
+            c if c.is_ascii_digit() || (c == '.' && next_is_digit(&chars, i)) => {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_ascii_digit() || chars[i].1 == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i].1, 'e' | 'E') {
+                    i += 1;
+                    if i < chars.len() && matches!(chars[i].1, '+' | '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].1.is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                TokenKind::Number(chars[start..i].iter().map(|&(_, c)| c).collect())
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                TokenKind::Name {
+                    name: chars[start..i].iter().map(|&(_, c)| c).collect(),
+                    quoted: false,
+                }
+            }
+            _ => {
+                let rest = &text[position..];
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(**s))
+                    .ok_or_else(|| syntax(position, "unexpected character"))?;
+                i += symbol.chars().count();
+                TokenKind::Symbol(symbol)
+            }
+        };
+        tokens.push(Token { kind, position });
+    }
+    Ok(tokens)
+}
+
+// Read a literal delimited by `close` starting at `start`, where a doubled closing
+// character stands for itself. Returns the content and the index after the literal.
+fn delimited(chars: &[(usize, char)], start: usize, close: char) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        let c = chars[i].1;
+        if c == close {
+            if chars.get(i + 1).is_some_and(|&(_, n)| n == close) {
+                value.push(close);
+                i += 2;
+                continue;
+            }
+            return Some((value, i + 1));
+        }
+        value.push(c);
+        i += 1;
+    }
+    None
+}
+
+fn next_is_digit(chars: &[(usize, char)], i: usize) -> bool {
+    chars.get(i + 1).is_some_and(|&(_, c)| c.is_ascii_digit())
+}
+
+pub(crate) fn syntax(position: usize, message: &'static str) -> ExpressionError {
+    ExpressionError::Syntax { position, message }
+}
@@ -0,0 +1,326 @@
+//! ADO.NET-style expressions used to filter and sort tables, e.g.
+//! `Status = 'PAID' AND Amount > 100` or `CreatedAt DESC, Name`.
+//!
+//! Comparisons follow SQL Server with a case-insensitive collation: text compares
+//! without regard to case or trailing spaces, NULL compares as unknown, and a filter
+//! keeps a row only when its expression is TRUE.
+
+mod eval;
+mod functions;
+mod lexer;
+mod parser;
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::value_order::collated;
+use super::{AggregateFunction, DataRow, DataSet, DataTable, DataValue, ExpressionError};
+
+pub(crate) use eval::{compare_text, parse_datetime, text_of, type_name};
+
+// What an expression is evaluated against: the row, and optionally its table and the
+// data set holding it, which aggregates and relation references need.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scope<'a> {
+    pub row: &'a DataRow,
+    pub table: Option<&'a DataTable>,
+    pub dataset: Option<&'a DataSet>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn row(row: &'a DataRow) -> Self {
+        Self {
+            row,
+            table: None,
+            dataset: None,
+        }
+    }
+
+    pub fn in_table(row: &'a DataRow, table: &'a DataTable) -> Self {
+        Self {
+            row,
+            table: Some(table),
+            dataset: None,
+        }
+    }
+}
+
+// Rows an aggregate runs over.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RowSource {
+    /// Every row of the current table.
+    Table,
+    /// The child rows of the current row, through the named relation or the only
+    /// relation whose parent is the current table.
+    Child(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Literal(DataValue),
+    Column(String),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        negated: bool,
+    },
+    In {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    Call {
+        function: String,
+        args: Vec<Expr>,
+    },
+    Aggregate {
+        function: AggregateFunction,
+        source: RowSource,
+        /// `None` for `COUNT(*)`.
+        column: Option<String>,
+    },
+    Parent {
+        relation: Option<String>,
+        column: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+/// A parsed expression over the columns of a row.
+///
+/// Supports column names (bracket names with spaces: `[Order Date]`), string, number,
+/// `#2024-01-31#` date, `TRUE`, `FALSE` and `NULL` literals, arithmetic, comparisons,
+/// `AND`/`OR`/`NOT`, `LIKE` with SQL Server patterns, `IN (...)`, `BETWEEN` and
+/// `IS [NOT] NULL`.
+///
+/// Arithmetic promotes operands like SQL Server (`int` → `bigint` → `decimal` →
+/// `float`) and any NULL operand makes the result NULL. Functions:
+///
+/// - logic: `IIF`, `ISNULL`, `COALESCE`, `NULLIF`
+/// - text: `LEN`, `UPPER`, `LOWER`, `TRIM`, `LTRIM`, `RTRIM`, `SUBSTRING`, `LEFT`,
+///   `RIGHT`, `REPLACE`, `CHARINDEX`, `CONCAT`
+/// - dates: `GETDATE`, `YEAR`, `MONTH`, `DAY`, `DATEPART`, `DATEADD`, `DATEDIFF`
+/// - numbers: `ABS`, `ROUND`
+///
+/// Within a table, `SUM`, `AVG`, `MIN`, `MAX` and `COUNT` aggregate a column over
+/// the whole table (`SUM(Amount)`), or over the child rows of a relation in a
+/// [`DataSet`] (`SUM(Child.Amount)`, `COUNT(Child(order_lines).Id)`), and
+/// `Parent.Name` or `Parent(relation).Name` reads the parent row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    text: String,
+    expr: Expr,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Self, ExpressionError> {
+        Ok(Self {
+            text: text.to_string(),
+            expr: parser::parse(text)?,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Evaluate against a row on its own. Aggregates and relation references need a
+    /// table; use [`Expression::evaluate_in`] for those.
+    pub fn evaluate(&self, row: &DataRow) -> Result<DataValue, ExpressionError> {
+        eval::evaluate(&self.expr, &Scope::row(row))
+    }
+
+    /// Evaluate against a row of table `table` in `ds`, so aggregates and
+    /// `Parent`/`Child` references resolve.
+    pub fn evaluate_in(
+        &self,
+        ds: &DataSet,
+        table: &str,
+        row: &DataRow,
+    ) -> Result<DataValue, ExpressionError> {
+        let table = ds
+            .tables
+            .get(table)
+            .ok_or_else(|| ExpressionError::UnknownTable(table.into()))?;
+        self.evaluate_scoped(&Scope {
+            row,
+            table: Some(table),
+            dataset: Some(ds),
+        })
+    }
+
+    /// Whether the row passes the expression used as a filter. FALSE and unknown
+    /// (NULL) both reject the row, like a `WHERE` clause.
+    pub fn matches(&self, row: &DataRow) -> Result<bool, ExpressionError> {
+        self.matches_scoped(&Scope::row(row))
+    }
+
+    pub(crate) fn evaluate_scoped(&self, scope: &Scope) -> Result<DataValue, ExpressionError> {
+        eval::evaluate(&self.expr, scope)
+    }
+
+    pub(crate) fn matches_scoped(&self, scope: &Scope) -> Result<bool, ExpressionError> {
+        Ok(eval::truth(&self.evaluate_scoped(scope)?)? == Some(true))
+    }
+
+    /// Names of the columns the expression reads.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        collect_columns(&self.expr, &mut columns);
+        columns
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A parsed sort specification such as `CreatedAt DESC, Name`. Each key is an
+/// expression, optionally followed by `ASC` or `DESC`. NULLs sort first in ascending
+/// order, as in SQL Server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortOrder {
+    keys: Vec<(Expr, bool)>,
+}
+
+impl SortOrder {
+    pub fn parse(text: &str) -> Result<Self, ExpressionError> {
+        Ok(Self {
+            keys: parser::parse_sort(text)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Key values of a row, in key order.
+    pub(crate) fn key(&self, scope: &Scope) -> Result<Vec<DataValue>, ExpressionError> {
+        self.keys
+            .iter()
+            .map(|(expr, _)| eval::evaluate(expr, scope))
+            .collect()
+    }
+
+    pub(crate) fn compare(&self, a: &[DataValue], b: &[DataValue]) -> std::cmp::Ordering {
+        self.keys
+            .iter()
+            .zip(a.iter().zip(b))
+            .map(|((_, descending), (a, b))| {
+                let ordering = collated(a, b);
+                if *descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+
+    pub(crate) fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        for (expr, _) in &self.keys {
+            collect_columns(expr, &mut columns);
+        }
+        columns
+    }
+}
+
+fn collect_columns<'a>(expr: &'a Expr, columns: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Column(name) => {
+            if !columns.contains(&name.as_str()) {
+                columns.push(name);
+            }
+        }
+        Expr::Negate(e) | Expr::Not(e) | Expr::IsNull { expr: e, .. } => {
+            collect_columns(e, columns)
+        }
+        Expr::Binary(_, l, r)
+        | Expr::Like {
+            expr: l,
+            pattern: r,
+            ..
+        } => {
+            collect_columns(l, columns);
+            collect_columns(r, columns);
+        }
+        Expr::In { expr, list, .. } => {
+            collect_columns(expr, columns);
+            for item in list {
+                collect_columns(item, columns);
+            }
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            for e in [expr, low, high] {
+                collect_columns(e, columns);
+            }
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_columns(arg, columns);
+            }
+        }
+        Expr::Aggregate {
+            source: RowSource::Table,
+            column: Some(name),
+            ..
+        } => {
+            if !columns.contains(&name.as_str()) {
+                columns.push(name);
+            }
+        }
+        // Columns of other tables.
+        Expr::Aggregate { .. } | Expr::Parent { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod eval_test;
+#[cfg(test)]
+mod functions_test;
+#[cfg(test)]
+mod parser_test;
@@ -0,0 +1,420 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use super::super::{AggregateFunction, DataValue, ExpressionError};
+use super::lexer::{syntax, tokenize, Token, TokenKind};
+use super::{functions, BinaryOp, Expr, RowSource};
+
+/// Parse a filter or computed-column expression.
+pub(crate) fn parse(text: &str) -> Result<Expr, ExpressionError> {
+    let mut parser = Parser::new(text)?;
+    let expr = parser.or()?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+/// Parse a sort specification: comma-separated expressions, each optionally followed
+/// by `ASC` or `DESC`. Returns the keys with their `descending` flag.
+pub(crate) fn parse_sort(text: &str) -> Result<Vec<(Expr, bool)>, ExpressionError> {
+    let mut parser = Parser::new(text)?;
+    let mut keys = Vec::new();
+    if parser.peek().is_none() {
+        return Ok(keys);
+    }
+    loop {
+        let expr = parser.or()?;
+        let descending = parser.keyword("DESC");
+        if !descending {
+            parser.keyword("ASC");
+        }
+        keys.push((expr, descending));
+        if !parser.symbol(",") {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(keys)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, ExpressionError> {
+        Ok(Self {
+            tokens: tokenize(text)?,
+            pos: 0,
+            end: text.len(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map_or(self.end, |t| t.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_keyword(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_symbol(symbol));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(
+        &mut self,
+        symbol: &str,
+        message: &'static str,
+    ) -> Result<(), ExpressionError> {
+        if self.symbol(symbol) {
+            Ok(())
+        } else {
+            Err(syntax(self.position(), message))
+        }
+    }
+
+    fn finish(&self) -> Result<(), ExpressionError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(t) => Err(syntax(t.position, "unexpected token")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.and()?;
+        while self.keyword("OR") {
+            left = binary(BinaryOp::Or, left, self.and()?);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.not()?;
+        while self.keyword("AND") {
+            left = binary(BinaryOp::And, left, self.not()?);
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, ExpressionError> {
+        if self.keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr, ExpressionError> {
+        let left = self.additive()?;
+        const COMPARISONS: &[(&str, BinaryOp)] = &[
+            ("=", BinaryOp::Equal),
+            ("<>", BinaryOp::NotEqual),
+            ("!=", BinaryOp::NotEqual),
+            ("<", BinaryOp::Less),
+            ("<=", BinaryOp::LessOrEqual),
+            (">", BinaryOp::Greater),
+            (">=", BinaryOp::GreaterOrEqual),
+        ];
+        for (symbol, op) in COMPARISONS {
+            if self.symbol(symbol) {
+                return Ok(binary(*op, left, self.additive()?));
+            }
+        }
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            if !self.keyword("NULL") {
+                return Err(syntax(self.position(), "expected NULL after IS"));
+            }
+            return Ok(Expr::IsNull {
+                expr: Box::new(left),
+                negated,
+            });
+        }
+        let negated = self.keyword("NOT");
+        let expr = Box::new(left);
+        if self.keyword("LIKE") {
+            return Ok(Expr::Like {
+                expr,
+                pattern: Box::new(self.additive()?),
+                negated,
+            });
+        }
+        if self.keyword("IN") {
+            self.expect_symbol("(", "expected ( after IN")?;
+            let mut list = vec![self.or()?];
+            while self.symbol(",") {
+                list.push(self.or()?);
+            }
+            self.expect_symbol(")", "expected ) to close the IN list")?;
+            return Ok(Expr::In {
+                expr,
+                list,
+                negated,
+            });
+        }
+        if self.keyword("BETWEEN") {
+            let low = Box::new(self.additive()?);
+            if !self.keyword("AND") {
+                return Err(syntax(self.position(), "expected AND in BETWEEN"));
+            }
+            let high = Box::new(self.additive()?);
+            return Ok(Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            });
+        }
+        if negated {
+            return Err(syntax(
+                self.position(),
+                "expected LIKE, IN or BETWEEN after NOT",
+            ));
+        }
+        Ok(*expr)
+    }
+
+    fn additive(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = if self.symbol("+") {
+                BinaryOp::Add
+            } else if self.symbol("-") {
+                BinaryOp::Subtract
+            } else {
+                return Ok(left);
+            };
+            left = binary(op, left, self.multiplicative()?);
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.symbol("*") {
+                BinaryOp::Multiply
+            } else if self.symbol("/") {
+                BinaryOp::Divide
+            } else if self.symbol("%") {
+                BinaryOp::Modulo
+            } else {
+                return Ok(left);
+            };
+            left = binary(op, left, self.unary()?);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.symbol("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.symbol("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        let position = self.position();
+        let token = self
+            .next()
+            .ok_or_else(|| syntax(position, "unexpected end of expression"))?;
+        match token.kind {
+            TokenKind::Number(text) => {
+                number(&text).ok_or_else(|| syntax(position, "invalid number"))
+            }
+            TokenKind::Str(s) => Ok(Expr::Literal(DataValue::Text(s))),
+            TokenKind::Date(text) => {
+                date(&text).ok_or_else(|| syntax(position, "invalid date literal"))
+            }
+            TokenKind::Symbol("(") => {
+                let expr = self.or()?;
+                self.expect_symbol(")", "expected )")?;
+                Ok(expr)
+            }
+            TokenKind::Name {
+                name,
+                quoted: false,
+            } if is_literal_keyword(&name) => {
+                Ok(Expr::Literal(match name.to_ascii_uppercase().as_str() {
+                    "TRUE" => DataValue::Bool(true),
+                    "FALSE" => DataValue::Bool(false),
+                    _ => DataValue::Null,
+                }))
+            }
+            TokenKind::Name {
+                name,
+                quoted: false,
+            } if is_reserved(&name) => Err(syntax(position, "unexpected keyword")),
+            TokenKind::Name {
+                name,
+                quoted: false,
+            } if self
+                .peek()
+                .is_some_and(|t| t.is_symbol("(") || t.is_symbol(".")) =>
+            {
+                self.reference(name, position)
+            }
+            TokenKind::Name { name, .. } => Ok(Expr::Column(name)),
+            TokenKind::Symbol(_) => Err(syntax(position, "unexpected symbol")),
+        }
+    }
+
+    // A function call, an aggregate or a `Parent` reference starting with `name`.
+    fn reference(&mut self, name: String, position: usize) -> Result<Expr, ExpressionError> {
+        if name.eq_ignore_ascii_case("Parent") {
+            let (relation, column) = self.related()?;
+            return Ok(Expr::Parent { relation, column });
+        }
+        if name.eq_ignore_ascii_case("Child") {
+            return Err(syntax(
+                position,
+                "child columns can only be used in aggregates",
+            ));
+        }
+        self.expect_symbol("(", "expected ( after the function name")?;
+        if let Some(function) = AggregateFunction::from_name(&name) {
+            let (source, column) = if function == AggregateFunction::Count && self.symbol("*") {
+                (RowSource::Table, None)
+            } else {
+                let position = self.position();
+                match self.next().map(|t| t.kind) {
+                    Some(TokenKind::Name {
+                        name,
+                        quoted: false,
+                    }) if name.eq_ignore_ascii_case("Child") => {
+                        let (relation, column) = self.related()?;
+                        (RowSource::Child(relation), Some(column))
+                    }
+                    Some(TokenKind::Name { name, .. }) => (RowSource::Table, Some(name)),
+                    _ => return Err(syntax(position, "expected a column to aggregate")),
+                }
+            };
+            self.expect_symbol(")", "expected )")?;
+            return Ok(Expr::Aggregate {
+                function,
+                source,
+                column,
+            });
+        }
+        let function = name.to_ascii_uppercase();
+        let (min, max) =
+            functions::arity(&function).ok_or(ExpressionError::UnknownFunction(name))?;
+        let mut args = Vec::new();
+        if !self.symbol(")") {
+            loop {
+                args.push(self.or()?);
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol(")", "expected ) to close the argument list")?;
+        }
+        if args.len() < min || args.len() > max {
+            return Err(ExpressionError::ArgumentCount {
+                function,
+                count: args.len(),
+            });
+        }
+        // Date parts are written as bare words: DATEADD(day, 1, OrderDate).
+        if functions::takes_date_part(&function) {
+            if let Some(Expr::Column(part)) = args.first() {
+                args[0] = Expr::Literal(DataValue::Text(part.clone()));
+            }
+        }
+        Ok(Expr::Call { function, args })
+    }
+
+    // `.column` or `(relation).column` after `Parent` or `Child`.
+    fn related(&mut self) -> Result<(Option<String>, String), ExpressionError> {
+        let relation = if self.symbol("(") {
+            let relation = self.name()?;
+            self.expect_symbol(")", "expected ) after the relation name")?;
+            Some(relation)
+        } else {
+            None
+        };
+        self.expect_symbol(".", "expected . and a column name")?;
+        Ok((relation, self.name()?))
+    }
+
+    fn name(&mut self) -> Result<String, ExpressionError> {
+        let position = self.position();
+        match self.next().map(|t| t.kind) {
+            Some(TokenKind::Name { name, .. }) => Ok(name),
+            _ => Err(syntax(position, "expected a name")),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr::Binary(op, Box::new(left), Box::new(right))
+}
+
+fn is_literal_keyword(word: &str) -> bool {
+    ["TRUE", "FALSE", "NULL"]
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(word))
+}
+
+fn is_reserved(word: &str) -> bool {
+    [
+        "AND", "OR", "NOT", "LIKE", "IN", "IS", "BETWEEN", "ASC", "DESC",
+    ]
+    .iter()
+    .any(|k| k.eq_ignore_ascii_case(word))
+}
+
+// Integers become the narrowest of int and bigint that holds them, other numbers
+// decimal, and numbers with an exponent float, like T-SQL literals.
+fn number(text: &str) -> Option<Expr> {
+    let value = if text.contains(['e', 'E']) {
+        DataValue::Float(text.parse().ok()?)
+    } else if text.contains('.') {
+        DataValue::Decimal(text.parse::<Decimal>().ok()?)
+    } else if let Ok(v) = text.parse::<i32>() {
+        DataValue::Int(v)
+    } else if let Ok(v) = text.parse::<i64>() {
+        DataValue::BigInt(v)
+    } else {
+        DataValue::Decimal(text.parse::<Decimal>().ok()?)
+    };
+    Some(Expr::Literal(value))
+}
+
+fn date(text: &str) -> Option<Expr> {
+    let text = text.trim();
+    if let Ok(d) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(Expr::Literal(DataValue::Date(d)));
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(text, "%m/%d/%Y") {
+        return Some(Expr::Literal(DataValue::Date(d)));
+    }
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%m/%d/%Y %H:%M:%S%.f",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+    .map(|dt| Expr::Literal(DataValue::DateTime(dt)))
+}
@@ -0,0 +1,113 @@
+use super::parser::parse;
+use super::*;
+
+fn same(a: &str, b: &str) {
+    assert_eq!(parse(a).unwrap(), parse(b).unwrap(), "{a} vs {b}");
+}
+
+fn syntax_error(text: &str) -> (usize, &'static str) {
+    match Expression::parse(text) {
+        Err(ExpressionError::Syntax { position, message }) => (position, message),
+        other => panic!("{text}: expected a syntax error, got {other:?}"),
+    }
+}
+
+#[test]
+fn binds_operators_by_precedence() {
+    same("1 + 2 * 3", "1 + (2 * 3)");
+    same("10 - 4 - 3", "(10 - 4) - 3");
+    same("8 / 2 % 3", "(8 / 2) % 3");
+    same("-a * b", "(-a) * b");
+    same("a + 1 = b * 2", "(a + 1) = (b * 2)");
+    same("a = 1 OR b = 2 AND c = 3", "a = 1 OR (b = 2 AND c = 3)");
+    same("NOT a = 1 AND b = 2", "(NOT a = 1) AND b = 2");
+    same("a < 1 OR a BETWEEN 2 AND 3", "a < 1 OR (a BETWEEN 2 AND 3)");
+    same(
+        "x LIKE 'a%' AND y IS NOT NULL",
+        "(x LIKE 'a%') AND (y IS NOT NULL)",
+    );
+
+    let row = DataRow::default();
+    let eval = |text: &str| Expression::parse(text).unwrap().evaluate(&row).unwrap();
+    assert_eq!(eval("1 + 2 * 3"), 7);
+    assert_eq!(eval("(1 + 2) * 3"), 9);
+    assert_eq!(eval("10 - 4 - 3"), 3);
+    assert_eq!(eval("TRUE OR FALSE AND FALSE"), true);
+}
+
+#[test]
+fn reads_literals_and_names() {
+    assert_eq!(
+        parse("[Order Date]").unwrap(),
+        Expr::Column("Order Date".into())
+    );
+    assert_eq!(
+        parse("'it''s'").unwrap(),
+        Expr::Literal(DataValue::Text("it's".into()))
+    );
+    assert_eq!(
+        parse("2147483648").unwrap(),
+        Expr::Literal(DataValue::BigInt(2147483648))
+    );
+    assert!(matches!(
+        parse("#2024-01-31#").unwrap(),
+        Expr::Literal(DataValue::Date(_))
+    ));
+    assert_eq!(
+        Expression::parse("[Unit Price] * Qty").unwrap().columns(),
+        ["Unit Price", "Qty"]
+    );
+}
+
+#[test]
+fn reports_unterminated_literals_and_brackets() {
+    assert_eq!(
+        syntax_error("Name = 'abc"),
+        (7, "unterminated string literal")
+    );
+    assert_eq!(
+        syntax_error("[Order Date = 1"),
+        (0, "unterminated column name")
+    );
+    assert_eq!(
+        syntax_error("d > #2024-01-01"),
+        (4, "unterminated date literal")
+    );
+    assert_eq!(syntax_error("(1 + 2"), (6, "expected )"));
+    assert_eq!(
+        syntax_error("a IN (1, 2"),
+        (10, "expected ) to close the IN list")
+    );
+    assert_eq!(syntax_error("1 +").1, "unexpected end of expression");
+    assert_eq!(syntax_error("1 + 2)"), (5, "unexpected token"));
+    assert_eq!(
+        syntax_error("a BETWEEN 1 OR 2").1,
+        "expected AND in BETWEEN"
+    );
+    assert_eq!(syntax_error("a IS 1").1, "expected NULL after IS");
+    assert_eq!(syntax_error("a = ?"), (4, "unexpected character"));
+    assert_eq!(syntax_error("#2024-13-01#"), (0, "invalid date literal"));
+}
+
+#[test]
+fn rejects_unknown_functions_and_argument_counts() {
+    assert_eq!(
+        Expression::parse("FOO(1)"),
+        Err(ExpressionError::UnknownFunction("FOO".into()))
+    );
+    assert_eq!(
+        Expression::parse("IIF(a = 1, 2)"),
+        Err(ExpressionError::ArgumentCount {
+            function: "IIF".into(),
+            count: 2
+        })
+    );
+}
+
+#[test]
+fn parses_sort_orders() {
+    let order = SortOrder::parse("[Created At] DESC, Name").unwrap();
+    assert_eq!(order.columns(), ["Created At", "Name"]);
+    assert!(SortOrder::parse("").unwrap().is_empty());
+    assert!(SortOrder::parse("Name DESC DESC").is_err());
+}
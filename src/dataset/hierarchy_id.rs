@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{DataValue, HierarchyIdError};
+
+/// A `hierarchyid` value: a path such as `/1/3/2/` from the root of a tree.
+///
+/// Each level is a label of one or more integers; `/1.1/` is what `GetDescendant`
+/// produces between `/1/` and `/2/`. Values order like SQL Server orders them, depth
+/// first, so a parent sorts before its children. Labels must lie between -4168 and
+/// 5199 (5198 before a `.`), the range SQL Server encodes in at most 21 bits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HierarchyId {
+    levels: Vec<Vec<i64>>,
+}
+
+// Bit patterns of the binary format (MS-SSCLRT): fixed bits, `x` for the bits of the
+// label minus the low end of its range, and `T`, which is 1 when the label ends the
+// level and 0 when a `.` follows. Labels followed by a `.` are stored plus one, so
+// `/1.1/` sorts between `/1/` and `/2/`.
+const PATTERNS: [(i64, i64, &str); 9] = [
+    (-4168, -73, "000110xxxxx0xxx0x1xxxT"),
+    (-72, -9, "0010xx0x1xxxT"),
+    (-8, -1, "00111xxxT"),
+    (0, 3, "01xxT"),
+    (4, 7, "100xxT"),
+    (8, 15, "101xxxT"),
+    (16, 79, "110xx0x1xxxT"),
+    (80, 1103, "1110xxx0xxx0x1xxxT"),
+    (1104, 5199, "11110xxxxx0xxx0x1xxxT"),
+];
+
+fn pattern(stored: i64) -> Option<&'static (i64, i64, &'static str)> {
+    PATTERNS
+        .iter()
+        .find(|(low, high, _)| (*low..=*high).contains(&stored))
+}
+
+// Value written for the label at `index` of a level `len` labels long.
+fn stored(label: i64, index: usize, len: usize) -> i64 {
+    if index + 1 == len {
+        label
+    } else {
+        label + 1
+    }
+}
+
+impl HierarchyId {
+    /// The root, `/`.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Build from the labels of each level, e.g. `[[1], [3], [2]]` for `/1/3/2/`.
+    pub fn from_levels(levels: Vec<Vec<i64>>) -> Result<Self, HierarchyIdError> {
+        for level in &levels {
+            if level.is_empty() {
+                return Err(HierarchyIdError::InvalidPath("empty level".into()));
+            }
+            for (i, &label) in level.iter().enumerate() {
+                if pattern(stored(label, i, level.len())).is_none() {
+                    return Err(HierarchyIdError::LabelOutOfRange(label));
+                }
+            }
+        }
+        Ok(Self { levels })
+    }
+
+    pub fn levels(&self) -> &[Vec<i64>] {
+        &self.levels
+    }
+
+    /// Depth in the tree, `GetLevel()`; the root is level 0.
+    pub fn level(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// `GetAncestor(1)`, or `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        self.ancestor(1)
+    }
+
+    /// `GetAncestor(n)`: the node `n` levels up, or `None` above the root.
+    pub fn ancestor(&self, n: usize) -> Option<Self> {
+        let depth = self.levels.len().checked_sub(n)?;
+        Some(Self {
+            levels: self.levels[..depth].to_vec(),
+        })
+    }
+
+    /// Child with a single-integer label, e.g. `/1/` and 3 give `/1/3/`.
+    pub fn child(&self, label: i64) -> Result<Self, HierarchyIdError> {
+        if pattern(label).is_none() {
+            return Err(HierarchyIdError::LabelOutOfRange(label));
+        }
+        let mut levels = self.levels.clone();
+        levels.push(vec![label]);
+        Ok(Self { levels })
+    }
+
+    /// `IsDescendantOf`: true when `ancestor` is this node or one of its ancestors.
+    pub fn is_descendant_of(&self, ancestor: &HierarchyId) -> bool {
+        self.levels.starts_with(&ancestor.levels)
+    }
+
+    /// Value held by `value`: a [`DataValue::HierarchyId`], the binary format in a
+    /// `Udt` or `Binary` value, or a path in text.
+    pub fn from_value(value: &DataValue) -> Result<Self, HierarchyIdError> {
+        match value {
+            DataValue::HierarchyId(id) => Ok(id.clone()),
+            DataValue::Udt { bytes, .. } | DataValue::Binary(bytes) => Self::from_bytes(bytes),
+            DataValue::Text(text) => text.parse(),
+            _ => Err(HierarchyIdError::InvalidBinary(
+                "value is not a hierarchyid",
+            )),
+        }
+    }
+
+    /// Decode SQL Server's binary format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HierarchyIdError> {
+        let bit = |i: usize| bytes[i / 8] >> (7 - i % 8) & 1 == 1;
+        let total = bytes.len() * 8;
+        let mut position = 0;
+        let mut levels = Vec::new();
+        let mut level = Vec::new();
+        // The encoding is padded with zero bits, and no pattern starts with four zeros.
+        while (position..total).any(bit) {
+            let &(low, _, bits) = PATTERNS
+                .iter()
+                .find(|(_, _, bits)| {
+                    let prefix = bits.split('x').next().unwrap_or_default();
+                    position + prefix.len() <= total
+                        && prefix
+                            .chars()
+                            .enumerate()
+                            .all(|(i, c)| bit(position + i) == (c == '1'))
+                })
+                .ok_or(HierarchyIdError::InvalidBinary("unknown bit pattern"))?;
+            if position + bits.len() > total {
+                return Err(HierarchyIdError::InvalidBinary("data ends inside a label"));
+            }
+            let mut offset = 0i64;
+            let mut last = false;
+            for c in bits.chars() {
+                let set = bit(position);
+                position += 1;
+                match c {
+                    'x' => offset = offset << 1 | set as i64,
+                    'T' => last = set,
+                    fixed if set != (fixed == '1') => {
+                        return Err(HierarchyIdError::InvalidBinary("invalid bit pattern"))
+                    }
+                    _ => {}
+                }
+            }
+            level.push(if last { low + offset } else { low + offset - 1 });
+            if last {
+                levels.push(std::mem::take(&mut level));
+            }
+        }
+        if !level.is_empty() {
+            return Err(HierarchyIdError::InvalidBinary("data ends inside a level"));
+        }
+        Ok(Self { levels })
+    }
+
+    /// Encode in SQL Server's binary format; the root is empty.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bits = Vec::new();
+        for level in &self.levels {
+            for (i, &label) in level.iter().enumerate() {
+                let value = stored(label, i, level.len());
+                let &(low, _, pattern) = pattern(value).expect("labels are checked on creation");
+                let offset = value - low;
+                let mut width = pattern.matches('x').count();
+                for c in pattern.chars() {
+                    bits.push(match c {
+                        'x' => {
+                            width -= 1;
+                            offset >> width & 1 == 1
+                        }
+                        'T' => i + 1 == level.len(),
+                        fixed => fixed == '1',
+                    });
+                }
+            }
+        }
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &set)| byte | (set as u8) << (7 - i))
+            })
+            .collect()
+    }
+}
+
+impl Ord for HierarchyId {
+    // SQL Server compares the binary form, which sorts depth first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl PartialOrd for HierarchyId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for HierarchyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("/")?;
+        for level in &self.levels {
+            let labels: Vec<String> = level.iter().map(i64::to_string).collect();
+            write!(f, "{}/", labels.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HierarchyId {
+    type Err = HierarchyIdError;
+
+    /// Parse a path like `/1/3/2/` or `/1.1/`; `/` is the root.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || HierarchyIdError::InvalidPath(s.to_string());
+        let inner = s
+            .trim()
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/').or((rest.is_empty()).then_some(rest)))
+            .ok_or_else(invalid)?;
+        if inner.is_empty() {
+            return Ok(Self::root());
+        }
+        let levels = inner
+            .split('/')
+            .map(|level| {
+                level
+                    .split('.')
+                    .map(|label| label.parse::<i64>().map_err(|_| invalid()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_levels(levels)
+    }
+}
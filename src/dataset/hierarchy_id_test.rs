@@ -0,0 +1,94 @@
+use super::*;
+
+fn id(path: &str) -> HierarchyId {
+    path.parse().unwrap()
+}
+
+#[test]
+fn encodes_paths_like_sql_server() {
+    let cases: [(&str, &[u8]); 8] = [
+        ("/", &[]),
+        ("/1/", &[0x58]),
+        ("/2/", &[0x68]),
+        ("/4/", &[0x84]),
+        ("/1/1/", &[0x5A, 0xC0]),
+        ("/1/2/", &[0x5B, 0x40]),
+        ("/-1/", &[0x3F, 0x80]),
+        ("/1.1/", &[0x62, 0xC0]),
+    ];
+    for (path, bytes) in cases {
+        assert_eq!(id(path).to_bytes(), bytes, "{path}");
+        assert_eq!(HierarchyId::from_bytes(bytes).unwrap(), id(path));
+    }
+    for path in ["/1.1/3/", "/-4168/5199/", "/80/1103.-72/16/"] {
+        let value = id(path);
+        assert_eq!(value.to_string(), path);
+        assert_eq!(HierarchyId::from_bytes(&value.to_bytes()).unwrap(), value);
+    }
+}
+
+#[test]
+fn navigates_the_tree() {
+    let node = id("/1/3/2/");
+    assert_eq!(node.level(), 3);
+    assert_eq!(node.parent(), Some(id("/1/3/")));
+    assert_eq!(node.ancestor(3), Some(HierarchyId::root()));
+    assert_eq!(node.ancestor(4), None);
+    assert!(node.is_descendant_of(&id("/1/")));
+    assert!(node.is_descendant_of(&node));
+    assert!(!node.is_descendant_of(&id("/1/3/2/1/")));
+    assert_eq!(id("/1/3/").child(2).unwrap(), node);
+
+    let mut nodes = [
+        id("/2/"),
+        id("/1/1/"),
+        id("/1.1/"),
+        id("/"),
+        id("/1/"),
+        id("/0/"),
+    ];
+    nodes.sort();
+    let paths: Vec<String> = nodes.iter().map(ToString::to_string).collect();
+    assert_eq!(paths, ["/", "/0/", "/1/", "/1/1/", "/1.1/", "/2/"]);
+    assert!(id("/1.-1/") > id("/1/1/") && id("/1.-1/") < id("/1.1/"));
+}
+
+#[test]
+fn rejects_invalid_hierarchy_ids() {
+    assert!(matches!(
+        "1/2".parse::<HierarchyId>(),
+        Err(HierarchyIdError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        "/1//".parse::<HierarchyId>(),
+        Err(HierarchyIdError::InvalidPath(_))
+    ));
+    assert_eq!(
+        "/6000/".parse::<HierarchyId>(),
+        Err(HierarchyIdError::LabelOutOfRange(6000))
+    );
+    // `/1.` without the label that ends the level.
+    assert!(HierarchyId::from_bytes(&[0x60]).is_err());
+}
+
+#[test]
+fn converts_values_to_hierarchy_ids() {
+    let node = id("/1/2/");
+    assert_eq!(
+        HierarchyId::from_value(&DataValue::Binary(vec![0x5B, 0x40])).unwrap(),
+        node
+    );
+    assert_eq!(HierarchyId::from_value(&"/1/2/".into()).unwrap(), node);
+
+    let mut table = DataTable::new("staff");
+    for path in ["/1/2/", "/1/"] {
+        let mut row = DataRow::default();
+        row.set("node", id(path)).unwrap();
+        table.rows.push(row);
+    }
+    let view = DataView::new(&table).sort("node").unwrap();
+    assert_eq!(
+        view.get(0).unwrap()["node"],
+        DataValue::HierarchyId(id("/1/"))
+    );
+}
@@ -0,0 +1,103 @@
+//! `serde_json` interop, enabled by the `json` feature.
+
+use base64::Engine;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde_json::{Number, Value};
+
+use super::{DataError, DataRow, DataValue};
+
+/// JSON form of a value: numbers stay numbers, dates and times become ISO 8601 strings
+/// and binary values base64 strings, as in SQL Server's `FOR JSON`. `decimal`, `money`
+/// and `numeric` values become JSON numbers and keep only a double's precision; cast
+/// them to text to keep every digit. Non-finite floats become `null`.
+impl From<&DataValue> for Value {
+    fn from(value: &DataValue) -> Self {
+        let base64 =
+            |bytes: &[u8]| Value::String(base64::engine::general_purpose::STANDARD.encode(bytes));
+        match value {
+            DataValue::Null => Value::Null,
+            DataValue::Bool(b) => Value::Bool(*b),
+            DataValue::TinyInt(v) => Value::from(*v),
+            DataValue::SmallInt(v) => Value::from(*v),
+            DataValue::Int(v) => Value::from(*v),
+            DataValue::BigInt(v) => Value::from(*v),
+            DataValue::Real(v) => float(*v as f64),
+            DataValue::Float(v) => float(*v),
+            DataValue::Decimal(d) | DataValue::Money(d) => float(d.to_f64().unwrap_or(f64::NAN)),
+            DataValue::Numeric(n) => float(n.to_string().parse().unwrap_or(f64::NAN)),
+            DataValue::Text(s) | DataValue::Xml(s) => Value::String(s.clone()),
+            DataValue::Binary(b) | DataValue::Udt { bytes: b, .. } => base64(b),
+            DataValue::Spatial(s) => base64(&s.to_bytes()),
+            DataValue::HierarchyId(h) => base64(&h.to_bytes()),
+            DataValue::Variant { value, .. } => Value::from(value.as_ref()),
+            DataValue::Guid(_)
+            | DataValue::Date(_)
+            | DataValue::Time(_)
+            | DataValue::DateTime(_)
+            | DataValue::DateTimeOffset(_) => Value::String(value.to_string()),
+        }
+    }
+}
+
+impl From<DataValue> for Value {
+    fn from(value: DataValue) -> Self {
+        Value::from(&value)
+    }
+}
+
+fn float(v: f64) -> Value {
+    Number::from_f64(v).map_or(Value::Null, Value::Number)
+}
+
+/// Value for a JSON value: integers become `Int` or `BigInt` (`Decimal` beyond
+/// `i64`), other numbers `Float`, strings `Text`, and arrays and objects their JSON
+/// text, ready for an `nvarchar` JSON column.
+impl From<Value> for DataValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => DataValue::Null,
+            Value::Bool(b) => DataValue::Bool(b),
+            Value::Number(n) => {
+                if let Some(v) = n.as_i64() {
+                    i32::try_from(v).map_or(DataValue::BigInt(v), DataValue::Int)
+                } else if let Some(v) = n.as_u64() {
+                    DataValue::Decimal(Decimal::from(v))
+                } else {
+                    DataValue::Float(n.as_f64().unwrap_or_default())
+                }
+            }
+            Value::String(s) => DataValue::Text(s),
+            other => DataValue::Text(other.to_string()),
+        }
+    }
+}
+
+impl From<&Value> for DataValue {
+    fn from(value: &Value) -> Self {
+        DataValue::from(value.clone())
+    }
+}
+
+impl DataRow {
+    /// Deserialize the JSON held by `column`, or `None` when it is NULL. Text columns
+    /// are parsed as JSON documents; other values are read through their JSON form,
+    /// so a number column deserializes into any numeric type.
+    pub fn get_json<T: DeserializeOwned>(&self, column: &str) -> Result<Option<T>, DataError> {
+        let value = self
+            .get(column)
+            .ok_or_else(|| DataError::ColumnNotFound(column.to_string()))?;
+        let invalid = |e: serde_json::Error| DataError::InvalidJson {
+            column: column.to_string(),
+            message: e.to_string(),
+        };
+        match value {
+            DataValue::Null => Ok(None),
+            DataValue::Text(s) => serde_json::from_str(s).map(Some).map_err(invalid),
+            other => serde_json::from_value(Value::from(other))
+                .map(Some)
+                .map_err(invalid),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::*;
+use crate::{Parameter, ParameterValue};
+
+#[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+struct Payload {
+    id: u32,
+    tags: Vec<String>,
+}
+
+#[test]
+fn converts_values_to_and_from_json() {
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    assert_eq!(Value::from(DataValue::Int(7)), json!(7));
+    assert_eq!(
+        Value::from(DataValue::Decimal(Decimal::new(1250, 2))),
+        json!(12.5)
+    );
+    assert_eq!(Value::from(DataValue::Float(f64::NAN)), Value::Null);
+    assert_eq!(Value::from(DataValue::Binary(vec![1, 2, 3])), json!("AQID"));
+    assert_eq!(
+        Value::from(DataValue::DateTime(date.and_hms_opt(8, 30, 0).unwrap())),
+        json!("2024-06-01T08:30:00")
+    );
+
+    assert_eq!(DataValue::from(json!(7)), DataValue::Int(7));
+    assert_eq!(
+        DataValue::from(json!(5_000_000_000i64)),
+        DataValue::BigInt(5_000_000_000)
+    );
+    assert_eq!(
+        DataValue::from(json!(u64::MAX)),
+        DataValue::Decimal(Decimal::from(u64::MAX))
+    );
+    assert_eq!(DataValue::from(json!(1.5)), DataValue::Float(1.5));
+    assert_eq!(
+        DataValue::from(json!({"a": [1]})),
+        DataValue::Text(r#"{"a":[1]}"#.into())
+    );
+}
+
+#[test]
+fn reads_json_columns() {
+    let mut row = DataRow::default();
+    row.set("payload", r#"{"id": 3, "tags": ["a", "b"]}"#)
+        .unwrap();
+    row.set("count", 12).unwrap();
+    row.set("missing", DataValue::Null).unwrap();
+    row.set("broken", "{").unwrap();
+
+    assert_eq!(
+        row.get_json::<Payload>("payload").unwrap(),
+        Some(Payload {
+            id: 3,
+            tags: vec!["a".into(), "b".into()]
+        })
+    );
+    assert_eq!(row.get_json::<u64>("count").unwrap(), Some(12));
+    assert_eq!(row.get_json::<Payload>("missing").unwrap(), None);
+    assert!(matches!(
+        row.get_json::<Payload>("broken"),
+        Err(DataError::InvalidJson { column, .. }) if column == "broken"
+    ));
+    assert_eq!(
+        row.get_json::<Payload>("other"),
+        Err(DataError::ColumnNotFound("other".into()))
+    );
+
+    let payload = Payload {
+        id: 1,
+        tags: vec!["x".into()],
+    };
+    let parameter = Parameter::json("payload", &payload).unwrap();
+    assert_eq!(
+        parameter.value,
+        ParameterValue::Scalar(DataValue::Text(r#"{"id":1,"tags":["x"]}"#.into()))
+    );
+}
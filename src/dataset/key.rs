@@ -0,0 +1,91 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::{DataRow, DataValue, Numeric};
+
+/// Hashable form of a [`DataValue`] used for key lookups. Whole numbers compare equal
+/// regardless of their storage type, so an `Int(1)` key finds a `BigInt(1)` row.
+/// Text compares exactly (case-sensitive).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum KeyPart {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Decimal(Decimal),
+    Numeric(Numeric),
+    Float(u64),
+    Text(String),
+    Binary(Vec<u8>),
+    Guid(Uuid),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+    Instant(DateTime<Utc>),
+}
+
+impl From<&DataValue> for KeyPart {
+    fn from(value: &DataValue) -> Self {
+        match value {
+            DataValue::Null => KeyPart::Null,
+            DataValue::TinyInt(v) => KeyPart::Integer(*v as i128),
+            DataValue::SmallInt(v) => KeyPart::Integer(*v as i128),
+            DataValue::Int(v) => KeyPart::Integer(*v as i128),
+            DataValue::BigInt(v) => KeyPart::Integer(*v as i128),
+            DataValue::Decimal(d) | DataValue::Money(d) => decimal_key(*d),
+            DataValue::Numeric(n) => match n.to_decimal() {
+                Some(d) => decimal_key(d),
+                None => {
+                    let n = n.normalize();
+                    if n.scale() == 0 {
+                        KeyPart::Integer(n.mantissa())
+                    } else {
+                        KeyPart::Numeric(n)
+                    }
+                }
+            },
+            DataValue::Real(f) => KeyPart::from(&DataValue::Float(*f as f64)),
+            DataValue::Float(f) if f.fract() == 0.0 && f.abs() < 1e38 => {
+                KeyPart::Integer(*f as i128)
+            }
+            // Adding 0.0 turns -0.0 into 0.0 so both hash alike.
+            DataValue::Float(f) => KeyPart::Float((*f + 0.0).to_bits()),
+            DataValue::Bool(b) => KeyPart::Bool(*b),
+            DataValue::Text(s) | DataValue::Xml(s) => KeyPart::Text(s.clone()),
+            DataValue::Binary(b) | DataValue::Udt { bytes: b, .. } => KeyPart::Binary(b.clone()),
+            DataValue::Spatial(s) => KeyPart::Binary(s.to_bytes()),
+            DataValue::HierarchyId(h) => KeyPart::Binary(h.to_bytes()),
+            DataValue::Guid(g) => KeyPart::Guid(*g),
+            DataValue::Date(d) => KeyPart::Date(*d),
+            DataValue::Time(t) => KeyPart::Time(*t),
+            DataValue::DateTime(dt) => KeyPart::DateTime(*dt),
+            DataValue::DateTimeOffset(dt) => KeyPart::Instant(dt.with_timezone(&Utc)),
+            DataValue::Variant { value, .. } => KeyPart::from(value.as_ref()),
+        }
+    }
+}
+
+fn decimal_key(d: Decimal) -> KeyPart {
+    let d = d.normalize();
+    if d.scale() == 0 {
+        KeyPart::Integer(d.mantissa())
+    } else {
+        KeyPart::Decimal(d)
+    }
+}
+
+pub(crate) fn row_key<'a, I>(values: I) -> Vec<KeyPart>
+where
+    I: IntoIterator<Item = &'a DataValue>,
+{
+    values.into_iter().map(KeyPart::from).collect()
+}
+
+// Key of `row` over `columns`; missing cells count as NULL.
+pub(crate) fn key_of(row: &DataRow, columns: &[String]) -> Vec<KeyPart> {
+    row_key(
+        columns
+            .iter()
+            .map(|c| row.get(c).unwrap_or(&DataValue::Null)),
+    )
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::key::{key_of, KeyPart};
+use super::{DataError, DataRow, DataTable, DataValue, RowState};
+
+/// What [`DataSet::merge`](super::DataSet::merge) and [`DataTable::merge`] do with
+/// tables, columns and relations the target does not have, like ADO.NET's
+/// `MissingSchemaAction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingSchemaAction {
+    /// Add the missing tables, columns and relations.
+    #[default]
+    Add,
+    /// Leave them out of the merge.
+    Ignore,
+    /// Fail with [`DataError::MissingSchema`] before changing anything.
+    Error,
+}
+
+/// Differences between two tables, from [`DataTable::diff`]. "Added" rows exist only
+/// in the other table and "removed" rows only in this one. Deleted rows are ignored.
+///
+/// `Display` lists every difference, which makes for readable assertion messages:
+///
+/// ```ignore
+/// let diff = expected.diff(&actual, &["id"])?;
+/// assert!(diff.is_empty(), "{diff}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableDiff {
+    pub added: Vec<DataRow>,
+    pub removed: Vec<DataRow>,
+    pub changed: Vec<RowDiff>,
+}
+
+/// A row present in both tables with different values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowDiff {
+    /// Key values of the row, or its position when the tables are compared by position.
+    pub key: Vec<DataValue>,
+    pub columns: Vec<ColumnDiff>,
+}
+
+/// A column whose value differs. A column missing from a row reads as NULL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDiff {
+    pub column: String,
+    pub before: DataValue,
+    pub after: DataValue,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub(crate) fn compute(
+        table: &DataTable,
+        other: &DataTable,
+        key: &[String],
+    ) -> Result<Self, DataError> {
+        let names: Vec<&str> = key.iter().map(String::as_str).collect();
+        table.check_columns(&names)?;
+        other.check_columns(&names)?;
+        let mut columns: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
+        for column in &other.columns {
+            if !columns.contains(&column.name) {
+                columns.push(column.name.clone());
+            }
+        }
+        let (before, after) = (live(table), live(other));
+        let mut diff = TableDiff::default();
+
+        if key.is_empty() {
+            for i in 0..before.len().max(after.len()) {
+                match (before.get(i), after.get(i)) {
+                    (Some(a), Some(b)) => {
+                        diff.compare(vec![DataValue::BigInt(i as i64)], a, b, &columns)
+                    }
+                    (Some(a), None) => diff.removed.push((*a).clone()),
+                    (None, Some(b)) => diff.added.push((*b).clone()),
+                    (None, None) => {}
+                }
+            }
+            return Ok(diff);
+        }
+
+        let after_index = index(&after, key)?;
+        let before_index = index(&before, key)?;
+        for row in &before {
+            match after_index.get(&key_of(row, key)) {
+                Some(&i) => {
+                    let values = key.iter().map(|c| row.get(c).cloned().unwrap_or_default());
+                    diff.compare(values.collect(), row, after[i], &columns);
+                }
+                None => diff.removed.push((*row).clone()),
+            }
+        }
+        for row in &after {
+            if !before_index.contains_key(&key_of(row, key)) {
+                diff.added.push((*row).clone());
+            }
+        }
+        Ok(diff)
+    }
+
+    fn compare(
+        &mut self,
+        key: Vec<DataValue>,
+        before: &DataRow,
+        after: &DataRow,
+        columns: &[String],
+    ) {
+        // Columns known from the schema first, then any other cell by name.
+        let mut extra: Vec<&String> = before
+            .cells
+            .keys()
+            .chain(after.cells.keys())
+            .filter(|c| !columns.contains(c))
+            .collect();
+        extra.sort();
+        extra.dedup();
+        let differences: Vec<ColumnDiff> = columns
+            .iter()
+            .chain(extra)
+            .filter_map(|column| {
+                let old = before.get(column).unwrap_or(&DataValue::Null);
+                let new = after.get(column).unwrap_or(&DataValue::Null);
+                (old != new).then(|| ColumnDiff {
+                    column: column.clone(),
+                    before: old.clone(),
+                    after: new.clone(),
+                })
+            })
+            .collect();
+        if !differences.is_empty() {
+            self.changed.push(RowDiff {
+                key,
+                columns: differences,
+            });
+        }
+    }
+}
+
+fn live(table: &DataTable) -> Vec<&DataRow> {
+    table
+        .rows
+        .iter()
+        .filter(|r| r.state() != RowState::Deleted)
+        .collect()
+}
+
+// Position of each row by key, failing on duplicate keys.
+fn index(rows: &[&DataRow], key: &[String]) -> Result<HashMap<Vec<KeyPart>, usize>, DataError> {
+    let mut index = HashMap::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        if index.insert(key_of(row, key), i).is_some() {
+            return Err(DataError::ConstraintViolation {
+                columns: key.to_vec(),
+                key: key
+                    .iter()
+                    .map(|c| row.get(c).cloned().unwrap_or_default())
+                    .collect(),
+            });
+        }
+    }
+    Ok(index)
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, sign: char, row: &DataRow) -> fmt::Result {
+    let mut names: Vec<&String> = row.cells.keys().collect();
+    names.sort();
+    write!(f, "{sign}")?;
+    for name in names {
+        write!(f, " {name}={:?}", row[name.as_str()])?;
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for row in &self.removed {
+            write_row(f, '-', row)?;
+        }
+        for row in &self.added {
+            write_row(f, '+', row)?;
+        }
+        for row in &self.changed {
+            writeln!(f, "~ {:?}", row.key)?;
+            for column in &row.columns {
+                writeln!(
+                    f,
+                    "    {}: {:?} -> {:?}",
+                    column.column, column.before, column.after
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use super::*;
+
+fn row(values: &[(&str, DataValue)]) -> DataRow {
+    let mut row = DataRow::default();
+    for (name, value) in values {
+        row.cells
+            .insert(name.to_string(), DataCell::new(value.clone()));
+    }
+    row
+}
+
+fn products(columns: &[&str], rows: &[&[(&str, DataValue)]]) -> DataTable {
+    let mut table = DataTable::new("products");
+    for name in columns {
+        table.columns.push(DataColumn {
+            name: name.to_string(),
+            ..Default::default()
+        });
+    }
+    for values in rows {
+        table.rows.push(row(values));
+    }
+    table
+}
+
+fn stock() -> DataTable {
+    let mut table = products(
+        &["id", "name", "qty"],
+        &[
+            &[("id", 1.into()), ("name", "bolt".into()), ("qty", 5.into())],
+            &[("id", 2.into()), ("name", "nut".into()), ("qty", 7.into())],
+        ],
+    );
+    table.set_primary_key(&["id"]).unwrap();
+    table
+}
+
+#[test]
+fn error_fails_before_changing_anything() {
+    let mut table = stock();
+    let other = products(
+        &["id", "name", "colour"],
+        &[
+            &[
+                ("id", 2.into()),
+                ("name", "washer".into()),
+                ("colour", "red".into()),
+            ],
+            &[
+                ("id", 3.into()),
+                ("name", "screw".into()),
+                ("colour", "blue".into()),
+            ],
+        ],
+    );
+    assert_eq!(
+        table.merge(&other, MissingSchemaAction::Error),
+        Err(DataError::MissingSchema("colour".into()))
+    );
+    assert_eq!(table.columns.len(), 3);
+    assert_eq!(table.rows.len(), 2);
+    assert_eq!(table[1]["name"], "nut");
+
+    let mut ds = DataSet::new();
+    ds.tables.insert("products".into(), stock());
+    let mut incoming = ds.clone();
+    incoming.tables.insert("orders".into(), {
+        let mut orders = DataTable::new("orders");
+        orders.columns.push(DataColumn {
+            name: "product_id".into(),
+            ..Default::default()
+        });
+        orders.rows.push(row(&[("product_id", 1.into())]));
+        orders
+    });
+    incoming
+        .add_relation(
+            "product_orders",
+            ("products", "id"),
+            ("orders", "product_id"),
+        )
+        .unwrap();
+    incoming.tables.get_mut("products").unwrap().rows[0]
+        .set("name", "hex bolt")
+        .unwrap();
+    ds.tables.insert("orders".into(), DataTable::new("orders"));
+    ds.tables
+        .get_mut("orders")
+        .unwrap()
+        .columns
+        .push(DataColumn {
+            name: "product_id".into(),
+            ..Default::default()
+        });
+
+    assert_eq!(
+        ds.merge(&incoming, MissingSchemaAction::Error),
+        Err(DataError::MissingSchema("product_orders".into()))
+    );
+    assert_eq!(ds.tables["products"][0]["name"], "bolt");
+    assert!(ds.tables["orders"].rows.is_empty());
+    assert!(ds.relations().is_empty());
+    assert_eq!(ds.tables["products"].rows.len(), 2);
+}
+
+#[test]
+fn ignore_drops_columns_the_target_lacks() {
+    let other = products(
+        &["id", "name", "colour"],
+        &[
+            &[
+                ("id", 2.into()),
+                ("name", "washer".into()),
+                ("colour", "red".into()),
+            ],
+            &[
+                ("id", 3.into()),
+                ("name", "screw".into()),
+                ("colour", "blue".into()),
+            ],
+        ],
+    );
+
+    let mut ignored = stock();
+    ignored.merge(&other, MissingSchemaAction::Ignore).unwrap();
+    assert_eq!(ignored.columns.len(), 3);
+    assert_eq!(ignored.rows.len(), 3);
+    assert!(ignored.rows.iter().all(|r| r.get("colour").is_none()));
+    assert_eq!(ignored.find(&[2.into()]).unwrap()["name"], "washer");
+    assert_eq!(ignored.find(&[3.into()]).unwrap()["name"], "screw");
+
+    let mut added = stock();
+    added.merge(&other, MissingSchemaAction::Add).unwrap();
+    assert_eq!(added.columns.len(), 4);
+    assert_eq!(added.find(&[3.into()]).unwrap()["colour"], "blue");
+    assert!(added.find(&[1.into()]).unwrap().get("colour").is_none());
+}
+
+#[test]
+fn replaces_rows_with_matching_keys() {
+    let mut table = stock();
+    table.accept_changes().unwrap();
+    let other = products(
+        &["id", "name"],
+        &[
+            &[("id", 1.into()), ("name", "hex bolt".into())],
+            &[("id", 4.into()), ("name", "rivet".into())],
+            &[("id", 4.into()), ("name", "pop rivet".into())],
+        ],
+    );
+    table.merge(&other, MissingSchemaAction::Error).unwrap();
+
+    assert_eq!(table.rows.len(), 3);
+    let bolt = table.find(&[1.into()]).unwrap();
+    assert_eq!(bolt["name"], "hex bolt");
+    assert_eq!(bolt["qty"], 5);
+    let rivet = table.find(&[4.into()]).unwrap();
+    assert_eq!(rivet["name"], "pop rivet");
+    assert!(rivet.get("qty").is_none());
+    assert_eq!(table.find(&[2.into()]).unwrap()["name"], "nut");
+
+    let mut unkeyed = stock();
+    unkeyed.set_primary_key(&[]).unwrap();
+    unkeyed.merge(&other, MissingSchemaAction::Error).unwrap();
+    assert_eq!(unkeyed.rows.len(), 5);
+    assert_eq!(unkeyed[0]["name"], "bolt");
+}
+
+#[test]
+fn reports_unique_constraint_conflicts() {
+    let mut table = stock();
+    table.add_unique_constraint(&["name"]).unwrap();
+    let other = products(
+        &["id", "name"],
+        &[&[("id", 3.into()), ("name", "nut".into())]],
+    );
+    assert_eq!(
+        table.merge(&other, MissingSchemaAction::Error),
+        Err(DataError::ConstraintViolation {
+            columns: vec!["name".into()],
+            key: vec!["nut".into()],
+        })
+    );
+
+    let mut renamed = stock();
+    renamed.add_unique_constraint(&["name"]).unwrap();
+    let other = products(
+        &["id", "name"],
+        &[
+            &[("id", 2.into()), ("name", "locknut".into())],
+            &[("id", 3.into()), ("name", "nut".into())],
+        ],
+    );
+    renamed.merge(&other, MissingSchemaAction::Error).unwrap();
+    assert_eq!(renamed.find(&[2.into()]).unwrap()["name"], "locknut");
+    assert_eq!(renamed.find(&[3.into()]).unwrap()["name"], "nut");
+}
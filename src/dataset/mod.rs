@@ -4,13 +4,72 @@ pub mod data_cell;
 pub mod data_row;
 pub mod data_table;
 pub mod data_set;
+pub mod data_relation;
+pub mod data_view;
+pub mod error;
+pub mod expression;
+pub mod aggregate;
+pub mod merge;
+pub mod render;
+pub mod spatial;
+pub mod hierarchy_id;
+pub mod numeric;
+pub mod time_zone;
+pub mod sql_type;
+mod key;
+mod value_order;
+mod cast;
+#[cfg(feature = "time")]
+mod time_conversions;
+#[cfg(feature = "json")]
+mod json;
 
 pub use data_value::DataValue;
 pub use data_column::DataColumn;
 pub use data_cell::DataCell;
-pub use data_row::DataRow;
-pub use data_table::DataTable;
+pub use data_row::{DataRow, RowState};
+pub use data_table::{ConversionWarning, DataTable};
 pub use data_set::DataSet;
+pub use data_relation::{DataRelation, Rule};
+pub use data_view::DataView;
+pub use aggregate::{Aggregate, AggregateFunction, GroupBy};
+pub use merge::{ColumnDiff, MissingSchemaAction, RowDiff, TableDiff};
+pub use render::{TableFormat, TableStyle};
+pub use spatial::{Coord, Shape, Spatial, SpatialKind};
+pub use hierarchy_id::HierarchyId;
+pub use numeric::Numeric;
+pub use time_zone::ServerTimeZone;
+pub use sql_type::SqlType;
+pub use error::{
+    CastError, DataError, ExpressionError, HierarchyIdError, ParseNumericError, SpatialError,
+};
+pub use expression::{Expression, SortOrder};
 
+#[cfg(test)]
+mod aggregate_test;
 #[cfg(test)]
 mod data_set_test;
+#[cfg(test)]
+mod data_table_test;
+#[cfg(test)]
+mod data_view_test;
+#[cfg(test)]
+mod merge_test;
+#[cfg(test)]
+mod render_test;
+#[cfg(test)]
+mod spatial_test;
+#[cfg(test)]
+mod hierarchy_id_test;
+#[cfg(test)]
+mod numeric_test;
+#[cfg(test)]
+mod time_zone_test;
+#[cfg(test)]
+mod data_value_test;
+#[cfg(test)]
+mod cast_test;
+#[cfg(all(test, feature = "time"))]
+mod time_conversions_test;
+#[cfg(all(test, feature = "json"))]
+mod json_test;
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use super::ParseNumericError;
+
+/// An exact `decimal`/`numeric` value of up to 38 digits, stored as an integer
+/// mantissa and a scale: `Numeric::new(12345, 2)` is `123.45`.
+///
+/// `rust_decimal::Decimal` holds 28 digits; this type carries the rest of SQL Server's
+/// range without rounding. See
+/// [`NumericMapping`](crate::infrastructure::mssql::NumericMapping) for when query
+/// results use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Numeric {
+    value: i128,
+    scale: u8,
+}
+
+const MAX_PRECISION: u8 = 38;
+const LIMIT: i128 = 10i128.pow(MAX_PRECISION as u32);
+
+impl Numeric {
+    /// `value / 10^scale`, or `None` beyond 38 digits or a scale above 38.
+    pub fn new(value: i128, scale: u8) -> Option<Self> {
+        (scale <= MAX_PRECISION && value.abs() < LIMIT).then_some(Self { value, scale })
+    }
+
+    /// The unscaled integer.
+    pub fn mantissa(&self) -> i128 {
+        self.value
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// The same value without trailing fractional zeros, so `1.50` becomes `1.5`.
+    pub fn normalize(&self) -> Self {
+        let mut n = *self;
+        while n.scale > 0 && n.value % 10 == 0 {
+            n.value /= 10;
+            n.scale -= 1;
+        }
+        n
+    }
+
+    /// The value as a `Decimal`, or `None` when it needs more than 28 digits.
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        let n = self.normalize();
+        Decimal::try_from_i128_with_scale(n.value, n.scale as u32).ok()
+    }
+
+    /// Nearest `f64`, for arithmetic that cannot be exact.
+    pub fn to_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    // Compare by value regardless of scale, so `1.50` equals `1.5`. Whole parts are
+    // compared first, then fractions widened to 38 places, which always fit an i128.
+    pub(crate) fn cmp_value(&self, other: &Self) -> Ordering {
+        let parts = |n: &Self| {
+            let unit = 10i128.pow(n.scale as u32);
+            let fraction = (n.value % unit) * 10i128.pow((MAX_PRECISION - n.scale) as u32);
+            (n.value / unit, fraction)
+        };
+        parts(self).cmp(&parts(other))
+    }
+}
+
+impl From<Decimal> for Numeric {
+    fn from(d: Decimal) -> Self {
+        Self {
+            value: d.mantissa(),
+            scale: d.scale() as u8,
+        }
+    }
+}
+
+impl fmt::Display for Numeric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.value.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let sign = if self.value < 0 { "-" } else { "" };
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{whole}.{fraction}")
+    }
+}
+
+impl FromStr for Numeric {
+    type Err = ParseNumericError;
+
+    /// Parse plain decimal notation such as `-12345678901234567890.123456789`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits = format!("{whole}{fraction}");
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseNumericError);
+        }
+        let scale = u8::try_from(fraction.len()).map_err(|_| ParseNumericError)?;
+        let value: i128 = digits.parse().map_err(|_| ParseNumericError)?;
+        Self::new(if negative { -value } else { value }, scale).ok_or(ParseNumericError)
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+use rust_decimal::Decimal;
+
+#[test]
+fn keeps_38_digit_numerics_exact() {
+    let big: Numeric = "-1234567890123456789012345678901.2345670".parse().unwrap();
+    assert_eq!(big.mantissa(), -12345678901234567890123456789012345670);
+    assert_eq!(big.scale(), 7);
+    assert_eq!(big.to_string(), "-1234567890123456789012345678901.2345670");
+    assert_eq!(
+        big.normalize().to_string(),
+        "-1234567890123456789012345678901.234567"
+    );
+    assert_eq!(big.to_decimal(), None);
+
+    let small = Numeric::new(5, 3).unwrap();
+    assert_eq!(small.to_string(), "0.005");
+    assert_eq!(small.to_decimal(), Some(Decimal::new(5, 3)));
+    assert_eq!(Numeric::from(Decimal::new(-125, 1)).to_string(), "-12.5");
+
+    assert_eq!(Numeric::new(10i128.pow(38), 0), None);
+    assert_eq!("1.2.3".parse::<Numeric>(), Err(ParseNumericError));
+    assert_eq!("".parse::<Numeric>(), Err(ParseNumericError));
+}
+
+#[test]
+fn uses_numerics_in_keys_and_expressions() {
+    let mut table = DataTable::new("totals");
+    table.columns.push(DataColumn {
+        name: "id".into(),
+        ..Default::default()
+    });
+    table.set_primary_key(&["id"]).unwrap();
+    let huge = Numeric::new(10i128.pow(37), 0).unwrap();
+    for id in [
+        DataValue::Numeric(huge),
+        Numeric::new(700, 2).unwrap().into(),
+    ] {
+        let mut row = DataRow::default();
+        row.set("id", id).unwrap();
+        table.add_row(row).unwrap();
+    }
+    assert!(table.find(&[DataValue::Int(7)]).is_some());
+    assert!(table.find(&[DataValue::Numeric(huge)]).is_some());
+
+    let view = DataView::new(&table).filter("id > 1000000").unwrap();
+    assert_eq!(view.len(), 1);
+}
@@ -0,0 +1,385 @@
+use std::fmt;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{DataRow, DataSet, DataTable, DataValue, RowState};
+
+/// Output format of [`DataTable::render`] and [`DataSet::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableStyle {
+    /// Grid drawn with `+`, `-` and `|`.
+    #[default]
+    Ascii,
+    /// Grid drawn with box-drawing characters.
+    Unicode,
+    /// GitHub-flavored Markdown table.
+    Markdown,
+    /// HTML `<table>` element.
+    Html,
+}
+
+/// Options for rendering tables. `Display` for [`DataTable`] and [`DataSet`] uses the
+/// defaults: an ASCII grid, at most 50 rows and 40 characters per cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableFormat {
+    pub style: TableStyle,
+    /// Rows rendered before the rest are summarized in a footer; `None` renders all.
+    pub max_rows: Option<usize>,
+    /// Widest cell in terminal columns, where CJK characters and most emoji take two;
+    /// longer text and binary values are cut with an ellipsis.
+    pub max_width: usize,
+    /// Text shown for NULL values.
+    pub null: String,
+}
+
+impl Default for TableFormat {
+    fn default() -> Self {
+        Self {
+            style: TableStyle::Ascii,
+            max_rows: Some(50),
+            max_width: 40,
+            null: "NULL".into(),
+        }
+    }
+}
+
+impl TableFormat {
+    pub fn new(style: TableStyle) -> Self {
+        Self {
+            style,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width.max(1);
+        self
+    }
+
+    pub fn with_null(mut self, null: &str) -> Self {
+        self.null = null.into();
+        self
+    }
+}
+
+// A table reduced to the text of its cells.
+struct Grid {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+    // Rows left out because of `max_rows`.
+    hidden: usize,
+}
+
+struct Cell {
+    text: String,
+    numeric: bool,
+    null: bool,
+}
+
+impl Grid {
+    fn new(table: &DataTable, format: &TableFormat) -> Self {
+        let live: Vec<&DataRow> = table
+            .rows
+            .iter()
+            .filter(|r| r.state() != RowState::Deleted)
+            .collect();
+        let headers: Vec<String> = if table.columns.is_empty() {
+            let mut names: Vec<String> =
+                live.iter().flat_map(|r| r.cells.keys().cloned()).collect();
+            names.sort();
+            names.dedup();
+            names
+        } else {
+            table.columns.iter().map(|c| c.name.clone()).collect()
+        };
+        let shown = format.max_rows.unwrap_or(usize::MAX).min(live.len());
+        let ellipsis = if format.style == TableStyle::Ascii {
+            "..."
+        } else {
+            "…"
+        };
+        let rows = live[..shown]
+            .iter()
+            .map(|row| {
+                headers
+                    .iter()
+                    .map(|name| {
+                        let value = row.get(name).unwrap_or(&DataValue::Null);
+                        Cell {
+                            text: truncate(
+                                &value_text(value, &format.null),
+                                format.max_width,
+                                ellipsis,
+                            ),
+                            numeric: is_numeric(value),
+                            null: value.is_null(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            headers: headers
+                .into_iter()
+                .map(|h| truncate(&h, format.max_width, ellipsis))
+                .collect(),
+            rows,
+            hidden: live.len() - shown,
+        }
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        self.headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                self.rows
+                    .iter()
+                    .map(|r| width(&r[i].text))
+                    .chain([width(header)])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn footer(&self) -> Option<String> {
+        match self.hidden {
+            0 => None,
+            1 => Some("(1 more row)".into()),
+            n => Some(format!("({n} more rows)")),
+        }
+    }
+
+    fn write_grid(&self, out: &mut String, unicode: bool) {
+        // Corner and junction characters: left, middle, right for top, header, bottom.
+        let (h, v) = if unicode { ('─', '│') } else { ('-', '|') };
+        let rules = if unicode {
+            [['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']]
+        } else {
+            [['+', '+', '+'], ['+', '+', '+'], ['+', '+', '+']]
+        };
+        let widths = self.widths();
+        let rule = |out: &mut String, [left, middle, right]: [char; 3]| {
+            out.push(left);
+            for (i, w) in widths.iter().enumerate() {
+                if i > 0 {
+                    out.push(middle);
+                }
+                out.extend(std::iter::repeat_n(h, w + 2));
+            }
+            out.push(right);
+            out.push('\n');
+        };
+        let line = |out: &mut String, cells: &mut dyn Iterator<Item = (&str, bool)>| {
+            out.push(v);
+            for ((text, right), w) in cells.zip(&widths) {
+                out.push(' ');
+                out.push_str(&pad(text, *w, right));
+                out.push(' ');
+                out.push(v);
+            }
+            out.push('\n');
+        };
+        rule(out, rules[0]);
+        line(out, &mut self.headers.iter().map(|h| (h.as_str(), false)));
+        rule(out, rules[1]);
+        for row in &self.rows {
+            line(out, &mut row.iter().map(|c| (c.text.as_str(), c.numeric)));
+        }
+        rule(out, rules[2]);
+        if let Some(footer) = self.footer() {
+            out.push_str(&footer);
+            out.push('\n');
+        }
+    }
+
+    fn write_markdown(&self, out: &mut String) {
+        let escape = |text: &str| text.replace('|', "\\|");
+        out.push('|');
+        for header in &self.headers {
+            out.push_str(&format!(" {} |", escape(header)));
+        }
+        out.push_str("\n|");
+        for i in 0..self.headers.len() {
+            let numeric =
+                !self.rows.is_empty() && self.rows.iter().all(|r| r[i].numeric || r[i].null);
+            out.push_str(if numeric { " ---: |" } else { " --- |" });
+        }
+        out.push('\n');
+        for row in &self.rows {
+            out.push('|');
+            for cell in row {
+                out.push_str(&format!(" {} |", escape(&cell.text)));
+            }
+            out.push('\n');
+        }
+        if let Some(footer) = self.footer() {
+            out.push_str(&format!("\n_{footer}_\n"));
+        }
+    }
+
+    fn write_html(&self, out: &mut String) {
+        out.push_str("<table>\n  <thead>\n    <tr>");
+        for header in &self.headers {
+            out.push_str(&format!("<th>{}</th>", escape_html(header)));
+        }
+        out.push_str("</tr>\n  </thead>\n  <tbody>\n");
+        for row in &self.rows {
+            out.push_str("    <tr>");
+            for cell in row {
+                let class = match (cell.null, cell.numeric) {
+                    (true, _) => " class=\"null\"",
+                    (false, true) => " class=\"number\"",
+                    _ => "",
+                };
+                out.push_str(&format!("<td{class}>{}</td>", escape_html(&cell.text)));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("  </tbody>\n");
+        if let Some(footer) = self.footer() {
+            out.push_str(&format!(
+                "  <tfoot>\n    <tr><td colspan=\"{}\">{footer}</td></tr>\n  </tfoot>\n",
+                self.headers.len().max(1)
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+}
+
+pub(crate) fn render_table(table: &DataTable, format: &TableFormat) -> String {
+    let grid = Grid::new(table, format);
+    let mut out = String::new();
+    match format.style {
+        TableStyle::Ascii => grid.write_grid(&mut out, false),
+        TableStyle::Unicode => grid.write_grid(&mut out, true),
+        TableStyle::Markdown => grid.write_markdown(&mut out),
+        TableStyle::Html => grid.write_html(&mut out),
+    }
+    out
+}
+
+// Tables in name order, each under a heading with its name.
+pub(crate) fn render_set(ds: &DataSet, format: &TableFormat) -> String {
+    let mut names: Vec<&String> = ds.tables.keys().collect();
+    names.sort();
+    let mut out = String::new();
+    for (i, name) in names.into_iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match format.style {
+            TableStyle::Markdown => out.push_str(&format!("### {name}\n\n")),
+            TableStyle::Html => out.push_str(&format!("<h3>{}</h3>\n", escape_html(name))),
+            TableStyle::Ascii | TableStyle::Unicode => out.push_str(&format!("{name}\n")),
+        }
+        out.push_str(&render_table(&ds.tables[name], format));
+    }
+    out
+}
+
+fn value_text(value: &DataValue, null: &str) -> String {
+    let text = match value {
+        DataValue::Null => return null.to_string(),
+        DataValue::Bool(b) => b.to_string(),
+        DataValue::TinyInt(v) => v.to_string(),
+        DataValue::SmallInt(v) => v.to_string(),
+        DataValue::Int(v) => v.to_string(),
+        DataValue::BigInt(v) => v.to_string(),
+        DataValue::Real(v) => v.to_string(),
+        DataValue::Float(v) => v.to_string(),
+        DataValue::Decimal(d) | DataValue::Money(d) => d.to_string(),
+        DataValue::Numeric(n) => n.to_string(),
+        DataValue::Text(s) | DataValue::Xml(s) => s.clone(),
+        DataValue::Variant { value, .. } => return value_text(value, null),
+        DataValue::Spatial(s) => s.to_wkt(),
+        DataValue::HierarchyId(h) => h.to_string(),
+        DataValue::Binary(bytes) | DataValue::Udt { bytes, .. } => {
+            let mut hex = String::with_capacity(2 + bytes.len() * 2);
+            hex.push_str("0x");
+            for byte in bytes {
+                hex.push_str(&format!("{byte:02X}"));
+            }
+            hex
+        }
+        DataValue::Guid(g) => g.to_string().to_uppercase(),
+        DataValue::Date(d) => d.format("%Y-%m-%d").to_string(),
+        DataValue::Time(t) => t.format("%H:%M:%S%.f").to_string(),
+        DataValue::DateTime(dt) => dt.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+        DataValue::DateTimeOffset(dt) => dt.format("%Y-%m-%d %H:%M:%S%.f %:z").to_string(),
+    };
+    // Line breaks and tabs would break the grid.
+    text.replace(['\r', '\n', '\t'], " ")
+}
+
+fn is_numeric(value: &DataValue) -> bool {
+    matches!(
+        value,
+        DataValue::TinyInt(_)
+            | DataValue::SmallInt(_)
+            | DataValue::Int(_)
+            | DataValue::BigInt(_)
+            | DataValue::Real(_)
+            | DataValue::Float(_)
+            | DataValue::Decimal(_)
+            | DataValue::Money(_)
+            | DataValue::Numeric(_)
+    ) || matches!(value, DataValue::Variant { value, .. } if is_numeric(value))
+}
+
+// Columns taken on a terminal, so that wide characters keep the grid aligned.
+fn width(text: &str) -> usize {
+    text.width()
+}
+
+fn truncate(text: &str, max: usize, ellipsis: &str) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut keep = max.saturating_sub(width(ellipsis));
+    let mut cut = String::new();
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if w > keep {
+            break;
+        }
+        keep -= w;
+        cut.push(c);
+    }
+    cut.push_str(ellipsis);
+    cut
+}
+
+fn pad(text: &str, columns: usize, right: bool) -> String {
+    let fill = " ".repeat(columns.saturating_sub(width(text)));
+    if right {
+        fill + text
+    } else {
+        format!("{text}{fill}")
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl fmt::Display for DataTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&render_table(self, &TableFormat::default()))
+    }
+}
+
+impl fmt::Display for DataSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&render_set(self, &TableFormat::default()))
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+
+fn table(columns: &[&str], rows: &[&[(&str, DataValue)]]) -> DataTable {
+    let mut table = DataTable::new("t");
+    for name in columns {
+        table.columns.push(DataColumn {
+            name: name.to_string(),
+            ..Default::default()
+        });
+    }
+    for values in rows {
+        let mut row = DataRow::default();
+        for (name, value) in values.iter() {
+            row.cells
+                .insert(name.to_string(), DataCell::new(value.clone()));
+        }
+        table.rows.push(row);
+    }
+    table
+}
+
+#[test]
+fn truncates_wide_text_binary_and_headers() {
+    let table = table(
+        &["id", "description", "data"],
+        &[&[
+            ("id", 1.into()),
+            ("description", "a rather long description".into()),
+            (
+                "data",
+                DataValue::Binary(vec![0xDE, 0xAD, 0xBE, 0xEF, 0x01]),
+            ),
+        ]],
+    );
+
+    assert_eq!(
+        table.render(&TableFormat::default().with_max_width(8)),
+        "+----+----------+----------+\n\
+         | id | descr... | data     |\n\
+         +----+----------+----------+\n\
+         |  1 | a rat... | 0xDEA... |\n\
+         +----+----------+----------+\n"
+    );
+    assert_eq!(
+        table.render(&TableFormat::new(TableStyle::Markdown).with_max_width(8)),
+        "| id | descrip… | data |\n| ---: | --- | --- |\n| 1 | a rathe… | 0xDEADB… |\n"
+    );
+    assert!(table
+        .render(&TableFormat::default().with_max_width(100))
+        .contains("| a rather long description | 0xDEADBEEF01 |"));
+}
+
+#[test]
+fn aligns_wide_characters() {
+    let table = table(
+        &["city", "note"],
+        &[
+            &[("city", "東京".into()), ("note", "🙂 ok".into())],
+            &[("city", "Zürich".into()), ("note", "cafe\u{301}".into())],
+        ],
+    );
+
+    assert_eq!(
+        table.render(&TableFormat::new(TableStyle::Unicode)),
+        "┌────────┬───────┐\n\
+         │ city   │ note  │\n\
+         ├────────┼───────┤\n\
+         │ 東京   │ 🙂 ok │\n\
+         │ Zürich │ cafe\u{301}  │\n\
+         └────────┴───────┘\n"
+    );
+
+    // A wide character that does not fit before the ellipsis is left out whole.
+    let long = self::table(&["name"], &[&[("name", "東京都庁舎".into())]]);
+    assert_eq!(
+        long.render(&TableFormat::new(TableStyle::Unicode).with_max_width(6)),
+        "┌───────┐\n\
+         │ name  │\n\
+         ├───────┤\n\
+         │ 東京… │\n\
+         └───────┘\n"
+    );
+    assert_eq!(
+        long.render(&TableFormat::default().with_max_width(6)),
+        "+-------+\n\
+         | name  |\n\
+         +-------+\n\
+         | 東... |\n\
+         +-------+\n"
+    );
+}
+
+#[test]
+fn renders_null_and_missing_cells() {
+    let table = table(
+        &["id", "amount", "note"],
+        &[
+            &[("id", 1.into()), ("amount", DataValue::Null)],
+            &[("id", 2.into()), ("amount", 5.into()), ("note", "x".into())],
+        ],
+    );
+
+    assert_eq!(
+        table.to_string(),
+        "+----+--------+------+\n\
+         | id | amount | note |\n\
+         +----+--------+------+\n\
+         |  1 | NULL   | NULL |\n\
+         |  2 |      5 | x    |\n\
+         +----+--------+------+\n"
+    );
+    assert_eq!(
+        table.render(&TableFormat::new(TableStyle::Markdown).with_null("")),
+        "| id | amount | note |\n| ---: | ---: | --- |\n| 1 |  |  |\n| 2 | 5 | x |\n"
+    );
+    let html = table.render(&TableFormat::new(TableStyle::Html).with_null("∅"));
+    assert!(html.contains(
+        "<tr><td class=\"number\">1</td><td class=\"null\">∅</td><td class=\"null\">∅</td></tr>"
+    ));
+
+    let mut deleted = table.clone();
+    deleted.accept_changes().unwrap();
+    deleted.delete_row(1).unwrap();
+    assert_eq!(
+        deleted.render(&TableFormat::default().with_null("-")),
+        "+----+--------+------+\n\
+         | id | amount | note |\n\
+         +----+--------+------+\n\
+         |  1 | -      | -    |\n\
+         +----+--------+------+\n"
+    );
+}
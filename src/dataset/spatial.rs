@@ -0,0 +1,1056 @@
+use std::fmt;
+
+use super::{DataValue, SpatialError};
+
+/// Whether a [`Spatial`] value is a `geography` (round earth, coordinates are
+/// longitude and latitude) or a `geometry` (flat plane).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpatialKind {
+    Geography,
+    Geometry,
+}
+
+impl SpatialKind {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SpatialKind::Geography => "geography",
+            SpatialKind::Geometry => "geometry",
+        }
+    }
+}
+
+/// A position. For `geography`, `x` is the longitude and `y` the latitude, the order
+/// SQL Server uses in WKT.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coord {
+    pub x: f64,
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
+impl Coord {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+            z: None,
+            m: None,
+        }
+    }
+}
+
+/// The OGC shapes SQL Server stores. Circular arcs (`CIRCULARSTRING`,
+/// `COMPOUNDCURVE`, `CURVEPOLYGON`) and `FULLGLOBE` are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A point, or `POINT EMPTY`.
+    Point(Option<Coord>),
+    LineString(Vec<Coord>),
+    /// Rings of a polygon: the exterior ring first, then the holes.
+    Polygon(Vec<Vec<Coord>>),
+    MultiPoint(Vec<Coord>),
+    MultiLineString(Vec<Vec<Coord>>),
+    MultiPolygon(Vec<Vec<Vec<Coord>>>),
+    GeometryCollection(Vec<Shape>),
+}
+
+/// A `geography` or `geometry` value with its spatial reference id.
+///
+/// Converts from and to WKT, WKB and SQL Server's own serialization format, which is
+/// what a spatial column holds when selected as `varbinary` and what is sent when the
+/// value is passed as a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spatial {
+    pub kind: SpatialKind,
+    pub srid: i32,
+    pub shape: Shape,
+}
+
+const HAS_Z: u8 = 0x01;
+const HAS_M: u8 = 0x02;
+const IS_VALID: u8 = 0x04;
+const SINGLE_POINT: u8 = 0x08;
+const SINGLE_LINE_SEGMENT: u8 = 0x10;
+
+// Figure attributes of serialization version 1.
+const INTERIOR_RING: u8 = 0;
+const STROKE: u8 = 1;
+const EXTERIOR_RING: u8 = 2;
+
+impl Spatial {
+    /// A `geography` in WGS 84 (SRID 4326).
+    pub fn geography(shape: Shape) -> Self {
+        Self {
+            kind: SpatialKind::Geography,
+            srid: 4326,
+            shape,
+        }
+    }
+
+    /// A `geometry` with SRID 0.
+    pub fn geometry(shape: Shape) -> Self {
+        Self {
+            kind: SpatialKind::Geometry,
+            srid: 0,
+            shape,
+        }
+    }
+
+    pub fn with_srid(mut self, srid: i32) -> Self {
+        self.srid = srid;
+        self
+    }
+
+    /// Spatial value held by `value`: a [`DataValue::Spatial`], SQL Server's binary
+    /// format in a `Udt` or `Binary` value, or WKT text (with SRID 4326 for
+    /// `geography` and 0 for `geometry`).
+    pub fn from_value(value: &DataValue, kind: SpatialKind) -> Result<Self, SpatialError> {
+        match value {
+            DataValue::Spatial(spatial) => Ok(spatial.clone()),
+            DataValue::Udt { bytes, .. } | DataValue::Binary(bytes) => {
+                Self::from_bytes(kind, bytes)
+            }
+            DataValue::Text(text) => {
+                let srid = match kind {
+                    SpatialKind::Geography => 4326,
+                    SpatialKind::Geometry => 0,
+                };
+                Self::from_wkt(kind, srid, text)
+            }
+            _ => Err(SpatialError::Invalid("value is not spatial data")),
+        }
+    }
+
+    /// Decode SQL Server's serialization format (MS-SSCLRT).
+    pub fn from_bytes(kind: SpatialKind, bytes: &[u8]) -> Result<Self, SpatialError> {
+        clr::decode(kind, bytes)
+    }
+
+    /// Encode in SQL Server's serialization format. Values are written as valid, so
+    /// SQL Server does not check them again; polygons must follow its ring rules.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        clr::encode(self)
+    }
+
+    /// Parse well-known text such as `POLYGON ((0 0, 10 0, 10 10, 0 0))`.
+    pub fn from_wkt(kind: SpatialKind, srid: i32, text: &str) -> Result<Self, SpatialError> {
+        Ok(Self {
+            kind,
+            srid,
+            shape: wkt::parse(text)?,
+        })
+    }
+
+    /// Well-known text as SQL Server's `STAsText` writes it.
+    pub fn to_wkt(&self) -> String {
+        let mut out = String::new();
+        wkt::write(&self.shape, &mut out);
+        out
+    }
+
+    /// Parse well-known binary, in either byte order, with ISO or EWKB Z/M flags. An
+    /// SRID embedded as EWKB takes precedence over `srid`.
+    pub fn from_wkb(kind: SpatialKind, srid: i32, bytes: &[u8]) -> Result<Self, SpatialError> {
+        let mut reader = wkb::Reader {
+            bytes,
+            position: 0,
+            srid: None,
+        };
+        let shape = reader.shape()?;
+        if reader.position != bytes.len() {
+            return Err(SpatialError::Invalid("trailing bytes after WKB"));
+        }
+        Ok(Self {
+            kind,
+            srid: reader.srid.unwrap_or(srid),
+            shape,
+        })
+    }
+
+    /// Little-endian ISO well-known binary, like SQL Server's `STAsBinary`.
+    pub fn to_wkb(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (z, m) = dimensions(&self.shape);
+        wkb::write(&self.shape, z, m, &mut out);
+        out
+    }
+}
+
+impl fmt::Display for Spatial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_wkt())
+    }
+}
+
+fn for_each_coord(shape: &Shape, f: &mut dyn FnMut(&Coord)) {
+    match shape {
+        Shape::Point(point) => point.iter().for_each(f),
+        Shape::LineString(coords) | Shape::MultiPoint(coords) => coords.iter().for_each(f),
+        Shape::Polygon(rings) | Shape::MultiLineString(rings) => rings.iter().flatten().for_each(f),
+        Shape::MultiPolygon(polygons) => polygons.iter().flatten().flatten().for_each(f),
+        Shape::GeometryCollection(shapes) => shapes.iter().for_each(|s| for_each_coord(s, f)),
+    }
+}
+
+// Whether any coordinate has a Z or an M value.
+fn dimensions(shape: &Shape) -> (bool, bool) {
+    let (mut z, mut m) = (false, false);
+    for_each_coord(shape, &mut |c| {
+        z |= c.z.is_some();
+        m |= c.m.is_some();
+    });
+    (z, m)
+}
+
+mod clr {
+    use super::*;
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl Reader<'_> {
+        fn take(&mut self, n: usize) -> Result<&[u8], SpatialError> {
+            let end = self
+                .position
+                .checked_add(n)
+                .ok_or(SpatialError::Truncated)?;
+            let slice = self
+                .bytes
+                .get(self.position..end)
+                .ok_or(SpatialError::Truncated)?;
+            self.position = end;
+            Ok(slice)
+        }
+
+        fn u8(&mut self) -> Result<u8, SpatialError> {
+            Ok(self.take(1)?[0])
+        }
+
+        fn i32(&mut self) -> Result<i32, SpatialError> {
+            Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+        }
+
+        fn count(&mut self) -> Result<usize, SpatialError> {
+            let n = u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize;
+            // Every element takes at least a byte, which bounds bogus counts.
+            if n > self.bytes.len() {
+                return Err(SpatialError::Truncated);
+            }
+            Ok(n)
+        }
+
+        fn f64(&mut self) -> Result<f64, SpatialError> {
+            Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+        }
+    }
+
+    struct Figure {
+        offset: usize,
+    }
+
+    struct ShapeEntry {
+        parent: i32,
+        figure: i32,
+        kind: u8,
+    }
+
+    pub(super) fn decode(kind: SpatialKind, bytes: &[u8]) -> Result<Spatial, SpatialError> {
+        let mut r = Reader { bytes, position: 0 };
+        let srid = r.i32()?;
+        let version = r.u8()?;
+        if version != 1 && version != 2 {
+            return Err(SpatialError::UnsupportedVersion(version));
+        }
+        let properties = r.u8()?;
+        let point_count = if properties & SINGLE_POINT != 0 {
+            1
+        } else if properties & SINGLE_LINE_SEGMENT != 0 {
+            2
+        } else {
+            r.count()?
+        };
+        let mut points = Vec::with_capacity(point_count);
+        for _ in 0..point_count {
+            let (a, b) = (r.f64()?, r.f64()?);
+            // Geography stores latitude first.
+            points.push(match kind {
+                SpatialKind::Geography => Coord::new(b, a),
+                SpatialKind::Geometry => Coord::new(a, b),
+            });
+        }
+        if properties & HAS_Z != 0 {
+            for point in &mut points {
+                point.z = Some(r.f64()?).filter(|z| !z.is_nan());
+            }
+        }
+        if properties & HAS_M != 0 {
+            for point in &mut points {
+                point.m = Some(r.f64()?).filter(|m| !m.is_nan());
+            }
+        }
+
+        let (figures, shapes) = if properties & (SINGLE_POINT | SINGLE_LINE_SEGMENT) != 0 {
+            let shape_kind = if properties & SINGLE_POINT != 0 { 1 } else { 2 };
+            (
+                vec![Figure { offset: 0 }],
+                vec![ShapeEntry {
+                    parent: -1,
+                    figure: 0,
+                    kind: shape_kind,
+                }],
+            )
+        } else {
+            let mut figures = Vec::new();
+            for _ in 0..r.count()? {
+                // The attribute (ring, stroke, arc) follows from the shape type.
+                r.u8()?;
+                let offset = usize::try_from(r.i32()?)
+                    .map_err(|_| SpatialError::Invalid("negative point offset"))?;
+                figures.push(Figure { offset });
+            }
+            let mut shapes = Vec::new();
+            for _ in 0..r.count()? {
+                let parent = r.i32()?;
+                let figure = r.i32()?;
+                let kind = r.u8()?;
+                shapes.push(ShapeEntry {
+                    parent,
+                    figure,
+                    kind,
+                });
+            }
+            // Version 2 appends arc segments, which only curves use.
+            (figures, shapes)
+        };
+        let layout = Layout {
+            points: &points,
+            figures: &figures,
+            shapes: &shapes,
+        };
+        let shape = match shapes.first() {
+            Some(_) => layout.shape(0)?,
+            None => return Err(SpatialError::Invalid("no shapes")),
+        };
+        Ok(Spatial { kind, srid, shape })
+    }
+
+    struct Layout<'a> {
+        points: &'a [Coord],
+        figures: &'a [Figure],
+        shapes: &'a [ShapeEntry],
+    }
+
+    impl Layout<'_> {
+        fn figure_points(&self, figure: usize) -> Result<Vec<Coord>, SpatialError> {
+            let start = self.figures[figure].offset;
+            let end = self
+                .figures
+                .get(figure + 1)
+                .map_or(self.points.len(), |f| f.offset);
+            self.points
+                .get(start..end)
+                .map(<[Coord]>::to_vec)
+                .ok_or(SpatialError::Invalid("figure points out of range"))
+        }
+
+        // Figures of a shape without children: up to the next shape that has any.
+        fn figure_range(&self, index: usize) -> Result<std::ops::Range<usize>, SpatialError> {
+            let start = match usize::try_from(self.shapes[index].figure) {
+                Ok(start) => start,
+                Err(_) => return Ok(0..0),
+            };
+            let end = self.shapes[index + 1..]
+                .iter()
+                .find_map(|s| usize::try_from(s.figure).ok())
+                .unwrap_or(self.figures.len());
+            if start > end || end > self.figures.len() {
+                return Err(SpatialError::Invalid("figure offsets out of range"));
+            }
+            Ok(start..end)
+        }
+
+        fn children(&self, index: usize) -> Result<Vec<Shape>, SpatialError> {
+            (index + 1..self.shapes.len())
+                .filter(|&i| self.shapes[i].parent == index as i32)
+                .map(|i| self.shape(i))
+                .collect()
+        }
+
+        fn shape(&self, index: usize) -> Result<Shape, SpatialError> {
+            let entry = &self.shapes[index];
+            Ok(match entry.kind {
+                1 => Shape::Point(
+                    self.figure_range(index)?
+                        .next()
+                        .map(|f| self.figure_points(f))
+                        .transpose()?
+                        .and_then(|points| points.first().copied()),
+                ),
+                2 => Shape::LineString(
+                    self.figure_range(index)?
+                        .next()
+                        .map(|f| self.figure_points(f))
+                        .transpose()?
+                        .unwrap_or_default(),
+                ),
+                3 => Shape::Polygon(
+                    self.figure_range(index)?
+                        .map(|f| self.figure_points(f))
+                        .collect::<Result<_, _>>()?,
+                ),
+                4 => Shape::MultiPoint(
+                    self.children(index)?
+                        .into_iter()
+                        .filter_map(|s| match s {
+                            Shape::Point(point) => point,
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                5 => Shape::MultiLineString(
+                    self.children(index)?
+                        .into_iter()
+                        .filter_map(|s| match s {
+                            Shape::LineString(line) => Some(line),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                6 => Shape::MultiPolygon(
+                    self.children(index)?
+                        .into_iter()
+                        .filter_map(|s| match s {
+                            Shape::Polygon(rings) => Some(rings),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                7 => Shape::GeometryCollection(self.children(index)?),
+                other => return Err(SpatialError::UnsupportedShape(other)),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct Writer {
+        points: Vec<Coord>,
+        figures: Vec<(u8, i32)>,
+        shapes: Vec<(i32, i32, u8)>,
+    }
+
+    impl Writer {
+        fn figure(&mut self, attribute: u8, coords: &[Coord]) {
+            self.figures.push((attribute, self.points.len() as i32));
+            self.points.extend_from_slice(coords);
+        }
+
+        // Add a shape; containers point at their first figure, or -1 when empty.
+        fn shape(&mut self, shape: &Shape, parent: i32) {
+            let index = self.shapes.len();
+            let first = self.figures.len() as i32;
+            let kind = match shape {
+                Shape::Point(_) => 1,
+                Shape::LineString(_) => 2,
+                Shape::Polygon(_) => 3,
+                Shape::MultiPoint(_) => 4,
+                Shape::MultiLineString(_) => 5,
+                Shape::MultiPolygon(_) => 6,
+                Shape::GeometryCollection(_) => 7,
+            };
+            self.shapes.push((parent, first, kind));
+            let me = index as i32;
+            match shape {
+                Shape::Point(point) => {
+                    if let Some(point) = point {
+                        self.figure(STROKE, std::slice::from_ref(point));
+                    }
+                }
+                Shape::LineString(coords) => {
+                    if !coords.is_empty() {
+                        self.figure(STROKE, coords);
+                    }
+                }
+                Shape::Polygon(rings) => {
+                    for (i, ring) in rings.iter().enumerate() {
+                        let attribute = if i == 0 { EXTERIOR_RING } else { INTERIOR_RING };
+                        self.figure(attribute, ring);
+                    }
+                }
+                Shape::MultiPoint(points) => {
+                    for point in points {
+                        self.shape(&Shape::Point(Some(*point)), me);
+                    }
+                }
+                Shape::MultiLineString(lines) => {
+                    for line in lines {
+                        self.shape(&Shape::LineString(line.clone()), me);
+                    }
+                }
+                Shape::MultiPolygon(polygons) => {
+                    for polygon in polygons {
+                        self.shape(&Shape::Polygon(polygon.clone()), me);
+                    }
+                }
+                Shape::GeometryCollection(shapes) => {
+                    for child in shapes {
+                        self.shape(child, me);
+                    }
+                }
+            }
+            if self.figures.len() as i32 == first {
+                self.shapes[index].1 = -1;
+            }
+        }
+    }
+
+    pub(super) fn encode(spatial: &Spatial) -> Vec<u8> {
+        let (has_z, has_m) = dimensions(&spatial.shape);
+        let mut properties = IS_VALID;
+        if has_z {
+            properties |= HAS_Z;
+        }
+        if has_m {
+            properties |= HAS_M;
+        }
+        let mut writer = Writer::default();
+        let single = matches!(spatial.shape, Shape::Point(Some(_)));
+        if single {
+            properties |= SINGLE_POINT;
+        }
+        writer.shape(&spatial.shape, -1);
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&spatial.srid.to_le_bytes());
+        out.push(1);
+        out.push(properties);
+        if !single {
+            out.extend_from_slice(&(writer.points.len() as u32).to_le_bytes());
+        }
+        for point in &writer.points {
+            let (a, b) = match spatial.kind {
+                SpatialKind::Geography => (point.y, point.x),
+                SpatialKind::Geometry => (point.x, point.y),
+            };
+            out.extend_from_slice(&a.to_le_bytes());
+            out.extend_from_slice(&b.to_le_bytes());
+        }
+        if has_z {
+            for point in &writer.points {
+                out.extend_from_slice(&point.z.unwrap_or(f64::NAN).to_le_bytes());
+            }
+        }
+        if has_m {
+            for point in &writer.points {
+                out.extend_from_slice(&point.m.unwrap_or(f64::NAN).to_le_bytes());
+            }
+        }
+        if single {
+            return out;
+        }
+        out.extend_from_slice(&(writer.figures.len() as u32).to_le_bytes());
+        for (attribute, offset) in &writer.figures {
+            out.push(*attribute);
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+        out.extend_from_slice(&(writer.shapes.len() as u32).to_le_bytes());
+        for (parent, figure, kind) in &writer.shapes {
+            out.extend_from_slice(&parent.to_le_bytes());
+            out.extend_from_slice(&figure.to_le_bytes());
+            out.push(*kind);
+        }
+        out
+    }
+}
+
+mod wkt {
+    use super::*;
+
+    pub(super) fn write(shape: &Shape, out: &mut String) {
+        let (name, empty) = match shape {
+            Shape::Point(p) => ("POINT", p.is_none()),
+            Shape::LineString(c) => ("LINESTRING", c.is_empty()),
+            Shape::Polygon(r) => ("POLYGON", r.is_empty()),
+            Shape::MultiPoint(c) => ("MULTIPOINT", c.is_empty()),
+            Shape::MultiLineString(l) => ("MULTILINESTRING", l.is_empty()),
+            Shape::MultiPolygon(p) => ("MULTIPOLYGON", p.is_empty()),
+            Shape::GeometryCollection(s) => ("GEOMETRYCOLLECTION", s.is_empty()),
+        };
+        out.push_str(name);
+        if empty {
+            out.push_str(" EMPTY");
+            return;
+        }
+        out.push(' ');
+        match shape {
+            Shape::Point(Some(c)) => coords(std::slice::from_ref(c), out),
+            Shape::LineString(c) => coords(c, out),
+            Shape::Polygon(rings) | Shape::MultiLineString(rings) => {
+                list(rings, out, |r, out| coords(r, out))
+            }
+            Shape::MultiPoint(points) => {
+                list(points, out, |p, out| coords(std::slice::from_ref(p), out))
+            }
+            Shape::MultiPolygon(polygons) => list(polygons, out, |p, out| {
+                list(p, out, |r, out| coords(r, out))
+            }),
+            Shape::GeometryCollection(shapes) => list(shapes, out, write),
+            Shape::Point(None) => {}
+        }
+    }
+
+    fn list<T>(items: &[T], out: &mut String, item: impl Fn(&T, &mut String)) {
+        out.push('(');
+        for (i, value) in items.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            item(value, out);
+        }
+        out.push(')');
+    }
+
+    // SQL Server writes Z and M as extra numbers, with NULL for a missing Z.
+    fn coords(coords: &[Coord], out: &mut String) {
+        list(coords, out, |c, out| {
+            out.push_str(&format!("{} {}", c.x, c.y));
+            match (c.z, c.m) {
+                (None, None) => {}
+                (Some(z), None) => out.push_str(&format!(" {z}")),
+                (z, Some(m)) => {
+                    let z = z.map_or("NULL".to_string(), |z| z.to_string());
+                    out.push_str(&format!(" {z} {m}"));
+                }
+            }
+        });
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Word(String),
+        Number(f64),
+        Open,
+        Close,
+        Comma,
+    }
+
+    struct Parser {
+        tokens: Vec<(usize, Token)>,
+        position: usize,
+        end: usize,
+    }
+
+    fn error(position: usize, message: &'static str) -> SpatialError {
+        SpatialError::Wkt { position, message }
+    }
+
+    fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, SpatialError> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let (at, c) = chars[i];
+            match c {
+                c if c.is_whitespace() => i += 1,
+                '(' => {
+                    tokens.push((at, Token::Open));
+                    i += 1;
+                }
+                ')' => {
+                    tokens.push((at, Token::Close));
+                    i += 1;
+                }
+                ',' => {
+                    tokens.push((at, Token::Comma));
+                    i += 1;
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let start = i;
+                    while i < chars.len() && chars[i].1.is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                    tokens.push((at, Token::Word(word.to_ascii_uppercase())));
+                }
+                c if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                    let start = i;
+                    while i < chars.len()
+                        && (chars[i].1.is_ascii_alphanumeric()
+                            || matches!(chars[i].1, '-' | '+' | '.'))
+                    {
+                        // Stop at a sign that does not follow an exponent.
+                        if i > start
+                            && matches!(chars[i].1, '-' | '+')
+                            && !matches!(chars[i - 1].1, 'e' | 'E')
+                        {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    let text: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                    let number = text.parse().map_err(|_| error(at, "invalid number"))?;
+                    tokens.push((at, Token::Number(number)));
+                }
+                _ => return Err(error(at, "unexpected character")),
+            }
+        }
+        Ok(tokens)
+    }
+
+    pub(super) fn parse(text: &str) -> Result<Shape, SpatialError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            end: text.len(),
+        };
+        let shape = parser.shape()?;
+        if parser.position < parser.tokens.len() {
+            return Err(error(parser.at(), "unexpected text after shape"));
+        }
+        Ok(shape)
+    }
+
+    impl Parser {
+        fn at(&self) -> usize {
+            self.tokens
+                .get(self.position)
+                .map_or(self.end, |(at, _)| *at)
+        }
+
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.position).map(|(_, t)| t)
+        }
+
+        fn next(&mut self) -> Option<Token> {
+            let token = self.peek().cloned();
+            self.position += 1;
+            token
+        }
+
+        fn expect(&mut self, token: Token, message: &'static str) -> Result<(), SpatialError> {
+            let at = self.at();
+            match self.next() {
+                Some(t) if t == token => Ok(()),
+                _ => Err(error(at, message)),
+            }
+        }
+
+        fn word(&mut self, word: &str) -> bool {
+            if self.peek() == Some(&Token::Word(word.into())) {
+                self.position += 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        // `EMPTY`, or the contents of a parenthesized, comma-separated list.
+        fn list<T>(
+            &mut self,
+            mut item: impl FnMut(&mut Self) -> Result<T, SpatialError>,
+        ) -> Result<Vec<T>, SpatialError> {
+            if self.word("EMPTY") {
+                return Ok(Vec::new());
+            }
+            self.expect(Token::Open, "expected '('")?;
+            let mut items = vec![item(self)?];
+            while self.peek() == Some(&Token::Comma) {
+                self.position += 1;
+                items.push(item(self)?);
+            }
+            self.expect(Token::Close, "expected ')'")?;
+            Ok(items)
+        }
+
+        fn coord(&mut self) -> Result<Coord, SpatialError> {
+            let mut values: Vec<Option<f64>> = Vec::new();
+            loop {
+                match self.peek() {
+                    Some(Token::Number(n)) => values.push(Some(*n)),
+                    Some(Token::Word(w)) if w == "NULL" => values.push(None),
+                    _ => break,
+                }
+                self.position += 1;
+            }
+            match values.as_slice() {
+                [Some(x), Some(y), rest @ ..] if rest.len() <= 2 => Ok(Coord {
+                    x: *x,
+                    y: *y,
+                    z: rest.first().copied().flatten(),
+                    m: rest.get(1).copied().flatten(),
+                }),
+                _ => Err(error(self.at(), "expected 2 to 4 coordinates")),
+            }
+        }
+
+        fn coords(&mut self) -> Result<Vec<Coord>, SpatialError> {
+            self.list(Self::coord)
+        }
+
+        fn rings(&mut self) -> Result<Vec<Vec<Coord>>, SpatialError> {
+            self.list(Self::coords)
+        }
+
+        // A point of a MULTIPOINT, with or without its own parentheses.
+        fn multi_point(&mut self) -> Result<Coord, SpatialError> {
+            if self.peek() == Some(&Token::Open) {
+                self.position += 1;
+                let coord = self.coord()?;
+                self.expect(Token::Close, "expected ')'")?;
+                Ok(coord)
+            } else {
+                self.coord()
+            }
+        }
+
+        fn shape(&mut self) -> Result<Shape, SpatialError> {
+            let at = self.at();
+            let name = match self.next() {
+                Some(Token::Word(name)) => name,
+                _ => return Err(error(at, "expected a shape name")),
+            };
+            // OGC dimension markers; the coordinates tell the dimensions anyway.
+            for marker in ["ZM", "Z", "M"] {
+                if self.word(marker) {
+                    break;
+                }
+            }
+            Ok(match name.as_str() {
+                "POINT" => {
+                    let mut points = self.coords()?;
+                    if points.len() > 1 {
+                        return Err(error(at, "a point has one coordinate"));
+                    }
+                    Shape::Point(points.pop())
+                }
+                "LINESTRING" => Shape::LineString(self.coords()?),
+                "POLYGON" => Shape::Polygon(self.rings()?),
+                "MULTIPOINT" => Shape::MultiPoint(self.list(Self::multi_point)?),
+                "MULTILINESTRING" => Shape::MultiLineString(self.rings()?),
+                "MULTIPOLYGON" => Shape::MultiPolygon(self.list(Self::rings)?),
+                "GEOMETRYCOLLECTION" => Shape::GeometryCollection(self.list(Self::shape)?),
+                _ => return Err(error(at, "unsupported shape")),
+            })
+        }
+    }
+}
+
+mod wkb {
+    use super::*;
+
+    const ISO_Z: u32 = 1000;
+    const ISO_M: u32 = 2000;
+    const EWKB_Z: u32 = 0x8000_0000;
+    const EWKB_M: u32 = 0x4000_0000;
+    const EWKB_SRID: u32 = 0x2000_0000;
+
+    pub(super) struct Reader<'a> {
+        pub bytes: &'a [u8],
+        pub position: usize,
+        // SRID of the outermost shape, when written as EWKB.
+        pub srid: Option<i32>,
+    }
+
+    impl Reader<'_> {
+        fn take<const N: usize>(&mut self) -> Result<[u8; N], SpatialError> {
+            let slice = self
+                .bytes
+                .get(self.position..self.position + N)
+                .ok_or(SpatialError::Truncated)?;
+            self.position += N;
+            Ok(slice.try_into().unwrap())
+        }
+
+        fn u32(&mut self, little: bool) -> Result<u32, SpatialError> {
+            let bytes = self.take::<4>()?;
+            Ok(if little {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            })
+        }
+
+        fn f64(&mut self, little: bool) -> Result<f64, SpatialError> {
+            let bytes = self.take::<8>()?;
+            Ok(if little {
+                f64::from_le_bytes(bytes)
+            } else {
+                f64::from_be_bytes(bytes)
+            })
+        }
+
+        fn count(&mut self, little: bool) -> Result<usize, SpatialError> {
+            let n = self.u32(little)? as usize;
+            if n > self.bytes.len() {
+                return Err(SpatialError::Truncated);
+            }
+            Ok(n)
+        }
+
+        fn coord(&mut self, little: bool, z: bool, m: bool) -> Result<Coord, SpatialError> {
+            let mut coord = Coord::new(self.f64(little)?, self.f64(little)?);
+            if z {
+                coord.z = Some(self.f64(little)?).filter(|v| !v.is_nan());
+            }
+            if m {
+                coord.m = Some(self.f64(little)?).filter(|v| !v.is_nan());
+            }
+            Ok(coord)
+        }
+
+        fn coords(&mut self, little: bool, z: bool, m: bool) -> Result<Vec<Coord>, SpatialError> {
+            (0..self.count(little)?)
+                .map(|_| self.coord(little, z, m))
+                .collect()
+        }
+
+        pub fn shape(&mut self) -> Result<Shape, SpatialError> {
+            let little = match self.take::<1>()?[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(SpatialError::Invalid("invalid WKB byte order")),
+            };
+            let code = self.u32(little)?;
+            if code & EWKB_SRID != 0 {
+                let srid = self.u32(little)? as i32;
+                if self.position == 9 {
+                    self.srid = Some(srid);
+                }
+            }
+            let z = code & EWKB_Z != 0 || matches!((code & 0xFFFF) / 1000, 1 | 3);
+            let m = code & EWKB_M != 0 || matches!((code & 0xFFFF) / 1000, 2 | 3);
+            let children = |r: &mut Self| -> Result<Vec<Shape>, SpatialError> {
+                (0..r.count(little)?).map(|_| r.shape()).collect()
+            };
+            Ok(match (code & 0xFFFF) % 1000 {
+                1 => {
+                    let coord = self.coord(little, z, m)?;
+                    // Empty points are written with NaN coordinates.
+                    Shape::Point(Some(coord).filter(|c| !(c.x.is_nan() && c.y.is_nan())))
+                }
+                2 => Shape::LineString(self.coords(little, z, m)?),
+                3 => Shape::Polygon(
+                    (0..self.count(little)?)
+                        .map(|_| self.coords(little, z, m))
+                        .collect::<Result<_, _>>()?,
+                ),
+                4 => Shape::MultiPoint(
+                    children(self)?
+                        .into_iter()
+                        .filter_map(|s| match s {
+                            Shape::Point(point) => point,
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                5 => Shape::MultiLineString(
+                    children(self)?
+                        .into_iter()
+                        .map(|s| match s {
+                            Shape::LineString(line) => Ok(line),
+                            _ => Err(SpatialError::Invalid("expected a linestring")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                6 => Shape::MultiPolygon(
+                    children(self)?
+                        .into_iter()
+                        .map(|s| match s {
+                            Shape::Polygon(rings) => Ok(rings),
+                            _ => Err(SpatialError::Invalid("expected a polygon")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                7 => Shape::GeometryCollection(children(self)?),
+                other => return Err(SpatialError::UnsupportedShape(other as u8)),
+            })
+        }
+    }
+
+    fn header(code: u32, z: bool, m: bool, out: &mut Vec<u8>) {
+        let mut code = code;
+        if z {
+            code += ISO_Z;
+        }
+        if m {
+            code += ISO_M;
+        }
+        out.push(1);
+        out.extend_from_slice(&code.to_le_bytes());
+    }
+
+    fn coord(c: &Coord, z: bool, m: bool, out: &mut Vec<u8>) {
+        out.extend_from_slice(&c.x.to_le_bytes());
+        out.extend_from_slice(&c.y.to_le_bytes());
+        if z {
+            out.extend_from_slice(&c.z.unwrap_or(f64::NAN).to_le_bytes());
+        }
+        if m {
+            out.extend_from_slice(&c.m.unwrap_or(f64::NAN).to_le_bytes());
+        }
+    }
+
+    fn coords(coords: &[Coord], z: bool, m: bool, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(coords.len() as u32).to_le_bytes());
+        for c in coords {
+            coord(c, z, m, out);
+        }
+    }
+
+    pub(super) fn write(shape: &Shape, z: bool, m: bool, out: &mut Vec<u8>) {
+        let count = |n: usize, out: &mut Vec<u8>| out.extend_from_slice(&(n as u32).to_le_bytes());
+        match shape {
+            Shape::Point(point) => {
+                header(1, z, m, out);
+                let empty = Coord {
+                    x: f64::NAN,
+                    y: f64::NAN,
+                    z: None,
+                    m: None,
+                };
+                coord(point.as_ref().unwrap_or(&empty), z, m, out);
+            }
+            Shape::LineString(line) => {
+                header(2, z, m, out);
+                coords(line, z, m, out);
+            }
+            Shape::Polygon(rings) => {
+                header(3, z, m, out);
+                count(rings.len(), out);
+                for ring in rings {
+                    coords(ring, z, m, out);
+                }
+            }
+            Shape::MultiPoint(points) => {
+                header(4, z, m, out);
+                count(points.len(), out);
+                for point in points {
+                    write(&Shape::Point(Some(*point)), z, m, out);
+                }
+            }
+            Shape::MultiLineString(lines) => {
+                header(5, z, m, out);
+                count(lines.len(), out);
+                for line in lines {
+                    write(&Shape::LineString(line.clone()), z, m, out);
+                }
+            }
+            Shape::MultiPolygon(polygons) => {
+                header(6, z, m, out);
+                count(polygons.len(), out);
+                for polygon in polygons {
+                    write(&Shape::Polygon(polygon.clone()), z, m, out);
+                }
+            }
+            Shape::GeometryCollection(shapes) => {
+                header(7, z, m, out);
+                count(shapes.len(), out);
+                for shape in shapes {
+                    write(shape, z, m, out);
+                }
+            }
+        }
+    }
+}
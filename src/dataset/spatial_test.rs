@@ -0,0 +1,304 @@
+use super::*;
+
+fn geography(wkt: &str) -> Spatial {
+    Spatial::from_wkt(SpatialKind::Geography, 4326, wkt).unwrap()
+}
+
+#[test]
+fn serializes_points_like_sql_server() {
+    let point = geography("POINT (-122.349 47.651)");
+    assert_eq!(
+        point.shape,
+        Shape::Point(Some(Coord::new(-122.349, 47.651)))
+    );
+
+    // SRID 4326, version 1, valid single point, then latitude and longitude.
+    let bytes = point.to_bytes();
+    assert_eq!(&bytes[..6], &[0xE6, 0x10, 0x00, 0x00, 0x01, 0x0C]);
+    assert_eq!(&bytes[6..14], &47.651f64.to_le_bytes());
+    assert_eq!(&bytes[14..], &(-122.349f64).to_le_bytes());
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geography, &bytes).unwrap(),
+        point
+    );
+
+    let geometry = Spatial::geometry(Shape::Point(Some(Coord::new(3.0, 4.0))));
+    assert_eq!(&geometry.to_bytes()[6..14], &3.0f64.to_le_bytes());
+}
+
+#[test]
+fn round_trips_shapes_through_every_format() {
+    let shapes = [
+        "POINT EMPTY",
+        "LINESTRING (0 0, 1 1, 2 0)",
+        "POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (2 2, 2 3, 3 3, 2 2))",
+        "MULTIPOINT ((1 2), (3 4))",
+        "MULTILINESTRING ((0 0, 1 1), (2 2, 3 3))",
+        "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))",
+        "GEOMETRYCOLLECTION (POINT (1 2), LINESTRING EMPTY, POLYGON ((0 0, 1 0, 1 1, 0 0)))",
+        "LINESTRING (1 2 3, 4 5 6)",
+        "POINT (1 2 NULL 4)",
+    ];
+    for wkt in shapes {
+        let spatial = Spatial::from_wkt(SpatialKind::Geometry, 0, wkt).unwrap();
+        assert_eq!(spatial.to_wkt(), wkt);
+        assert_eq!(spatial.to_string(), wkt);
+        let bytes = spatial.to_bytes();
+        assert_eq!(
+            Spatial::from_bytes(SpatialKind::Geometry, &bytes).unwrap(),
+            spatial,
+            "{wkt}"
+        );
+        let wkb = spatial.to_wkb();
+        assert_eq!(
+            Spatial::from_wkb(SpatialKind::Geometry, 0, &wkb).unwrap(),
+            spatial,
+            "{wkt}"
+        );
+    }
+}
+
+#[test]
+fn reads_other_wkt_and_wkb_spellings() {
+    let spatial = geography("multipoint z (1 2 3, 4 5 6)");
+    assert_eq!(spatial.to_wkt(), "MULTIPOINT ((1 2 3), (4 5 6))");
+
+    let mut wkb = vec![0x00, 0x00, 0x00, 0x00, 0x01];
+    wkb.extend_from_slice(&1.5f64.to_be_bytes());
+    wkb.extend_from_slice(&(-2.0f64).to_be_bytes());
+    let point = Spatial::from_wkb(SpatialKind::Geometry, 0, &wkb).unwrap();
+    assert_eq!(point.to_wkt(), "POINT (1.5 -2)");
+    assert_eq!(&point.to_wkb()[..5], &[0x01, 0x01, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn rejects_invalid_spatial_data() {
+    assert!(matches!(
+        Spatial::from_wkt(SpatialKind::Geometry, 0, "POINT (1)"),
+        Err(SpatialError::Wkt { .. })
+    ));
+    assert!(matches!(
+        Spatial::from_wkt(SpatialKind::Geometry, 0, "CIRCULARSTRING (0 0, 1 1, 2 0)"),
+        Err(SpatialError::Wkt { position: 0, .. })
+    ));
+    let bytes = geography("LINESTRING (0 0, 1 1)").to_bytes();
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geography, &bytes[..bytes.len() - 1]),
+        Err(SpatialError::Truncated)
+    );
+    let mut curve = bytes.clone();
+    *curve.last_mut().unwrap() = 8;
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geography, &curve),
+        Err(SpatialError::UnsupportedShape(8))
+    );
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geography, &[0, 0, 0, 0, 9, 0]),
+        Err(SpatialError::UnsupportedVersion(9))
+    );
+}
+
+#[test]
+fn converts_values_to_spatial() {
+    let zone = geography("POLYGON ((0 0, 1 0, 1 1, 0 0))");
+    let binary = DataValue::Binary(zone.to_bytes());
+    assert_eq!(
+        Spatial::from_value(&binary, SpatialKind::Geography).unwrap(),
+        zone
+    );
+    let text = DataValue::from("POLYGON ((0 0, 1 0, 1 1, 0 0))");
+    assert_eq!(
+        Spatial::from_value(&text, SpatialKind::Geography).unwrap(),
+        zone
+    );
+
+    let value = DataValue::from(zone);
+    assert_eq!(expression::type_name(&value), "geography");
+    let mut table = DataTable::new("zones");
+    let mut row = DataRow::default();
+    row.set("zone", value).unwrap();
+    table.rows.push(row);
+    assert!(table
+        .to_string()
+        .contains("| POLYGON ((0 0, 1 0, 1 1, 0 0)) |"));
+}
+
+fn wkb_header(big_endian: bool, code: u32) -> Vec<u8> {
+    if big_endian {
+        let mut bytes = vec![0x00];
+        bytes.extend_from_slice(&code.to_be_bytes());
+        bytes
+    } else {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&code.to_le_bytes());
+        bytes
+    }
+}
+
+fn wkb_u32(big_endian: bool, value: u32) -> [u8; 4] {
+    if big_endian {
+        value.to_be_bytes()
+    } else {
+        value.to_le_bytes()
+    }
+}
+
+fn wkb_points(big_endian: bool, points: &[(f64, f64)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (x, y) in points {
+        for value in [x, y] {
+            if big_endian {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+#[test]
+fn keeps_the_srid_of_every_format() {
+    for srid in [4269, 0, -1] {
+        let spatial = geography("POINT (1 2)").with_srid(srid);
+        let bytes = spatial.to_bytes();
+        assert_eq!(&bytes[..4], &srid.to_le_bytes());
+        assert_eq!(
+            Spatial::from_bytes(SpatialKind::Geography, &bytes)
+                .unwrap()
+                .srid,
+            srid
+        );
+        let wkb = spatial.to_wkb();
+        assert_eq!(
+            Spatial::from_wkb(SpatialKind::Geography, srid, &wkb)
+                .unwrap()
+                .srid,
+            srid
+        );
+    }
+
+    let text = DataValue::from("POINT (1 2)");
+    assert_eq!(
+        Spatial::from_value(&text, SpatialKind::Geography)
+            .unwrap()
+            .srid,
+        4326
+    );
+    assert_eq!(
+        Spatial::from_value(&text, SpatialKind::Geometry)
+            .unwrap()
+            .srid,
+        0
+    );
+
+    // EWKB point with the SRID flag: the embedded SRID wins over the argument.
+    let mut ewkb = wkb_header(false, 0x2000_0001);
+    ewkb.extend_from_slice(&3857u32.to_le_bytes());
+    ewkb.extend_from_slice(&wkb_points(false, &[(5.0, 6.0)]));
+    let point = Spatial::from_wkb(SpatialKind::Geometry, 0, &ewkb).unwrap();
+    assert_eq!(point.srid, 3857);
+    assert_eq!(point.shape, Shape::Point(Some(Coord::new(5.0, 6.0))));
+}
+
+#[test]
+fn round_trips_empty_shapes() {
+    let shapes = [
+        "POINT EMPTY",
+        "LINESTRING EMPTY",
+        "POLYGON EMPTY",
+        "MULTIPOINT EMPTY",
+        "MULTILINESTRING EMPTY",
+        "MULTIPOLYGON EMPTY",
+        "GEOMETRYCOLLECTION EMPTY",
+    ];
+    for kind in [SpatialKind::Geography, SpatialKind::Geometry] {
+        for wkt in shapes {
+            let spatial = Spatial::from_wkt(kind, 4326, wkt).unwrap();
+            assert_eq!(spatial.to_wkt(), wkt);
+            assert_eq!(
+                Spatial::from_bytes(kind, &spatial.to_bytes()).unwrap(),
+                spatial,
+                "{wkt}"
+            );
+            assert_eq!(
+                Spatial::from_wkb(kind, 4326, &spatial.to_wkb()).unwrap(),
+                spatial,
+                "{wkt}"
+            );
+        }
+    }
+
+    // No points and no figures, one shape pointing at figure -1.
+    let empty = Spatial::from_wkt(SpatialKind::Geometry, 0, "GEOMETRYCOLLECTION EMPTY").unwrap();
+    let mut expected = vec![0, 0, 0, 0, 0x01, 0x04, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+    expected.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x07]);
+    assert_eq!(empty.to_bytes(), expected);
+
+    // WKB has no empty point, so it is written as NaN coordinates.
+    let point = Spatial::from_wkt(SpatialKind::Geometry, 0, "POINT EMPTY").unwrap();
+    let wkb = point.to_wkb();
+    assert_eq!(wkb.len(), 21);
+    assert!(f64::from_le_bytes(wkb[5..13].try_into().unwrap()).is_nan());
+}
+
+#[test]
+fn reads_multi_part_and_collection_wkb() {
+    // Big-endian multipolygon whose second child is little-endian.
+    let mut wkb = wkb_header(true, 6);
+    wkb.extend_from_slice(&wkb_u32(true, 2));
+    for big_endian in [true, false] {
+        wkb.extend_from_slice(&wkb_header(big_endian, 3));
+        wkb.extend_from_slice(&wkb_u32(big_endian, 1));
+        wkb.extend_from_slice(&wkb_u32(big_endian, 4));
+        wkb.extend_from_slice(&wkb_points(
+            big_endian,
+            &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)],
+        ));
+    }
+    let multi = Spatial::from_wkb(SpatialKind::Geometry, 0, &wkb).unwrap();
+    assert_eq!(
+        multi.to_wkt(),
+        "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((0 0, 1 0, 1 1, 0 0)))"
+    );
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geometry, &multi.to_bytes()).unwrap(),
+        multi
+    );
+
+    // A collection holding a multipoint and a linestring.
+    let mut wkb = wkb_header(false, 7);
+    wkb.extend_from_slice(&wkb_u32(false, 2));
+    wkb.extend_from_slice(&wkb_header(false, 4));
+    wkb.extend_from_slice(&wkb_u32(false, 2));
+    for point in [(1.0, 2.0), (3.0, 4.0)] {
+        wkb.extend_from_slice(&wkb_header(false, 1));
+        wkb.extend_from_slice(&wkb_points(false, &[point]));
+    }
+    wkb.extend_from_slice(&wkb_header(false, 2));
+    wkb.extend_from_slice(&wkb_u32(false, 2));
+    wkb.extend_from_slice(&wkb_points(false, &[(0.0, 0.0), (5.0, 5.0)]));
+    let collection = Spatial::from_wkb(SpatialKind::Geometry, 0, &wkb).unwrap();
+    assert_eq!(
+        collection.to_wkt(),
+        "GEOMETRYCOLLECTION (MULTIPOINT ((1 2), (3 4)), LINESTRING (0 0, 5 5))"
+    );
+    assert_eq!(
+        Spatial::from_bytes(SpatialKind::Geometry, &collection.to_bytes()).unwrap(),
+        collection
+    );
+    assert_eq!(
+        Spatial::from_wkb(SpatialKind::Geometry, 0, &collection.to_wkb()).unwrap(),
+        collection
+    );
+
+    // A multilinestring may only hold linestrings.
+    let mut wkb = wkb_header(false, 5);
+    wkb.extend_from_slice(&wkb_u32(false, 1));
+    wkb.extend_from_slice(&wkb_header(false, 1));
+    wkb.extend_from_slice(&wkb_points(false, &[(1.0, 2.0)]));
+    assert_eq!(
+        Spatial::from_wkb(SpatialKind::Geometry, 0, &wkb),
+        Err(SpatialError::Invalid("expected a linestring"))
+    );
+}
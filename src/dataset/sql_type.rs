@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// A SQL Server data type, the target of [`DataValue::cast`](super::DataValue::cast).
+/// Lengths of `None` stand for `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SqlType {
+    Bit,
+    TinyInt,
+    SmallInt,
+    Int,
+    BigInt,
+    Real,
+    Float,
+    Decimal { precision: u8, scale: u8 },
+    Money,
+    SmallMoney,
+    Char(u32),
+    VarChar(Option<u32>),
+    NChar(u32),
+    NVarChar(Option<u32>),
+    Binary(u32),
+    VarBinary(Option<u32>),
+    UniqueIdentifier,
+    Date,
+    Time,
+    SmallDateTime,
+    DateTime,
+    DateTime2,
+    DateTimeOffset,
+    Xml,
+}
+
+impl SqlType {
+    /// The type's name without length, precision or scale, e.g. `nvarchar`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SqlType::Bit => "bit",
+            SqlType::TinyInt => "tinyint",
+            SqlType::SmallInt => "smallint",
+            SqlType::Int => "int",
+            SqlType::BigInt => "bigint",
+            SqlType::Real => "real",
+            SqlType::Float => "float",
+            SqlType::Decimal { .. } => "decimal",
+            SqlType::Money => "money",
+            SqlType::SmallMoney => "smallmoney",
+            SqlType::Char(_) => "char",
+            SqlType::VarChar(_) => "varchar",
+            SqlType::NChar(_) => "nchar",
+            SqlType::NVarChar(_) => "nvarchar",
+            SqlType::Binary(_) => "binary",
+            SqlType::VarBinary(_) => "varbinary",
+            SqlType::UniqueIdentifier => "uniqueidentifier",
+            SqlType::Date => "date",
+            SqlType::Time => "time",
+            SqlType::SmallDateTime => "smalldatetime",
+            SqlType::DateTime => "datetime",
+            SqlType::DateTime2 => "datetime2",
+            SqlType::DateTimeOffset => "datetimeoffset",
+            SqlType::Xml => "xml",
+        }
+    }
+}
+
+impl fmt::Display for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name();
+        match self {
+            SqlType::Decimal { precision, scale } => write!(f, "{name}({precision},{scale})"),
+            SqlType::Char(n) | SqlType::NChar(n) | SqlType::Binary(n) => write!(f, "{name}({n})"),
+            SqlType::VarChar(n) | SqlType::NVarChar(n) | SqlType::VarBinary(n) => match n {
+                Some(n) => write!(f, "{name}({n})"),
+                None => write!(f, "{name}(max)"),
+            },
+            _ => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Conversions between [`DataValue`] and the `time` crate, enabled by the `time`
+//! feature. Values are stored in their chrono form, so `time` and chrono values of
+//! the same moment compare and convert alike.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use super::expression::type_name;
+use super::{DataValue, ExpressionError};
+
+fn naive_date(v: Date) -> NaiveDate {
+    NaiveDate::from_ymd_opt(v.year(), v.month() as u32, v.day() as u32)
+        .expect("time dates are within chrono's range")
+}
+
+fn naive_time(v: Time) -> NaiveTime {
+    NaiveTime::from_hms_nano_opt(
+        v.hour() as u32,
+        v.minute() as u32,
+        v.second() as u32,
+        v.nanosecond(),
+    )
+    .expect("time values are valid chrono times")
+}
+
+fn naive_date_time(v: PrimitiveDateTime) -> NaiveDateTime {
+    NaiveDateTime::new(naive_date(v.date()), naive_time(v.time()))
+}
+
+fn date_time_offset(v: OffsetDateTime) -> DateTime<FixedOffset> {
+    let offset =
+        FixedOffset::east_opt(v.offset().whole_seconds()).expect("time offsets are within a day");
+    let local = naive_date_time(PrimitiveDateTime::new(v.date(), v.time()));
+    let utc = local - chrono::Duration::seconds(offset.local_minus_utc() as i64);
+    DateTime::from_naive_utc_and_offset(utc, offset)
+}
+
+fn date(v: NaiveDate) -> Option<Date> {
+    use chrono::Datelike;
+    let month = Month::try_from(v.month() as u8).ok()?;
+    Date::from_calendar_date(v.year(), month, v.day() as u8).ok()
+}
+
+// Leap seconds, which chrono represents and `time` does not, fail to convert.
+fn time(v: NaiveTime) -> Option<Time> {
+    Time::from_hms_nano(
+        v.hour() as u8,
+        v.minute() as u8,
+        v.second() as u8,
+        v.nanosecond(),
+    )
+    .ok()
+}
+
+fn primitive(v: NaiveDateTime) -> Option<PrimitiveDateTime> {
+    Some(PrimitiveDateTime::new(date(v.date())?, time(v.time())?))
+}
+
+impl From<Date> for DataValue {
+    fn from(v: Date) -> Self {
+        DataValue::Date(naive_date(v))
+    }
+}
+
+impl From<Time> for DataValue {
+    fn from(v: Time) -> Self {
+        DataValue::Time(naive_time(v))
+    }
+}
+
+impl From<PrimitiveDateTime> for DataValue {
+    fn from(v: PrimitiveDateTime) -> Self {
+        DataValue::DateTime(naive_date_time(v))
+    }
+}
+
+impl From<OffsetDateTime> for DataValue {
+    fn from(v: OffsetDateTime) -> Self {
+        DataValue::DateTimeOffset(date_time_offset(v))
+    }
+}
+
+fn conversion(value: &DataValue, target: &'static str) -> ExpressionError {
+    ExpressionError::Conversion {
+        value: format!("{} {value:?}", type_name(value)),
+        target,
+    }
+}
+
+// The base value of a `sql_variant`, any other value as is.
+fn base(value: &DataValue) -> &DataValue {
+    match value {
+        DataValue::Variant { value, .. } => base(value),
+        other => other,
+    }
+}
+
+impl TryFrom<&DataValue> for Date {
+    type Error = ExpressionError;
+
+    fn try_from(value: &DataValue) -> Result<Self, Self::Error> {
+        match base(value) {
+            DataValue::Date(v) => date(*v),
+            _ => None,
+        }
+        .ok_or_else(|| conversion(value, "time::Date"))
+    }
+}
+
+impl TryFrom<&DataValue> for Time {
+    type Error = ExpressionError;
+
+    fn try_from(value: &DataValue) -> Result<Self, Self::Error> {
+        match base(value) {
+            DataValue::Time(v) => time(*v),
+            _ => None,
+        }
+        .ok_or_else(|| conversion(value, "time::Time"))
+    }
+}
+
+impl TryFrom<&DataValue> for PrimitiveDateTime {
+    type Error = ExpressionError;
+
+    fn try_from(value: &DataValue) -> Result<Self, Self::Error> {
+        match base(value) {
+            DataValue::DateTime(v) => primitive(*v),
+            _ => None,
+        }
+        .ok_or_else(|| conversion(value, "time::PrimitiveDateTime"))
+    }
+}
+
+impl TryFrom<&DataValue> for OffsetDateTime {
+    type Error = ExpressionError;
+
+    fn try_from(value: &DataValue) -> Result<Self, Self::Error> {
+        match base(value) {
+            DataValue::DateTimeOffset(v) => {
+                let offset = UtcOffset::from_whole_seconds(v.offset().local_minus_utc()).ok();
+                offset
+                    .zip(primitive(v.naive_local()))
+                    .map(|(offset, local)| local.assume_offset(offset))
+            }
+            _ => None,
+        }
+        .ok_or_else(|| conversion(value, "time::OffsetDateTime"))
+    }
+}
+
+impl PartialEq<Date> for DataValue {
+    fn eq(&self, other: &Date) -> bool {
+        match self {
+            DataValue::Date(v) => *v == naive_date(*other),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<Time> for DataValue {
+    fn eq(&self, other: &Time) -> bool {
+        match self {
+            DataValue::Time(v) => *v == naive_time(*other),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<PrimitiveDateTime> for DataValue {
+    fn eq(&self, other: &PrimitiveDateTime) -> bool {
+        match self {
+            DataValue::DateTime(v) => *v == naive_date_time(*other),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<OffsetDateTime> for DataValue {
+    fn eq(&self, other: &OffsetDateTime) -> bool {
+        match self {
+            DataValue::DateTimeOffset(v) => *v == date_time_offset(*other),
+            _ => false,
+        }
+    }
+}
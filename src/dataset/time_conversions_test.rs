@@ -0,0 +1,65 @@
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+use time::macros::{date, datetime, time};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+use super::*;
+
+#[test]
+fn converts_time_values_to_data_values() {
+    let day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+    assert_eq!(DataValue::from(date!(2024 - 02 - 29)), DataValue::Date(day));
+    assert_eq!(
+        DataValue::from(time!(13:45:30.123456789)),
+        DataValue::Time(chrono::NaiveTime::from_hms_nano_opt(13, 45, 30, 123_456_789).unwrap())
+    );
+    assert_eq!(
+        DataValue::from(datetime!(2024-02-29 13:45)),
+        DataValue::DateTime(day.and_hms_opt(13, 45, 0).unwrap())
+    );
+    let offset = FixedOffset::east_opt(-5 * 3600).unwrap();
+    assert_eq!(
+        DataValue::from(datetime!(2024-02-29 13:45 -5)),
+        DataValue::DateTimeOffset(offset.with_ymd_and_hms(2024, 2, 29, 13, 45, 0).unwrap())
+    );
+
+    let value: DataValue = date!(2024 - 02 - 29).into();
+    assert_eq!(value, date!(2024 - 02 - 29));
+    assert_ne!(value, date!(2024 - 03 - 01));
+    // The same instant in another offset.
+    let value: DataValue = datetime!(2024-02-29 13:45 -5).into();
+    assert_eq!(value, datetime!(2024-02-29 18:45 UTC));
+}
+
+#[test]
+fn converts_data_values_to_time_values() {
+    let stamp = datetime!(2024-02-29 13:45:30.5 +2);
+    let value = DataValue::from(stamp);
+    assert_eq!(OffsetDateTime::try_from(&value).unwrap(), stamp);
+    assert_eq!(
+        OffsetDateTime::try_from(&value).unwrap().offset(),
+        stamp.offset()
+    );
+    assert_eq!(
+        PrimitiveDateTime::try_from(&DataValue::from(datetime!(1900-01-01 0:00))).unwrap(),
+        datetime!(1900-01-01 0:00)
+    );
+    assert_eq!(
+        Time::try_from(&DataValue::from(time!(23:59:59))).unwrap(),
+        time!(23:59:59)
+    );
+
+    let variant = DataValue::Variant {
+        base_type: "date".into(),
+        value: Box::new(date!(2000 - 01 - 01).into()),
+    };
+    assert_eq!(Date::try_from(&variant).unwrap(), date!(2000 - 01 - 01));
+
+    assert!(matches!(
+        Date::try_from(&DataValue::Text("2000-01-01".into())),
+        Err(ExpressionError::Conversion {
+            target: "time::Date",
+            ..
+        })
+    ));
+    assert!(Time::try_from(&DataValue::Null).is_err());
+}
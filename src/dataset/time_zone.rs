@@ -0,0 +1,80 @@
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDateTime, TimeZone};
+
+/// The time zone a server's `datetime`, `datetime2` and `smalldatetime` values are
+/// written in. Those types store a wall-clock time with no offset; the zone turns them
+/// into instants and back.
+///
+/// A fixed offset never changes. A named zone, available with the `chrono-tz` feature,
+/// follows daylight saving time: a wall-clock time repeated when clocks go back is
+/// read as its first occurrence, and one skipped when clocks go forward does not
+/// exist and fails to convert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerTimeZone {
+    Fixed(FixedOffset),
+    #[cfg(feature = "chrono-tz")]
+    Named(chrono_tz::Tz),
+}
+
+impl Default for ServerTimeZone {
+    fn default() -> Self {
+        Self::utc()
+    }
+}
+
+impl ServerTimeZone {
+    pub fn utc() -> Self {
+        Self::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// The instant `local` names in this zone, with the zone's offset at that instant,
+    /// or `None` when the time was skipped by a daylight saving transition.
+    pub fn from_local(&self, local: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Self::Fixed(offset) => earliest(offset.from_local_datetime(local)),
+            #[cfg(feature = "chrono-tz")]
+            Self::Named(tz) => earliest(tz.from_local_datetime(local)).map(|v| v.fixed_offset()),
+        }
+    }
+
+    /// The wall-clock time of `instant` in this zone, as the server stores it.
+    pub fn to_local<Tz: TimeZone>(&self, instant: &DateTime<Tz>) -> NaiveDateTime {
+        let utc = instant.naive_utc();
+        match self {
+            Self::Fixed(offset) => offset.from_utc_datetime(&utc).naive_local(),
+            #[cfg(feature = "chrono-tz")]
+            Self::Named(tz) => tz.from_utc_datetime(&utc).naive_local(),
+        }
+    }
+}
+
+fn earliest<Tz: TimeZone>(result: LocalResult<DateTime<Tz>>) -> Option<DateTime<Tz>> {
+    match result {
+        LocalResult::Single(v) | LocalResult::Ambiguous(v, _) => Some(v),
+        LocalResult::None => None,
+    }
+}
+
+impl From<FixedOffset> for ServerTimeZone {
+    fn from(offset: FixedOffset) -> Self {
+        Self::Fixed(offset)
+    }
+}
+
+#[cfg(feature = "chrono-tz")]
+impl From<chrono_tz::Tz> for ServerTimeZone {
+    fn from(tz: chrono_tz::Tz) -> Self {
+        Self::Named(tz)
+    }
+}
+
+impl fmt::Display for ServerTimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(offset) => write!(f, "{offset}"),
+            #[cfg(feature = "chrono-tz")]
+            Self::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
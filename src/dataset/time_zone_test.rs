@@ -0,0 +1,58 @@
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use super::*;
+
+fn local(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+#[test]
+fn converts_wall_clock_times_with_a_fixed_offset() {
+    let zone = ServerTimeZone::from(FixedOffset::west_opt(5 * 3600).unwrap());
+    let value = DataValue::DateTime(local("2024-06-01 08:00"));
+    assert_eq!(
+        value.to_utc(&zone),
+        Some(Utc.with_ymd_and_hms(2024, 6, 1, 13, 0, 0).unwrap())
+    );
+    assert_eq!(
+        value.to_zoned(&zone).unwrap().to_rfc3339(),
+        "2024-06-01T08:00:00-05:00"
+    );
+
+    let instant = Utc
+        .with_ymd_and_hms(2024, 6, 1, 13, 0, 0)
+        .unwrap()
+        .fixed_offset();
+    assert_eq!(
+        DataValue::DateTimeOffset(instant).to_utc(&zone),
+        value.to_utc(&zone)
+    );
+    assert_eq!(
+        DataValue::DateTimeOffset(instant).to_server_local(&zone),
+        value
+    );
+    assert_eq!(DataValue::from(7).to_server_local(&zone), 7);
+    assert_eq!(DataValue::Date(NaiveDate::MIN).to_utc(&zone), None);
+    assert_eq!(ServerTimeZone::default().to_string(), "+00:00");
+}
+
+#[cfg(feature = "chrono-tz")]
+#[test]
+fn follows_daylight_saving_time_in_named_zones() {
+    let zone = ServerTimeZone::from(chrono_tz::America::New_York);
+    let utc = |s: &str| {
+        DataValue::DateTime(local(s))
+            .to_utc(&zone)
+            .map(|v| v.to_string())
+    };
+
+    assert_eq!(utc("2024-01-15 12:00").unwrap(), "2024-01-15 17:00:00 UTC");
+    assert_eq!(utc("2024-07-15 12:00").unwrap(), "2024-07-15 16:00:00 UTC");
+    // Clocks skip from 02:00 to 03:00 on 10 March and repeat 01:00-02:00 on 3 November.
+    assert_eq!(utc("2024-03-10 02:30"), None);
+    assert_eq!(utc("2024-11-03 01:30").unwrap(), "2024-11-03 05:30:00 UTC");
+    assert_eq!(zone.to_string(), "America/New_York");
+
+    let after = Utc.with_ymd_and_hms(2024, 11, 3, 6, 30, 0).unwrap();
+    assert_eq!(zone.to_local(&after), local("2024-11-03 01:30"));
+}
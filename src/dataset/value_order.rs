@@ -0,0 +1,305 @@
+//! `Eq`, `Hash` and `Ord` for [`DataValue`]. The order compares SQL Server's type
+//! families first, then values within a family as SQL Server does, and finally the
+//! variant and exact content so that it is total and agrees with equality.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use uuid::Uuid;
+
+use super::expression::compare_text;
+use super::{DataValue, Numeric};
+
+impl PartialEq for DataValue {
+    fn eq(&self, other: &Self) -> bool {
+        exact(self, other).is_eq()
+    }
+}
+
+impl Eq for DataValue {}
+
+impl Hash for DataValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        rank(self).hash(state);
+        match self {
+            DataValue::Null => {}
+            DataValue::TinyInt(v) => v.hash(state),
+            DataValue::SmallInt(v) => v.hash(state),
+            DataValue::Int(v) => v.hash(state),
+            DataValue::BigInt(v) => v.hash(state),
+            DataValue::Real(v) => float_bits(*v as f64).hash(state),
+            DataValue::Float(v) => float_bits(*v).hash(state),
+            DataValue::Decimal(v) | DataValue::Money(v) => v.hash(state),
+            DataValue::Numeric(v) => v.hash(state),
+            DataValue::Bool(v) => v.hash(state),
+            DataValue::Text(v) | DataValue::Xml(v) => v.hash(state),
+            DataValue::Binary(v) => v.hash(state),
+            DataValue::Guid(v) => v.hash(state),
+            DataValue::Date(v) => v.hash(state),
+            DataValue::Time(v) => v.hash(state),
+            DataValue::DateTime(v) => v.hash(state),
+            DataValue::DateTimeOffset(v) => v.hash(state),
+            DataValue::Variant { base_type, value } => {
+                base_type.hash(state);
+                value.hash(state);
+            }
+            DataValue::Udt { type_name, bytes } => {
+                type_name.hash(state);
+                bytes.hash(state);
+            }
+            DataValue::Spatial(v) => {
+                v.kind.hash(state);
+                v.to_bytes().hash(state);
+            }
+            DataValue::HierarchyId(v) => v.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for DataValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DataValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        collated(self, other).then_with(|| exact(self, other))
+    }
+}
+
+/// The order of [`Ord for DataValue`](DataValue) without its final tie-break, so
+/// values SQL Server considers equal, such as `'abc'` and `'ABC '`, compare equal.
+/// Used for sorting and `MIN`/`MAX`.
+pub(crate) fn collated(a: &DataValue, b: &DataValue) -> Ordering {
+    let (a, b) = (base(a), base(b));
+    family(a).cmp(&family(b)).then_with(|| within_family(a, b))
+}
+
+// The value a `sql_variant` holds; other values as they are.
+fn base(value: &DataValue) -> &DataValue {
+    match value {
+        DataValue::Variant { value, .. } => base(value),
+        other => other,
+    }
+}
+
+// SQL Server's data type families, lowest first.
+fn family(value: &DataValue) -> u8 {
+    match value {
+        DataValue::Null => 0,
+        DataValue::Guid(_) => 1,
+        DataValue::Binary(_)
+        | DataValue::Udt { .. }
+        | DataValue::Spatial(_)
+        | DataValue::HierarchyId(_) => 2,
+        DataValue::Text(_) | DataValue::Xml(_) => 3,
+        DataValue::Date(_)
+        | DataValue::Time(_)
+        | DataValue::DateTime(_)
+        | DataValue::DateTimeOffset(_) => 5,
+        DataValue::Variant { value, .. } => family(value),
+        _ => 4,
+    }
+}
+
+// Compare two values of the same family like SQL Server.
+fn within_family(a: &DataValue, b: &DataValue) -> Ordering {
+    match (a, b) {
+        (DataValue::Guid(x), DataValue::Guid(y)) => guid_key(x).cmp(&guid_key(y)),
+        (DataValue::Text(x) | DataValue::Xml(x), DataValue::Text(y) | DataValue::Xml(y)) => {
+            compare_text(x, y)
+        }
+        _ => match family(a) {
+            2 => bytes(a).cmp(&bytes(b)),
+            4 => number_key(a).cmp(&number_key(b)),
+            5 => moment(a).cmp(&moment(b)),
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+// SQL Server sorts `uniqueidentifier` by its last six bytes first, then by the
+// remaining groups from right to left, each in storage order.
+fn guid_key(guid: &Uuid) -> [u8; 16] {
+    let b = guid.as_bytes();
+    [
+        b[10], b[11], b[12], b[13], b[14], b[15], b[8], b[9], b[7], b[6], b[5], b[4], b[3], b[2],
+        b[1], b[0],
+    ]
+}
+
+fn bytes(value: &DataValue) -> Cow<'_, [u8]> {
+    match value {
+        DataValue::Binary(b) | DataValue::Udt { bytes: b, .. } => Cow::Borrowed(b),
+        DataValue::Spatial(s) => Cow::Owned(s.to_bytes()),
+        DataValue::HierarchyId(h) => Cow::Owned(h.to_bytes()),
+        _ => Cow::Borrowed(&[]),
+    }
+}
+
+// Numbers compare by their nearest double and then exactly, so decimals that round to
+// the same double still order by value. Floats have no exact part and sort first among
+// such ties.
+struct NumberKey {
+    approximate: f64,
+    exact: Option<Numeric>,
+}
+
+impl PartialEq for NumberKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for NumberKey {}
+
+impl PartialOrd for NumberKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NumberKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        float_order(self.approximate, other.approximate).then_with(|| {
+            match (&self.exact, &other.exact) {
+                (Some(x), Some(y)) => x.cmp_value(y),
+                (x, y) => x.is_some().cmp(&y.is_some()),
+            }
+        })
+    }
+}
+
+fn number_key(value: &DataValue) -> NumberKey {
+    let exact = match value {
+        DataValue::Real(v) => return approximate(*v as f64),
+        DataValue::Float(v) => return approximate(*v),
+        DataValue::Bool(v) => Numeric::new(*v as i128, 0),
+        DataValue::TinyInt(v) => Numeric::new(*v as i128, 0),
+        DataValue::SmallInt(v) => Numeric::new(*v as i128, 0),
+        DataValue::Int(v) => Numeric::new(*v as i128, 0),
+        DataValue::BigInt(v) => Numeric::new(*v as i128, 0),
+        DataValue::Decimal(d) | DataValue::Money(d) => Some(Numeric::from(*d)),
+        DataValue::Numeric(n) => Some(*n),
+        _ => None,
+    };
+    NumberKey {
+        // Parsing the exact text rounds correctly, so the approximation never
+        // reorders values.
+        approximate: exact.map_or(0.0, |n| n.to_string().parse().unwrap_or(0.0)),
+        exact,
+    }
+}
+
+fn approximate(v: f64) -> NumberKey {
+    NumberKey {
+        approximate: v,
+        exact: None,
+    }
+}
+
+// `time` values compare as times on 1900-01-01 and `datetimeoffset` values by their
+// UTC time, as SQL Server converts them for comparison.
+fn moment(value: &DataValue) -> Option<NaiveDateTime> {
+    match value {
+        DataValue::Date(d) => d.and_hms_opt(0, 0, 0),
+        DataValue::Time(t) => NaiveDate::from_ymd_opt(1900, 1, 1).map(|d| d.and_time(*t)),
+        DataValue::DateTime(dt) => Some(*dt),
+        DataValue::DateTimeOffset(dt) => Some(dt.naive_utc()),
+        _ => None,
+    }
+}
+
+// -0.0 equals 0.0 and every NaN equals every other.
+fn float_bits(v: f64) -> u64 {
+    if v.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        (v + 0.0).to_bits()
+    }
+}
+
+fn float_order(x: f64, y: f64) -> Ordering {
+    f64::from_bits(float_bits(x)).total_cmp(&f64::from_bits(float_bits(y)))
+}
+
+// Position of the variant in the declaration of `DataValue`.
+fn rank(value: &DataValue) -> u8 {
+    match value {
+        DataValue::TinyInt(_) => 0,
+        DataValue::SmallInt(_) => 1,
+        DataValue::Int(_) => 2,
+        DataValue::BigInt(_) => 3,
+        DataValue::Real(_) => 4,
+        DataValue::Float(_) => 5,
+        DataValue::Decimal(_) => 6,
+        DataValue::Numeric(_) => 7,
+        DataValue::Money(_) => 8,
+        DataValue::Bool(_) => 9,
+        DataValue::Text(_) => 10,
+        DataValue::Xml(_) => 11,
+        DataValue::Binary(_) => 12,
+        DataValue::Guid(_) => 13,
+        DataValue::Date(_) => 14,
+        DataValue::Time(_) => 15,
+        DataValue::DateTime(_) => 16,
+        DataValue::DateTimeOffset(_) => 17,
+        DataValue::Variant { .. } => 18,
+        DataValue::Udt { .. } => 19,
+        DataValue::Spatial(_) => 20,
+        DataValue::HierarchyId(_) => 21,
+        DataValue::Null => 22,
+    }
+}
+
+// Order by variant and then by content, agreeing with structural equality.
+fn exact(a: &DataValue, b: &DataValue) -> Ordering {
+    use DataValue::*;
+    match (a, b) {
+        (Null, Null) => Ordering::Equal,
+        (TinyInt(x), TinyInt(y)) => x.cmp(y),
+        (SmallInt(x), SmallInt(y)) => x.cmp(y),
+        (Int(x), Int(y)) => x.cmp(y),
+        (BigInt(x), BigInt(y)) => x.cmp(y),
+        (Real(x), Real(y)) => float_order(*x as f64, *y as f64),
+        (Float(x), Float(y)) => float_order(*x, *y),
+        (Decimal(x), Decimal(y)) | (Money(x), Money(y)) => x.cmp(y),
+        (Numeric(x), Numeric(y)) => x.cmp_value(y).then(x.scale().cmp(&y.scale())),
+        (Bool(x), Bool(y)) => x.cmp(y),
+        (Text(x), Text(y)) | (Xml(x), Xml(y)) => x.cmp(y),
+        (Binary(x), Binary(y)) => x.cmp(y),
+        (Guid(x), Guid(y)) => x.cmp(y),
+        (Date(x), Date(y)) => x.cmp(y),
+        (Time(x), Time(y)) => x.cmp(y),
+        (DateTime(x), DateTime(y)) => x.cmp(y),
+        (DateTimeOffset(x), DateTimeOffset(y)) => x.cmp(y),
+        (
+            Variant {
+                base_type: t,
+                value: x,
+            },
+            Variant {
+                base_type: u,
+                value: y,
+            },
+        ) => t.cmp(u).then_with(|| exact(x, y)),
+        (
+            Udt {
+                type_name: t,
+                bytes: x,
+            },
+            Udt {
+                type_name: u,
+                bytes: y,
+            },
+        ) => t.cmp(u).then_with(|| x.cmp(y)),
+        (Spatial(x), Spatial(y)) => (x.kind as u8)
+            .cmp(&(y.kind as u8))
+            .then_with(|| x.to_bytes().cmp(&y.to_bytes())),
+        (HierarchyId(x), HierarchyId(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
@@ -0,0 +1,400 @@
+use anyhow::Result;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::dataset::DataValue;
+use crate::query::{col, ident, where_clause, Condition, ParamSink, Update};
+use crate::{quote_name, Command};
+
+use super::{SqlConnection, Transaction};
+
+// SQL Server recommends `.WRITE` chunks in multiples of 8040 bytes.
+const DEFAULT_CHUNK_SIZE: usize = 8040 * 128;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum BlobError {
+    #[error("a blob needs at least one key column to locate its row")]
+    MissingKey,
+    #[error("no row matches the key of the blob")]
+    RowNotFound,
+    #[error("text written to a blob is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("text read from a blob is not valid UTF-16")]
+    InvalidUtf16,
+    #[error("unexpected value {0:?} read from a blob")]
+    UnexpectedValue(DataValue),
+    #[error(transparent)]
+    Command(#[from] crate::CommandError),
+}
+
+/// How a [`Blob`] column stores its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobKind {
+    /// `varbinary(max)`: bytes are copied unchanged.
+    Binary,
+    /// `nvarchar(max)`: UTF-8 on the Rust side, UTF-16 on the server.
+    Text,
+}
+
+/// Progress of a [`Blob`] transfer, reported after every chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobProgress {
+    /// Bytes of the stored value read so far, or bytes taken from the reader so far
+    /// when writing.
+    pub transferred: u64,
+    /// Stored size of the value (`DATALENGTH`) when reading; unknown when writing.
+    pub total: Option<u64>,
+}
+
+/// A `varbinary(max)` or `nvarchar(max)` value in one row, transferred in chunks so
+/// that large documents never have to fit in memory.
+///
+/// Reads page through the value with `SUBSTRING`; writes clear it and append each
+/// chunk with an `UPDATE ... SET column.WRITE(...)`. The key columns must identify a
+/// single row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob {
+    table: String,
+    column: String,
+    kind: BlobKind,
+    key: Vec<(String, DataValue)>,
+    chunk_size: usize,
+}
+
+impl Blob {
+    pub fn binary(table: &str, column: &str) -> Self {
+        Self::new(table, column, BlobKind::Binary)
+    }
+
+    pub fn text(table: &str, column: &str) -> Self {
+        Self::new(table, column, BlobKind::Text)
+    }
+
+    fn new(table: &str, column: &str, kind: BlobKind) -> Self {
+        Self {
+            table: table.into(),
+            column: column.into(),
+            kind,
+            key: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Add a key column; multiple keys are combined with `AND`.
+    pub fn with_key<T: Into<DataValue>>(mut self, column: &str, value: T) -> Self {
+        self.key.push((column.into(), value.into()));
+        self
+    }
+
+    /// Bytes transferred per round trip, about 1 MB by default. Text is read in
+    /// chunks of half as many characters.
+    pub fn with_chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(2);
+        self
+    }
+
+    pub fn kind(&self) -> BlobKind {
+        self.kind
+    }
+
+    /// `SELECT` of the stored size of the value, in bytes.
+    pub fn length_command(&self) -> Result<Command, BlobError> {
+        let mut sink = ParamSink::default();
+        let sql = format!(
+            "SELECT DATALENGTH({}) FROM {}{}",
+            self.target_column()?,
+            ident(&self.table)?,
+            where_clause(&self.locate()?, &mut sink)?
+        );
+        Ok(sink.into_command(sql))
+    }
+
+    /// `SELECT` of the chunk starting at `offset`, counted from zero in bytes for
+    /// binary values and in characters for text. Text chunks are returned as their
+    /// UTF-16 bytes so that a character split between chunks survives.
+    pub fn read_command(&self, offset: u64) -> Result<Command, BlobError> {
+        let mut sink = ParamSink::default();
+        let substring = format!(
+            "SUBSTRING({}, {}, {})",
+            self.target_column()?,
+            sink.bind(DataValue::BigInt(offset as i64 + 1)),
+            sink.bind(DataValue::BigInt(self.units() as i64))
+        );
+        let selected = match self.kind {
+            BlobKind::Binary => substring,
+            BlobKind::Text => format!("CAST({} AS varbinary(max))", substring),
+        };
+        let sql = format!(
+            "SELECT {} FROM {}{}",
+            selected,
+            ident(&self.table)?,
+            where_clause(&self.locate()?, &mut sink)?
+        );
+        Ok(sink.into_command(sql))
+    }
+
+    /// `UPDATE` setting the value to an empty one, which `.WRITE` can append to.
+    pub fn clear_command(&self) -> Result<Command, BlobError> {
+        let empty = match self.kind {
+            BlobKind::Binary => DataValue::Binary(Vec::new()),
+            BlobKind::Text => DataValue::Text(String::new()),
+        };
+        let update = self.locate()?.into_iter().fold(
+            Update::table(&self.table).set(&quote_name(&self.column), empty),
+            Update::filter,
+        );
+        Ok(update.build()?)
+    }
+
+    /// `UPDATE` appending `chunk` to the value with `.WRITE`.
+    pub fn append_command(&self, chunk: DataValue) -> Result<Command, BlobError> {
+        let mut sink = ParamSink::default();
+        let sql = format!(
+            "UPDATE {} SET {}.WRITE({}, NULL, NULL){}",
+            ident(&self.table)?,
+            self.target_column()?,
+            sink.bind(chunk),
+            where_clause(&self.locate()?, &mut sink)?
+        );
+        Ok(sink.into_command(sql))
+    }
+
+    /// Copy the value into `writer` chunk by chunk and return the number of bytes
+    /// written. Text is written as UTF-8. A NULL value writes nothing.
+    pub async fn read_to<W>(
+        &self,
+        connection: &mut SqlConnection,
+        writer: &mut W,
+        mut progress: impl FnMut(BlobProgress),
+    ) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let (sql, params) = self.length_command()?.try_build()?;
+        let Some(total) = stored_length(connection.execute_scalar(&sql, params).await?)? else {
+            return Ok(0);
+        };
+        let mut decoder = Utf16Decoder::default();
+        let (mut transferred, mut written, mut offset) = (0, 0, 0);
+        progress(BlobProgress {
+            transferred,
+            total: Some(total),
+        });
+        while transferred < total {
+            let (sql, params) = self.read_command(offset)?.try_build()?;
+            let chunk = chunk_bytes(connection.execute_scalar(&sql, params).await?)?;
+            // The value shrank since its size was read.
+            if chunk.is_empty() {
+                break;
+            }
+            transferred += chunk.len() as u64;
+            offset += self.units();
+            let data = match self.kind {
+                BlobKind::Binary => chunk,
+                BlobKind::Text => decoder.push(&chunk)?.into_bytes(),
+            };
+            writer.write_all(&data).await?;
+            written += data.len() as u64;
+            progress(BlobProgress {
+                transferred,
+                total: Some(total),
+            });
+        }
+        decoder.finish()?;
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Replace the value with the contents of `reader` and return the number of bytes
+    /// read. Text must be UTF-8. The chunks are written in one transaction, so a
+    /// failure leaves the previous value in place.
+    pub async fn write_from<R>(
+        &self,
+        connection: &mut SqlConnection,
+        reader: &mut R,
+        mut progress: impl FnMut(BlobProgress),
+    ) -> Result<u64>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut transaction = connection.begin().await?;
+        match self
+            .write_chunks(&mut transaction, reader, &mut progress)
+            .await
+        {
+            Ok(transferred) => {
+                transaction.commit().await?;
+                Ok(transferred)
+            }
+            Err(e) => {
+                // Report the failed write rather than a rollback error it may cause.
+                let _ = transaction.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn write_chunks<R>(
+        &self,
+        transaction: &mut Transaction<'_>,
+        reader: &mut R,
+        progress: &mut impl FnMut(BlobProgress),
+    ) -> Result<u64>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let (sql, params) = self.clear_command()?.try_build()?;
+        if transaction.execute_non_query(&sql, params).await? == 0 {
+            return Err(BlobError::RowNotFound.into());
+        }
+        let mut buffer = vec![0; self.chunk_size];
+        let mut decoder = Utf8Decoder::default();
+        let mut transferred = 0;
+        progress(BlobProgress {
+            transferred,
+            total: None,
+        });
+        loop {
+            let read = fill(reader, &mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            let chunk = match self.kind {
+                BlobKind::Binary => DataValue::Binary(buffer[..read].to_vec()),
+                BlobKind::Text => DataValue::Text(decoder.push(&buffer[..read])?),
+            };
+            // A text chunk holding only the start of a character waits for the rest.
+            if chunk != DataValue::Text(String::new()) {
+                let (sql, params) = self.append_command(chunk)?.try_build()?;
+                transaction.execute_non_query(&sql, params).await?;
+            }
+            transferred += read as u64;
+            progress(BlobProgress {
+                transferred,
+                total: None,
+            });
+        }
+        decoder.finish()?;
+        Ok(transferred)
+    }
+
+    // Chunk length in the unit `SUBSTRING` counts for the column.
+    fn units(&self) -> u64 {
+        match self.kind {
+            BlobKind::Binary => self.chunk_size as u64,
+            BlobKind::Text => (self.chunk_size / 2) as u64,
+        }
+    }
+
+    fn target_column(&self) -> Result<String, BlobError> {
+        Ok(ident(&quote_name(&self.column))?)
+    }
+
+    fn locate(&self) -> Result<Vec<Condition>, BlobError> {
+        if self.key.is_empty() {
+            return Err(BlobError::MissingKey);
+        }
+        Ok(self
+            .key
+            .iter()
+            .map(|(column, value)| col(&quote_name(column)).eq(value.clone()))
+            .collect())
+    }
+}
+
+// Read until `buffer` is full or the reader is exhausted.
+async fn fill<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let read = reader.read(&mut buffer[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+// The byte length `DATALENGTH` returned, or `None` for a NULL value.
+pub(crate) fn stored_length(value: Option<DataValue>) -> Result<Option<u64>, BlobError> {
+    match value {
+        None => Err(BlobError::RowNotFound),
+        Some(DataValue::Null) => Ok(None),
+        Some(DataValue::BigInt(n)) if n >= 0 => Ok(Some(n as u64)),
+        Some(DataValue::Int(n)) if n >= 0 => Ok(Some(n as u64)),
+        Some(other) => Err(BlobError::UnexpectedValue(other)),
+    }
+}
+
+// The bytes of a chunk read with `SUBSTRING`. NULL means the value shrank to nothing
+// since its length was read.
+pub(crate) fn chunk_bytes(value: Option<DataValue>) -> Result<Vec<u8>, BlobError> {
+    match value {
+        None => Err(BlobError::RowNotFound),
+        Some(DataValue::Binary(bytes)) => Ok(bytes),
+        Some(DataValue::Null) => Ok(Vec::new()),
+        Some(other) => Err(BlobError::UnexpectedValue(other)),
+    }
+}
+
+// Decodes UTF-16LE chunks, holding back an odd byte or a high surrogate that the next
+// chunk completes.
+#[derive(Debug, Default)]
+pub(crate) struct Utf16Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf16Decoder {
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<String, BlobError> {
+        self.pending.extend_from_slice(bytes);
+        let mut units: Vec<u16> = self
+            .pending
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let mut held = self.pending.len() % 2;
+        if units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+            units.pop();
+            held += 2;
+        }
+        let text = String::from_utf16(&units).map_err(|_| BlobError::InvalidUtf16)?;
+        self.pending.drain(..self.pending.len() - held);
+        Ok(text)
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), BlobError> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(BlobError::InvalidUtf16)
+        }
+    }
+}
+
+// Decodes UTF-8 chunks, holding back a character split at the end of a chunk.
+#[derive(Debug, Default)]
+pub(crate) struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<String, BlobError> {
+        self.pending.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(BlobError::InvalidUtf8),
+        };
+        let rest = self.pending.split_off(valid);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        String::from_utf8(complete).map_err(|_| BlobError::InvalidUtf8)
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), BlobError> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(BlobError::InvalidUtf8)
+        }
+    }
+}
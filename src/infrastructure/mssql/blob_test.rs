@@ -0,0 +1,122 @@
+use super::blob::{chunk_bytes, stored_length, Utf16Decoder, Utf8Decoder};
+use super::*;
+use crate::dataset::DataValue;
+
+#[test]
+fn pages_binary_values_with_substring() {
+    let blob = Blob::binary("dbo.Documents", "Content")
+        .with_key("Id", 7)
+        .with_chunk_size(4096);
+    let length = blob.length_command().unwrap();
+    assert_eq!(
+        length.text,
+        "SELECT DATALENGTH([Content]) FROM [dbo].[Documents] WHERE [Id] = @P1"
+    );
+    let read = blob.read_command(8192).unwrap();
+    assert_eq!(
+        read.text,
+        "SELECT SUBSTRING([Content], @P1, @P2) FROM [dbo].[Documents] WHERE [Id] = @P3"
+    );
+    let (_, values) = read.bind().unwrap();
+    assert_eq!(
+        values,
+        vec![
+            DataValue::BigInt(8193),
+            DataValue::BigInt(4096),
+            DataValue::Int(7)
+        ]
+    );
+}
+
+#[test]
+fn reads_text_as_utf16_bytes() {
+    let blob = Blob::text("Notes", "Body")
+        .with_key("Owner", "ann")
+        .with_key("Day", 3)
+        .with_chunk_size(100);
+    let read = blob.read_command(0).unwrap();
+    assert_eq!(
+        read.text,
+        "SELECT CAST(SUBSTRING([Body], @P1, @P2) AS varbinary(max)) FROM [Notes] WHERE [Owner] = @P3 AND [Day] = @P4"
+    );
+    let (_, values) = read.bind().unwrap();
+    assert_eq!(values[..2], [DataValue::BigInt(1), DataValue::BigInt(50)]);
+}
+
+#[test]
+fn writes_by_clearing_and_appending() {
+    let blob = Blob::binary("dbo.Documents", "Content").with_key("Id", 7);
+    let clear = blob.clear_command().unwrap();
+    assert_eq!(
+        clear.text,
+        "UPDATE [dbo].[Documents] SET [Content] = @P1 WHERE [Id] = @P2"
+    );
+    let (_, values) = clear.bind().unwrap();
+    assert_eq!(values[0], DataValue::Binary(Vec::new()));
+
+    let append = blob.append_command(vec![1u8, 2].into()).unwrap();
+    assert_eq!(
+        append.text,
+        "UPDATE [dbo].[Documents] SET [Content].WRITE(@P1, NULL, NULL) WHERE [Id] = @P2"
+    );
+}
+
+#[test]
+fn requires_a_key() {
+    let blob = Blob::binary("Documents", "Content");
+    assert_eq!(blob.length_command().err(), Some(BlobError::MissingKey));
+    assert_eq!(blob.clear_command().err(), Some(BlobError::MissingKey));
+}
+
+#[test]
+fn decodes_characters_split_between_chunks() {
+    let bytes: Vec<u8> = "a😀é".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let mut decoder = Utf16Decoder::default();
+    // The split falls inside the surrogate pair and then inside a code unit.
+    assert_eq!(decoder.push(&bytes[..4]).unwrap(), "a");
+    assert_eq!(decoder.push(&bytes[4..7]).unwrap(), "😀");
+    assert_eq!(decoder.push(&bytes[7..]).unwrap(), "é");
+    assert!(decoder.finish().is_ok());
+
+    let mut decoder = Utf16Decoder::default();
+    decoder.push(&bytes[..4]).unwrap();
+    assert_eq!(decoder.finish(), Err(BlobError::InvalidUtf16));
+
+    let bytes = "a😀é".as_bytes();
+    let mut decoder = Utf8Decoder::default();
+    assert_eq!(decoder.push(&bytes[..3]).unwrap(), "a");
+    assert_eq!(decoder.push(&bytes[3..6]).unwrap(), "😀");
+    assert_eq!(decoder.push(&bytes[6..]).unwrap(), "é");
+    assert!(decoder.finish().is_ok());
+    assert_eq!(
+        Utf8Decoder::default().push(&[0x61, 0xFF, 0x62]),
+        Err(BlobError::InvalidUtf8)
+    );
+}
+
+#[test]
+fn rejects_unexpected_lengths_and_chunks() {
+    assert_eq!(stored_length(Some(DataValue::BigInt(12))), Ok(Some(12)));
+    assert_eq!(stored_length(Some(DataValue::Int(3))), Ok(Some(3)));
+    assert_eq!(stored_length(Some(DataValue::Null)), Ok(None));
+    assert_eq!(stored_length(None), Err(BlobError::RowNotFound));
+    assert_eq!(
+        stored_length(Some(DataValue::Text("12".into()))),
+        Err(BlobError::UnexpectedValue(DataValue::Text("12".into())))
+    );
+    assert_eq!(
+        stored_length(Some(DataValue::Int(-1))),
+        Err(BlobError::UnexpectedValue(DataValue::Int(-1)))
+    );
+
+    assert_eq!(
+        chunk_bytes(Some(DataValue::Binary(vec![1, 2]))),
+        Ok(vec![1, 2])
+    );
+    assert_eq!(chunk_bytes(Some(DataValue::Null)), Ok(Vec::new()));
+    assert_eq!(chunk_bytes(None), Err(BlobError::RowNotFound));
+    assert_eq!(
+        chunk_bytes(Some(DataValue::Text("ab".into()))),
+        Err(BlobError::UnexpectedValue(DataValue::Text("ab".into())))
+    );
+}
@@ -1,5 +1,35 @@
 use tiberius::{AuthMethod, Config};
 
+use crate::dataset::ServerTimeZone;
+
+use super::{ConversionPolicy, ValueConverter, ValueConverters};
+
+/// How `decimal` and `numeric` columns are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumericMapping {
+    /// `DataValue::Decimal`. Values beyond its 28 digits fail the query instead of
+    /// being rounded.
+    #[default]
+    Decimal,
+    /// `DataValue::Decimal` when the value fits, `DataValue::Numeric` otherwise.
+    DecimalOrNumeric,
+    /// Always `DataValue::Numeric`, exact to 38 digits.
+    Numeric,
+}
+
+/// How `datetime`, `datetime2` and `smalldatetime` columns are read. The values are
+/// wall-clock times in the configured [`ServerTimeZone`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateTimeMapping {
+    /// `DataValue::DateTime`, as stored.
+    #[default]
+    Naive,
+    /// `DataValue::DateTimeOffset` in UTC.
+    Utc,
+    /// `DataValue::DateTimeOffset` with the server zone's offset at that instant.
+    Zoned,
+}
+
 #[derive(Debug, Clone)]
 pub struct MssqlConfig {
     pub host: String,
@@ -8,6 +38,11 @@ pub struct MssqlConfig {
     pub password: String,
     pub database: String,
     pub trust_cert: bool,
+    pub numeric_mapping: NumericMapping,
+    pub conversion_policy: ConversionPolicy,
+    pub converters: ValueConverters,
+    pub time_zone: ServerTimeZone,
+    pub datetime_mapping: DateTimeMapping,
 }
 
 impl MssqlConfig {
@@ -26,9 +61,56 @@ impl MssqlConfig {
             password: password.into(),
             database: database.into(),
             trust_cert,
+            numeric_mapping: NumericMapping::default(),
+            conversion_policy: ConversionPolicy::default(),
+            converters: ValueConverters::default(),
+            time_zone: ServerTimeZone::default(),
+            datetime_mapping: DateTimeMapping::default(),
         }
     }
 
+    pub fn with_numeric_mapping(mut self, mapping: NumericMapping) -> Self {
+        self.numeric_mapping = mapping;
+        self
+    }
+
+    /// Declare the server's time zone, UTC by default. Used by
+    /// [`with_datetime_mapping`](Self::with_datetime_mapping).
+    pub fn with_time_zone(mut self, zone: impl Into<ServerTimeZone>) -> Self {
+        self.time_zone = zone.into();
+        self
+    }
+
+    pub fn with_datetime_mapping(mut self, mapping: DateTimeMapping) -> Self {
+        self.datetime_mapping = mapping;
+        self
+    }
+
+    pub fn with_conversion_policy(mut self, policy: ConversionPolicy) -> Self {
+        self.conversion_policy = policy;
+        self
+    }
+
+    /// Convert the values of every column named `column` with `converter`.
+    pub fn with_column_converter<C: ValueConverter + 'static>(
+        mut self,
+        column: &str,
+        converter: C,
+    ) -> Self {
+        self.converters.add_column(column, converter);
+        self
+    }
+
+    /// Convert the values of every column of type `sql_type` with `converter`.
+    pub fn with_type_converter<C: ValueConverter + 'static>(
+        mut self,
+        sql_type: &str,
+        converter: C,
+    ) -> Self {
+        self.converters.add_type(sql_type, converter);
+        self
+    }
+
     pub fn to_config(&self) -> Config {
         let mut cfg = Config::new();
         cfg.host(&self.host);
@@ -0,0 +1,355 @@
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::dataset::{DataColumn, DataRow, DataTable, DataValue, RowState};
+use crate::query::{col, Condition, Delete, Insert, Update};
+use crate::{quote_name, Command, CommandError, Identifier};
+
+use super::sql_connection::{flag, text};
+use super::SqlConnection;
+
+/// How UPDATE and DELETE statements detect that a row changed in the database
+/// since it was read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConcurrencyMode {
+    /// Match on key columns only; the last writer wins.
+    KeyOnly,
+    /// Every original column value must still match (ADO.NET's default).
+    OriginalValues,
+    /// The named `rowversion` column must still match. It is never written.
+    RowVersion(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AdapterError {
+    #[error("row {row} has no value for key column {column:?}")]
+    MissingKeyColumn { row: usize, column: String },
+    #[error("concurrency violation: row {row} was changed or deleted by another user")]
+    ConcurrencyViolation { row: usize },
+    #[error(transparent)]
+    Command(#[from] CommandError),
+}
+
+/// Writes the pending changes of a [`DataTable`] back to a database table,
+/// similar to ADO.NET's `SqlDataAdapter` with a `SqlCommandBuilder`.
+///
+/// Added rows become INSERTs, modified rows UPDATEs of the changed columns, and
+/// deleted rows DELETEs. UPDATE and DELETE locate rows by their original key values
+/// and apply the configured [`ConcurrencyMode`]. Columns the server generates, such
+/// as IDENTITY columns, are never written; see [`DataAdapter::describe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataAdapter {
+    table: String,
+    key_columns: Vec<String>,
+    concurrency: ConcurrencyMode,
+    generated: Vec<String>,
+    incomparable: Vec<String>,
+}
+
+impl DataAdapter {
+    pub fn new(table: &str, key_columns: &[&str]) -> Self {
+        Self {
+            table: table.into(),
+            key_columns: key_columns.iter().map(|c| c.to_string()).collect(),
+            concurrency: ConcurrencyMode::OriginalValues,
+            generated: Vec::new(),
+            incomparable: Vec::new(),
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: ConcurrencyMode) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Columns the server fills in, such as IDENTITY columns. They are left out of
+    /// INSERT and UPDATE, and an INSERT reads them back into the row with
+    /// `OUTPUT inserted.<column>`.
+    pub fn with_generated_columns(mut self, columns: &[&str]) -> Self {
+        self.generated.extend(columns.iter().map(|c| c.to_string()));
+        self
+    }
+
+    /// Read the target table's metadata with `sp_describe_first_result_set`.
+    /// IDENTITY, computed and rowversion columns are treated as generated, and
+    /// columns whose type can't be compared with `=` (text, ntext, image, xml and
+    /// CLR types such as geography) are left out of [`ConcurrencyMode::OriginalValues`]
+    /// checks.
+    pub async fn describe(mut self, connection: &mut SqlConnection) -> Result<Self> {
+        let sql = format!("SELECT * FROM {}", self.target()?);
+        if let Some(description) = connection.describe(&sql, "", false).await? {
+            let (generated, incomparable) = columns_from_description(&description);
+            self.generated.extend(generated);
+            self.incomparable.extend(incomparable);
+        }
+        Ok(self)
+    }
+
+    /// INSERT for an added row, returning the generated columns when there are any.
+    pub fn insert_command(
+        &self,
+        table: &DataTable,
+        row: &DataRow,
+    ) -> Result<Command, AdapterError> {
+        let insert = self.writable_columns(table, row).into_iter().fold(
+            Insert::into(&self.target()?),
+            |insert, c| {
+                let value = row.get(&c).cloned().unwrap_or_default();
+                insert.value(&quote_name(&c), value)
+            },
+        );
+        let insert = if self.generated.is_empty() {
+            insert
+        } else {
+            insert.output(self.generated.iter().map(|c| quote_name(c)))
+        };
+        Ok(insert.build()?)
+    }
+
+    /// UPDATE for the columns whose value differs from the original, or `None` when
+    /// nothing changed.
+    pub fn update_command(
+        &self,
+        table: &DataTable,
+        row: &DataRow,
+        index: usize,
+    ) -> Result<Option<Command>, AdapterError> {
+        let changed: Vec<String> = self
+            .writable_columns(table, row)
+            .into_iter()
+            .filter(|c| row.get(c) != row.original(c))
+            .collect();
+        if changed.is_empty() {
+            return Ok(None);
+        }
+        let update = changed
+            .iter()
+            .fold(Update::table(&self.target()?), |update, c| {
+                update.set(&quote_name(c), row.get(c).cloned().unwrap_or_default())
+            });
+        let update = self
+            .locate(table, row, index)?
+            .into_iter()
+            .fold(update, Update::filter);
+        Ok(Some(update.build()?))
+    }
+
+    pub fn delete_command(
+        &self,
+        table: &DataTable,
+        row: &DataRow,
+        index: usize,
+    ) -> Result<Command, AdapterError> {
+        let delete = self
+            .locate(table, row, index)?
+            .into_iter()
+            .fold(Delete::from(&self.target()?), Delete::filter);
+        Ok(delete.build()?)
+    }
+
+    /// Commands for every pending change, paired with the index of the row they apply to.
+    pub fn commands(&self, table: &DataTable) -> Result<Vec<(usize, Command)>, AdapterError> {
+        let mut commands = Vec::new();
+        for (i, row) in table.rows.iter().enumerate() {
+            let command = match row.state() {
+                RowState::Unchanged => None,
+                RowState::Added => Some(self.insert_command(table, row)?),
+                RowState::Modified => self.update_command(table, row, i)?,
+                RowState::Deleted => Some(self.delete_command(table, row, i)?),
+            };
+            if let Some(command) = command {
+                commands.push((i, command));
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Apply all pending changes in a single transaction and accept them on success.
+    /// An UPDATE or DELETE that affects no rows rolls everything back with
+    /// [`AdapterError::ConcurrencyViolation`]. Generated columns returned by INSERTs
+    /// are stored in the added rows. Returns the total rows affected.
+    pub async fn update(
+        &self,
+        connection: &mut SqlConnection,
+        table: &mut DataTable,
+    ) -> Result<u64> {
+        // Render every statement before the transaction starts, so an invalid command
+        // cannot leave it open.
+        let statements = self
+            .commands(table)?
+            .into_iter()
+            .map(|(row, command)| Ok((row, command.try_build()?)))
+            .collect::<Result<Vec<_>, AdapterError>>()?;
+        if statements.is_empty() {
+            table.accept_changes()?;
+            return Ok(0);
+        }
+        let mut transaction = connection.begin().await?;
+        let mut total = 0;
+        let mut inserted = Vec::new();
+        for (row, (sql, params)) in statements {
+            let reads_back =
+                !self.generated.is_empty() && table.rows[row].state() == RowState::Added;
+            let result = if reads_back {
+                transaction.execute(&sql, params).await.map(|mut ds| {
+                    let rows = ds
+                        .tables
+                        .remove("table0")
+                        .map(|t| t.rows)
+                        .unwrap_or_default();
+                    let affected = rows.len() as u64;
+                    inserted.extend(rows.into_iter().map(|values| (row, values)));
+                    affected
+                })
+            } else {
+                transaction.execute_non_query(&sql, params).await
+            };
+            let affected = match result {
+                Ok(affected) => affected,
+                Err(e) => {
+                    let _ = transaction.rollback().await;
+                    return Err(e);
+                }
+            };
+            if affected == 0 {
+                let _ = transaction.rollback().await;
+                return Err(AdapterError::ConcurrencyViolation { row }.into());
+            }
+            total += affected;
+        }
+        transaction.commit().await?;
+        for (row, values) in inserted {
+            for (column, cell) in values.cells {
+                table.rows[row].set(&column, cell.value)?;
+            }
+        }
+        table.accept_changes()?;
+        Ok(total)
+    }
+
+    // The target table, validated and rendered for the builders.
+    fn target(&self) -> Result<String, AdapterError> {
+        Ok(Identifier::parse(&self.table)?.quoted())
+    }
+
+    fn row_version(&self) -> Option<&str> {
+        match &self.concurrency {
+            ConcurrencyMode::RowVersion(column) => Some(column),
+            _ => None,
+        }
+    }
+
+    // Columns written by INSERT and UPDATE: every column except the rowversion,
+    // generated and computed columns.
+    fn writable_columns(&self, table: &DataTable, row: &DataRow) -> Vec<String> {
+        let mut columns: Vec<String> = if table.columns.is_empty() {
+            let mut names: Vec<String> = row.cells.keys().cloned().collect();
+            names.sort();
+            names
+        } else {
+            table
+                .columns
+                .iter()
+                .filter(|c| !table.is_computed(&c.name))
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        if let Some(rv) = self.row_version() {
+            columns.retain(|c| !c.eq_ignore_ascii_case(rv));
+        }
+        columns.retain(|c| !contains(&self.generated, c));
+        columns
+    }
+
+    // Whether `column` can appear in an `=` comparison.
+    fn is_comparable(&self, table: &DataTable, column: &str) -> bool {
+        let declared = table
+            .columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(column));
+        !contains(&self.incomparable, column) && declared.is_none_or(is_comparable_column)
+    }
+
+    // WHERE conditions locating the row by its original values.
+    fn locate(
+        &self,
+        table: &DataTable,
+        row: &DataRow,
+        index: usize,
+    ) -> Result<Vec<Condition>, AdapterError> {
+        let original = |column: &str| -> Result<DataValue, AdapterError> {
+            row.original(column)
+                .cloned()
+                .ok_or_else(|| AdapterError::MissingKeyColumn {
+                    row: index,
+                    column: column.to_string(),
+                })
+        };
+        let mut conditions = Vec::new();
+        for key in &self.key_columns {
+            conditions.push(col(&quote_name(key)).eq(original(key)?));
+        }
+        match &self.concurrency {
+            ConcurrencyMode::KeyOnly => {}
+            ConcurrencyMode::RowVersion(column) => {
+                conditions.push(col(&quote_name(column)).eq(original(column)?));
+            }
+            ConcurrencyMode::OriginalValues => {
+                for column in self.writable_columns(table, row) {
+                    if contains(&self.key_columns, &column) || !self.is_comparable(table, &column) {
+                        continue;
+                    }
+                    let value = row.original(&column).cloned().unwrap_or_default();
+                    conditions.push(col(&quote_name(&column)).eq(value));
+                }
+            }
+        }
+        Ok(conditions)
+    }
+}
+
+// Generated and incomparable columns from a `sp_describe_first_result_set` description.
+pub(crate) fn columns_from_description(description: &DataTable) -> (Vec<String>, Vec<String>) {
+    let mut generated = Vec::new();
+    let mut incomparable = Vec::new();
+    for row in &description.rows {
+        let Some(name) = text(row, "name") else {
+            continue;
+        };
+        let type_name = text(row, "system_type_name").unwrap_or_default();
+        let base_type = type_name.split('(').next().unwrap_or_default().trim();
+        if flag(row, "is_identity_column")
+            || flag(row, "is_computed_column")
+            || base_type.eq_ignore_ascii_case("timestamp")
+        {
+            generated.push(name.to_string());
+        }
+        // CLR types other than hierarchyid have no `=` operator.
+        let clr = text(row, "assembly_qualified_type_name").is_some()
+            && !base_type.eq_ignore_ascii_case("hierarchyid");
+        if clr
+            || INCOMPARABLE_TYPES
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(base_type))
+        {
+            incomparable.push(name.to_string());
+        }
+    }
+    (generated, incomparable)
+}
+
+// Server types that can't be compared with `=`.
+const INCOMPARABLE_TYPES: &[&str] = &["text", "ntext", "image", "xml", "geography", "geometry"];
+
+// Whether a column read into a table can be compared, judging by its reported type.
+// Tiberius reports every CLR type as `Udt`, so all of them are skipped.
+fn is_comparable_column(column: &DataColumn) -> bool {
+    !matches!(
+        column.sql_type.as_str(),
+        "Text" | "NText" | "Image" | "Xml" | "Udt"
+    )
+}
+
+fn contains(columns: &[String], column: &str) -> bool {
+    columns.iter().any(|c| c.eq_ignore_ascii_case(column))
+}
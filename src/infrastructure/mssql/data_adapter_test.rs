@@ -0,0 +1,168 @@
+use super::data_adapter::columns_from_description;
+use super::*;
+use crate::dataset::{DataCell, DataColumn, DataRow, DataTable, DataValue};
+
+fn orders() -> DataTable {
+    let mut table = DataTable::new("orders");
+    for name in ["Id", "Status", "Note", "Version"] {
+        table.columns.push(DataColumn::new(name, "Int4"));
+    }
+    let mut row = DataRow::default();
+    row.cells.insert("Id".into(), DataCell::new(1));
+    row.cells.insert("Status".into(), DataCell::new("NEW"));
+    row.cells.insert(
+        "Note".into(),
+        DataCell {
+            value: DataValue::Null,
+        },
+    );
+    row.cells
+        .insert("Version".into(), DataCell::new(vec![0u8, 1]));
+    table.rows.push(row);
+    table
+}
+
+#[test]
+fn generates_insert_for_added_rows() {
+    let mut table = orders();
+    let mut row = DataRow::default();
+    row.cells.insert("Id".into(), DataCell::new(2));
+    row.cells.insert("Status".into(), DataCell::new("NEW"));
+    table.add_row(row).unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"])
+        .with_concurrency(ConcurrencyMode::RowVersion("Version".into()));
+    let commands = adapter.commands(&table).unwrap();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].0, 1);
+    assert_eq!(
+        commands[0].1.text,
+        "INSERT INTO [dbo].[Orders] ([Id], [Status], [Note]) VALUES (@P1, @P2, @P3)"
+    );
+}
+
+#[test]
+fn generates_update_with_original_values() {
+    let mut table = orders();
+    table.rows[0].set("Status", "PAID").unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"]);
+    let (_, cmd) = adapter.commands(&table).unwrap().remove(0);
+    assert_eq!(
+        cmd.text,
+        "UPDATE [dbo].[Orders] SET [Status] = @P1 WHERE [Id] = @P2 AND [Status] = @P3 \
+         AND [Note] IS NULL AND [Version] = @P4"
+    );
+    let (_, values) = cmd.bind().unwrap();
+    assert_eq!(values[0], "PAID");
+    assert_eq!(values[2], "NEW");
+}
+
+#[test]
+fn generates_rowversion_and_key_only_checks() {
+    let mut table = orders();
+    table.rows[0].set("Status", "PAID").unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"])
+        .with_concurrency(ConcurrencyMode::RowVersion("Version".into()));
+    let (_, cmd) = adapter.commands(&table).unwrap().remove(0);
+    assert_eq!(
+        cmd.text,
+        "UPDATE [dbo].[Orders] SET [Status] = @P1 WHERE [Id] = @P2 AND [Version] = @P3"
+    );
+
+    table.accept_changes().unwrap();
+    table.delete_row(0).unwrap();
+    let adapter =
+        DataAdapter::new("dbo.Orders", &["Id"]).with_concurrency(ConcurrencyMode::KeyOnly);
+    let (_, cmd) = adapter.commands(&table).unwrap().remove(0);
+    assert_eq!(cmd.text, "DELETE FROM [dbo].[Orders] WHERE [Id] = @P1");
+}
+
+#[test]
+fn skips_unchanged_updates_and_reports_missing_keys() {
+    let mut table = orders();
+    table.rows[0].set_modified();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"]);
+    assert!(adapter.commands(&table).unwrap().is_empty());
+
+    table.delete_row(0).unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["OrderId"]);
+    assert_eq!(
+        adapter.commands(&table).err(),
+        Some(AdapterError::MissingKeyColumn {
+            row: 0,
+            column: "OrderId".into()
+        })
+    );
+}
+
+#[test]
+fn leaves_generated_columns_to_the_server() {
+    let mut table = orders();
+    table.rows[0].set("Status", "PAID").unwrap();
+    let mut row = DataRow::default();
+    row.cells.insert("Status".into(), DataCell::new("NEW"));
+    table.add_row(row).unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"])
+        .with_concurrency(ConcurrencyMode::KeyOnly)
+        .with_generated_columns(&["Id", "Version"]);
+    let commands = adapter.commands(&table).unwrap();
+    assert_eq!(
+        commands[0].1.text,
+        "UPDATE [dbo].[Orders] SET [Status] = @P1 WHERE [Id] = @P2"
+    );
+    assert_eq!(
+        commands[1].1.text,
+        "INSERT INTO [dbo].[Orders] ([Status], [Note]) OUTPUT inserted.[Id], inserted.[Version] \
+         VALUES (@P1, @P2)"
+    );
+}
+
+#[test]
+fn original_values_skip_incomparable_columns() {
+    let mut table = orders();
+    table.columns[2].sql_type = "NText".into();
+    table.rows[0].set("Status", "PAID").unwrap();
+    let adapter = DataAdapter::new("dbo.Orders", &["Id"]);
+    let (_, cmd) = adapter.commands(&table).unwrap().remove(0);
+    assert_eq!(
+        cmd.text,
+        "UPDATE [dbo].[Orders] SET [Status] = @P1 WHERE [Id] = @P2 AND [Status] = @P3 \
+         AND [Version] = @P4"
+    );
+}
+
+#[test]
+fn reads_generated_and_incomparable_columns_from_description() {
+    let mut description = DataTable::new("table0");
+    let columns: [(&str, &str, bool, bool, Option<&str>); 5] = [
+        ("Id", "int", true, false, None),
+        ("Total", "decimal(10,2)", false, true, None),
+        ("RowVer", "timestamp", false, false, None),
+        ("Notes", "ntext", false, false, None),
+        (
+            "Area",
+            "geography",
+            false,
+            false,
+            Some("Microsoft.SqlServer.Types.SqlGeography"),
+        ),
+    ];
+    for (name, type_name, identity, computed, assembly) in columns {
+        let mut row = DataRow::default();
+        row.cells.insert("name".into(), DataCell::new(name));
+        row.cells
+            .insert("system_type_name".into(), DataCell::new(type_name));
+        row.cells
+            .insert("is_identity_column".into(), DataCell::new(identity));
+        row.cells
+            .insert("is_computed_column".into(), DataCell::new(computed));
+        let assembly = assembly.map(DataValue::from).unwrap_or_default();
+        row.cells.insert(
+            "assembly_qualified_type_name".into(),
+            DataCell::new(assembly),
+        );
+        description.rows.push(row);
+    }
+    let (generated, incomparable) = columns_from_description(&description);
+    assert_eq!(generated, ["Id", "Total", "RowVer"]);
+    assert_eq!(incomparable, ["Notes", "Area"]);
+}
@@ -1,5 +1,20 @@
+pub mod blob;
 pub mod config;
 pub mod sql_connection;
+pub mod transaction;
+pub mod data_adapter;
+pub mod value_mapper;
 
-pub use config::MssqlConfig;
+pub use blob::{Blob, BlobError, BlobKind, BlobProgress};
+pub use config::{DateTimeMapping, MssqlConfig, NumericMapping};
 pub use sql_connection::SqlConnection;
+pub use transaction::Transaction;
+pub use data_adapter::{AdapterError, ConcurrencyMode, DataAdapter};
+pub use value_mapper::{ConversionPolicy, ValueConverter, ValueConverters};
+
+#[cfg(test)]
+mod blob_test;
+#[cfg(test)]
+mod data_adapter_test;
+#[cfg(test)]
+mod value_mapper_test;
@@ -1,25 +1,30 @@
 use anyhow::Result;
-use futures::StreamExt;
+use futures::stream::{BoxStream, StreamExt};
 use tiberius::{Client, QueryItem};
 use tokio::net::TcpStream;
 use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
 
-use crate::dataset::{DataCell, DataColumn, DataRow, DataSet, DataTable, DataValue};
+use crate::dataset::{
+    DataCell, DataColumn, DataRow, DataSet, DataTable, DataValue, ServerTimeZone,
+};
 
-use super::MssqlConfig;
+use super::value_mapper::ValueMapper;
+use super::{MssqlConfig, Transaction};
 
 pub struct SqlConnection {
     client: Client<Compat<TcpStream>>,
+    mapper: ValueMapper,
 }
 
 impl SqlConnection {
     pub async fn connect(config: MssqlConfig) -> Result<Self> {
         let cfg = config.to_config();
+        let mapper = ValueMapper::new(&config);
         let addr = cfg.get_addr();
         let tcp = TcpStream::connect(addr).await?;
         tcp.set_nodelay(true)?;
         let client = Client::connect(cfg, tcp.compat_write()).await?;
-        Ok(Self { client })
+        Ok(Self { client, mapper })
     }
 
     pub async fn execute(
@@ -41,16 +46,7 @@ impl SqlConnection {
                         dataset.tables.insert(table.name.clone(), table);
                     }
                     let mut table = DataTable::new(&format!("table{}", meta.result_index()));
-                    table.columns = meta
-                        .columns()
-                        .iter()
-                        .map(|c| DataColumn {
-                            name: c.name().to_string(),
-                            sql_type: format!("{:?}", c.column_type()),
-                            size: None,
-                            nullable: true,
-                        })
-                        .collect();
+                    table.columns = meta.columns().iter().map(data_column).collect();
                     current = Some(table);
                 }
                 QueryItem::Row(row) => {
@@ -59,117 +55,9 @@ impl SqlConnection {
                     }
                     let table = current.as_mut().unwrap();
                     let mut data_row = DataRow::default();
+                    let index = table.rows.len();
                     for (cd, col) in row.into_iter().zip(table.columns.iter()) {
-                        let v = match cd {
-                            tiberius::ColumnData::U8(opt) => {
-                                opt.map(DataValue::TinyInt).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::I16(opt) => {
-                                opt.map(DataValue::SmallInt).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::I32(opt) => {
-                                opt.map(DataValue::Int).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::I64(opt) => {
-                                opt.map(DataValue::BigInt).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::F32(opt) => opt
-                                .map(|v| DataValue::Float(v as f64))
-                                .unwrap_or(DataValue::Null),
-                            tiberius::ColumnData::F64(opt) => {
-                                opt.map(DataValue::Float).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::Bit(opt) => {
-                                opt.map(DataValue::Bool).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::String(opt) => {
-                                if let Some(s) = opt.as_ref() {
-                                    DataValue::Text(s.to_string())
-                                } else {
-                                    DataValue::Null
-                                }
-                            }
-                            tiberius::ColumnData::Guid(opt) => {
-                                opt.map(DataValue::Guid).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::Binary(opt) => {
-                                if let Some(b) = opt.as_ref() {
-                                    DataValue::Binary(b.to_vec())
-                                } else {
-                                    DataValue::Null
-                                }
-                            }
-                            tiberius::ColumnData::Numeric(opt) => opt
-                                .map(|n| {
-                                    match rust_decimal::Decimal::try_from_i128_with_scale(
-                                        n.value(),
-                                        n.scale() as u32,
-                                    ) {
-                                        Ok(d) => DataValue::Decimal(d),
-                                        Err(e) => {
-                                            eprintln!("failed to convert numeric: {e}");
-                                            DataValue::Null
-                                        }
-                                    }
-                                })
-                                .unwrap_or(DataValue::Null),
-                            tiberius::ColumnData::DateTime(opt) => {
-                                let val: Option<chrono::NaiveDateTime> =
-                                    <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::DateTime(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::SmallDateTime(opt) => {
-                                let val: Option<chrono::NaiveDateTime> =
-                                    <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::SmallDateTime(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::DateTime2(opt) => {
-                                let val: Option<chrono::NaiveDateTime> =
-                                    <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::DateTime2(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::Time(opt) => {
-                                let val: Option<chrono::NaiveTime> =
-                                    <chrono::NaiveTime as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::Time(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::Time).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::Date(opt) => {
-                                let val: Option<chrono::NaiveDate> =
-                                    <chrono::NaiveDate as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::Date(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::Date).unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::DateTimeOffset(opt) => {
-                                let val: Option<chrono::DateTime<chrono::FixedOffset>> =
-                                    <chrono::DateTime<chrono::FixedOffset> as tiberius::FromSqlOwned>::from_sql_owned(
-                                        tiberius::ColumnData::DateTimeOffset(opt),
-                                    )
-                                    .unwrap();
-                                val.map(DataValue::DateTimeOffset)
-                                    .unwrap_or(DataValue::Null)
-                            }
-                            tiberius::ColumnData::Xml(opt) => {
-                                if let Some(x) = opt.as_ref() {
-                                    DataValue::Text(x.as_ref().to_string())
-                                } else {
-                                    DataValue::Null
-                                }
-                            }
-                        };
+                        let v = self.mapper.map(cd, col, index, &mut table.warnings)?;
                         data_row
                             .cells
                             .insert(col.name.clone(), DataCell { value: v });
@@ -210,15 +98,20 @@ impl SqlConnection {
             .map(|p| p.as_ref() as &dyn tiberius::ToSql)
             .collect();
         let mut stream = self.client.query(sql, &param_refs[..]).await?;
+        let mut column = DataColumn::default();
         while let Some(item) = stream.next().await {
             match item? {
-                tiberius::QueryItem::Metadata(_) => {
-                    // ignore metadata for scalar
+                tiberius::QueryItem::Metadata(meta) => {
+                    // Only the first column is read.
+                    if let Some(first) = meta.columns().first() {
+                        column.name = first.name().to_string();
+                        column.sql_type = format!("{:?}", first.column_type());
+                    }
                 }
                 tiberius::QueryItem::Row(row) => {
                     // Take first column only
                     if let Some(cd) = row.into_iter().next() {
-                        let v = map_column_data(cd);
+                        let v = self.mapper.map(cd, &column, 0, &mut Vec::new())?;
                         return Ok(Some(v));
                     } else {
                         return Ok(Some(crate::dataset::DataValue::Null));
@@ -228,90 +121,170 @@ impl SqlConnection {
         }
         Ok(None)
     }
+
+    /// Stream the rows of every result set as they arrive instead of collecting them
+    /// into a [`DataSet`]. Values replaced by the conversion policy are not reported.
+    pub async fn execute_stream(
+        &mut self,
+        sql: &str,
+        params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<BoxStream<'_, Result<DataRow>>> {
+        let param_refs: Vec<&dyn tiberius::ToSql> = params
+            .iter()
+            .map(|p| p.as_ref() as &dyn tiberius::ToSql)
+            .collect();
+        let mapper = &self.mapper;
+        let rows = self
+            .client
+            .query(sql, &param_refs[..])
+            .await?
+            .into_row_stream();
+        // Columns of the current result set and the index of the next row in it.
+        let mut current: Option<(usize, Vec<DataColumn>)> = None;
+        let mut index = 0;
+        let rows = rows.map(move |row| {
+            let row = row?;
+            if current.as_ref().map(|(result, _)| *result) != Some(row.result_index()) {
+                let columns = row.columns().iter().map(data_column).collect();
+                current = Some((row.result_index(), columns));
+                index = 0;
+            }
+            let columns = current
+                .as_ref()
+                .map(|(_, columns)| columns.as_slice())
+                .unwrap_or_default();
+            let mut data_row = DataRow::default();
+            for (cd, col) in row.into_iter().zip(columns) {
+                let value = mapper.map(cd, col, index, &mut Vec::new())?;
+                data_row.cells.insert(col.name.clone(), DataCell { value });
+            }
+            index += 1;
+            Ok(data_row)
+        });
+        Ok(rows.boxed())
+    }
+
+    // Primary key of the first result set of `sql`, read from
+    // `sp_describe_first_result_set` in browse mode.
+    pub(crate) async fn key_columns(
+        &mut self,
+        sql: &str,
+        declarations: &str,
+    ) -> Result<Option<Vec<String>>> {
+        let described = self.describe(sql, declarations, true).await?;
+        Ok(described.as_ref().and_then(key_from_description))
+    }
+
+    // One row per column of the first result set of `sql`, as returned by
+    // `sp_describe_first_result_set`.
+    pub(crate) async fn describe(
+        &mut self,
+        sql: &str,
+        declarations: &str,
+        browse: bool,
+    ) -> Result<Option<DataTable>> {
+        let params: Vec<Box<dyn tiberius::ToSql + Send + Sync>> = vec![
+            Box::new(sql.to_string()),
+            Box::new((!declarations.is_empty()).then(|| declarations.to_string())),
+            Box::new(browse as i32),
+        ];
+        let mut described = self
+            .execute(
+                "EXEC sp_describe_first_result_set @tsql = @P1, @params = @P2, @browse_information_mode = @P3",
+                params,
+            )
+            .await?;
+        Ok(described.tables.remove("table0"))
+    }
+
+    /// The server time zone this connection was configured with.
+    pub fn time_zone(&self) -> &ServerTimeZone {
+        self.mapper.time_zone()
+    }
+
+    /// Start a transaction on this connection.
+    pub async fn begin(&mut self) -> Result<Transaction<'_>> {
+        self.batch("BEGIN TRANSACTION").await?;
+        Ok(Transaction::new(self))
+    }
+
+    // Run a parameterless batch, such as transaction control statements, which must
+    // not be wrapped in `sp_executesql`.
+    pub(crate) async fn batch(&mut self, sql: &str) -> Result<()> {
+        self.client.simple_query(sql).await?.into_results().await?;
+        Ok(())
+    }
 }
 
-pub(crate) fn rows_affected_total(counts: &[u64]) -> u64 {
-    counts.iter().copied().sum()
+fn data_column(column: &tiberius::Column) -> DataColumn {
+    DataColumn::new(column.name(), &format!("{:?}", column.column_type()))
 }
 
-#[inline]
-fn map_column_data(cd: tiberius::ColumnData<'_>) -> crate::dataset::DataValue {
-    use crate::dataset::DataValue;
-    match cd {
-        tiberius::ColumnData::U8(opt) => opt.map(DataValue::TinyInt).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::I16(opt) => opt.map(DataValue::SmallInt).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::I32(opt) => opt.map(DataValue::Int).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::I64(opt) => opt.map(DataValue::BigInt).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::F32(opt) => opt.map(|v| DataValue::Float(v as f64)).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::F64(opt) => opt.map(DataValue::Float).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::Bit(opt) => opt.map(DataValue::Bool).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::String(opt) => {
-            if let Some(s) = opt.as_ref() { DataValue::Text(s.to_string()) } else { DataValue::Null }
-        }
-        tiberius::ColumnData::Guid(opt) => opt.map(DataValue::Guid).unwrap_or(DataValue::Null),
-        tiberius::ColumnData::Binary(opt) => {
-            if let Some(b) = opt.as_ref() { DataValue::Binary(b.to_vec()) } else { DataValue::Null }
-        }
-        tiberius::ColumnData::Numeric(opt) => opt
-            .map(|n| {
-                match rust_decimal::Decimal::try_from_i128_with_scale(n.value(), n.scale() as u32) {
-                    Ok(d) => DataValue::Decimal(d),
-                    Err(_) => DataValue::Null,
-                }
-            })
-            .unwrap_or(DataValue::Null),
-        tiberius::ColumnData::DateTime(opt) => {
-            let val: Option<chrono::NaiveDateTime> =
-                <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::DateTime(opt),
-                ).unwrap();
-            val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-        }
-        tiberius::ColumnData::SmallDateTime(opt) => {
-            let val: Option<chrono::NaiveDateTime> =
-                <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::SmallDateTime(opt),
-                ).unwrap();
-            val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-        }
-        tiberius::ColumnData::DateTime2(opt) => {
-            let val: Option<chrono::NaiveDateTime> =
-                <chrono::NaiveDateTime as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::DateTime2(opt),
-                ).unwrap();
-            val.map(DataValue::DateTime).unwrap_or(DataValue::Null)
-        }
-        tiberius::ColumnData::Time(opt) => {
-            let val: Option<chrono::NaiveTime> =
-                <chrono::NaiveTime as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::Time(opt),
-                ).unwrap();
-            val.map(DataValue::Time).unwrap_or(DataValue::Null)
-        }
-        tiberius::ColumnData::Date(opt) => {
-            let val: Option<chrono::NaiveDate> =
-                <chrono::NaiveDate as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::Date(opt),
-                ).unwrap();
-            val.map(DataValue::Date).unwrap_or(DataValue::Null)
-        }
-        tiberius::ColumnData::DateTimeOffset(opt) => {
-            let val: Option<chrono::DateTime<chrono::FixedOffset>> =
-                <chrono::DateTime<chrono::FixedOffset> as tiberius::FromSqlOwned>::from_sql_owned(
-                    tiberius::ColumnData::DateTimeOffset(opt),
-                ).unwrap();
-            val.map(DataValue::DateTimeOffset).unwrap_or(DataValue::Null)
+// Key columns from a browse-mode result set description: every column must come from
+// the same base table, and the unique key browse mode reports must be fully selected
+// (it adds hidden columns for key parts that are not).
+pub(crate) fn key_from_description(description: &DataTable) -> Option<Vec<String>> {
+    let mut source = None;
+    let mut key = Vec::new();
+    for row in &description.rows {
+        if let Some(table) = text(row, "source_table") {
+            let origin = (
+                text(row, "source_server"),
+                text(row, "source_database"),
+                text(row, "source_schema"),
+                table,
+            );
+            if *source.get_or_insert(origin) != origin {
+                return None;
+            }
         }
-        tiberius::ColumnData::Xml(opt) => {
-            if let Some(x) = opt.as_ref() { crate::dataset::DataValue::Text(x.as_ref().to_string()) } else { crate::dataset::DataValue::Null }
+        if flag(row, "is_part_of_unique_key") {
+            if flag(row, "is_hidden") {
+                return None;
+            }
+            key.push(text(row, "name")?.to_string());
         }
     }
+    (source.is_some() && !key.is_empty()).then_some(key)
+}
+
+pub(crate) fn text<'a>(row: &'a DataRow, column: &str) -> Option<&'a str> {
+    match row.get(column) {
+        Some(DataValue::Text(s)) => Some(s),
+        _ => None,
+    }
+}
+
+pub(crate) fn flag(row: &DataRow, column: &str) -> bool {
+    row.get(column) == Some(&DataValue::Bool(true))
+}
+
+pub(crate) fn rows_affected_total(counts: &[u64]) -> u64 {
+    counts.iter().copied().sum()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{rows_affected_total, map_column_data};
-    use crate::dataset::DataValue;
+    use super::{key_from_description, rows_affected_total};
+    use crate::dataset::{DataCell, DataRow, DataTable, DataValue};
+
+    fn describe(columns: &[(&str, Option<&str>, bool, bool)]) -> DataTable {
+        let mut table = DataTable::new("table0");
+        for (name, source, hidden, key) in columns {
+            let mut row = DataRow::default();
+            row.cells.insert("name".into(), DataCell::new(*name));
+            row.cells
+                .insert("source_schema".into(), DataCell::new("dbo"));
+            let source = source.map(DataValue::from).unwrap_or_default();
+            row.cells
+                .insert("source_table".into(), DataCell::new(source));
+            row.cells.insert("is_hidden".into(), DataCell::new(*hidden));
+            row.cells
+                .insert("is_part_of_unique_key".into(), DataCell::new(*key));
+            table.rows.push(row);
+        }
+        table
+    }
 
     #[test]
     fn sums_rows_affected_slice() {
@@ -323,11 +296,24 @@ mod tests {
     }
 
     #[test]
-    fn maps_basic_column_types() {
-        assert_eq!(map_column_data(tiberius::ColumnData::I32(Some(5))), 5);
-        assert_eq!(map_column_data(tiberius::ColumnData::F64(Some(2.5))), 2.5);
-        assert_eq!(map_column_data(tiberius::ColumnData::Bit(Some(true))), true);
-        assert_eq!(map_column_data(tiberius::ColumnData::String(Some("hi".into()))), "hi");
-        assert!(matches!(map_column_data(tiberius::ColumnData::I32(None)), DataValue::Null));
+    fn detects_key_of_single_table_results() {
+        let single = describe(&[
+            ("id", Some("Orders"), false, true),
+            ("total", Some("Orders"), false, false),
+            ("doubled", None, false, false),
+        ]);
+        assert_eq!(key_from_description(&single), Some(vec!["id".to_string()]));
+
+        let key_not_selected = describe(&[
+            ("total", Some("Orders"), false, false),
+            ("id", Some("Orders"), true, true),
+        ]);
+        assert_eq!(key_from_description(&key_not_selected), None);
+
+        let join = describe(&[
+            ("id", Some("Orders"), false, true),
+            ("name", Some("Customers"), false, false),
+        ]);
+        assert_eq!(key_from_description(&join), None);
     }
 }
@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use crate::dataset::{DataSet, DataValue};
+
+use super::SqlConnection;
+
+/// A transaction opened with [`SqlConnection::begin`].
+///
+/// Statements run through the transaction until [`commit`](Self::commit) or
+/// [`rollback`](Self::rollback) is called. Dropping it without either leaves the
+/// transaction open on the connection; the server rolls it back when the
+/// connection closes.
+pub struct Transaction<'a> {
+    connection: &'a mut SqlConnection,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(connection: &'a mut SqlConnection) -> Self {
+        Self { connection }
+    }
+
+    pub(crate) fn connection(&mut self) -> &mut SqlConnection {
+        self.connection
+    }
+
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<DataSet> {
+        self.connection.execute(sql, params).await
+    }
+
+    pub async fn execute_non_query(
+        &mut self,
+        sql: &str,
+        params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<u64> {
+        self.connection.execute_non_query(sql, params).await
+    }
+
+    pub async fn execute_scalar(
+        &mut self,
+        sql: &str,
+        params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<Option<DataValue>> {
+        self.connection.execute_scalar(sql, params).await
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.connection.batch("COMMIT TRANSACTION").await
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        self.connection
+            .batch("IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION")
+            .await
+    }
+}
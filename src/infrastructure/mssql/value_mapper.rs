@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Result;
+use tiberius::{ColumnData, FromSqlOwned};
+
+use crate::dataset::{ConversionWarning, DataColumn, DataValue, ServerTimeZone};
+
+use super::{DateTimeMapping, MssqlConfig, NumericMapping};
+
+/// What happens when a column value cannot be converted to a [`DataValue`], either
+/// by the built-in mapping or by a [`ValueConverter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConversionPolicy {
+    /// Fail the query.
+    #[default]
+    Error,
+    /// Use NULL and record a [`ConversionWarning`] in the table's `warnings`.
+    /// `execute_scalar` has no table to hold the warning.
+    Null,
+    /// Use the value's text and record a [`ConversionWarning`].
+    Text,
+}
+
+/// Custom conversion applied to a column's values after the built-in mapping. NULL
+/// values are not passed to converters.
+///
+/// Closures taking a `&DataValue` and returning `anyhow::Result<DataValue>` implement
+/// this trait.
+pub trait ValueConverter: Send + Sync {
+    fn convert(&self, value: &DataValue) -> Result<DataValue>;
+}
+
+impl<F> ValueConverter for F
+where
+    F: Fn(&DataValue) -> Result<DataValue> + Send + Sync,
+{
+    fn convert(&self, value: &DataValue) -> Result<DataValue> {
+        self(value)
+    }
+}
+
+/// Converters registered by column name or by SQL type, both matched
+/// case-insensitively. A column converter wins over a type converter. Types are named
+/// as in [`DataColumn::sql_type`], e.g. `Bitn`, `BigVarChar` or `NVarchar`.
+#[derive(Clone, Default)]
+pub struct ValueConverters {
+    columns: HashMap<String, Arc<dyn ValueConverter>>,
+    types: HashMap<String, Arc<dyn ValueConverter>>,
+}
+
+impl ValueConverters {
+    pub fn add_column<C: ValueConverter + 'static>(&mut self, column: &str, converter: C) {
+        self.columns
+            .insert(column.to_lowercase(), Arc::new(converter));
+    }
+
+    pub fn add_type<C: ValueConverter + 'static>(&mut self, sql_type: &str, converter: C) {
+        self.types
+            .insert(sql_type.to_lowercase(), Arc::new(converter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty() && self.types.is_empty()
+    }
+
+    fn get(&self, column: &DataColumn) -> Option<&dyn ValueConverter> {
+        if self.is_empty() {
+            return None;
+        }
+        self.columns
+            .get(&column.name.to_lowercase())
+            .or_else(|| self.types.get(&column.sql_type.to_lowercase()))
+            .map(|c| c.as_ref())
+    }
+}
+
+impl fmt::Debug for ValueConverters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut columns: Vec<&String> = self.columns.keys().collect();
+        let mut types: Vec<&String> = self.types.keys().collect();
+        columns.sort();
+        types.sort();
+        f.debug_struct("ValueConverters")
+            .field("columns", &columns)
+            .field("types", &types)
+            .finish()
+    }
+}
+
+// A value that could not be converted: its text and the reason.
+#[derive(Debug)]
+pub(crate) struct Failure {
+    text: String,
+    pub(crate) message: String,
+}
+
+/// Turns column data into [`DataValue`]s for `execute` and `execute_scalar`,
+/// applying the configured numeric and date time mappings, converters and conversion
+/// policy.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ValueMapper {
+    policy: ConversionPolicy,
+    numeric: NumericMapping,
+    datetime: DateTimeMapping,
+    time_zone: ServerTimeZone,
+    converters: ValueConverters,
+}
+
+impl ValueMapper {
+    pub(crate) fn new(config: &MssqlConfig) -> Self {
+        Self {
+            policy: config.conversion_policy,
+            numeric: config.numeric_mapping,
+            datetime: config.datetime_mapping,
+            time_zone: config.time_zone,
+            converters: config.converters.clone(),
+        }
+    }
+
+    pub(crate) fn time_zone(&self) -> &ServerTimeZone {
+        &self.time_zone
+    }
+
+    /// Value of `column` in row `row`; failures the policy tolerates are added to
+    /// `warnings`.
+    pub(crate) fn map(
+        &self,
+        cd: ColumnData<'_>,
+        column: &DataColumn,
+        row: usize,
+        warnings: &mut Vec<ConversionWarning>,
+    ) -> Result<DataValue> {
+        let value =
+            column_value(cd, &column.sql_type, self.numeric).and_then(|value| self.zoned(value));
+        let value = match value {
+            Ok(value) => value,
+            Err(failure) => return self.recover(failure, column, row, warnings),
+        };
+        match self.converters.get(column) {
+            Some(converter) if !value.is_null() => match converter.convert(&value) {
+                Ok(converted) => Ok(converted),
+                Err(e) => {
+                    let failure = Failure {
+                        text: crate::dataset::expression::text_of(&value).unwrap_or_default(),
+                        message: e.to_string(),
+                    };
+                    self.recover(failure, column, row, warnings)
+                }
+            },
+            _ => Ok(value),
+        }
+    }
+
+    // Wall-clock `DateTime` values as instants, per the date time mapping.
+    fn zoned(&self, value: DataValue) -> Result<DataValue, Failure> {
+        let local = match value {
+            DataValue::DateTime(local) if self.datetime != DateTimeMapping::Naive => local,
+            other => return Ok(other),
+        };
+        let instant = self.time_zone.from_local(&local).ok_or_else(|| Failure {
+            text: local.to_string(),
+            message: format!("{local} does not exist in time zone {}", self.time_zone),
+        })?;
+        Ok(DataValue::DateTimeOffset(match self.datetime {
+            DateTimeMapping::Utc => instant.to_utc().fixed_offset(),
+            _ => instant,
+        }))
+    }
+
+    fn recover(
+        &self,
+        failure: Failure,
+        column: &DataColumn,
+        row: usize,
+        warnings: &mut Vec<ConversionWarning>,
+    ) -> Result<DataValue> {
+        let value = match self.policy {
+            ConversionPolicy::Error => anyhow::bail!(
+                "cannot convert column {:?} in row {row}: {}",
+                column.name,
+                failure.message
+            ),
+            ConversionPolicy::Null => DataValue::Null,
+            ConversionPolicy::Text => DataValue::Text(failure.text),
+        };
+        warnings.push(ConversionWarning {
+            row,
+            column: column.name.clone(),
+            message: failure.message,
+        });
+        Ok(value)
+    }
+}
+
+// Value of a cell, using the column type to restore `money` and `smallmoney`, which
+// tiberius decodes as floats. Tiberius cannot read UDT columns at all, so spatial
+// values arrive as `varbinary` and are decoded with `Spatial::from_value`.
+pub(crate) fn column_value(
+    cd: ColumnData<'_>,
+    sql_type: &str,
+    numeric: NumericMapping,
+) -> Result<DataValue, Failure> {
+    match (cd, sql_type) {
+        (ColumnData::F64(Some(v)), "Money" | "Money4") => money_value(v),
+        (ColumnData::Numeric(Some(n)), _) => numeric_value(n, numeric),
+        (cd, _) => map_column_data(cd),
+    }
+}
+
+// Tiberius divides the stored count of ten-thousandths by 10 000 as a float, so the
+// count is recovered by multiplying back. This is exact up to 2^52 ten-thousandths,
+// about ±450 billion; larger `money` values may be off in the last place.
+fn money_value(v: f64) -> Result<DataValue, Failure> {
+    let units = (v * 10_000.0).round();
+    if !units.is_finite() || units.abs() > i64::MAX as f64 {
+        return Err(Failure {
+            text: v.to_string(),
+            message: format!("{v} is not a valid money value"),
+        });
+    }
+    Ok(DataValue::Money(rust_decimal::Decimal::new(
+        units as i64,
+        4,
+    )))
+}
+
+fn numeric_value(
+    n: tiberius::numeric::Numeric,
+    mapping: NumericMapping,
+) -> Result<DataValue, Failure> {
+    let failure = |message: String| Failure {
+        text: n.to_string(),
+        message,
+    };
+    let exact = crate::dataset::Numeric::new(n.value(), n.scale())
+        .ok_or_else(|| failure(format!("numeric value {n} exceeds 38 digits")))?;
+    let decimal = rust_decimal::Decimal::try_from_i128_with_scale(n.value(), n.scale() as u32);
+    Ok(match (mapping, decimal) {
+        (NumericMapping::Numeric, _) => DataValue::Numeric(exact),
+        (_, Ok(d)) => DataValue::Decimal(d),
+        (NumericMapping::DecimalOrNumeric, Err(_)) => DataValue::Numeric(exact),
+        (NumericMapping::Decimal, Err(_)) => {
+            return Err(failure(format!(
+                "numeric value {exact} does not fit in a Decimal; read it with NumericMapping::DecimalOrNumeric"
+            )))
+        }
+    })
+}
+
+// Date and time values through tiberius' chrono conversions.
+fn chrono_value<T: FromSqlOwned>(
+    cd: ColumnData<'static>,
+    wrap: fn(T) -> DataValue,
+) -> Result<DataValue, Failure> {
+    let raw = cd.clone();
+    match T::from_sql_owned(cd) {
+        Ok(value) => Ok(value.map(wrap).unwrap_or(DataValue::Null)),
+        Err(e) => Err(Failure {
+            text: format!("{raw:?}"),
+            message: e.to_string(),
+        }),
+    }
+}
+
+#[inline]
+pub(crate) fn map_column_data(cd: ColumnData<'_>) -> Result<DataValue, Failure> {
+    Ok(match cd {
+        ColumnData::U8(opt) => opt.map(DataValue::TinyInt).unwrap_or(DataValue::Null),
+        ColumnData::I16(opt) => opt.map(DataValue::SmallInt).unwrap_or(DataValue::Null),
+        ColumnData::I32(opt) => opt.map(DataValue::Int).unwrap_or(DataValue::Null),
+        ColumnData::I64(opt) => opt.map(DataValue::BigInt).unwrap_or(DataValue::Null),
+        ColumnData::F32(opt) => opt.map(DataValue::Real).unwrap_or(DataValue::Null),
+        ColumnData::F64(opt) => opt.map(DataValue::Float).unwrap_or(DataValue::Null),
+        ColumnData::Bit(opt) => opt.map(DataValue::Bool).unwrap_or(DataValue::Null),
+        ColumnData::String(opt) => {
+            if let Some(s) = opt.as_ref() {
+                DataValue::Text(s.to_string())
+            } else {
+                DataValue::Null
+            }
+        }
+        ColumnData::Guid(opt) => opt.map(DataValue::Guid).unwrap_or(DataValue::Null),
+        ColumnData::Binary(opt) => {
+            if let Some(b) = opt.as_ref() {
+                DataValue::Binary(b.to_vec())
+            } else {
+                DataValue::Null
+            }
+        }
+        // `column_value` applies the configured mapping before getting here.
+        ColumnData::Numeric(opt) => match opt {
+            Some(n) => return numeric_value(n, NumericMapping::DecimalOrNumeric),
+            None => DataValue::Null,
+        },
+        ColumnData::DateTime(opt) => {
+            return chrono_value(ColumnData::DateTime(opt), DataValue::DateTime)
+        }
+        ColumnData::SmallDateTime(opt) => {
+            return chrono_value(ColumnData::SmallDateTime(opt), DataValue::DateTime)
+        }
+        ColumnData::DateTime2(opt) => {
+            return chrono_value(ColumnData::DateTime2(opt), DataValue::DateTime)
+        }
+        ColumnData::Time(opt) => return chrono_value(ColumnData::Time(opt), DataValue::Time),
+        ColumnData::Date(opt) => return chrono_value(ColumnData::Date(opt), DataValue::Date),
+        ColumnData::DateTimeOffset(opt) => {
+            return chrono_value(ColumnData::DateTimeOffset(opt), DataValue::DateTimeOffset)
+        }
+        ColumnData::Xml(opt) => {
+            if let Some(x) = opt.as_ref() {
+                DataValue::Xml(x.as_ref().to_string())
+            } else {
+                DataValue::Null
+            }
+        }
+    })
+}
@@ -0,0 +1,187 @@
+use super::value_mapper::{column_value, ValueMapper};
+use super::*;
+use crate::dataset::{DataColumn, DataValue, Numeric};
+use tiberius::ColumnData;
+
+fn map_column_data(cd: ColumnData<'_>) -> DataValue {
+    super::value_mapper::map_column_data(cd).unwrap()
+}
+
+fn column(name: &str, sql_type: &str) -> DataColumn {
+    DataColumn {
+        name: name.into(),
+        sql_type: sql_type.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn maps_basic_column_types() {
+    assert_eq!(map_column_data(tiberius::ColumnData::I32(Some(5))), 5);
+    assert_eq!(map_column_data(tiberius::ColumnData::F64(Some(2.5))), 2.5);
+    assert_eq!(map_column_data(tiberius::ColumnData::Bit(Some(true))), true);
+    assert_eq!(
+        map_column_data(tiberius::ColumnData::String(Some("hi".into()))),
+        "hi"
+    );
+    assert!(matches!(
+        map_column_data(tiberius::ColumnData::I32(None)),
+        DataValue::Null
+    ));
+}
+
+#[test]
+fn keeps_real_money_and_xml_types() {
+    assert_eq!(
+        map_column_data(tiberius::ColumnData::F32(Some(1.5))),
+        DataValue::Real(1.5)
+    );
+    let xml = tiberius::xml::XmlData::new("<a/>");
+    assert_eq!(
+        map_column_data(tiberius::ColumnData::Xml(Some(std::borrow::Cow::Owned(
+            xml
+        )))),
+        DataValue::Xml("<a/>".into())
+    );
+    let money = |cd, sql_type| column_value(cd, sql_type, NumericMapping::Decimal).unwrap();
+    assert_eq!(
+        money(tiberius::ColumnData::F64(Some(12.3456)), "Money"),
+        DataValue::Money(rust_decimal::Decimal::new(123456, 4))
+    );
+    // Decoded by tiberius as the count of ten-thousandths divided by 10 000.
+    assert_eq!(
+        money(
+            tiberius::ColumnData::F64(Some(-4_503_599_627_370_495_f64 / 1e4)),
+            "Money"
+        ),
+        DataValue::Money(rust_decimal::Decimal::new(-4_503_599_627_370_495, 4))
+    );
+    assert_eq!(money(tiberius::ColumnData::F64(Some(2.5)), "Float8"), 2.5);
+    assert!(money(tiberius::ColumnData::F64(None), "Money4").is_null());
+}
+
+#[test]
+fn maps_numerics_by_policy() {
+    let big = || {
+        tiberius::ColumnData::Numeric(Some(tiberius::numeric::Numeric::new_with_scale(
+            12345678901234567890123456789012345678,
+            4,
+        )))
+    };
+    let small =
+        || tiberius::ColumnData::Numeric(Some(tiberius::numeric::Numeric::new_with_scale(1250, 2)));
+    let exact = Numeric::new(12345678901234567890123456789012345678, 4).unwrap();
+
+    let error = column_value(big(), "Numericn", NumericMapping::Decimal).unwrap_err();
+    assert!(error
+        .message
+        .contains("1234567890123456789012345678901234.5678"));
+    assert_eq!(
+        column_value(big(), "Numericn", NumericMapping::DecimalOrNumeric).unwrap(),
+        DataValue::Numeric(exact)
+    );
+    assert_eq!(
+        column_value(small(), "Numericn", NumericMapping::DecimalOrNumeric).unwrap(),
+        rust_decimal::Decimal::new(1250, 2)
+    );
+    assert_eq!(
+        column_value(small(), "Numericn", NumericMapping::Numeric).unwrap(),
+        DataValue::Numeric(Numeric::new(1250, 2).unwrap())
+    );
+    assert!(column_value(
+        tiberius::ColumnData::Numeric(None),
+        "Numericn",
+        NumericMapping::Numeric
+    )
+    .unwrap()
+    .is_null());
+}
+
+#[test]
+fn applies_converters_and_conversion_policy() {
+    let mut config = MssqlConfig::new("localhost", 1433, "sa", "", "master", true)
+        .with_type_converter("Bitn", |v: &DataValue| {
+            Ok(DataValue::from(if *v == true {
+                "active"
+            } else {
+                "inactive"
+            }))
+        })
+        .with_column_converter("code", |v: &DataValue| match v {
+            DataValue::Text(s) => Ok(DataValue::Int(s.trim().parse()?)),
+            other => Ok(other.clone()),
+        });
+    let mut warnings = Vec::new();
+    let text = |s: &'static str| ColumnData::String(Some(s.into()));
+
+    let mapper = ValueMapper::new(&config);
+    let flag = column("enabled", "Bitn");
+    let code = column("Code", "NVarchar");
+    assert_eq!(
+        mapper
+            .map(ColumnData::Bit(Some(true)), &flag, 0, &mut warnings)
+            .unwrap(),
+        "active"
+    );
+    assert!(mapper
+        .map(ColumnData::Bit(None), &flag, 0, &mut warnings)
+        .unwrap()
+        .is_null());
+    assert_eq!(
+        mapper.map(text(" 42"), &code, 0, &mut warnings).unwrap(),
+        42
+    );
+    let error = mapper.map(text("x1"), &code, 3, &mut warnings).unwrap_err();
+    assert!(error.to_string().contains("\"Code\" in row 3"));
+
+    config = config.with_conversion_policy(ConversionPolicy::Null);
+    let mapper = ValueMapper::new(&config);
+    assert!(mapper
+        .map(text("x1"), &code, 3, &mut warnings)
+        .unwrap()
+        .is_null());
+    config = config.with_conversion_policy(ConversionPolicy::Text);
+    let mapper = ValueMapper::new(&config);
+    assert_eq!(
+        mapper.map(text("x2"), &code, 4, &mut warnings).unwrap(),
+        "x2"
+    );
+    let rows: Vec<(usize, &str)> = warnings
+        .iter()
+        .map(|w| (w.row, w.column.as_str()))
+        .collect();
+    assert_eq!(rows, [(3, "Code"), (4, "Code")]);
+}
+
+#[test]
+fn reads_datetimes_in_the_server_time_zone() {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+    use tiberius::ToSql;
+
+    let local = NaiveDate::from_ymd_opt(2024, 3, 1)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap();
+    let column = column("created", "Datetime2");
+    let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+    let read = |config: MssqlConfig| {
+        ValueMapper::new(&config)
+            .map(local.to_sql(), &column, 0, &mut Vec::new())
+            .unwrap()
+    };
+
+    let config = MssqlConfig::new("localhost", 1433, "sa", "", "master", true);
+    assert_eq!(read(config.clone()), DataValue::DateTime(local));
+    let config = config.with_time_zone(zone);
+    let zoned = read(config.clone().with_datetime_mapping(DateTimeMapping::Zoned));
+    assert_eq!(
+        zoned,
+        DataValue::DateTimeOffset(zone.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap())
+    );
+    let DataValue::DateTimeOffset(utc) = read(config.with_datetime_mapping(DateTimeMapping::Utc))
+    else {
+        panic!("expected a datetimeoffset");
+    };
+    assert_eq!(utc.offset().local_minus_utc(), 0);
+    assert_eq!(utc.naive_local().to_string(), "2024-03-01 07:30:00");
+}
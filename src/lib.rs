@@ -1,9 +1,15 @@
 pub mod dataset;
 pub mod services;
 pub mod infrastructure;
+pub mod query;
 mod repositories;
+#[cfg(feature = "testing")]
+pub mod testing;
 
-pub use repositories::{Command, CommandType, Parameter};
+pub use repositories::{
+    quote_name, validate_parameter_name, Command, CommandError, CommandType, Executor, Identifier,
+    Parameter, ParameterUsage, ParameterValue,
+};
 pub use services::{dataset_service::DatasetService, service::Service};
 
 use anyhow::Result;
@@ -25,7 +31,7 @@ pub async fn execute(config: MssqlConfig, command: Command) -> Result<DataSet> {
 /// returned count is the sum of row counts reported by the server.
 pub async fn execute_non_query(config: MssqlConfig, command: Command) -> Result<u64> {
     let mut connection = SqlConnection::connect(config).await?;
-    let (sql, params) = command.build();
+    let (sql, params) = command.try_build_for(connection.time_zone())?;
     connection.execute_non_query(&sql, params).await
 }
 
@@ -33,6 +39,48 @@ pub async fn execute_non_query(config: MssqlConfig, command: Command) -> Result<
 /// as a `DataValue`. If the command returns no rows, returns `Ok(None)`.
 pub async fn execute_scalar(config: MssqlConfig, command: Command) -> Result<Option<DataValue>> {
     let mut connection = SqlConnection::connect(config).await?;
-    let (sql, params) = command.build();
+    let (sql, params) = command.try_build_for(connection.time_zone())?;
     connection.execute_scalar(&sql, params).await
 }
+
+/// Execute a `FOR JSON` query and return the whole JSON document, which SQL Server
+/// splits across rows. Returns `Ok(None)` when the query produced no rows or a NULL.
+pub async fn execute_json(config: MssqlConfig, command: Command) -> Result<Option<String>> {
+    let dataset = execute(config, command.with_concatenated_result()).await?;
+    Ok(document(dataset))
+}
+
+/// Execute a `FOR XML` query and return the whole XML document, which SQL Server
+/// splits across rows. Returns `Ok(None)` when the query produced no rows or a NULL.
+pub async fn execute_xml(config: MssqlConfig, command: Command) -> Result<Option<String>> {
+    let dataset = execute(config, command.with_concatenated_result()).await?;
+    Ok(document(dataset))
+}
+
+/// Execute a `FOR JSON` query like [`execute_json`] and deserialize the document.
+#[cfg(feature = "json")]
+pub async fn execute_json_as<T: serde::de::DeserializeOwned>(
+    config: MssqlConfig,
+    command: Command,
+) -> Result<Option<T>> {
+    match execute_json(config, command).await? {
+        Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+        None => Ok(None),
+    }
+}
+
+// The text of the first column of the first row of a concatenated result.
+fn document(mut dataset: DataSet) -> Option<String> {
+    let table = dataset.tables.remove("table0")?;
+    let column = table.columns.first().map(|c| c.name.clone());
+    let mut row = table.rows.into_iter().next()?;
+    let cell = match column {
+        Some(name) => row.cells.remove(&name)?,
+        None => row.cells.into_values().next()?,
+    };
+    match cell.value {
+        DataValue::Null => None,
+        DataValue::Text(s) | DataValue::Xml(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
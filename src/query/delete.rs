@@ -0,0 +1,62 @@
+use crate::{Command, CommandError};
+
+use super::{ident, output_clause, where_clause, Condition, ParamSink};
+
+/// Builder for `DELETE` statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delete {
+    table: String,
+    top: Option<u64>,
+    filters: Vec<Condition>,
+    output: Option<Vec<String>>,
+}
+
+impl Delete {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from(table: &str) -> Self {
+        Self {
+            table: table.into(),
+            top: None,
+            filters: Vec::new(),
+            output: None,
+        }
+    }
+
+    pub fn top(mut self, rows: u64) -> Self {
+        self.top = Some(rows);
+        self
+    }
+
+    /// Add a condition; multiple filters are combined with `AND`.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filters.push(condition);
+        self
+    }
+
+    /// Return the deleted rows with `OUTPUT deleted.*`.
+    pub fn output_deleted(mut self) -> Self {
+        self.output = Some(Vec::new());
+        self
+    }
+
+    pub fn output<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.output = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn build(&self) -> Result<Command, CommandError> {
+        let mut sink = ParamSink::default();
+        let mut sql = String::from("DELETE ");
+        if let Some(top) = self.top {
+            sql.push_str(&format!("TOP ({}) ", top));
+        }
+        sql.push_str(&format!("FROM {}", ident(&self.table)?));
+        sql.push_str(&output_clause("deleted", &self.output)?);
+        sql.push_str(&where_clause(&self.filters, &mut sink)?);
+        Ok(sink.into_command(sql))
+    }
+}
@@ -0,0 +1,273 @@
+use crate::dataset::DataValue;
+use crate::CommandError;
+
+use super::{ident, ParamSink};
+
+/// A column reference used to build conditions and orderings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    name: String,
+}
+
+/// Reference a column by name. Names are parsed like [`Identifier::parse`](crate::Identifier::parse),
+/// so qualified names such as `o.Status` work and names with spaces must be bracketed.
+pub fn col(name: &str) -> Column {
+    Column { name: name.into() }
+}
+
+/// A predicate rendered into a `WHERE` clause. Every value is bound as a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare {
+        column: String,
+        op: &'static str,
+        value: DataValue,
+    },
+    IsNull(String),
+    IsNotNull(String),
+    In {
+        column: String,
+        values: Vec<DataValue>,
+        negated: bool,
+    },
+    Like {
+        column: String,
+        pattern: DataValue,
+        negated: bool,
+    },
+    Between {
+        column: String,
+        low: DataValue,
+        high: DataValue,
+    },
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// One `ORDER BY` term.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub column: String,
+    pub descending: bool,
+}
+
+impl Column {
+    fn compare(self, op: &'static str, value: DataValue) -> Condition {
+        Condition::Compare {
+            column: self.name,
+            op,
+            value,
+        }
+    }
+
+    /// `column = value`; comparing with `DataValue::Null` renders `IS NULL`.
+    pub fn eq<T: Into<DataValue>>(self, value: T) -> Condition {
+        match value.into() {
+            DataValue::Null => Condition::IsNull(self.name),
+            v => self.compare("=", v),
+        }
+    }
+
+    /// `column <> value`; comparing with `DataValue::Null` renders `IS NOT NULL`.
+    pub fn ne<T: Into<DataValue>>(self, value: T) -> Condition {
+        match value.into() {
+            DataValue::Null => Condition::IsNotNull(self.name),
+            v => self.compare("<>", v),
+        }
+    }
+
+    pub fn lt<T: Into<DataValue>>(self, value: T) -> Condition {
+        self.compare("<", value.into())
+    }
+
+    pub fn le<T: Into<DataValue>>(self, value: T) -> Condition {
+        self.compare("<=", value.into())
+    }
+
+    pub fn gt<T: Into<DataValue>>(self, value: T) -> Condition {
+        self.compare(">", value.into())
+    }
+
+    pub fn ge<T: Into<DataValue>>(self, value: T) -> Condition {
+        self.compare(">=", value.into())
+    }
+
+    pub fn like<T: Into<DataValue>>(self, pattern: T) -> Condition {
+        Condition::Like {
+            column: self.name,
+            pattern: pattern.into(),
+            negated: false,
+        }
+    }
+
+    pub fn not_like<T: Into<DataValue>>(self, pattern: T) -> Condition {
+        Condition::Like {
+            column: self.name,
+            pattern: pattern.into(),
+            negated: true,
+        }
+    }
+
+    pub fn between<T: Into<DataValue>>(self, low: T, high: T) -> Condition {
+        Condition::Between {
+            column: self.name,
+            low: low.into(),
+            high: high.into(),
+        }
+    }
+
+    /// `column IN (...)`, bound as a list parameter.
+    pub fn is_in<T, I>(self, values: I) -> Condition
+    where
+        T: Into<DataValue>,
+        I: IntoIterator<Item = T>,
+    {
+        Condition::In {
+            column: self.name,
+            values: values.into_iter().map(Into::into).collect(),
+            negated: false,
+        }
+    }
+
+    pub fn not_in<T, I>(self, values: I) -> Condition
+    where
+        T: Into<DataValue>,
+        I: IntoIterator<Item = T>,
+    {
+        Condition::In {
+            column: self.name,
+            values: values.into_iter().map(Into::into).collect(),
+            negated: true,
+        }
+    }
+
+    pub fn is_null(self) -> Condition {
+        Condition::IsNull(self.name)
+    }
+
+    pub fn is_not_null(self) -> Condition {
+        Condition::IsNotNull(self.name)
+    }
+
+    pub fn asc(self) -> OrderBy {
+        OrderBy {
+            column: self.name,
+            descending: false,
+        }
+    }
+
+    pub fn desc(self) -> OrderBy {
+        OrderBy {
+            column: self.name,
+            descending: true,
+        }
+    }
+}
+
+impl From<Column> for OrderBy {
+    fn from(column: Column) -> Self {
+        column.asc()
+    }
+}
+
+impl Condition {
+    pub fn and(self, other: Condition) -> Condition {
+        match self {
+            Condition::And(mut all) => {
+                all.push(other);
+                Condition::And(all)
+            }
+            c => Condition::And(vec![c, other]),
+        }
+    }
+
+    pub fn or(self, other: Condition) -> Condition {
+        match self {
+            Condition::Or(mut any) => {
+                any.push(other);
+                Condition::Or(any)
+            }
+            c => Condition::Or(vec![c, other]),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Condition {
+        Condition::Not(Box::new(self))
+    }
+
+    pub(crate) fn render(&self, sink: &mut ParamSink) -> Result<String, CommandError> {
+        Ok(match self {
+            Condition::Compare { column, op, value } => {
+                format!("{} {} {}", ident(column)?, op, sink.bind(value.clone()))
+            }
+            Condition::IsNull(column) => format!("{} IS NULL", ident(column)?),
+            Condition::IsNotNull(column) => format!("{} IS NOT NULL", ident(column)?),
+            Condition::In {
+                column,
+                values,
+                negated,
+            } => format!(
+                "{} {}IN ({})",
+                ident(column)?,
+                if *negated { "NOT " } else { "" },
+                sink.bind_list(values.clone())
+            ),
+            Condition::Like {
+                column,
+                pattern,
+                negated,
+            } => format!(
+                "{} {}LIKE {}",
+                ident(column)?,
+                if *negated { "NOT " } else { "" },
+                sink.bind(pattern.clone())
+            ),
+            Condition::Between { column, low, high } => format!(
+                "{} BETWEEN {} AND {}",
+                ident(column)?,
+                sink.bind(low.clone()),
+                sink.bind(high.clone())
+            ),
+            Condition::And(all) => join(all, " AND ", "1 = 1", sink)?,
+            Condition::Or(any) => join(any, " OR ", "1 = 0", sink)?,
+            Condition::Not(inner) => format!("NOT {}", inner.render_nested(sink)?),
+        })
+    }
+
+    // Render with parentheses when combined with other conditions.
+    pub(crate) fn render_nested(&self, sink: &mut ParamSink) -> Result<String, CommandError> {
+        let sql = self.render(sink)?;
+        Ok(match self {
+            Condition::And(v) | Condition::Or(v) if v.len() > 1 => format!("({})", sql),
+            _ => sql,
+        })
+    }
+}
+
+fn join(
+    conditions: &[Condition],
+    separator: &str,
+    empty: &str,
+    sink: &mut ParamSink,
+) -> Result<String, CommandError> {
+    if conditions.is_empty() {
+        return Ok(empty.into());
+    }
+    Ok(conditions
+        .iter()
+        .map(|c| c.render_nested(sink))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(separator))
+}
+
+impl OrderBy {
+    pub(crate) fn render(&self) -> Result<String, CommandError> {
+        Ok(format!(
+            "{}{}",
+            ident(&self.column)?,
+            if self.descending { " DESC" } else { "" }
+        ))
+    }
+}
@@ -0,0 +1,63 @@
+use crate::dataset::DataValue;
+use crate::{Command, CommandError};
+
+use super::{ident, ident_list, output_clause, ParamSink};
+
+/// Builder for single-row `INSERT` statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Insert {
+    table: String,
+    values: Vec<(String, DataValue)>,
+    output: Option<Vec<String>>,
+}
+
+impl Insert {
+    pub fn into(table: &str) -> Self {
+        Self {
+            table: table.into(),
+            values: Vec::new(),
+            output: None,
+        }
+    }
+
+    pub fn value<T: Into<DataValue>>(mut self, column: &str, value: T) -> Self {
+        self.values.push((column.into(), value.into()));
+        self
+    }
+
+    /// Return the inserted row with `OUTPUT inserted.*`.
+    pub fn output_inserted(mut self) -> Self {
+        self.output = Some(Vec::new());
+        self
+    }
+
+    /// Return selected columns of the inserted row, e.g. a generated identity.
+    pub fn output<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.output = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn build(&self) -> Result<Command, CommandError> {
+        let mut sink = ParamSink::default();
+        let mut sql = format!("INSERT INTO {}", ident(&self.table)?);
+        if self.values.is_empty() {
+            sql.push_str(&output_clause("inserted", &self.output)?);
+            sql.push_str(" DEFAULT VALUES");
+            return Ok(sink.into_command(sql));
+        }
+        let columns: Vec<String> = self.values.iter().map(|(c, _)| c.clone()).collect();
+        sql.push_str(&format!(" ({})", ident_list(&columns)?));
+        sql.push_str(&output_clause("inserted", &self.output)?);
+        let placeholders: Vec<String> = self
+            .values
+            .iter()
+            .map(|(_, v)| sink.bind(v.clone()))
+            .collect();
+        sql.push_str(&format!(" VALUES ({})", placeholders.join(", ")));
+        Ok(sink.into_command(sql))
+    }
+}
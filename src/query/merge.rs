@@ -0,0 +1,108 @@
+use crate::dataset::DataValue;
+use crate::{Command, CommandError};
+
+use super::{ident, ident_list, ParamSink};
+
+/// Builder for a single-row upsert using `MERGE ... WITH (HOLDLOCK)`.
+///
+/// Rows matching on the key columns are updated, others are inserted, and the
+/// statement outputs `$action` followed by the resulting row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    table: String,
+    keys: Vec<String>,
+    values: Vec<(String, DataValue)>,
+    output: bool,
+}
+
+impl Merge {
+    pub fn into(table: &str) -> Self {
+        Self {
+            table: table.into(),
+            keys: Vec::new(),
+            values: Vec::new(),
+            output: false,
+        }
+    }
+
+    /// Columns used to match the incoming row against existing rows.
+    pub fn on<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn value<T: Into<DataValue>>(mut self, column: &str, value: T) -> Self {
+        self.values.push((column.into(), value.into()));
+        self
+    }
+
+    /// Return `$action, inserted.*` for the merged row.
+    pub fn output(mut self) -> Self {
+        self.output = true;
+        self
+    }
+
+    pub fn build(&self) -> Result<Command, CommandError> {
+        if self.keys.is_empty() {
+            return Err(CommandError::InvalidQuery("MERGE requires key columns"));
+        }
+        let has = |c: &String| self.values.iter().any(|(v, _)| v.eq_ignore_ascii_case(c));
+        if !self.keys.iter().all(has) {
+            return Err(CommandError::InvalidQuery("MERGE key columns need a value"));
+        }
+        let mut sink = ParamSink::default();
+        let columns: Vec<String> = self.values.iter().map(|(c, _)| c.clone()).collect();
+        let placeholders: Vec<String> = self
+            .values
+            .iter()
+            .map(|(_, v)| sink.bind(v.clone()))
+            .collect();
+        let on = self
+            .keys
+            .iter()
+            .map(|k| {
+                let k = ident(k)?;
+                Ok(format!("target.{} = source.{}", k, k))
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        let mut sql = format!(
+            "MERGE INTO {} WITH (HOLDLOCK) AS target USING (VALUES ({})) AS source ({}) ON {}",
+            ident(&self.table)?,
+            placeholders.join(", "),
+            ident_list(&columns)?,
+            on.join(" AND ")
+        );
+        let updates = columns
+            .iter()
+            .filter(|c| !self.keys.iter().any(|k| k.eq_ignore_ascii_case(c)))
+            .map(|c| {
+                let c = ident(c)?;
+                Ok(format!("target.{} = source.{}", c, c))
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        if !updates.is_empty() {
+            sql.push_str(&format!(
+                " WHEN MATCHED THEN UPDATE SET {}",
+                updates.join(", ")
+            ));
+        }
+        let sources = columns
+            .iter()
+            .map(|c| Ok(format!("source.{}", ident(c)?)))
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        sql.push_str(&format!(
+            " WHEN NOT MATCHED THEN INSERT ({}) VALUES ({})",
+            ident_list(&columns)?,
+            sources.join(", ")
+        ));
+        if self.output {
+            sql.push_str(" OUTPUT $action, inserted.*");
+        }
+        sql.push(';');
+        Ok(sink.into_command(sql))
+    }
+}
@@ -0,0 +1,93 @@
+//! Typed builders that emit [`Command`](crate::Command)s with every value bound as a
+//! positional parameter, so dynamic SQL never needs string concatenation.
+
+pub mod delete;
+pub mod expr;
+pub mod insert;
+pub mod merge;
+pub mod select;
+pub mod update;
+
+pub use delete::Delete;
+pub use expr::{col, Column, Condition, OrderBy};
+pub use insert::Insert;
+pub use merge::Merge;
+pub use select::Select;
+pub use update::Update;
+
+use crate::dataset::DataValue;
+use crate::{Command, CommandError, Identifier, Parameter};
+
+// Collects bound values while a statement is rendered, handing out `@P1..@Pn`.
+#[derive(Default)]
+pub(crate) struct ParamSink {
+    params: Vec<Parameter>,
+}
+
+impl ParamSink {
+    pub(crate) fn bind(&mut self, value: DataValue) -> String {
+        let name = format!("P{}", self.params.len() + 1);
+        self.params.push(Parameter::new(&name, value));
+        format!("@{}", name)
+    }
+
+    pub(crate) fn bind_list(&mut self, values: Vec<DataValue>) -> String {
+        let name = format!("P{}", self.params.len() + 1);
+        self.params.push(Parameter::list(&name, values));
+        format!("@{}", name)
+    }
+
+    pub(crate) fn into_command(self, sql: String) -> Command {
+        self.params
+            .into_iter()
+            .fold(Command::query(&sql), Command::with_param)
+    }
+}
+
+pub(crate) fn ident(name: &str) -> Result<String, CommandError> {
+    Ok(Identifier::parse(name)?.quoted())
+}
+
+pub(crate) fn ident_list(names: &[String]) -> Result<String, CommandError> {
+    Ok(names
+        .iter()
+        .map(|n| ident(n))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(", "))
+}
+
+// Render the WHERE clause for a list of conditions combined with AND.
+pub(crate) fn where_clause(
+    filters: &[Condition],
+    sink: &mut ParamSink,
+) -> Result<String, CommandError> {
+    if filters.is_empty() {
+        return Ok(String::new());
+    }
+    let parts = filters
+        .iter()
+        .map(|c| c.render_nested(sink))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!(" WHERE {}", parts.join(" AND ")))
+}
+
+// Render an `OUTPUT` clause for the `inserted` or `deleted` pseudo table.
+pub(crate) fn output_clause(
+    source: &str,
+    columns: &Option<Vec<String>>,
+) -> Result<String, CommandError> {
+    match columns {
+        None => Ok(String::new()),
+        Some(cols) if cols.is_empty() => Ok(format!(" OUTPUT {}.*", source)),
+        Some(cols) => {
+            let cols = cols
+                .iter()
+                .map(|c| Ok(format!("{}.{}", source, ident(c)?)))
+                .collect::<Result<Vec<_>, CommandError>>()?;
+            Ok(format!(" OUTPUT {}", cols.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod query_test;
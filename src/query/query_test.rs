@@ -0,0 +1,136 @@
+use super::*;
+use crate::dataset::DataValue;
+
+fn bound(cmd: &Command) -> Vec<DataValue> {
+    cmd.bind().unwrap().1
+}
+
+#[test]
+fn select_with_filters_order_and_paging() {
+    let cmd = Select::from("dbo.Orders")
+        .columns(["Id", "Status", "[Order Date]"])
+        .filter(col("Status").eq("PAID"))
+        .filter(col("Amount").gt(100).or(col("Priority").is_not_null()))
+        .filter(col("Region").is_in(["N", "S"]))
+        .order_by(col("CreatedAt").desc())
+        .order_by(col("Id"))
+        .offset_fetch(40, 20)
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "SELECT [Id], [Status], [Order Date] FROM [dbo].[Orders] WHERE [Status] = @P1 \
+         AND ([Amount] > @P2 OR [Priority] IS NOT NULL) AND [Region] IN (@P3) \
+         ORDER BY [CreatedAt] DESC, [Id] OFFSET 40 ROWS FETCH NEXT 20 ROWS ONLY"
+    );
+    let (sql, values) = cmd.bind().unwrap();
+    assert!(sql.contains("[Region] IN (@P3, @P4)"));
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], "PAID");
+    assert!(cmd.validate().is_ok());
+}
+
+#[test]
+fn select_top_distinct_and_null_comparisons() {
+    let cmd = Select::from("Users")
+        .distinct()
+        .top(5)
+        .column("Email")
+        .filter(
+            col("DeletedAt")
+                .eq(DataValue::Null)
+                .and(col("Name").like("A%"))
+                .not(),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "SELECT DISTINCT TOP (5) [Email] FROM [Users] WHERE NOT ([DeletedAt] IS NULL AND [Name] LIKE @P1)"
+    );
+}
+
+#[test]
+fn select_rejects_invalid_paging_and_identifiers() {
+    assert!(Select::from("t").offset_fetch(0, 10).build().is_err());
+    assert!(Select::from("t")
+        .top(1)
+        .order_by(col("a"))
+        .offset(1)
+        .build()
+        .is_err());
+    assert!(Select::from("t; DROP TABLE t").build().is_err());
+    assert!(Select::from("t")
+        .filter(col("a = 1 --").eq(1))
+        .build()
+        .is_err());
+}
+
+#[test]
+fn insert_with_output() {
+    let cmd = Insert::into("dbo.Orders")
+        .value("Status", "NEW")
+        .value("Amount", 10)
+        .output(["Id"])
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "INSERT INTO [dbo].[Orders] ([Status], [Amount]) OUTPUT inserted.[Id] VALUES (@P1, @P2)"
+    );
+    assert_eq!(
+        bound(&cmd),
+        vec![DataValue::from("NEW"), DataValue::Int(10)]
+    );
+    assert_eq!(
+        Insert::into("t").output_inserted().build().unwrap().text,
+        "INSERT INTO [t] OUTPUT inserted.* DEFAULT VALUES"
+    );
+}
+
+#[test]
+fn update_and_delete() {
+    let cmd = Update::table("dbo.Orders")
+        .set("Status", "PAID")
+        .filter(col("Id").eq(7))
+        .output_inserted()
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "UPDATE [dbo].[Orders] SET [Status] = @P1 OUTPUT inserted.* WHERE [Id] = @P2"
+    );
+    assert!(Update::table("t").build().is_err());
+
+    let cmd = Delete::from("dbo.Orders")
+        .top(100)
+        .filter(col("CreatedAt").between(1, 2))
+        .output_deleted()
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "DELETE TOP (100) FROM [dbo].[Orders] OUTPUT deleted.* WHERE [CreatedAt] BETWEEN @P1 AND @P2"
+    );
+}
+
+#[test]
+fn merge_upserts_by_key() {
+    let cmd = Merge::into("dbo.Orders")
+        .on(["Id"])
+        .value("Id", 1)
+        .value("Status", "PAID")
+        .output()
+        .build()
+        .unwrap();
+    assert_eq!(
+        cmd.text,
+        "MERGE INTO [dbo].[Orders] WITH (HOLDLOCK) AS target USING (VALUES (@P1, @P2)) \
+         AS source ([Id], [Status]) ON target.[Id] = source.[Id] \
+         WHEN MATCHED THEN UPDATE SET target.[Status] = source.[Status] \
+         WHEN NOT MATCHED THEN INSERT ([Id], [Status]) VALUES (source.[Id], source.[Status]) \
+         OUTPUT $action, inserted.*;"
+    );
+    assert!(Merge::into("t").value("a", 1).build().is_err());
+    assert!(Merge::into("t").on(["Id"]).value("a", 1).build().is_err());
+}
@@ -0,0 +1,138 @@
+use crate::{Command, CommandError};
+
+use super::{ident, ident_list, where_clause, Condition, OrderBy, ParamSink};
+
+/// Builder for `SELECT` statements.
+///
+/// ```
+/// use mssqlrust::query::{col, Select};
+///
+/// let cmd = Select::from("dbo.Orders")
+///     .columns(["Id", "Status"])
+///     .filter(col("Status").eq("PAID"))
+///     .order_by(col("CreatedAt").desc())
+///     .offset_fetch(20, 10)
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     cmd.text,
+///     "SELECT [Id], [Status] FROM [dbo].[Orders] WHERE [Status] = @P1 \
+///      ORDER BY [CreatedAt] DESC OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    table: String,
+    columns: Vec<String>,
+    distinct: bool,
+    top: Option<u64>,
+    filters: Vec<Condition>,
+    order_by: Vec<OrderBy>,
+    offset: Option<(u64, Option<u64>)>,
+}
+
+impl Select {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from(table: &str) -> Self {
+        Self {
+            table: table.into(),
+            columns: Vec::new(),
+            distinct: false,
+            top: None,
+            filters: Vec::new(),
+            order_by: Vec::new(),
+            offset: None,
+        }
+    }
+
+    /// Columns to return; all columns (`*`) when none are given.
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns.extend(columns.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn column(mut self, column: &str) -> Self {
+        self.columns.push(column.into());
+        self
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    pub fn top(mut self, rows: u64) -> Self {
+        self.top = Some(rows);
+        self
+    }
+
+    /// Add a condition; multiple filters are combined with `AND`.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filters.push(condition);
+        self
+    }
+
+    pub fn order_by<O: Into<OrderBy>>(mut self, order: O) -> Self {
+        self.order_by.push(order.into());
+        self
+    }
+
+    /// Page the result with `OFFSET .. ROWS FETCH NEXT .. ROWS ONLY`. Requires `order_by`.
+    pub fn offset_fetch(mut self, offset: u64, fetch: u64) -> Self {
+        self.offset = Some((offset, Some(fetch)));
+        self
+    }
+
+    /// Skip rows with `OFFSET .. ROWS` and return the rest. Requires `order_by`.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some((offset, None));
+        self
+    }
+
+    pub fn build(&self) -> Result<Command, CommandError> {
+        if self.offset.is_some() && self.order_by.is_empty() {
+            return Err(CommandError::InvalidQuery("OFFSET requires ORDER BY"));
+        }
+        if self.offset.is_some() && self.top.is_some() {
+            return Err(CommandError::InvalidQuery(
+                "TOP cannot be combined with OFFSET",
+            ));
+        }
+        let mut sink = ParamSink::default();
+        let mut sql = String::from("SELECT ");
+        if self.distinct {
+            sql.push_str("DISTINCT ");
+        }
+        if let Some(top) = self.top {
+            sql.push_str(&format!("TOP ({}) ", top));
+        }
+        if self.columns.is_empty() {
+            sql.push('*');
+        } else {
+            sql.push_str(&ident_list(&self.columns)?);
+        }
+        sql.push_str(" FROM ");
+        sql.push_str(&ident(&self.table)?);
+        sql.push_str(&where_clause(&self.filters, &mut sink)?);
+        if !self.order_by.is_empty() {
+            let terms = self
+                .order_by
+                .iter()
+                .map(OrderBy::render)
+                .collect::<Result<Vec<_>, _>>()?;
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&terms.join(", "));
+        }
+        if let Some((offset, fetch)) = self.offset {
+            sql.push_str(&format!(" OFFSET {} ROWS", offset));
+            if let Some(fetch) = fetch {
+                sql.push_str(&format!(" FETCH NEXT {} ROWS ONLY", fetch));
+            }
+        }
+        Ok(sink.into_command(sql))
+    }
+}
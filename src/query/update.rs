@@ -0,0 +1,68 @@
+use crate::dataset::DataValue;
+use crate::{Command, CommandError};
+
+use super::{ident, output_clause, where_clause, Condition, ParamSink};
+
+/// Builder for `UPDATE` statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    table: String,
+    assignments: Vec<(String, DataValue)>,
+    filters: Vec<Condition>,
+    output: Option<Vec<String>>,
+}
+
+impl Update {
+    pub fn table(table: &str) -> Self {
+        Self {
+            table: table.into(),
+            assignments: Vec::new(),
+            filters: Vec::new(),
+            output: None,
+        }
+    }
+
+    pub fn set<T: Into<DataValue>>(mut self, column: &str, value: T) -> Self {
+        self.assignments.push((column.into(), value.into()));
+        self
+    }
+
+    /// Add a condition; multiple filters are combined with `AND`.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filters.push(condition);
+        self
+    }
+
+    /// Return the updated rows with `OUTPUT inserted.*`.
+    pub fn output_inserted(mut self) -> Self {
+        self.output = Some(Vec::new());
+        self
+    }
+
+    pub fn output<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.output = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn build(&self) -> Result<Command, CommandError> {
+        if self.assignments.is_empty() {
+            return Err(CommandError::InvalidQuery(
+                "UPDATE requires at least one SET",
+            ));
+        }
+        let mut sink = ParamSink::default();
+        let sets = self
+            .assignments
+            .iter()
+            .map(|(c, v)| Ok(format!("{} = {}", ident(c)?, sink.bind(v.clone()))))
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        let mut sql = format!("UPDATE {} SET {}", ident(&self.table)?, sets.join(", "));
+        sql.push_str(&output_clause("inserted", &self.output)?);
+        sql.push_str(&where_clause(&self.filters, &mut sink)?);
+        Ok(sink.into_command(sql))
+    }
+}
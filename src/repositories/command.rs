@@ -1,4 +1,13 @@
-use super::parameter::Parameter;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::dataset::{DataValue, ServerTimeZone};
+
+use super::error::CommandError;
+use super::identifier::{validate_parameter_name, Identifier};
+use super::parameter::{
+    list_element_type, list_to_json, list_to_json_lossy, Parameter, ParameterValue,
+};
 
 pub enum CommandType {
     Text,
@@ -9,6 +18,51 @@ pub struct Command {
     pub text: String,
     pub command_type: CommandType,
     pub parameters: Vec<Parameter>,
+    /// Ask the server for key information and set the primary key of the first
+    /// result table. See [`Command::with_key_info`].
+    pub key_info: bool,
+    /// Join the rows of the first result table into one value, as `FOR JSON` and
+    /// `FOR XML` results need. See [`Command::with_concatenated_result`].
+    pub concatenate: bool,
+}
+
+/// Mismatches between the parameters supplied to a text [`Command`] and the
+/// variables its SQL actually references.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParameterUsage {
+    /// Supplied parameters that the SQL never references.
+    pub unused: Vec<String>,
+    /// Variables referenced by the SQL that are neither supplied nor declared in the batch.
+    pub missing: Vec<String>,
+}
+
+impl ParameterUsage {
+    pub fn is_empty(&self) -> bool {
+        self.unused.is_empty() && self.missing.is_empty()
+    }
+}
+
+impl fmt::Display for ParameterUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |names: &[String]| {
+            names
+                .iter()
+                .map(|n| format!("@{}", n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match (self.unused.is_empty(), self.missing.is_empty()) {
+            (true, true) => write!(f, "all parameters referenced"),
+            (false, true) => write!(f, "unused: {}", list(&self.unused)),
+            (true, false) => write!(f, "missing: {}", list(&self.missing)),
+            (false, false) => write!(
+                f,
+                "unused: {}; missing: {}",
+                list(&self.unused),
+                list(&self.missing)
+            ),
+        }
+    }
 }
 
 impl Command {
@@ -17,6 +71,8 @@ impl Command {
             text: text.into(),
             command_type: CommandType::Text,
             parameters: Vec::new(),
+            key_info: false,
+            concatenate: false,
         }
     }
 
@@ -25,7 +81,39 @@ impl Command {
             text: name.into(),
             command_type: CommandType::StoredProcedure,
             parameters: Vec::new(),
+            key_info: false,
+            concatenate: false,
+        }
+    }
+
+    /// Create a text command from a template where each `{}` is replaced by the next
+    /// identifier. Object names can only be spliced into SQL this way, so they are
+    /// always bracketed like `QUOTENAME`. Braces inside literals and comments are left alone.
+    pub fn query_fmt(template: &str, identifiers: &[Identifier]) -> Result<Self, CommandError> {
+        let tokens = tokenize(template);
+        let mut text = String::with_capacity(template.len());
+        let mut supplied = identifiers.iter();
+        let mut expected = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i].text == "{" && tokens.get(i + 1).is_some_and(|t| t.text == "}") {
+                expected += 1;
+                if let Some(identifier) = supplied.next() {
+                    text.push_str(&identifier.quoted());
+                }
+                i += 2;
+                continue;
+            }
+            text.push_str(tokens[i].text);
+            i += 1;
+        }
+        if expected != identifiers.len() {
+            return Err(CommandError::PlaceholderMismatch {
+                expected,
+                found: identifiers.len(),
+            });
         }
+        Ok(Self::query(&text))
     }
 
     pub fn with_param(mut self, param: Parameter) -> Self {
@@ -33,84 +121,804 @@ impl Command {
         self
     }
 
+    /// Detect the primary key of the first result set, like ADO.NET's
+    /// `CommandBehavior.KeyInfo`. When every column comes from a single base table and
+    /// the result includes all columns of one of its unique keys, those columns become
+    /// the table's primary key so [`DataTable::find`](crate::dataset::DataTable::find)
+    /// works right away. This costs one extra round trip to `sp_describe_first_result_set`;
+    /// when the server cannot describe the batch, the table is returned without a key.
+    pub fn with_key_info(mut self) -> Self {
+        self.key_info = true;
+        self
+    }
+
+    /// Reassemble a result the server splits across rows. SQL Server returns the
+    /// output of `FOR JSON` and `FOR XML` queries in rows of about 2033 characters;
+    /// with this flag the first result table holds a single row whose first column is
+    /// the whole document. Tables with at most one row are left as they are.
+    pub fn with_concatenated_result(mut self) -> Self {
+        self.concatenate = true;
+        self
+    }
+
+    /// Compare the supplied parameters with the variables referenced by the SQL text.
+    /// Stored procedure commands always report an empty usage, since every parameter
+    /// is passed as a named argument.
+    pub fn parameter_usage(&self) -> ParameterUsage {
+        match self.command_type {
+            CommandType::Text => {
+                let analysis = SqlAnalysis::new(&self.text);
+                let ordinals = self.ordinals();
+                let bound = self.bound_count();
+                let mut referenced = HashSet::new();
+                let mut missing = Vec::new();
+                for name in analysis.references() {
+                    let key = normalize(name);
+                    if ordinals.contains_key(&key) {
+                        referenced.insert(key);
+                    } else if !is_positional_within(name, bound)
+                        && !missing.iter().any(|m: &String| normalize(m) == key)
+                    {
+                        missing.push(name.to_string());
+                    }
+                }
+                let unused = self
+                    .parameters
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, p)| {
+                        let key = normalize(&p.name);
+                        !referenced.contains(&key) || ordinals.get(&key) != Some(i)
+                    })
+                    .map(|(_, p)| trim_at(&p.name).to_string())
+                    .collect();
+                ParameterUsage { unused, missing }
+            }
+            CommandType::StoredProcedure => ParameterUsage::default(),
+        }
+    }
+
+    /// Fail with [`CommandError::ParameterMismatch`] when a parameter is supplied but
+    /// never referenced, or a variable is referenced but never supplied.
+    pub fn validate(&self) -> Result<(), CommandError> {
+        let usage = self.parameter_usage();
+        if usage.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandError::ParameterMismatch(usage))
+        }
+    }
+
+    /// Render the SQL and positional parameters sent to the server without validating
+    /// the command. Parameter and procedure names are used as written, values the server
+    /// would convert are sent unconverted when their type name is invalid, and infinite
+    /// or NaN floats in JSON lists become NULL. Prefer [`Command::try_build`], which
+    /// reports these problems instead.
     pub fn build(&self) -> (String, Vec<Box<dyn tiberius::ToSql + Send + Sync>>) {
-        let params: Vec<Box<dyn tiberius::ToSql + Send + Sync>> = self
-            .parameters
-            .iter()
-            .map(|p| p.value.to_tiberius())
-            .collect();
+        match self.render(&ServerTimeZone::default(), false) {
+            Ok((sql, values)) => (sql, values.iter().map(DataValue::to_tiberius).collect()),
+            Err(e) => unreachable!("rendering without validation failed: {}", e),
+        }
+    }
+
+    /// Render the SQL and positional parameters sent to the server. Fails when a
+    /// parameter name is not a valid variable name or, for stored procedures, when the
+    /// procedure name cannot be parsed as an [`Identifier`].
+    #[allow(clippy::type_complexity)]
+    pub fn try_build(
+        &self,
+    ) -> Result<(String, Vec<Box<dyn tiberius::ToSql + Send + Sync>>), CommandError> {
+        self.try_build_for(&ServerTimeZone::default())
+    }
+
+    /// [`try_build`](Self::try_build) for a server in `zone`, which parameters marked
+    /// with [`Parameter::with_server_time_zone`] are converted to. Executing a command
+    /// through a connection uses the zone it was configured with.
+    #[allow(clippy::type_complexity)]
+    pub fn try_build_for(
+        &self,
+        zone: &ServerTimeZone,
+    ) -> Result<(String, Vec<Box<dyn tiberius::ToSql + Send + Sync>>), CommandError> {
+        let (sql, values) = self.bind_for(zone)?;
+        let params = values.iter().map(DataValue::to_tiberius).collect();
+        Ok((sql, params))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn bind(&self) -> Result<(String, Vec<DataValue>), CommandError> {
+        self.bind_for(&ServerTimeZone::default())
+    }
+
+    /// Render the SQL sent to the server together with the values bound to
+    /// `@P1..@Pn`, after list parameters have been expanded and the values of
+    /// parameters marked with [`Parameter::with_server_time_zone`] converted to `zone`.
+    pub(crate) fn bind_for(
+        &self,
+        zone: &ServerTimeZone,
+    ) -> Result<(String, Vec<DataValue>), CommandError> {
+        self.render(zone, true)
+    }
+
+    // Render the SQL and bound values, failing on invalid names and values when
+    // `strict`, and passing them through as they are otherwise.
+    fn render(
+        &self,
+        zone: &ServerTimeZone,
+        strict: bool,
+    ) -> Result<(String, Vec<DataValue>), CommandError> {
+        if strict {
+            for p in &self.parameters {
+                validate_parameter_name(&p.name)?;
+            }
+        }
+        let (bindings, values) = self.bindings(zone, strict)?;
         match self.command_type {
             CommandType::Text => {
                 // Allow using named parameters (e.g., @id) in text queries by rewriting
                 // them to positional placeholders (@P1, @P2, ...), which Tiberius expects.
-                let mut sql = self.text.clone();
-                for (i, p) in self.parameters.iter().enumerate() {
-                    let name = p.name.as_str();
-                    let trimmed = if name.starts_with('@') { &name[1..] } else { name };
-                    // If the user already uses ordinal placeholders (P1, P2, ...), skip rewrite.
-                    if trimmed.len() >= 2
-                        && trimmed.as_bytes()[0] == b'P'
-                        && trimmed[1..].bytes().all(|b| b.is_ascii_digit())
-                    {
-                        continue;
+                // Only genuine variable references are rewritten: literals, comments,
+                // quoted identifiers, system functions and local variables are left alone.
+                // A parameter named like a placeholder (`P1`, `P2`, ...) but supplied at a
+                // different position is not rewritten, so `@P2` keeps meaning the second
+                // bound value.
+                let analysis = SqlAnalysis::new(&self.text);
+                let ordinals = self.ordinals();
+                let mut sql = String::with_capacity(self.text.len());
+                for (token, role) in analysis.tokens.iter().zip(&analysis.roles) {
+                    let ordinal = match role {
+                        Some(VariableRole::Reference) if !analysis.is_declared(token.text) => {
+                            ordinals.get(&normalize(token.text)).filter(|&&i| {
+                                !is_misplaced_placeholder(&self.parameters[i].name, i)
+                            })
+                        }
+                        _ => None,
+                    };
+                    match ordinal {
+                        Some(&i) => sql.push_str(&bindings[i].placeholder()),
+                        None => sql.push_str(token.text),
                     }
-                    let needle = format!("@{}", trimmed);
-                    let replacement = format!("@P{}", i + 1);
-                    sql = replace_param_token(&sql, &needle, &replacement);
                 }
-                (sql, params)
+                Ok((sql, values))
             }
             CommandType::StoredProcedure => {
-                let mut sql = format!("EXEC {}", self.text);
+                let procedure = match Identifier::parse(&self.text) {
+                    Ok(procedure) => procedure.quoted(),
+                    Err(e) if strict => return Err(e),
+                    Err(_) => self.text.clone(),
+                };
+                // Arguments to EXEC cannot be expressions, so values converted on the
+                // server are first assigned to local variables of their type.
+                let mut sql: String = bindings
+                    .iter()
+                    .filter_map(|b| match b {
+                        Binding::Scalar(slot) => slot.declaration(),
+                        _ => None,
+                    })
+                    .collect();
+                sql.push_str(&format!("EXEC {}", procedure));
                 if !self.parameters.is_empty() {
                     let param_str = self
                         .parameters
                         .iter()
-                        .enumerate()
-                        .map(|(i, p)| {
-                            let name = if p.name.starts_with("@") {
-                                p.name.trim_start_matches('@')
-                            } else {
-                                p.name.as_str()
-                            };
-                            format!("@{} = @P{}", name, i + 1)
-                        })
+                        .zip(&bindings)
+                        .map(|(p, b)| format!("@{} = {}", trim_at(&p.name), b.argument()))
                         .collect::<Vec<_>>()
                         .join(", ");
                     sql.push(' ');
                     sql.push_str(&param_str);
                 }
-                (sql, params)
+                Ok((sql, values))
             }
         }
     }
-}
 
-// Replace all occurrences of `needle` in `haystack` that end at an identifier boundary
-// (i.e., the character following the match is not [A-Za-z0-9_]). This prevents replacing
-// `@id` inside `@id2`.
-fn replace_param_token(haystack: &str, needle: &str, replacement: &str) -> String {
-    let bytes = haystack.as_bytes();
-    let nbytes = needle.as_bytes();
-    let mut i = 0;
-    let mut out = String::with_capacity(haystack.len());
-    while i < bytes.len() {
-        if i + nbytes.len() <= bytes.len() && &bytes[i..i + nbytes.len()] == nbytes {
-            // Boundary check: next char must be absent or not [A-Za-z0-9_]
-            let boundary_ok = match bytes.get(i + nbytes.len()) {
-                None => true,
-                Some(&c) => {
-                    !(c.is_ascii_alphanumeric() || c == b'_')
+    // Assign positional slots to every parameter and collect the bound values.
+    fn bindings(
+        &self,
+        zone: &ServerTimeZone,
+        strict: bool,
+    ) -> Result<(Vec<Binding>, Vec<DataValue>), CommandError> {
+        let inline = self.inline_lists();
+        let mut bindings = Vec::with_capacity(self.parameters.len());
+        let mut values = Vec::with_capacity(self.parameters.len());
+        for (p, inline) in self.parameters.iter().zip(inline) {
+            let local;
+            let value = match p.server_time_zone {
+                true => {
+                    local = p.value.to_server_local(zone);
+                    &local
+                }
+                false => &p.value,
+            };
+            let binding = match value {
+                ParameterValue::Scalar(value) => {
+                    values.push(value.clone());
+                    Binding::Scalar(Slot::new(values.len(), value, strict)?)
+                }
+                ParameterValue::List(list) if inline && list.is_empty() => Binding::Empty,
+                ParameterValue::List(list) if inline => {
+                    let slots = list
+                        .iter()
+                        .map(|value| {
+                            values.push(value.clone());
+                            Slot::new(values.len(), value, strict)
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Binding::Inline(slots)
+                }
+                ParameterValue::List(list) => {
+                    let json = match list_to_json(list) {
+                        Some(json) => json,
+                        None if strict => {
+                            return Err(CommandError::NonFiniteListValue(trim_at(&p.name).into()))
+                        }
+                        None => list_to_json_lossy(list),
+                    };
+                    values.push(DataValue::Text(json));
+                    Binding::Json {
+                        ordinal: values.len(),
+                        element_type: list_element_type(list),
+                        unpack: matches!(self.command_type, CommandType::Text),
+                    }
                 }
             };
-            if boundary_ok {
-                out.push_str(replacement);
-                i += nbytes.len();
+            bindings.push(binding);
+        }
+        Ok((bindings, values))
+    }
+
+    // Decide which list parameters are expanded inline. Stored procedures always get
+    // JSON; text commands fall back to JSON for long lists and, largest first, for any
+    // lists that would push the request over the server's parameter limit.
+    fn inline_lists(&self) -> Vec<bool> {
+        let text = matches!(self.command_type, CommandType::Text);
+        let mut inline: Vec<bool> = self
+            .parameters
+            .iter()
+            .map(|p| {
+                text && matches!(&p.value, ParameterValue::List(l) if l.len() <= Parameter::INLINE_LIST_LIMIT)
+            })
+            .collect();
+        let len = |i: usize| match &self.parameters[i].value {
+            ParameterValue::List(list) => list.len(),
+            ParameterValue::Scalar(_) => 1,
+        };
+        let mut total: usize = (0..inline.len())
+            .map(|i| if inline[i] { len(i) } else { 1 })
+            .sum();
+        while total > Parameter::MAX_PARAMETERS {
+            let Some(largest) = (0..inline.len())
+                .filter(|&i| inline[i] && len(i) > 1)
+                .max_by_key(|&i| len(i))
+            else {
+                break;
+            };
+            inline[largest] = false;
+            total -= len(largest) - 1;
+        }
+        inline
+    }
+
+    // Map each normalized parameter name to the index of the first parameter using it.
+    fn ordinals(&self) -> HashMap<String, usize> {
+        let mut ordinals = HashMap::new();
+        for (i, p) in self.parameters.iter().enumerate() {
+            ordinals.entry(normalize(&p.name)).or_insert(i);
+        }
+        ordinals
+    }
+
+    // Number of values bound to the request once lists are expanded.
+    fn bound_count(&self) -> usize {
+        self.parameters
+            .iter()
+            .zip(self.inline_lists())
+            .map(|(p, inline)| match &p.value {
+                ParameterValue::List(list) if inline => list.len(),
+                _ => 1,
+            })
+            .sum()
+    }
+}
+
+// How a supplied parameter maps onto the positional placeholders sent to the server.
+enum Binding {
+    Scalar(Slot),
+    Inline(Vec<Slot>),
+    Json {
+        ordinal: usize,
+        element_type: String,
+        unpack: bool,
+    },
+    Empty,
+}
+
+impl Binding {
+    fn placeholder(&self) -> String {
+        match self {
+            Binding::Scalar(slot) => slot.placeholder(),
+            Binding::Inline(slots) => slots
+                .iter()
+                .map(Slot::placeholder)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Binding::Json {
+                ordinal,
+                element_type,
+                unpack: true,
+            } => format!(
+                "SELECT [value] FROM OPENJSON(@P{}) WITH ([value] {} '$')",
+                ordinal, element_type
+            ),
+            Binding::Json { ordinal, .. } => format!("@P{}", ordinal),
+            Binding::Empty => "SELECT NULL WHERE 1 = 0".into(),
+        }
+    }
+
+    // The value passed for a stored procedure parameter.
+    fn argument(&self) -> String {
+        match self {
+            Binding::Scalar(slot) if slot.conversion.is_some() => format!("@__P{}", slot.ordinal),
+            _ => self.placeholder(),
+        }
+    }
+}
+
+// One positional value. Tiberius has no wire type for `money`, `sql_variant` or CLR
+// types, so those values are sent as their closest type and converted on the server.
+struct Slot {
+    ordinal: usize,
+    // The SQL type and the expression converting `@Pn` to it.
+    conversion: Option<(String, String)>,
+}
+
+impl Slot {
+    // Without `strict`, a value whose type name is invalid is sent unconverted.
+    fn new(ordinal: usize, value: &DataValue, strict: bool) -> Result<Self, CommandError> {
+        let p = format!("@P{}", ordinal);
+        let checked = |result: Result<String, CommandError>| match result {
+            Ok(name) => Ok(Some(name)),
+            Err(e) if strict => Err(e),
+            Err(_) => Ok(None),
+        };
+        let conversion = match value {
+            DataValue::Money(_) => Some(("money".to_string(), format!("CAST({} AS money)", p))),
+            DataValue::Variant { base_type, value } => {
+                checked(variant_base_type(base_type, value))?.map(|base| {
+                    (
+                        "sql_variant".to_string(),
+                        format!("CAST(CAST({} AS {}) AS sql_variant)", p, base),
+                    )
+                })
+            }
+            DataValue::Udt { type_name, .. } => {
+                checked(Identifier::parse(type_name).map(|id| id.quoted()))?
+                    .map(|udt| (udt.clone(), format!("CAST({} AS {})", p, udt)))
+            }
+            DataValue::Spatial(v) => {
+                let kind = v.kind.type_name();
+                Some((kind.to_string(), format!("CAST({} AS {})", p, kind)))
+            }
+            DataValue::HierarchyId(_) => Some((
+                "hierarchyid".to_string(),
+                format!("CAST({} AS hierarchyid)", p),
+            )),
+            _ => None,
+        };
+        Ok(Slot {
+            ordinal,
+            conversion,
+        })
+    }
+
+    fn placeholder(&self) -> String {
+        match &self.conversion {
+            Some((_, expr)) => expr.clone(),
+            None => format!("@P{}", self.ordinal),
+        }
+    }
+
+    fn declaration(&self) -> Option<String> {
+        let (sql_type, expr) = self.conversion.as_ref()?;
+        Some(format!(
+            "DECLARE @__P{} {} = {}; ",
+            self.ordinal, sql_type, expr
+        ))
+    }
+}
+
+// Render the base type of a `sql_variant` value for `CAST`. Sized types given without
+// a length or precision are sized from the value, since their defaults would truncate it.
+fn variant_base_type(base_type: &str, value: &DataValue) -> Result<String, CommandError> {
+    let invalid = || CommandError::InvalidTypeName(base_type.to_string());
+    let base_type = base_type.trim();
+    let (name, args) = match base_type.split_once('(') {
+        Some((name, args)) => (
+            name.trim_end(),
+            Some(args.strip_suffix(')').ok_or_else(invalid)?),
+        ),
+        None => (base_type, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(invalid());
+    }
+    if let Some(args) = args {
+        let valid = args.split(',').all(|arg| {
+            let arg = arg.trim();
+            arg.eq_ignore_ascii_case("max")
+                || (!arg.is_empty() && arg.chars().all(|c| c.is_ascii_digit()))
+        });
+        return if valid {
+            Ok(base_type.to_string())
+        } else {
+            Err(invalid())
+        };
+    }
+    let name_lower = name.to_ascii_lowercase();
+    let size = match (name_lower.as_str(), value) {
+        ("char" | "varchar", DataValue::Text(s)) => Some(s.len().clamp(1, 8000).to_string()),
+        ("nchar" | "nvarchar", DataValue::Text(s)) => {
+            Some(s.encode_utf16().count().clamp(1, 4000).to_string())
+        }
+        ("binary" | "varbinary", DataValue::Binary(b)) => Some(b.len().clamp(1, 8000).to_string()),
+        ("decimal" | "numeric", DataValue::Decimal(d) | DataValue::Money(d)) => {
+            Some(format!("38, {}", d.scale()))
+        }
+        ("decimal" | "numeric", DataValue::Numeric(n)) => Some(format!("38, {}", n.scale())),
+        _ => None,
+    };
+    Ok(match size {
+        Some(size) => format!("{}({})", name, size),
+        None => name.to_string(),
+    })
+}
+
+fn trim_at(name: &str) -> &str {
+    name.strip_prefix('@').unwrap_or(name)
+}
+
+// T-SQL variable names are case-insensitive, so lookups use a lowercase key.
+fn normalize(name: &str) -> String {
+    trim_at(name).to_lowercase()
+}
+
+// Whether the parameter at `index` is named like a positional placeholder (`P1`,
+// `@P2`, ...) other than its own.
+fn is_misplaced_placeholder(name: &str, index: usize) -> bool {
+    let name = trim_at(name);
+    name.len() >= 2
+        && name.as_bytes()[0].eq_ignore_ascii_case(&b'P')
+        && name[1..].bytes().all(|b| b.is_ascii_digit())
+        && name[1..].parse::<usize>().ok() != Some(index + 1)
+}
+
+// `@P3` is accepted without a matching parameter name as long as three values are bound.
+fn is_positional_within(name: &str, count: usize) -> bool {
+    let name = trim_at(name);
+    name.len() >= 2
+        && name.as_bytes()[0].eq_ignore_ascii_case(&b'P')
+        && name[1..].bytes().all(|b| b.is_ascii_digit())
+        && name[1..]
+            .parse::<usize>()
+            .is_ok_and(|n| n >= 1 && n <= count)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    StringLiteral,
+    QuotedIdentifier,
+    Variable,
+    SystemVariable,
+    Word,
+    Symbol,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+impl Token<'_> {
+    fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment
+        )
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')
+}
+
+fn scan_while(sql: &str, start: usize, pred: impl Fn(char) -> bool) -> usize {
+    sql[start..]
+        .char_indices()
+        .find(|&(_, c)| !pred(c))
+        .map(|(i, _)| start + i)
+        .unwrap_or(sql.len())
+}
+
+// Scan a delimited token whose opening delimiter is at `open`. A doubled closing
+// delimiter is an escape. Unterminated tokens run to the end of the input.
+fn scan_delimited(sql: &str, open: usize, close: u8) -> usize {
+    let bytes = sql.as_bytes();
+    let mut i = open + 1;
+    while i < bytes.len() {
+        if bytes[i] == close {
+            if bytes.get(i + 1) == Some(&close) {
+                i += 2;
                 continue;
             }
+            return i + 1;
         }
-        out.push(bytes[i] as char);
         i += 1;
     }
-    out
+    bytes.len()
+}
+
+// Block comments nest in T-SQL.
+fn scan_block_comment(sql: &str, start: usize) -> usize {
+    let bytes = sql.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Split T-SQL text into tokens. Concatenating the token texts reproduces the input.
+pub(crate) fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < sql.len() {
+        let start = i;
+        let c = sql[i..].chars().next().unwrap_or_default();
+        let next = bytes.get(i + 1).copied();
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i = scan_while(sql, i, char::is_whitespace);
+                TokenKind::Whitespace
+            }
+            '-' if next == Some(b'-') => {
+                i = sql[i..].find('\n').map(|n| i + n).unwrap_or(sql.len());
+                TokenKind::LineComment
+            }
+            '/' if next == Some(b'*') => {
+                i = scan_block_comment(sql, i);
+                TokenKind::BlockComment
+            }
+            '\'' => {
+                i = scan_delimited(sql, i, b'\'');
+                TokenKind::StringLiteral
+            }
+            'N' | 'n' if next == Some(b'\'') => {
+                i = scan_delimited(sql, i + 1, b'\'');
+                TokenKind::StringLiteral
+            }
+            '[' => {
+                i = scan_delimited(sql, i, b']');
+                TokenKind::QuotedIdentifier
+            }
+            '"' => {
+                i = scan_delimited(sql, i, b'"');
+                TokenKind::QuotedIdentifier
+            }
+            '@' if next == Some(b'@') => {
+                i = scan_while(sql, i + 2, is_identifier_char);
+                TokenKind::SystemVariable
+            }
+            '@' => {
+                i = scan_while(sql, i + 1, is_identifier_char);
+                if i == start + 1 {
+                    TokenKind::Symbol
+                } else {
+                    TokenKind::Variable
+                }
+            }
+            c if is_identifier_char(c) => {
+                i = scan_while(sql, i, is_identifier_char);
+                TokenKind::Word
+            }
+            c => {
+                i += c.len_utf8();
+                TokenKind::Symbol
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &sql[start..i],
+        });
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VariableRole {
+    /// A use of the variable's value.
+    Reference,
+    /// A local variable or routine parameter being declared.
+    Declaration,
+    /// The parameter name on the left of `@name = value` in an `EXEC` argument list.
+    ArgumentName,
+}
+
+// Keywords that start a new statement and therefore end a DECLARE or EXEC list.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "SET",
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "MERGE",
+    "EXEC",
+    "EXECUTE",
+    "DECLARE",
+    "IF",
+    "ELSE",
+    "WHILE",
+    "BEGIN",
+    "END",
+    "RETURN",
+    "PRINT",
+    "WITH",
+    "THROW",
+    "RAISERROR",
+    "FETCH",
+    "OPEN",
+    "CLOSE",
+    "DEALLOCATE",
+    "TRUNCATE",
+    "CREATE",
+    "ALTER",
+    "DROP",
+    "GO",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Statement {
+    Other,
+    Declare { expect_variable: bool },
+    Create,
+    Routine,
+    Exec { first: bool },
+}
+
+struct SqlAnalysis<'a> {
+    tokens: Vec<Token<'a>>,
+    roles: Vec<Option<VariableRole>>,
+    declared: HashSet<String>,
+}
+
+impl<'a> SqlAnalysis<'a> {
+    fn new(sql: &'a str) -> Self {
+        let tokens = tokenize(sql);
+        let significant: Vec<usize> = (0..tokens.len())
+            .filter(|&i| !tokens[i].is_trivia())
+            .collect();
+        let mut roles = vec![None; tokens.len()];
+        let mut declared = HashSet::new();
+        let mut statement = Statement::Other;
+        let mut depth = 0i32;
+        for (pos, &idx) in significant.iter().enumerate() {
+            let token = tokens[idx];
+            let next = significant.get(pos + 1).map(|&n| tokens[n]);
+            match token.kind {
+                TokenKind::Variable => {
+                    let role = match statement {
+                        Statement::Declare {
+                            expect_variable: true,
+                        } => {
+                            statement = Statement::Declare {
+                                expect_variable: false,
+                            };
+                            VariableRole::Declaration
+                        }
+                        Statement::Routine => VariableRole::Declaration,
+                        Statement::Exec { first: false }
+                            if depth == 0 && next.is_some_and(|n| n.is_symbol("=")) =>
+                        {
+                            VariableRole::ArgumentName
+                        }
+                        _ => VariableRole::Reference,
+                    };
+                    if role == VariableRole::Declaration {
+                        declared.insert(normalize(token.text));
+                    }
+                    roles[idx] = Some(role);
+                }
+                TokenKind::Symbol => match token.text {
+                    "(" => depth += 1,
+                    ")" => depth -= 1,
+                    ";" => {
+                        statement = Statement::Other;
+                        depth = 0;
+                    }
+                    "," if depth == 0 => {
+                        if let Statement::Declare { .. } = statement {
+                            statement = Statement::Declare {
+                                expect_variable: true,
+                            };
+                        }
+                    }
+                    _ => {}
+                },
+                TokenKind::Word => {
+                    if statement == Statement::Routine {
+                        if depth == 0 && token.is_word("AS") {
+                            statement = Statement::Other;
+                        }
+                    } else if statement == Statement::Create
+                        && ["PROCEDURE", "PROC", "FUNCTION"]
+                            .iter()
+                            .any(|w| token.is_word(w))
+                    {
+                        statement = Statement::Routine;
+                    } else if depth == 0 && STATEMENT_KEYWORDS.iter().any(|w| token.is_word(w)) {
+                        statement = if token.is_word("DECLARE") {
+                            Statement::Declare {
+                                expect_variable: true,
+                            }
+                        } else if token.is_word("EXEC") || token.is_word("EXECUTE") {
+                            Statement::Exec { first: true }
+                        } else if token.is_word("CREATE") || token.is_word("ALTER") {
+                            Statement::Create
+                        } else {
+                            Statement::Other
+                        };
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            if let Statement::Exec { first: true } = statement {
+                statement = Statement::Exec { first: false };
+            }
+        }
+        Self {
+            tokens,
+            roles,
+            declared,
+        }
+    }
+
+    fn is_declared(&self, variable: &str) -> bool {
+        self.declared.contains(&normalize(variable))
+    }
+
+    // Names (without `@`) of variables referenced but not declared in the batch.
+    fn references(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.tokens
+            .iter()
+            .zip(&self.roles)
+            .filter(|(t, r)| **r == Some(VariableRole::Reference) && !self.is_declared(t.text))
+            .map(|(t, _)| &t.text[1..])
+    }
 }
@@ -0,0 +1,344 @@
+use super::command::{tokenize, TokenKind};
+use super::*;
+use crate::dataset::DataValue;
+
+#[test]
+fn tokenizer_round_trips_input() {
+    let sql = "SELECT [a]]b], N'x''y' /* c /* nested */ */ -- tail\nFROM t WHERE @id = @@ROWCOUNT";
+    let tokens = tokenize(sql);
+    let joined: String = tokens.iter().map(|t| t.text).collect();
+    assert_eq!(joined, sql);
+    let kinds: Vec<_> = tokens
+        .iter()
+        .filter(|t| t.kind != TokenKind::Whitespace)
+        .map(|t| (t.kind, t.text))
+        .collect();
+    assert!(kinds.contains(&(TokenKind::QuotedIdentifier, "[a]]b]")));
+    assert!(kinds.contains(&(TokenKind::StringLiteral, "N'x''y'")));
+    assert!(kinds.contains(&(TokenKind::BlockComment, "/* c /* nested */ */")));
+    assert!(kinds.contains(&(TokenKind::LineComment, "-- tail")));
+    assert!(kinds.contains(&(TokenKind::Variable, "@id")));
+    assert!(kinds.contains(&(TokenKind::SystemVariable, "@@ROWCOUNT")));
+}
+
+#[test]
+fn rewrites_only_genuine_references() {
+    let cmd = Command::query(
+        "SELECT 'contact@id' AS email, [@id] AS col -- uses @id\n\
+         FROM t /* @id */ WHERE id = @id AND n = @@ROWCOUNT",
+    )
+    .with_param(Parameter::new("id", 1));
+    let (sql, params) = cmd.build();
+    assert_eq!(
+        sql,
+        "SELECT 'contact@id' AS email, [@id] AS col -- uses @id\n\
+         FROM t /* @id */ WHERE id = @P1 AND n = @@ROWCOUNT"
+    );
+    assert_eq!(params.len(), 1);
+}
+
+#[test]
+fn rewrites_repeated_and_case_insensitive_references() {
+    let cmd = Command::query("SELECT @a, @b, @A, @id2 FROM t WHERE x = @Id")
+        .with_param(Parameter::new("a", 1))
+        .with_param(Parameter::new("@b", 2))
+        .with_param(Parameter::new("id", 3))
+        .with_param(Parameter::new("id2", 4));
+    let (sql, _) = cmd.build();
+    assert_eq!(sql, "SELECT @P1, @P2, @P1, @P4 FROM t WHERE x = @P3");
+    assert!(cmd.validate().is_ok());
+}
+
+#[test]
+fn leaves_local_variables_and_exec_argument_names() {
+    let cmd = Command::query(
+        "DECLARE @total int = @seed, @t TABLE (a int, b int); \
+         EXEC dbo.sp_calc @seed = @seed, @total = @total OUTPUT",
+    )
+    .with_param(Parameter::new("seed", 10))
+    .with_param(Parameter::new("total", 0));
+    let (sql, _) = cmd.build();
+    assert_eq!(
+        sql,
+        "DECLARE @total int = @P1, @t TABLE (a int, b int); \
+         EXEC dbo.sp_calc @seed = @P1, @total = @total OUTPUT"
+    );
+    let usage = cmd.parameter_usage();
+    assert_eq!(usage.unused, vec!["total".to_string()]);
+    assert!(usage.missing.is_empty());
+}
+
+#[test]
+fn reports_unused_and_missing_parameters() {
+    let cmd = Command::query("SELECT * FROM t WHERE a = @a AND b = @b AND c = @P3")
+        .with_param(Parameter::new("a", 1))
+        .with_param(Parameter::new("z", 2));
+    let usage = cmd.parameter_usage();
+    assert_eq!(usage.unused, vec!["z".to_string()]);
+    assert_eq!(usage.missing, vec!["b".to_string(), "P3".to_string()]);
+    match cmd.validate() {
+        Err(CommandError::ParameterMismatch(u)) => {
+            assert_eq!(u.to_string(), "unused: @z; missing: @b, @P3")
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn positional_placeholders_within_range_are_supplied() {
+    let cmd = Command::query("SELECT @P1, @P2")
+        .with_param(Parameter::new("P1", 1))
+        .with_param(Parameter::new("x", 2));
+    let usage = cmd.parameter_usage();
+    assert_eq!(usage.unused, vec!["x".to_string()]);
+    assert!(usage.missing.is_empty());
+    assert_eq!(cmd.build().0, "SELECT @P1, @P2");
+}
+
+#[test]
+fn expands_small_lists_inline() {
+    let cmd = Command::query("SELECT * FROM t WHERE id IN (@ids) AND kind = @kind")
+        .with_param(Parameter::list("ids", vec![1, 2, 3]))
+        .with_param(Parameter::new("kind", "a"));
+    let (sql, values) = cmd.bind().unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id IN (@P1, @P2, @P3) AND kind = @P4"
+    );
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[2], 3);
+    assert_eq!(values[3], "a");
+    assert!(cmd.validate().is_ok());
+}
+
+#[test]
+fn sends_large_lists_as_json() {
+    let ids: Vec<i64> = (0..(Parameter::INLINE_LIST_LIMIT as i64 + 1)).collect();
+    let cmd = Command::query("SELECT * FROM t WHERE id IN (@ids)")
+        .with_param(Parameter::list("ids", ids));
+    let (sql, values) = cmd.bind().unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id IN (SELECT [value] FROM OPENJSON(@P1) WITH ([value] bigint '$'))"
+    );
+    assert_eq!(values.len(), 1);
+    match &values[0] {
+        DataValue::Text(json) => assert!(json.starts_with("[0,1,2,") && json.ends_with(",64]")),
+        other => panic!("unexpected value: {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_non_finite_floats_in_json_lists() {
+    let mut values = vec![0.5f64; Parameter::INLINE_LIST_LIMIT + 1];
+    values[3] = f64::NAN;
+    let cmd = Command::query("SELECT * FROM t WHERE x IN (@xs)")
+        .with_param(Parameter::list("@xs", values));
+    assert_eq!(
+        cmd.bind().err(),
+        Some(CommandError::NonFiniteListValue("xs".into()))
+    );
+}
+
+#[test]
+fn sends_money_lists_with_their_type() {
+    let prices = (0..=Parameter::INLINE_LIST_LIMIT)
+        .map(|i| DataValue::Money(rust_decimal::Decimal::new(i as i64 * 125, 2)));
+    let cmd = Command::query("SELECT * FROM t WHERE price IN (@prices)")
+        .with_param(Parameter::list("prices", prices));
+    let (sql, values) = cmd.bind().unwrap();
+    assert!(sql.ends_with("WITH ([value] money '$'))"));
+    match &values[0] {
+        DataValue::Text(json) => assert!(json.starts_with("[0.00,1.25,2.50,")),
+        other => panic!("unexpected value: {:?}", other),
+    }
+}
+
+#[test]
+fn converts_typed_values_on_the_server() {
+    let cmd =
+        Command::query("UPDATE t SET price = @price, tag = @tag, node = @node WHERE id IN (@ids)")
+            .with_param(Parameter::new(
+                "price",
+                DataValue::Money(rust_decimal::Decimal::new(1250, 2)),
+            ))
+            .with_param(Parameter::new(
+                "tag",
+                DataValue::Variant {
+                    base_type: "nvarchar".into(),
+                    value: Box::new(DataValue::from("abc")),
+                },
+            ))
+            .with_param(Parameter::new(
+                "node",
+                DataValue::Udt {
+                    type_name: "hierarchyid".into(),
+                    bytes: vec![0x58],
+                },
+            ))
+            .with_param(Parameter::list(
+                "ids",
+                vec![
+                    DataValue::Int(1),
+                    DataValue::Money(rust_decimal::Decimal::ONE),
+                ],
+            ));
+    let (sql, values) = cmd.bind().unwrap();
+    assert_eq!(
+        sql,
+        "UPDATE t SET price = CAST(@P1 AS money), \
+         tag = CAST(CAST(@P2 AS nvarchar(3)) AS sql_variant), \
+         node = CAST(@P3 AS [hierarchyid]) WHERE id IN (@P4, CAST(@P5 AS money))"
+    );
+    assert_eq!(values.len(), 5);
+}
+
+#[test]
+fn declares_typed_stored_procedure_arguments() {
+    let cmd = Command::stored_procedure("sp_price")
+        .with_param(Parameter::new("id", 1))
+        .with_param(Parameter::new(
+            "amount",
+            DataValue::Variant {
+                base_type: "decimal".into(),
+                value: Box::new(DataValue::Decimal(rust_decimal::Decimal::new(12345, 3))),
+            },
+        ));
+    let (sql, _) = cmd.bind().unwrap();
+    assert_eq!(
+        sql,
+        "DECLARE @__P2 sql_variant = CAST(CAST(@P2 AS decimal(38, 3)) AS sql_variant); \
+         EXEC [sp_price] @id = @P1, @amount = @__P2"
+    );
+}
+
+#[test]
+fn rejects_invalid_variant_base_types() {
+    let variant = |base_type: &str| DataValue::Variant {
+        base_type: base_type.into(),
+        value: Box::new(DataValue::Int(1)),
+    };
+    let bind = |base_type: &str| {
+        Command::query("SELECT @v")
+            .with_param(Parameter::new("v", variant(base_type)))
+            .bind()
+            .map(|(sql, _)| sql)
+    };
+    assert_eq!(
+        bind("varchar(max)").unwrap(),
+        "SELECT CAST(CAST(@P1 AS varchar(max)) AS sql_variant)"
+    );
+    assert_eq!(
+        bind("int").unwrap(),
+        "SELECT CAST(CAST(@P1 AS int) AS sql_variant)"
+    );
+    assert_eq!(
+        bind("int); DROP TABLE t; --").err(),
+        Some(CommandError::InvalidTypeName(
+            "int); DROP TABLE t; --".into()
+        ))
+    );
+}
+
+#[test]
+fn converts_marked_parameters_to_the_server_zone() {
+    use crate::dataset::ServerTimeZone;
+    use chrono::{DateTime, FixedOffset, NaiveDate};
+
+    let instant: DateTime<FixedOffset> = "2024-03-01T10:00:00Z".parse().unwrap();
+    let local = NaiveDate::from_ymd_opt(2024, 3, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    let zone = ServerTimeZone::from(FixedOffset::east_opt(2 * 3600).unwrap());
+    let cmd = Command::query("SELECT @at, @offset, @ats")
+        .with_param(Parameter::new("at", instant).with_server_time_zone())
+        .with_param(Parameter::new("offset", instant))
+        .with_param(Parameter::list("ats", vec![instant]).with_server_time_zone());
+    let (_, values) = cmd.bind_for(&zone).unwrap();
+    assert_eq!(
+        values,
+        [
+            DataValue::DateTime(local),
+            DataValue::DateTimeOffset(instant),
+            DataValue::DateTime(local)
+        ]
+    );
+    let (_, values) = cmd.bind().unwrap();
+    assert_eq!(
+        values[0],
+        DataValue::DateTime(local - chrono::Duration::hours(2))
+    );
+}
+
+#[test]
+fn stays_under_parameter_limit() {
+    let a: Vec<i32> = (0..60).collect();
+    let mut cmd = Command::query("SELECT 1");
+    for i in 0..40 {
+        cmd = cmd.with_param(Parameter::list(&format!("l{}", i), a.clone()));
+    }
+    let (_, values) = cmd.bind().unwrap();
+    assert!(values.len() <= Parameter::MAX_PARAMETERS);
+}
+
+#[test]
+fn empty_list_matches_nothing() {
+    let cmd = Command::query("SELECT * FROM t WHERE id NOT IN (@ids)")
+        .with_param(Parameter::list("ids", Vec::<i32>::new()));
+    let (sql, values) = cmd.bind().unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM t WHERE id NOT IN (SELECT NULL WHERE 1 = 0)"
+    );
+    assert!(values.is_empty());
+}
+
+#[test]
+fn stored_procedure_receives_lists_as_json() {
+    let cmd = Command::stored_procedure("sp_by_codes")
+        .with_param(Parameter::list("codes", vec!["a\"b", "c"]))
+        .with_param(Parameter::new("flag", true));
+    let (sql, values) = cmd.bind().unwrap();
+    assert_eq!(sql, "EXEC [sp_by_codes] @codes = @P1, @flag = @P2");
+    assert_eq!(values[0], r#"["a\"b","c"]"#);
+}
+
+#[test]
+fn placeholder_named_parameters_are_not_rewritten() {
+    // `@P2` refers to the second bound value, not to the parameter named "P2".
+    let cmd = Command::query("SELECT @P1, @P2")
+        .with_param(Parameter::new("P2", 2))
+        .with_param(Parameter::new("P1", 1));
+    assert_eq!(cmd.build().0, "SELECT @P1, @P2");
+}
+
+#[test]
+fn lowercase_placeholder_named_parameters_are_not_rewritten() {
+    let cmd = Command::query("SELECT @p1, @p2")
+        .with_param(Parameter::new("p2", 2))
+        .with_param(Parameter::new("p1", 1));
+    assert_eq!(cmd.build().0, "SELECT @p1, @p2");
+}
+
+#[test]
+fn build_passes_invalid_commands_through() {
+    let cmd =
+        Command::stored_procedure("sp_x; DROP TABLE t").with_param(Parameter::new("id = 1; --", 1));
+    assert!(cmd.try_build().is_err());
+    assert_eq!(cmd.build().0, "EXEC sp_x; DROP TABLE t @id = 1; -- = @P1");
+
+    let variant = DataValue::Variant {
+        base_type: "int); --".into(),
+        value: Box::new(DataValue::Int(1)),
+    };
+    let mut values = vec![0.5f64; Parameter::INLINE_LIST_LIMIT + 1];
+    values[3] = f64::INFINITY;
+    let cmd = Command::query("SELECT @v WHERE x IN (@xs)")
+        .with_param(Parameter::new("v", variant))
+        .with_param(Parameter::list("xs", values));
+    assert!(cmd.try_build().is_err());
+    let (sql, params) = cmd.build();
+    assert!(sql.starts_with("SELECT @P1 WHERE x IN (SELECT"), "{sql}");
+    assert_eq!(params.len(), 2);
+}
@@ -1,9 +1,9 @@
 use anyhow::Result;
 use async_trait::async_trait;
 
-use crate::dataset::DataSet;
+use crate::dataset::{DataCell, DataRow, DataSet, DataTable, DataValue};
 
-use super::{command::Command, query_executor::QueryExecutor};
+use super::{command::Command, parameter::parameter_declarations, query_executor::QueryExecutor};
 
 #[async_trait]
 pub trait DatasetRepository {
@@ -23,7 +23,79 @@ impl<E: QueryExecutor + Send> MssqlDatasetRepository<E> {
 #[async_trait]
 impl<E: QueryExecutor + Send> DatasetRepository for MssqlDatasetRepository<E> {
     async fn execute(&mut self, command: Command) -> Result<DataSet> {
-        let (sql, params) = command.build();
-        self.executor.query(&sql, params).await
+        fetch(&mut self.executor, command).await
     }
 }
+
+// Run a command and apply the result options it carries.
+pub(crate) async fn fetch<E: QueryExecutor + Send>(
+    executor: &mut E,
+    command: Command,
+) -> Result<DataSet> {
+    let (sql, values) = command.bind_for(&executor.time_zone())?;
+    let params = values.iter().map(DataValue::to_tiberius).collect();
+    let mut dataset = executor.query(&sql, params).await?;
+    let mut key = None;
+    if command.key_info && dataset.tables.contains_key("table0") {
+        let declarations = parameter_declarations(&values);
+        // The query has already run, so a batch the server cannot describe, such as
+        // one using temp tables or dynamic SQL, just gets no key.
+        key = executor
+            .key_columns(&sql, &declarations)
+            .await
+            .ok()
+            .flatten();
+    }
+    apply_result_options(&mut dataset, &command, key);
+    Ok(dataset)
+}
+
+// Apply the result options of `command` to its result, given the key columns the
+// server described for it.
+pub(crate) fn apply_result_options(
+    dataset: &mut DataSet,
+    command: &Command,
+    key: Option<Vec<String>>,
+) {
+    if let (true, Some(table), Some(key)) =
+        (command.key_info, dataset.tables.get_mut("table0"), key)
+    {
+        let key: Vec<&str> = key.iter().map(String::as_str).collect();
+        // A unique index may still allow a NULL key; leave such tables without one.
+        let _ = table.set_primary_key(&key);
+    }
+    if command.concatenate {
+        if let Some(table) = dataset.tables.get_mut("table0") {
+            concatenate(table);
+        }
+    }
+}
+
+// Join the first column of every row into a single row, keeping only that column.
+// NULL fragments are skipped; a table of NULLs becomes a single NULL.
+fn concatenate(table: &mut DataTable) {
+    if table.rows.len() <= 1 {
+        return;
+    }
+    let column = match table.columns.first() {
+        Some(column) => column.name.clone(),
+        None => match table.rows[0].cells.keys().next() {
+            Some(name) => name.clone(),
+            None => return,
+        },
+    };
+    let mut text: Option<String> = None;
+    for row in &table.rows {
+        let fragment = match row.get(&column) {
+            None | Some(DataValue::Null) => continue,
+            Some(DataValue::Text(s) | DataValue::Xml(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        };
+        text.get_or_insert_with(String::new).push_str(&fragment);
+    }
+    let mut row = DataRow::default();
+    let value = text.map_or(DataValue::Null, DataValue::Text);
+    row.cells.insert(column, DataCell::new(value));
+    table.columns.truncate(1);
+    table.rows = vec![row];
+}
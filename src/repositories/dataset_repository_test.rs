@@ -1,6 +1,8 @@
 use super::query_executor::QueryExecutor;
 use super::*;
-use crate::dataset::{DataSet};
+use crate::dataset::{
+    DataCell, DataColumn, DataRow, DataSet, DataTable, DataValue, ServerTimeZone,
+};
 use crate::repositories::Parameter;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -51,7 +53,7 @@ async fn test_sp_command() {
     let mut repo = MssqlDatasetRepository::new(exec);
     let cmd = Command::stored_procedure("sp_test").with_param(Parameter::new("id", 1));
     repo.execute(cmd).await.unwrap();
-    assert_eq!(*sql_ref.lock().unwrap(), "EXEC sp_test @id = @P1");
+    assert_eq!(*sql_ref.lock().unwrap(), "EXEC [sp_test] @id = @P1");
     assert_eq!(*params_ref.lock().unwrap(), 1);
 }
 
@@ -66,6 +68,148 @@ async fn test_sp_command_with_at_prefix() {
     let mut repo = MssqlDatasetRepository::new(exec);
     let cmd = Command::stored_procedure("sp_test").with_param(Parameter::new("@id", 1));
     repo.execute(cmd).await.unwrap();
-    assert_eq!(*sql_ref.lock().unwrap(), "EXEC sp_test @id = @P1");
+    assert_eq!(*sql_ref.lock().unwrap(), "EXEC [sp_test] @id = @P1");
     assert_eq!(*params_ref.lock().unwrap(), 1);
 }
+
+struct KeyedExecutor {
+    declarations: Arc<Mutex<String>>,
+}
+
+#[async_trait]
+impl QueryExecutor for KeyedExecutor {
+    async fn query(
+        &mut self,
+        _sql: &str,
+        _params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<DataSet> {
+        let mut table = DataTable::new("table0");
+        for id in [1, 2] {
+            let mut row = DataRow::default();
+            row.cells.insert("id".into(), DataCell::new(id));
+            table.rows.push(row);
+        }
+        let mut ds = DataSet::new();
+        ds.tables.insert(table.name.clone(), table);
+        Ok(ds)
+    }
+
+    async fn key_columns(&mut self, sql: &str, declarations: &str) -> Result<Option<Vec<String>>> {
+        // Like sp_describe_first_result_set, which cannot describe temp tables.
+        if sql.contains('#') {
+            anyhow::bail!("Invalid object name '#t'.");
+        }
+        *self.declarations.lock().unwrap() = declarations.to_string();
+        Ok(Some(vec!["id".into()]))
+    }
+}
+
+#[tokio::test]
+async fn test_key_info_sets_primary_key() {
+    let declarations = Arc::new(Mutex::new(String::new()));
+    let exec = KeyedExecutor {
+        declarations: declarations.clone(),
+    };
+    let mut repo = MssqlDatasetRepository::new(exec);
+    let cmd = Command::query("SELECT id FROM t WHERE id > @min")
+        .with_param(Parameter::new("min", 0))
+        .with_key_info();
+    let ds = repo.execute(cmd).await.unwrap();
+    let table = &ds.tables["table0"];
+    assert_eq!(table.primary_key(), ["id".to_string()]);
+    assert_eq!(table.find_index(&[2.into()]), Some(1));
+    assert_eq!(*declarations.lock().unwrap(), "@P1 int");
+
+    let ds = repo
+        .execute(Command::query("SELECT id FROM t"))
+        .await
+        .unwrap();
+    assert!(ds.tables["table0"].primary_key().is_empty());
+
+    let ds = repo
+        .execute(Command::query("SELECT id FROM #t").with_key_info())
+        .await
+        .unwrap();
+    assert_eq!(ds.tables["table0"].rows.len(), 2);
+    assert!(ds.tables["table0"].primary_key().is_empty());
+}
+
+struct FragmentExecutor;
+
+#[async_trait]
+impl QueryExecutor for FragmentExecutor {
+    async fn query(
+        &mut self,
+        _sql: &str,
+        _params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<DataSet> {
+        let column = "JSON_F52E2B61-18A1-11d1-B105-00805F49916B";
+        let mut table = DataTable::new("table0");
+        table.columns.push(DataColumn {
+            name: column.into(),
+            sql_type: "nvarchar".into(),
+            ..Default::default()
+        });
+        for fragment in [r#"[{"id":1},"#, r#"{"id":2}"#, "]"] {
+            let mut row = DataRow::default();
+            row.cells.insert(column.into(), DataCell::new(fragment));
+            table.rows.push(row);
+        }
+        let mut ds = DataSet::new();
+        ds.tables.insert(table.name.clone(), table);
+        Ok(ds)
+    }
+}
+
+#[tokio::test]
+async fn test_concatenated_result_joins_fragments() {
+    let mut repo = MssqlDatasetRepository::new(FragmentExecutor);
+    let cmd = Command::query("SELECT id FROM t FOR JSON PATH").with_concatenated_result();
+    let ds = repo.execute(cmd).await.unwrap();
+    let table = &ds.tables["table0"];
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table.columns.len(), 1);
+    assert_eq!(
+        table.rows[0].get(&table.columns[0].name),
+        Some(&DataValue::Text(r#"[{"id":1},{"id":2}]"#.into()))
+    );
+
+    let ds = repo.execute(Command::query("SELECT 1")).await.unwrap();
+    assert_eq!(ds.tables["table0"].rows.len(), 3);
+}
+
+struct ZonedExecutor {
+    params: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl QueryExecutor for ZonedExecutor {
+    async fn query(
+        &mut self,
+        _sql: &str,
+        params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>,
+    ) -> Result<DataSet> {
+        *self.params.lock().unwrap() = params.iter().map(|p| format!("{:?}", p.to_sql())).collect();
+        Ok(DataSet::new())
+    }
+
+    fn time_zone(&self) -> ServerTimeZone {
+        chrono::FixedOffset::west_opt(5 * 3600).unwrap().into()
+    }
+}
+
+#[tokio::test]
+async fn test_binds_marked_parameters_in_the_executor_zone() {
+    let params = Arc::new(Mutex::new(Vec::new()));
+    let mut repo = MssqlDatasetRepository::new(ZonedExecutor {
+        params: params.clone(),
+    });
+    let at: chrono::DateTime<chrono::FixedOffset> = "2024-03-01T10:00:00Z".parse().unwrap();
+    let cmd = Command::query("INSERT INTO audit (at, at_offset) VALUES (@at, @at_offset)")
+        .with_param(Parameter::new("at", at).with_server_time_zone())
+        .with_param(Parameter::new("at_offset", at));
+    repo.execute(cmd).await.unwrap();
+    let params = params.lock().unwrap();
+    assert!(params[0].starts_with("DateTime2("), "{}", params[0]);
+    assert!(params[1].starts_with("DateTimeOffset("), "{}", params[1]);
+}
@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+use super::command::ParameterUsage;
+
+/// Errors raised while preparing a [`Command`](super::Command) for execution.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CommandError {
+    #[error("parameter mismatch ({0})")]
+    ParameterMismatch(ParameterUsage),
+    #[error("invalid identifier {name:?}: {reason}")]
+    InvalidIdentifier { name: String, reason: &'static str },
+    #[error("invalid parameter name {0:?}")]
+    InvalidParameterName(String),
+    #[error("list parameter {0:?} holds an infinite or NaN float")]
+    NonFiniteListValue(String),
+    #[error("invalid type name {0:?}")]
+    InvalidTypeName(String),
+    #[error("invalid query: {0}")]
+    InvalidQuery(&'static str),
+    #[error("query template has {expected} placeholders but {found} identifiers were supplied")]
+    PlaceholderMismatch { expected: usize, found: usize },
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+
+use crate::dataset::{DataRow, DataSet, DataValue};
+use crate::infrastructure::mssql::{SqlConnection, Transaction};
+
+use super::command::Command;
+use super::dataset_repository::fetch;
+
+/// Something that runs [`Command`]s: a [`SqlConnection`], a [`Transaction`], or a
+/// fake in tests. Application code generic over `Executor` runs unchanged against
+/// each of them.
+///
+/// Mutable references and boxes of executors are executors too, so a connection
+/// borrowed from a pool can be passed as `&mut *pooled`, and `Box<dyn Executor>`
+/// works where the kind of executor is chosen at run time.
+#[async_trait]
+pub trait Executor: Send {
+    /// Run a command and collect every result set, applying
+    /// [`Command::with_key_info`] and [`Command::with_concatenated_result`].
+    async fn query(&mut self, command: Command) -> Result<DataSet>;
+
+    /// Run a command and return the total number of rows it affected.
+    async fn non_query(&mut self, command: Command) -> Result<u64>;
+
+    /// Run a command and return the first column of its first row, or `None` when it
+    /// returns no rows.
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>>;
+
+    /// Run a command and stream the rows of every result set as they arrive.
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>>;
+}
+
+#[async_trait]
+impl Executor for SqlConnection {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        fetch(self, command).await
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        let (sql, params) = command.try_build_for(self.time_zone())?;
+        self.execute_non_query(&sql, params).await
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        let (sql, params) = command.try_build_for(self.time_zone())?;
+        self.execute_scalar(&sql, params).await
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        let (sql, params) = command.try_build_for(self.time_zone())?;
+        self.execute_stream(&sql, params).await
+    }
+}
+
+#[async_trait]
+impl Executor for Transaction<'_> {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        self.connection().query(command).await
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        self.connection().non_query(command).await
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        self.connection().scalar(command).await
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        self.connection().stream(command).await
+    }
+}
+
+#[async_trait]
+impl<E: Executor + ?Sized> Executor for &mut E {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        (**self).query(command).await
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        (**self).non_query(command).await
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        (**self).scalar(command).await
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        (**self).stream(command).await
+    }
+}
+
+#[async_trait]
+impl<E: Executor + ?Sized> Executor for Box<E> {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        (**self).query(command).await
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        (**self).non_query(command).await
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        (**self).scalar(command).await
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        (**self).stream(command).await
+    }
+}
@@ -0,0 +1,85 @@
+use super::*;
+use crate::dataset::{DataCell, DataRow, DataSet, DataValue};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+#[derive(Default)]
+struct RecordingExecutor {
+    calls: Vec<String>,
+}
+
+#[async_trait]
+impl Executor for RecordingExecutor {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        self.calls.push(format!("query {}", command.text));
+        Ok(DataSet::new())
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        self.calls.push(format!("non_query {}", command.text));
+        Ok(2)
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        self.calls.push(format!("scalar {}", command.text));
+        Ok(Some(DataValue::Int(5)))
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        self.calls.push(format!("stream {}", command.text));
+        let rows = (1..=3).map(|id| {
+            let mut row = DataRow::default();
+            row.cells.insert("id".into(), DataCell::new(id));
+            Ok(row)
+        });
+        Ok(stream::iter(rows).boxed())
+    }
+}
+
+// Application code written against the trait.
+async fn archive<E: Executor>(executor: &mut E) -> Result<(u64, Option<DataValue>)> {
+    let moved = executor
+        .non_query(Command::query(
+            "DELETE FROM orders OUTPUT deleted.* INTO archive",
+        ))
+        .await?;
+    let left = executor
+        .scalar(Command::query("SELECT COUNT(*) FROM orders"))
+        .await?;
+    Ok((moved, left))
+}
+
+#[tokio::test]
+async fn generic_code_runs_against_any_executor() {
+    let mut executor = RecordingExecutor::default();
+    let result = archive(&mut executor).await.unwrap();
+    assert_eq!(result, (2, Some(DataValue::Int(5))));
+    assert_eq!(
+        executor.calls,
+        [
+            "non_query DELETE FROM orders OUTPUT deleted.* INTO archive",
+            "scalar SELECT COUNT(*) FROM orders"
+        ]
+    );
+
+    // Through a mutable reference and a boxed trait object.
+    archive(&mut &mut executor).await.unwrap();
+    let mut boxed: Box<dyn Executor> = Box::new(RecordingExecutor::default());
+    archive(&mut boxed).await.unwrap();
+    boxed.query(Command::query("SELECT 1")).await.unwrap();
+}
+
+#[tokio::test]
+async fn streams_rows() {
+    let mut executor = RecordingExecutor::default();
+    let rows: Vec<DataRow> = executor
+        .stream(Command::query("SELECT id FROM t"))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2].get("id"), Some(&DataValue::Int(3)));
+}
@@ -0,0 +1,373 @@
+use std::fmt;
+
+use super::error::CommandError;
+
+/// A SQL Server object name of up to four parts (`server.database.schema.object`).
+///
+/// Identifiers are the only way to splice object names into SQL text: every part is
+/// wrapped in brackets with `]` doubled, exactly like T-SQL's `QUOTENAME`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identifier {
+    parts: Vec<String>,
+}
+
+impl Identifier {
+    /// Longest name SQL Server accepts for a single part (`sysname`).
+    pub const MAX_PART_LEN: usize = 128;
+    /// Most parts an object name may have (`server.database.schema.object`).
+    pub const MAX_PARTS: usize = 4;
+
+    /// A single-part name taken literally. Any character is allowed; dots are part of
+    /// the name rather than separators.
+    pub fn new(name: &str) -> Result<Self, CommandError> {
+        Self::from_parts([name])
+    }
+
+    /// Build a multi-part name from unquoted parts. Leading parts may be empty to
+    /// select the default (e.g. `db..table`); the last part may not.
+    pub fn from_parts<I, S>(parts: I) -> Result<Self, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let parts: Vec<String> = parts.into_iter().map(Into::into).collect();
+        let display = parts.join(".");
+        if parts.is_empty() || parts.len() > Self::MAX_PARTS {
+            return Err(invalid(&display, "expected between one and four parts"));
+        }
+        if parts.last().is_some_and(|p| p.is_empty()) {
+            return Err(invalid(&display, "object name is empty"));
+        }
+        if parts.iter().any(|p| p.chars().count() > Self::MAX_PART_LEN) {
+            return Err(invalid(&display, "part is longer than 128 characters"));
+        }
+        if parts.iter().any(|p| p.contains('\0')) {
+            return Err(invalid(&display, "part contains a NUL character"));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Parse a possibly multi-part name such as `dbo.Orders`, `[my db].dbo.[Order Lines]`
+    /// or `"schema"."table"`. Unquoted parts must be regular identifiers.
+    pub fn parse(name: &str) -> Result<Self, CommandError> {
+        let mut parts = Vec::new();
+        let mut rest = name.trim();
+        loop {
+            let (part, tail) = match rest.chars().next() {
+                Some(open @ ('[' | '"')) => {
+                    let close = if open == '[' { ']' } else { '"' };
+                    parse_delimited(rest, close)
+                        .ok_or_else(|| invalid(name, "unterminated quoted part"))?
+                }
+                _ => {
+                    let end = rest.find('.').unwrap_or(rest.len());
+                    let part = rest[..end].trim();
+                    if !part.is_empty() && !is_regular(part) {
+                        return Err(invalid(name, "unquoted part is not a regular identifier"));
+                    }
+                    (part.to_string(), &rest[end..])
+                }
+            };
+            parts.push(part);
+            let tail = tail.trim_start();
+            match tail.strip_prefix('.') {
+                Some(next) => rest = next.trim_start(),
+                None if tail.is_empty() => break,
+                None => return Err(invalid(name, "unexpected characters after quoted part")),
+            }
+        }
+        Self::from_parts(parts)
+    }
+
+    pub fn parts(&self) -> &[String] {
+        &self.parts
+    }
+
+    /// The last part, i.e. the object name itself.
+    pub fn name(&self) -> &str {
+        self.parts.last().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Every part bracketed, matching `QUOTENAME` applied part by part.
+    pub fn quoted(&self) -> String {
+        self.parts
+            .iter()
+            .map(|p| {
+                if p.is_empty() {
+                    String::new()
+                } else {
+                    quote_name(p)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// Renders [`Identifier::quoted`], so the text is always safe to splice into SQL.
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.quoted())
+    }
+}
+
+impl std::str::FromStr for Identifier {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Bracket a single name part, doubling any closing bracket (`QUOTENAME(name)`).
+pub fn quote_name(part: &str) -> String {
+    format!("[{}]", part.replace(']', "]]"))
+}
+
+/// Check that `name` (with or without a leading `@`) is a valid T-SQL variable name.
+pub fn validate_parameter_name(name: &str) -> Result<(), CommandError> {
+    let bare = name.strip_prefix('@').unwrap_or(name);
+    let mut chars = bare.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$'))
+        }
+        _ => false,
+    };
+    if valid && bare.chars().count() < Identifier::MAX_PART_LEN {
+        Ok(())
+    } else {
+        Err(CommandError::InvalidParameterName(name.to_string()))
+    }
+}
+
+fn invalid(name: &str, reason: &'static str) -> CommandError {
+    CommandError::InvalidIdentifier {
+        name: name.to_string(),
+        reason,
+    }
+}
+
+// Parse `[part]` or `"part"` at the start of `input`, returning the unescaped part and
+// the remaining input.
+fn parse_delimited(input: &str, close: char) -> Option<(String, &str)> {
+    let mut part = String::new();
+    let mut chars = input.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == close {
+            if chars.peek().is_some_and(|&(_, n)| n == close) {
+                chars.next();
+                part.push(close);
+                continue;
+            }
+            return Some((part, &input[i + c.len_utf8()..]));
+        }
+        part.push(c);
+    }
+    None
+}
+
+// A regular identifier can be used without delimiters: it starts with a letter, `_` or
+// `#`, continues with letters, digits, `_`, `@`, `#` or `$`, and is not reserved.
+pub(crate) fn is_regular(part: &str) -> bool {
+    let mut chars = part.chars();
+    let starts_ok = matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '#');
+    starts_ok
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$'))
+        && !is_reserved(part)
+}
+
+fn is_reserved(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(word))
+}
+
+// Transact-SQL reserved keywords, which must be delimited when used as names.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "AUTHORIZATION",
+    "BACKUP",
+    "BEGIN",
+    "BETWEEN",
+    "BREAK",
+    "BROWSE",
+    "BULK",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CHECK",
+    "CHECKPOINT",
+    "CLOSE",
+    "CLUSTERED",
+    "COALESCE",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "COMPUTE",
+    "CONSTRAINT",
+    "CONTAINS",
+    "CONTAINSTABLE",
+    "CONTINUE",
+    "CONVERT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "CURSOR",
+    "DATABASE",
+    "DBCC",
+    "DEALLOCATE",
+    "DECLARE",
+    "DEFAULT",
+    "DELETE",
+    "DENY",
+    "DESC",
+    "DISK",
+    "DISTINCT",
+    "DISTRIBUTED",
+    "DOUBLE",
+    "DROP",
+    "DUMP",
+    "ELSE",
+    "END",
+    "ERRLVL",
+    "ESCAPE",
+    "EXCEPT",
+    "EXEC",
+    "EXECUTE",
+    "EXISTS",
+    "EXIT",
+    "EXTERNAL",
+    "FETCH",
+    "FILE",
+    "FILLFACTOR",
+    "FOR",
+    "FOREIGN",
+    "FREETEXT",
+    "FREETEXTTABLE",
+    "FROM",
+    "FULL",
+    "FUNCTION",
+    "GOTO",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "HOLDLOCK",
+    "IDENTITY",
+    "IDENTITY_INSERT",
+    "IDENTITYCOL",
+    "IF",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "KILL",
+    "LEFT",
+    "LIKE",
+    "LINENO",
+    "LOAD",
+    "MERGE",
+    "NATIONAL",
+    "NOCHECK",
+    "NONCLUSTERED",
+    "NOT",
+    "NULL",
+    "NULLIF",
+    "OF",
+    "OFF",
+    "OFFSETS",
+    "ON",
+    "OPEN",
+    "OPENDATASOURCE",
+    "OPENQUERY",
+    "OPENROWSET",
+    "OPENXML",
+    "OPTION",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PERCENT",
+    "PIVOT",
+    "PLAN",
+    "PRECISION",
+    "PRIMARY",
+    "PRINT",
+    "PROC",
+    "PROCEDURE",
+    "PUBLIC",
+    "RAISERROR",
+    "READ",
+    "READTEXT",
+    "RECONFIGURE",
+    "REFERENCES",
+    "REPLICATION",
+    "RESTORE",
+    "RESTRICT",
+    "RETURN",
+    "REVERT",
+    "REVOKE",
+    "RIGHT",
+    "ROLLBACK",
+    "ROWCOUNT",
+    "ROWGUIDCOL",
+    "RULE",
+    "SAVE",
+    "SCHEMA",
+    "SECURITYAUDIT",
+    "SELECT",
+    "SEMANTICKEYPHRASETABLE",
+    "SEMANTICSIMILARITYDETAILSTABLE",
+    "SEMANTICSIMILARITYTABLE",
+    "SESSION_USER",
+    "SET",
+    "SETUSER",
+    "SHUTDOWN",
+    "SOME",
+    "STATISTICS",
+    "SYSTEM_USER",
+    "TABLE",
+    "TABLESAMPLE",
+    "TEXTSIZE",
+    "THEN",
+    "TO",
+    "TOP",
+    "TRAN",
+    "TRANSACTION",
+    "TRIGGER",
+    "TRUNCATE",
+    "TRY_CONVERT",
+    "TSEQUAL",
+    "UNION",
+    "UNIQUE",
+    "UNPIVOT",
+    "UPDATE",
+    "UPDATETEXT",
+    "USE",
+    "USER",
+    "VALUES",
+    "VARYING",
+    "VIEW",
+    "WAITFOR",
+    "WHEN",
+    "WHERE",
+    "WHILE",
+    "WITH",
+    "WITHIN",
+    "WRITETEXT",
+];
@@ -0,0 +1,82 @@
+use super::*;
+
+#[test]
+fn parses_multi_part_names() {
+    let id = Identifier::parse("[my server].db..[Order Lines]").unwrap();
+    assert_eq!(id.parts(), &["my server", "db", "", "Order Lines"]);
+    assert_eq!(id.name(), "Order Lines");
+    assert_eq!(id.to_string(), "[my server].[db]..[Order Lines]");
+    assert_eq!(id.quoted(), "[my server].[db]..[Order Lines]");
+
+    let id: Identifier = "\"dbo\" . Orders".parse().unwrap();
+    assert_eq!(id.to_string(), "[dbo].[Orders]");
+}
+
+#[test]
+fn quotes_like_quotename() {
+    assert_eq!(quote_name("a]b"), "[a]]b]");
+    let id = Identifier::new("x]; DROP TABLE t; --").unwrap();
+    assert_eq!(id.to_string(), "[x]]; DROP TABLE t; --]");
+    assert_eq!(Identifier::parse("[a]]b]").unwrap().name(), "a]b");
+    assert_eq!(Identifier::new("select").unwrap().to_string(), "[select]");
+}
+
+#[test]
+fn rejects_invalid_names() {
+    assert!(Identifier::parse("sp_x; DROP TABLE t").is_err());
+    assert!(Identifier::parse("a.b.c.d.e").is_err());
+    assert!(Identifier::parse("dbo.").is_err());
+    assert!(Identifier::parse("[unterminated").is_err());
+    assert!(Identifier::new(&"x".repeat(129)).is_err());
+}
+
+#[test]
+fn validates_parameter_names() {
+    assert!(validate_parameter_name("@id").is_ok());
+    assert!(validate_parameter_name("order_id2").is_ok());
+    assert!(validate_parameter_name("id = 1; DROP TABLE t").is_err());
+    assert!(validate_parameter_name("1id").is_err());
+    assert!(validate_parameter_name("@").is_err());
+}
+
+#[test]
+fn stored_procedure_names_are_quoted() {
+    let (sql, _) = Command::stored_procedure("dbo.[my proc]")
+        .with_param(Parameter::new("id", 1))
+        .build();
+    assert_eq!(sql, "EXEC [dbo].[my proc] @id = @P1");
+    assert!(Command::stored_procedure("sp_x; DROP TABLE t")
+        .try_build()
+        .is_err());
+    let err = Command::stored_procedure("sp_x")
+        .with_param(Parameter::new("id = 1; --", 1))
+        .try_build()
+        .err();
+    assert_eq!(
+        err,
+        Some(CommandError::InvalidParameterName("id = 1; --".into()))
+    );
+}
+
+#[test]
+fn query_fmt_only_splices_identifiers() {
+    let table = Identifier::parse("dbo.[Order Lines]").unwrap();
+    let column = Identifier::new("Status").unwrap();
+    let cmd = Command::query_fmt(
+        "SELECT {} FROM {} WHERE note = '{}' AND id = @id",
+        &[column, table],
+    )
+    .unwrap()
+    .with_param(Parameter::new("id", 1));
+    assert_eq!(
+        cmd.build().0,
+        "SELECT [Status] FROM [dbo].[Order Lines] WHERE note = '{}' AND id = @P1"
+    );
+    assert_eq!(
+        Command::query_fmt("SELECT * FROM {}", &[]).err(),
+        Some(CommandError::PlaceholderMismatch {
+            expected: 1,
+            found: 0
+        })
+    );
+}
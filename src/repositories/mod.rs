@@ -1,11 +1,27 @@
 mod command;
+mod error;
+mod executor;
+mod identifier;
 mod parameter;
 mod query_executor;
 mod dataset_repository;
 
-pub use command::{Command, CommandType};
-pub use parameter::Parameter;
+pub use command::{Command, CommandType, ParameterUsage};
+pub use error::CommandError;
+pub use executor::Executor;
+pub use identifier::{quote_name, validate_parameter_name, Identifier};
+pub use parameter::{Parameter, ParameterValue};
+#[cfg(feature = "testing")]
+pub(crate) use dataset_repository::apply_result_options;
+#[cfg(feature = "testing")]
+pub(crate) use parameter::list_to_json;
 pub(crate) use dataset_repository::{DatasetRepository, MssqlDatasetRepository};
 
+#[cfg(test)]
+mod command_test;
 #[cfg(test)]
 mod dataset_repository_test;
+#[cfg(test)]
+mod executor_test;
+#[cfg(test)]
+mod identifier_test;
@@ -1,18 +1,227 @@
-use crate::dataset::DataValue;
+use base64::Engine;
+use chrono::Timelike;
+
+use crate::dataset::{DataValue, ServerTimeZone};
 
 pub struct Parameter {
     pub name: String,
-    pub value: DataValue,
+    pub value: ParameterValue,
+    /// Send `DateTimeOffset` values as wall-clock time in the server's time zone. See
+    /// [`Parameter::with_server_time_zone`].
+    pub server_time_zone: bool,
+}
+
+/// Value of a [`Parameter`]: a single value, or the elements of a list parameter that
+/// expands into one placeholder per element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterValue {
+    Scalar(DataValue),
+    List(Vec<DataValue>),
+}
+
+impl ParameterValue {
+    // The value with every `DateTimeOffset` converted to wall-clock time in `zone`.
+    pub(crate) fn to_server_local(&self, zone: &ServerTimeZone) -> ParameterValue {
+        match self {
+            ParameterValue::Scalar(value) => ParameterValue::Scalar(value.to_server_local(zone)),
+            ParameterValue::List(list) => {
+                ParameterValue::List(list.iter().map(|v| v.to_server_local(zone)).collect())
+            }
+        }
+    }
 }
 
 impl Parameter {
+    /// Lists longer than this are sent as a single JSON array and unpacked with `OPENJSON`.
+    pub const INLINE_LIST_LIMIT: usize = 64;
+    /// Maximum number of parameters SQL Server accepts in a single request.
+    pub const MAX_PARAMETERS: usize = 2100;
+
     pub fn new<T>(name: &str, value: T) -> Self
     where
         T: Into<DataValue>,
     {
         Self {
             name: name.into(),
-            value: value.into(),
+            value: ParameterValue::Scalar(value.into()),
+            server_time_zone: false,
+        }
+    }
+
+    /// Create a list parameter for `IN (@name)` predicates.
+    ///
+    /// In a text command `@name` expands to `@P1, @P2, ...` for lists of up to
+    /// [`INLINE_LIST_LIMIT`](Self::INLINE_LIST_LIMIT) elements, and to
+    /// `SELECT [value] FROM OPENJSON(@Pn) WITH (...)` for longer lists or when inline
+    /// expansion would exceed [`MAX_PARAMETERS`](Self::MAX_PARAMETERS). An empty list
+    /// expands to a subquery with no rows, so `IN` matches nothing and `NOT IN` matches
+    /// everything. Stored procedures always receive the list as a JSON array.
+    pub fn list<T, I>(name: &str, values: I) -> Self
+    where
+        T: Into<DataValue>,
+        I: IntoIterator<Item = T>,
+    {
+        Self {
+            name: name.into(),
+            value: ParameterValue::List(values.into_iter().map(Into::into).collect()),
+            server_time_zone: false,
+        }
+    }
+
+    /// Create a parameter holding `value` serialized as JSON text, for `OPENJSON`,
+    /// `JSON_VALUE` and JSON columns.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(
+        name: &str,
+        value: &T,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(Self::new(name, serde_json::to_string(value)?))
+    }
+
+    /// Bind `DateTimeOffset` values as the wall-clock time of that instant in the
+    /// connection's [`ServerTimeZone`], for `datetime` and `datetime2` columns, which
+    /// would otherwise store the value's own wall-clock time and drop its offset.
+    /// Leave `datetimeoffset` parameters unmarked.
+    pub fn with_server_time_zone(mut self) -> Self {
+        self.server_time_zone = true;
+        self
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self.value, ParameterValue::List(_))
+    }
+}
+
+// SQL type used in the `OPENJSON ... WITH` clause for the elements of a list.
+pub(crate) fn list_element_type(values: &[DataValue]) -> String {
+    let first = values.iter().find(|v| !v.is_null());
+    match first {
+        Some(DataValue::TinyInt(_)) => "tinyint".into(),
+        Some(DataValue::SmallInt(_)) => "smallint".into(),
+        Some(DataValue::Int(_)) => "int".into(),
+        Some(DataValue::BigInt(_)) => "bigint".into(),
+        Some(DataValue::Real(_)) => "real".into(),
+        Some(DataValue::Float(_)) => "float".into(),
+        Some(DataValue::Money(_)) => "money".into(),
+        Some(DataValue::Xml(_)) => "xml".into(),
+        // Parameters carry the base value and UDTs travel as varbinary.
+        Some(DataValue::Variant { value, .. }) => list_element_type(std::slice::from_ref(value)),
+        Some(DataValue::Udt { .. } | DataValue::Spatial(_) | DataValue::HierarchyId(_)) => {
+            "varbinary(max)".into()
+        }
+        Some(DataValue::Decimal(_) | DataValue::Numeric(_)) => {
+            let scale = values
+                .iter()
+                .filter_map(|v| match v {
+                    DataValue::Decimal(d) => Some(d.scale()),
+                    DataValue::Numeric(n) => Some(n.scale() as u32),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0);
+            format!("decimal(38, {})", scale)
+        }
+        Some(DataValue::Bool(_)) => "bit".into(),
+        Some(DataValue::Binary(_)) => "varbinary(max)".into(),
+        Some(DataValue::Guid(_)) => "uniqueidentifier".into(),
+        Some(DataValue::Date(_)) => "date".into(),
+        Some(DataValue::Time(_)) => "time(7)".into(),
+        Some(DataValue::DateTime(_)) => "datetime2(7)".into(),
+        Some(DataValue::DateTimeOffset(_)) => "datetimeoffset(7)".into(),
+        Some(DataValue::Text(_)) | Some(DataValue::Null) | None => "nvarchar(max)".into(),
+    }
+}
+
+// Declarations of the positional parameters of a batch (`@P1 int, @P2 nvarchar(max)`),
+// as `sp_describe_first_result_set` expects them.
+pub(crate) fn parameter_declarations(values: &[DataValue]) -> String {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("@P{} {}", i + 1, list_element_type(std::slice::from_ref(v))))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Encode list values as a JSON array in the formats `OPENJSON` converts back to SQL types,
+// or `None` when a float is infinite or NaN, which JSON cannot represent.
+pub(crate) fn list_to_json(values: &[DataValue]) -> Option<String> {
+    let items: Vec<String> = values
+        .iter()
+        .map(|v| json_value(v, false))
+        .collect::<Option<_>>()?;
+    Some(format!("[{}]", items.join(",")))
+}
+
+// Like `list_to_json`, but infinite and NaN floats become `null`.
+pub(crate) fn list_to_json_lossy(values: &[DataValue]) -> String {
+    let items: Vec<String> = values
+        .iter()
+        .map(|v| json_value(v, true).unwrap_or_else(|| "null".into()))
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+fn json_value(value: &DataValue, lossy: bool) -> Option<String> {
+    Some(match value {
+        DataValue::TinyInt(n) => n.to_string(),
+        DataValue::SmallInt(n) => n.to_string(),
+        DataValue::Int(n) => n.to_string(),
+        DataValue::BigInt(n) => n.to_string(),
+        DataValue::Real(f) if f.is_finite() => f.to_string(),
+        DataValue::Float(f) if f.is_finite() => f.to_string(),
+        DataValue::Real(_) | DataValue::Float(_) if lossy => "null".into(),
+        DataValue::Real(_) | DataValue::Float(_) => return None,
+        DataValue::Null => "null".into(),
+        DataValue::Decimal(d) | DataValue::Money(d) => d.to_string(),
+        DataValue::Numeric(n) => n.to_string(),
+        DataValue::Bool(b) => b.to_string(),
+        DataValue::Text(s) | DataValue::Xml(s) => json_string(s),
+        DataValue::Variant { value, .. } => json_value(value, lossy)?,
+        DataValue::Binary(b) | DataValue::Udt { bytes: b, .. } => {
+            json_string(&base64::engine::general_purpose::STANDARD.encode(b))
+        }
+        DataValue::Spatial(s) => {
+            json_string(&base64::engine::general_purpose::STANDARD.encode(s.to_bytes()))
+        }
+        DataValue::HierarchyId(h) => {
+            json_string(&base64::engine::general_purpose::STANDARD.encode(h.to_bytes()))
+        }
+        DataValue::Guid(g) => json_string(&g.to_string()),
+        DataValue::Date(d) => json_string(&d.format("%Y-%m-%d").to_string()),
+        DataValue::Time(t) => json_string(&format!(
+            "{}.{:07}",
+            t.format("%H:%M:%S"),
+            t.nanosecond() % 1_000_000_000 / 100
+        )),
+        DataValue::DateTime(dt) => json_string(&format!(
+            "{}.{:07}",
+            dt.format("%Y-%m-%dT%H:%M:%S"),
+            dt.nanosecond() % 1_000_000_000 / 100
+        )),
+        DataValue::DateTimeOffset(dt) => json_string(&format!(
+            "{}.{:07}{}",
+            dt.format("%Y-%m-%dT%H:%M:%S"),
+            dt.nanosecond() % 1_000_000_000 / 100,
+            dt.format("%:z")
+        )),
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
         }
     }
+    out.push('"');
+    out
 }
@@ -1,13 +1,30 @@
 use anyhow::Result;
 use async_trait::async_trait;
 
-use crate::dataset::DataSet;
+use crate::dataset::{DataSet, ServerTimeZone};
 
 use crate::infrastructure::mssql::SqlConnection;
 
 #[async_trait]
 pub trait QueryExecutor {
     async fn query(&mut self, sql: &str, params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>) -> Result<DataSet>;
+
+    /// Primary key columns of the first result set of `sql`, when they can be
+    /// determined. `declarations` declares its positional parameters.
+    async fn key_columns(
+        &mut self,
+        _sql: &str,
+        _declarations: &str,
+    ) -> Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// Time zone that parameters marked with
+    /// [`Parameter::with_server_time_zone`](super::Parameter::with_server_time_zone)
+    /// are converted to.
+    fn time_zone(&self) -> ServerTimeZone {
+        ServerTimeZone::default()
+    }
 }
 
 #[async_trait]
@@ -15,4 +32,12 @@ impl QueryExecutor for SqlConnection {
     async fn query(&mut self, sql: &str, params: Vec<Box<dyn tiberius::ToSql + Send + Sync>>) -> Result<DataSet> {
         self.execute(sql, params).await
     }
+
+    async fn key_columns(&mut self, sql: &str, declarations: &str) -> Result<Option<Vec<String>>> {
+        SqlConnection::key_columns(self, sql, declarations).await
+    }
+
+    fn time_zone(&self) -> ServerTimeZone {
+        *SqlConnection::time_zone(self)
+    }
 }
@@ -5,4 +5,5 @@ pub use service::Service;
 pub use dataset_service::DatasetService;
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod dataset_service_test;
@@ -0,0 +1,463 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+
+use crate::dataset::{DataCell, DataColumn, DataRow, DataSet, DataTable, DataValue};
+use crate::repositories::{apply_result_options, list_to_json};
+use crate::{Command, Executor, ParameterValue};
+
+/// The [`Executor`] method a command was run through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Query,
+    NonQuery,
+    Scalar,
+    Stream,
+}
+
+/// A command received by a [`FakeExecutor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub operation: Operation,
+    /// Command text as written; the procedure name for stored procedures.
+    pub text: String,
+    /// Parameters by name without the `@`. List parameters hold their JSON array text.
+    pub params: Vec<(String, DataValue)>,
+}
+
+impl Call {
+    fn new(operation: Operation, command: &Command) -> Self {
+        let params = command
+            .parameters
+            .iter()
+            .map(|p| {
+                let value = match &p.value {
+                    ParameterValue::Scalar(value) => value.clone(),
+                    ParameterValue::List(values) => {
+                        list_to_json(values).map_or(DataValue::Null, DataValue::Text)
+                    }
+                };
+                (bare(&p.name).to_string(), value)
+            })
+            .collect();
+        Self {
+            operation,
+            text: command.text.clone(),
+            params,
+        }
+    }
+
+    fn param(&self, name: &str) -> Option<&DataValue> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(bare(name)))
+            .map(|(_, v)| v)
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} `{}`", self.operation, self.text)?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { " with" } else { "," };
+            write!(f, "{} @{} = {:?}", separator, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+enum TextMatch {
+    Exact(String),
+    Contains(String),
+    Predicate(String, Box<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+/// A command a [`FakeExecutor`] expects, how often, and what it answers.
+///
+/// An expectation matches once by default and answers with an empty result: no tables,
+/// no rows affected and no scalar.
+pub struct Expectation {
+    text: TextMatch,
+    operation: Option<Operation>,
+    params: Vec<(String, DataValue)>,
+    // Whether parameters beyond `params` are rejected.
+    exact_params: bool,
+    times: usize,
+    used: usize,
+    dataset: DataSet,
+    key: Option<Vec<String>>,
+    rows_affected: u64,
+    error: Option<String>,
+}
+
+impl Expectation {
+    /// Expect `command` itself: the same text, ignoring differences in whitespace, and
+    /// exactly the same parameters.
+    pub fn command(command: &Command) -> Self {
+        let mut expectation = Self::new(TextMatch::Exact(command.text.clone()));
+        expectation.params = Call::new(Operation::Query, command).params;
+        expectation.exact_params = true;
+        expectation
+    }
+
+    /// Expect this text, ignoring differences in whitespace.
+    pub fn sql(text: &str) -> Self {
+        Self::new(TextMatch::Exact(text.into()))
+    }
+
+    pub fn sql_containing(fragment: &str) -> Self {
+        Self::new(TextMatch::Contains(fragment.into()))
+    }
+
+    /// Expect text accepted by `predicate`, such as a compiled regex's `is_match`.
+    /// `description` names the pattern in failure reports.
+    pub fn sql_matching<F>(description: &str, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self::new(TextMatch::Predicate(
+            description.into(),
+            Box::new(predicate),
+        ))
+    }
+
+    fn new(text: TextMatch) -> Self {
+        Self {
+            text,
+            operation: None,
+            params: Vec::new(),
+            exact_params: false,
+            times: 1,
+            used: 0,
+            dataset: DataSet::new(),
+            key: None,
+            rows_affected: 0,
+            error: None,
+        }
+    }
+
+    /// Require a parameter with this value; other parameters are still accepted.
+    pub fn with_param<T: Into<DataValue>>(mut self, name: &str, value: T) -> Self {
+        self.params.push((bare(name).to_string(), value.into()));
+        self
+    }
+
+    /// Only match calls made through `operation`, such as [`Operation::Scalar`].
+    /// Calls through any [`Executor`] method match by default.
+    pub fn with_operation(mut self, operation: Operation) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Expect exactly `n` matching calls instead of one.
+    pub fn times(mut self, n: usize) -> Self {
+        self.times = n;
+        self
+    }
+
+    /// Answer with `dataset`. `scalar` answers with its first value and `stream` with
+    /// the rows of its tables in result order.
+    pub fn returns(mut self, dataset: DataSet) -> Self {
+        self.dataset = dataset;
+        self
+    }
+
+    /// Answer with `table` as the only result set.
+    pub fn returns_table(mut self, mut table: DataTable) -> Self {
+        table.name = "table0".into();
+        self.dataset = DataSet::new();
+        self.dataset.tables.insert(table.name.clone(), table);
+        self
+    }
+
+    /// Answer with a single unnamed column holding `value`, as `SELECT COUNT(*)`
+    /// returns.
+    pub fn returns_scalar<T: Into<DataValue>>(self, value: T) -> Self {
+        let mut table = DataTable::new("table0");
+        table.columns.push(DataColumn::default());
+        let mut row = DataRow::default();
+        row.cells.insert(String::new(), DataCell::new(value.into()));
+        table.rows.push(row);
+        self.returns_table(table)
+    }
+
+    /// Describe `columns` as the key of the first result set, as the server would for
+    /// a [`Command::with_key_info`] query. Without this such a query gets no key, as
+    /// when the server cannot describe the batch.
+    pub fn returns_key(mut self, columns: &[&str]) -> Self {
+        self.key = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    pub fn returns_rows_affected(mut self, n: u64) -> Self {
+        self.rows_affected = n;
+        self
+    }
+
+    /// Fail the call with `message`, as a server error would.
+    pub fn returns_error(mut self, message: &str) -> Self {
+        self.error = Some(message.into());
+        self
+    }
+
+    fn remaining(&self) -> usize {
+        self.times.saturating_sub(self.used)
+    }
+
+    fn matches(&self, call: &Call) -> bool {
+        self.differences(call).is_empty()
+    }
+
+    // Why `call` does not match, one line per difference.
+    fn differences(&self, call: &Call) -> Vec<String> {
+        let mut differences = Vec::new();
+        if let Some(operation) = self.operation.filter(|&o| o != call.operation) {
+            differences.push(format!(
+                "expected a {:?} call, found {:?}",
+                operation, call.operation
+            ));
+        }
+        match &self.text {
+            TextMatch::Exact(text) => {
+                let (expected, found) = (collapse(text), collapse(&call.text));
+                if expected != found {
+                    let at = expected
+                        .chars()
+                        .zip(found.chars())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    differences.push(format!("expected text: {}", expected));
+                    differences.push(format!("   found text: {}", found));
+                    differences.push(format!(
+                        "               {}^ first difference at character {}",
+                        " ".repeat(at),
+                        at + 1
+                    ));
+                }
+            }
+            TextMatch::Contains(fragment) => {
+                if !call.text.contains(fragment.as_str()) {
+                    differences.push(format!("text does not contain `{}`", fragment));
+                }
+            }
+            TextMatch::Predicate(description, predicate) => {
+                if !predicate(&call.text) {
+                    differences.push(format!("text does not match {}", description));
+                }
+            }
+        }
+        for (name, expected) in &self.params {
+            match call.param(name) {
+                None => differences.push(format!("@{} is missing", name)),
+                Some(found) if found != expected => differences.push(format!(
+                    "@{}: expected {:?}, found {:?}",
+                    name, expected, found
+                )),
+                Some(_) => {}
+            }
+        }
+        if self.exact_params {
+            for (name, _) in &call.params {
+                if !self
+                    .params
+                    .iter()
+                    .any(|(n, _)| n.eq_ignore_ascii_case(name))
+                {
+                    differences.push(format!("@{} is not expected", name));
+                }
+            }
+        }
+        differences
+    }
+
+    fn describe(&self) -> String {
+        let text = match &self.text {
+            TextMatch::Exact(text) => format!("`{}`", collapse(text)),
+            TextMatch::Contains(fragment) => format!("text containing `{}`", fragment),
+            TextMatch::Predicate(description, _) => format!("text matching {}", description),
+        };
+        let operation = match self.operation {
+            Some(operation) => format!("{:?} ", operation),
+            None => String::new(),
+        };
+        format!(
+            "{}{} (called {} of {} times)",
+            operation, text, self.used, self.times
+        )
+    }
+}
+
+impl fmt::Debug for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// An in-memory [`Executor`] for unit tests. It answers each command with the first
+/// [`Expectation`] that matches and records every call.
+///
+/// A call that matches no expectation fails with a report showing how it differs from
+/// the expectations still waiting. [`verify`](Self::verify) panics when such a call was
+/// made or an expectation was not called as often as required.
+///
+/// ```
+/// use mssqlrust::testing::{Expectation, FakeExecutor};
+/// use mssqlrust::{Command, Executor, Parameter};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let mut db = FakeExecutor::new().with_expectation(
+///     Expectation::sql("SELECT COUNT(*) FROM Orders WHERE Status = @status")
+///         .with_param("status", "open")
+///         .returns_scalar(3),
+/// );
+/// let cmd = Command::query("SELECT COUNT(*) FROM Orders WHERE Status = @status")
+///     .with_param(Parameter::new("status", "open"));
+/// assert_eq!(db.scalar(cmd).await?, Some(3.into()));
+/// db.verify();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct FakeExecutor {
+    expectations: Vec<Expectation>,
+    calls: Vec<Call>,
+    unmatched: Vec<String>,
+    ordered: bool,
+}
+
+impl FakeExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_expectation(mut self, expectation: Expectation) -> Self {
+        self.expectations.push(expectation);
+        self
+    }
+
+    /// Require calls in the order the expectations were added. Each expectation must
+    /// be called as often as it expects before the next one matches.
+    pub fn in_order(mut self) -> Self {
+        self.ordered = true;
+        self
+    }
+
+    /// Every call received so far, matched or not, in order.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// Panic unless every call matched and every expectation was called as often as
+    /// it expects.
+    pub fn verify(&self) {
+        let mut problems: Vec<String> = self.unmatched.clone();
+        problems.extend(
+            self.expectations
+                .iter()
+                .filter(|e| e.remaining() > 0)
+                .map(|e| format!("expectation not met: {}", e.describe())),
+        );
+        if !problems.is_empty() {
+            panic!("FakeExecutor verification failed:\n{}", problems.join("\n"));
+        }
+    }
+
+    // The matching expectation after recording the call, or the failure it answers with.
+    fn respond(&mut self, operation: Operation, command: &Command) -> Result<&Expectation> {
+        let call = Call::new(operation, command);
+        self.calls.push(call.clone());
+        command.bind()?;
+        let found = if self.ordered {
+            self.expectations
+                .iter()
+                .position(|e| e.remaining() > 0)
+                .filter(|&i| self.expectations[i].matches(&call))
+        } else {
+            self.expectations
+                .iter()
+                .position(|e| e.remaining() > 0 && e.matches(&call))
+        };
+        let Some(index) = found else {
+            let report = self.report(&call);
+            self.unmatched.push(report.clone());
+            return Err(anyhow!(report));
+        };
+        let expectation = &mut self.expectations[index];
+        expectation.used += 1;
+        match &expectation.error {
+            Some(message) => Err(anyhow!(message.clone())),
+            None => Ok(expectation),
+        }
+    }
+
+    // Describe an unexpected call and how it differs from each candidate expectation.
+    fn report(&self, call: &Call) -> String {
+        let mut candidates = self.expectations.iter().filter(|e| e.remaining() > 0);
+        let candidates: Vec<&Expectation> = if self.ordered {
+            candidates.next().into_iter().collect()
+        } else {
+            candidates.collect()
+        };
+        let mut report = format!("unexpected call: {}", call);
+        if candidates.is_empty() {
+            report.push_str("\n  no expectations are waiting");
+        }
+        for expectation in candidates {
+            report.push_str(&format!("\n  vs {}", expectation.describe()));
+            for difference in expectation.differences(call) {
+                report.push_str(&format!("\n    {}", difference));
+            }
+        }
+        report
+    }
+}
+
+#[async_trait]
+impl Executor for FakeExecutor {
+    async fn query(&mut self, command: Command) -> Result<DataSet> {
+        let expectation = self.respond(Operation::Query, &command)?;
+        let mut dataset = expectation.dataset.clone();
+        apply_result_options(&mut dataset, &command, expectation.key.clone());
+        Ok(dataset)
+    }
+
+    async fn non_query(&mut self, command: Command) -> Result<u64> {
+        Ok(self.respond(Operation::NonQuery, &command)?.rows_affected)
+    }
+
+    async fn scalar(&mut self, command: Command) -> Result<Option<DataValue>> {
+        let dataset = &self.respond(Operation::Scalar, &command)?.dataset;
+        Ok(result_rows(dataset).next().map(|(table, row)| {
+            let value = match table.columns.first() {
+                Some(column) => row.get(&column.name),
+                None => row.cells.values().next().map(|c| &c.value),
+            };
+            value.cloned().unwrap_or_default()
+        }))
+    }
+
+    async fn stream<'a>(&'a mut self, command: Command) -> Result<BoxStream<'a, Result<DataRow>>> {
+        let dataset = &self.respond(Operation::Stream, &command)?.dataset;
+        let rows: Vec<Result<DataRow>> = result_rows(dataset)
+            .map(|(_, row)| Ok(row.clone()))
+            .collect();
+        Ok(stream::iter(rows).boxed())
+    }
+}
+
+// Rows of `table0`, `table1`, ... in result order, with the table holding each.
+fn result_rows(dataset: &DataSet) -> impl Iterator<Item = (&DataTable, &DataRow)> {
+    (0..)
+        .map_while(|i| dataset.tables.get(&format!("table{}", i)))
+        .flat_map(|table| table.rows.iter().map(move |row| (table, row)))
+}
+
+fn bare(name: &str) -> &str {
+    name.strip_prefix('@').unwrap_or(name)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
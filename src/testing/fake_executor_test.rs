@@ -0,0 +1,195 @@
+use super::*;
+use crate::dataset::{DataCell, DataRow, DataTable, DataValue};
+use crate::{Command, Executor, Parameter};
+use futures::TryStreamExt;
+
+fn orders() -> DataTable {
+    let mut table = DataTable::new("orders");
+    for id in [1, 2] {
+        let mut row = DataRow::default();
+        row.cells.insert("Id".into(), DataCell::new(id));
+        table.rows.push(row);
+    }
+    table
+}
+
+#[tokio::test]
+async fn answers_matching_commands() {
+    let command = Command::query("SELECT Id FROM Orders WHERE CustomerId = @customer")
+        .with_param(Parameter::new("@customer", 7));
+    let mut fake = FakeExecutor::new()
+        .with_expectation(Expectation::command(&command).returns_table(orders()))
+        .with_expectation(
+            Expectation::sql_containing("UPDATE Orders")
+                .with_param("status", "closed")
+                .returns_rows_affected(2),
+        )
+        .with_expectation(Expectation::sql("SELECT  COUNT(*)\n  FROM Orders").returns_scalar(9));
+
+    let ds = fake.query(command).await.unwrap();
+    assert_eq!(ds.tables["table0"].rows.len(), 2);
+    let update = Command::query("UPDATE Orders SET Status = @status WHERE Id IN (@ids)")
+        .with_param(Parameter::new("status", "closed"))
+        .with_param(Parameter::list("ids", [1, 2]));
+    assert_eq!(fake.non_query(update).await.unwrap(), 2);
+    let count = fake
+        .scalar(Command::query("SELECT COUNT(*) FROM Orders"))
+        .await;
+    assert_eq!(count.unwrap(), Some(DataValue::Int(9)));
+    fake.verify();
+
+    assert_eq!(fake.calls().len(), 3);
+    assert_eq!(fake.calls()[1].operation, Operation::NonQuery);
+    assert_eq!(
+        fake.calls()[1].params[1],
+        ("ids".to_string(), DataValue::Text("[1,2]".into()))
+    );
+}
+
+#[tokio::test]
+async fn counts_calls_and_streams_rows() {
+    let mut fake = FakeExecutor::new().with_expectation(
+        Expectation::sql_matching("a SELECT from Orders", |sql| {
+            sql.starts_with("SELECT") && sql.contains("Orders")
+        })
+        .returns_table(orders())
+        .times(2),
+    );
+    for _ in 0..2 {
+        let rows: Vec<DataRow> = fake
+            .stream(Command::query("SELECT Id FROM Orders"))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows[1].get("Id"), Some(&DataValue::Int(2)));
+    }
+    let third = fake.query(Command::query("SELECT Id FROM Orders")).await;
+    assert!(third
+        .unwrap_err()
+        .to_string()
+        .contains("no expectations are waiting"));
+}
+
+#[tokio::test]
+async fn returns_canned_errors() {
+    let mut fake = FakeExecutor::new()
+        .with_expectation(Expectation::sql_containing("DELETE").returns_error("deadlock victim"));
+    let err = fake
+        .non_query(Command::query("DELETE FROM Orders"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "deadlock victim");
+    fake.verify();
+}
+
+#[tokio::test]
+async fn reports_how_unmatched_calls_differ() {
+    let mut fake = FakeExecutor::new().with_expectation(
+        Expectation::sql("SELECT * FROM Orders WHERE Id = @id").with_param("id", 1),
+    );
+    let command =
+        Command::query("SELECT * FROM Order WHERE Id = @id").with_param(Parameter::new("id", 1i64));
+    let report = fake.query(command).await.unwrap_err().to_string();
+    let expected = [
+        "unexpected call: Query `SELECT * FROM Order WHERE Id = @id` with @id = BigInt(1)",
+        "  vs `SELECT * FROM Orders WHERE Id = @id` (called 0 of 1 times)",
+        "    expected text: SELECT * FROM Orders WHERE Id = @id",
+        "       found text: SELECT * FROM Order WHERE Id = @id",
+        "                                      ^ first difference at character 20",
+        "    @id: expected Int(1), found BigInt(1)",
+    ];
+    assert_eq!(report, expected.join("\n"));
+}
+
+#[tokio::test]
+async fn enforces_order() {
+    let mut fake = FakeExecutor::new()
+        .with_expectation(Expectation::sql("BEGIN WORK"))
+        .with_expectation(Expectation::sql("COMMIT"))
+        .in_order();
+    assert!(fake.non_query(Command::query("COMMIT")).await.is_err());
+    fake.non_query(Command::query("BEGIN WORK")).await.unwrap();
+    fake.non_query(Command::query("COMMIT")).await.unwrap();
+}
+
+#[test]
+#[should_panic(expected = "expectation not met: `COMMIT` (called 0 of 1 times)")]
+fn verify_reports_missing_calls() {
+    FakeExecutor::new()
+        .with_expectation(Expectation::sql("COMMIT"))
+        .verify();
+}
+
+#[tokio::test]
+async fn applies_key_info_and_concatenation_like_a_connection() {
+    let mut json = DataTable::new("json");
+    for fragment in [r#"[{"Id":1},"#, r#"{"Id":2}]"#] {
+        let mut row = DataRow::default();
+        row.cells.insert("json".into(), DataCell::new(fragment));
+        json.rows.push(row);
+    }
+    let mut fake = FakeExecutor::new()
+        .with_expectation(
+            Expectation::sql("SELECT Id FROM Orders")
+                .returns_table(orders())
+                .returns_key(&["Id"])
+                .times(2),
+        )
+        .with_expectation(Expectation::sql("SELECT Id FROM #Orders").returns_table(orders()))
+        .with_expectation(
+            Expectation::sql("SELECT Id FROM Orders FOR JSON PATH").returns_table(json),
+        );
+
+    let keyed = fake
+        .query(Command::query("SELECT Id FROM Orders").with_key_info())
+        .await
+        .unwrap();
+    assert_eq!(keyed.tables["table0"].primary_key(), ["Id".to_string()]);
+    assert_eq!(keyed.tables["table0"].find_index(&[2.into()]), Some(1));
+    let plain = fake
+        .query(Command::query("SELECT Id FROM Orders"))
+        .await
+        .unwrap();
+    assert!(plain.tables["table0"].primary_key().is_empty());
+    // Nothing describes the temp table's key.
+    let undescribed = fake
+        .query(Command::query("SELECT Id FROM #Orders").with_key_info())
+        .await
+        .unwrap();
+    assert!(undescribed.tables["table0"].primary_key().is_empty());
+
+    let document = fake
+        .query(Command::query("SELECT Id FROM Orders FOR JSON PATH").with_concatenated_result())
+        .await
+        .unwrap();
+    let table = &document.tables["table0"];
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table[0]["json"], r#"[{"Id":1},{"Id":2}]"#);
+    fake.verify();
+}
+
+#[tokio::test]
+async fn matches_the_operation() {
+    let mut fake = FakeExecutor::new().with_expectation(
+        Expectation::sql("SELECT COUNT(*) FROM Orders")
+            .with_operation(Operation::Scalar)
+            .returns_scalar(2),
+    );
+    let report = fake
+        .query(Command::query("SELECT COUNT(*) FROM Orders"))
+        .await
+        .unwrap_err()
+        .to_string();
+    let expected = [
+        "unexpected call: Query `SELECT COUNT(*) FROM Orders`",
+        "  vs Scalar `SELECT COUNT(*) FROM Orders` (called 0 of 1 times)",
+        "    expected a Scalar call, found Query",
+    ];
+    assert_eq!(report, expected.join("\n"));
+    let count = fake
+        .scalar(Command::query("SELECT COUNT(*) FROM Orders"))
+        .await;
+    assert_eq!(count.unwrap(), Some(DataValue::Int(2)));
+}
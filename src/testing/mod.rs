@@ -0,0 +1,9 @@
+//! Test doubles for code written against [`Executor`](crate::Executor), enabled by the
+//! `testing` feature.
+
+mod fake_executor;
+
+pub use fake_executor::{Call, Expectation, FakeExecutor, Operation};
+
+#[cfg(test)]
+mod fake_executor_test;
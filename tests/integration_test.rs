@@ -1,6 +1,6 @@
 use mssqlrust::dataset::DataValue::Null;
 use mssqlrust::infrastructure::mssql::MssqlConfig;
-use mssqlrust::{execute, Command, Parameter};
+use mssqlrust::{execute, execute_json, Command, Parameter};
 
 use chrono::{DateTime, NaiveDate, NaiveTime};
 use futures::StreamExt;
@@ -268,3 +268,177 @@ async fn scalar_query_and_sp() {
     let value = mssqlrust::execute_scalar(config, sp).await.unwrap();
     assert_eq!(value.unwrap(), 6);
 }
+
+#[tokio::test]
+#[ignore]
+async fn sql_specific_types_query() {
+    use mssqlrust::dataset::DataValue;
+
+    let config = test_config();
+    let cmd = Command::query(
+        "SELECT \
+            CAST(1.5 AS real) AS real_col, \
+            CAST(12.3456 AS money) AS money_col, \
+            CAST(N'<a>1</a>' AS xml) AS xml_col, \
+            CAST(SQL_VARIANT_PROPERTY(CAST(@real AS sql_variant), 'BaseType') AS nvarchar(20)) AS real_type, \
+            CAST(SQL_VARIANT_PROPERTY(CAST(@variant AS sql_variant), 'BaseType') AS nvarchar(20)) AS variant_type",
+    )
+    .with_param(Parameter::new("real", DataValue::Real(2.5)))
+    .with_param(Parameter::new(
+        "variant",
+        DataValue::Variant {
+            base_type: "int".into(),
+            value: Box::new(DataValue::Int(3)),
+        },
+    ));
+    let ds = execute(config, cmd).await.unwrap();
+    let row = &ds.tables["table0"][0];
+    assert_eq!(row["real_col"], DataValue::Real(1.5));
+    assert_eq!(row["money_col"], DataValue::Money(Decimal::new(123456, 4)));
+    assert_eq!(row["xml_col"], DataValue::Xml("<a>1</a>".into()));
+    assert_eq!(row["real_type"], "real");
+    assert_eq!(row["variant_type"], "int");
+}
+
+#[tokio::test]
+#[ignore]
+async fn for_json_reassembled() {
+    let config = test_config();
+    let cmd = Command::query(
+        "SELECT TOP 500 object_id AS id, name FROM sys.all_objects ORDER BY object_id FOR JSON PATH",
+    );
+    let json = execute_json(config, cmd).await.unwrap().unwrap();
+    assert!(json.len() > 2033);
+    assert!(json.starts_with('[') && json.ends_with(']'));
+}
+
+#[tokio::test]
+#[ignore]
+async fn blob_round_trip() {
+    use mssqlrust::infrastructure::mssql::{Blob, SqlConnection};
+
+    let config = test_config();
+    run_ddl(
+        &config,
+        "IF OBJECT_ID('blob_test', 'U') IS NOT NULL DROP TABLE blob_test; \
+         CREATE TABLE blob_test (id int PRIMARY KEY, data varbinary(max), body nvarchar(max)); \
+         INSERT INTO blob_test (id) VALUES (1)",
+    )
+    .await;
+    let mut connection = SqlConnection::connect(config).await.unwrap();
+
+    let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+    let blob = Blob::binary("blob_test", "data").with_key("id", 1);
+    let mut chunks = 0;
+    let written = blob
+        .write_from(&mut connection, &mut &data[..], |_| chunks += 1)
+        .await
+        .unwrap();
+    assert_eq!(written, data.len() as u64);
+    assert!(chunks > 2);
+    let mut read = Vec::new();
+    blob.read_to(&mut connection, &mut read, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(read, data);
+
+    let body = "ü😀".repeat(100_000);
+    let blob = Blob::text("blob_test", "body")
+        .with_key("id", 1)
+        .with_chunk_size(8001);
+    blob.write_from(&mut connection, &mut body.as_bytes(), |_| {})
+        .await
+        .unwrap();
+    let mut read = Vec::new();
+    blob.read_to(&mut connection, &mut read, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(String::from_utf8(read).unwrap(), body);
+}
+
+fn executor_query() -> Command {
+    Command::query("SELECT @id AS id, @name AS name, @at AS at")
+        .with_param(Parameter::new("id", 7))
+        .with_param(Parameter::new("name", "Zoë"))
+        .with_param(Parameter::new(
+            "at",
+            NaiveDate::from_ymd_opt(2024, 2, 29)
+                .unwrap()
+                .and_hms_opt(12, 30, 0)
+                .unwrap(),
+        ))
+}
+
+fn executor_json() -> Command {
+    Command::query(
+        "SELECT TOP 50 name, object_id FROM sys.all_objects ORDER BY object_id FOR JSON PATH",
+    )
+    .with_concatenated_result()
+}
+
+// Run the commands the free functions ran through an `Executor`.
+async fn run_through_executor<E: mssqlrust::Executor>(
+    executor: &mut E,
+) -> (
+    mssqlrust::dataset::DataSet,
+    Option<String>,
+    Option<mssqlrust::dataset::DataValue>,
+    u64,
+) {
+    let ds = executor.query(executor_query()).await.unwrap();
+    let json = executor.query(executor_json()).await.unwrap();
+    let table = &json.tables["table0"];
+    let document = match &table[0][table.columns[0].name.as_str()] {
+        mssqlrust::dataset::DataValue::Text(text) => Some(text.clone()),
+        _ => None,
+    };
+    let scalar = executor.scalar(executor_query()).await.unwrap();
+    let affected = executor
+        .non_query(Command::query(
+            "DECLARE @t TABLE (id int); INSERT INTO @t VALUES (1), (2)",
+        ))
+        .await
+        .unwrap();
+    (ds, document, scalar, affected)
+}
+
+#[tokio::test]
+#[ignore]
+async fn executor_matches_free_functions() {
+    use mssqlrust::infrastructure::mssql::SqlConnection;
+    use mssqlrust::Executor;
+
+    let config = test_config();
+    let expected_ds = execute(config.clone(), executor_query()).await.unwrap();
+    let expected_json = execute_json(config.clone(), executor_json()).await.unwrap();
+    let expected_scalar = mssqlrust::execute_scalar(config.clone(), executor_query())
+        .await
+        .unwrap();
+    let expected_affected = mssqlrust::execute_non_query(
+        config.clone(),
+        Command::query("DECLARE @t TABLE (id int); INSERT INTO @t VALUES (1), (2)"),
+    )
+    .await
+    .unwrap();
+    let expected = (
+        expected_ds,
+        expected_json,
+        expected_scalar,
+        expected_affected,
+    );
+
+    let mut connection = SqlConnection::connect(config).await.unwrap();
+    assert_eq!(run_through_executor(&mut connection).await, expected);
+
+    let mut transaction = connection.begin().await.unwrap();
+    assert_eq!(run_through_executor(&mut transaction).await, expected);
+    let rows: Vec<_> = transaction
+        .stream(executor_query())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].as_ref().unwrap(), &expected.0.tables["table0"][0]);
+    transaction.rollback().await.unwrap();
+}